@@ -0,0 +1,24 @@
+#![no_main]
+
+// Whatever GET /challenge returns must either fail to parse or flow
+// through preimage construction, the difficulty check and deadline
+// parsing without panicking.
+
+use libfuzzer_sys::fuzz_target;
+use scavenger_miner::mining::{build_preimage, meets_difficulty};
+use scavenger_miner::types::{parse_deadline, ChallengeResponse};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(response) = serde_json::from_slice::<ChallengeResponse>(data) else {
+        return;
+    };
+    let _ = response.code.is_mineable();
+    let _ = response.code.poll_interval();
+    let _ = response.code.to_string();
+    let _ = parse_deadline(&response.mining_period_ends);
+    let _ = parse_deadline(&response.challenge.latest_submission);
+
+    let challenge = &response.challenge;
+    let preimage = build_preimage("0000000000000000", "addr1qfuzz", challenge);
+    let _ = meets_difficulty(preimage.as_bytes(), &challenge.difficulty);
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+// Arbitrary digest against an arbitrary difficulty: the comparator must
+// never panic and must agree with the trace printed in forensic reports.
+
+use libfuzzer_sys::fuzz_target;
+use scavenger_miner::mining::{difficulty_trace, meets_difficulty};
+
+fuzz_target!(|input: (&[u8], &str)| {
+    let (hash, difficulty) = input;
+    let passes = meets_difficulty(hash, difficulty);
+    let trace = difficulty_trace(hash, difficulty);
+    let verdict = trace.last().expect("trace is never empty");
+    assert_eq!(passes, verdict.ends_with("PASS"), "{:?}", trace);
+});
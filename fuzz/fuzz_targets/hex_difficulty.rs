@@ -0,0 +1,26 @@
+#![no_main]
+
+// Raw bytes as the difficulty string, against the two extreme digests.
+// Whatever the server sends, an all-zero hash can only pass if the
+// string is valid hex no wider than the digest, and an all-0xff hash can
+// never beat a target that has a byte below 0xff anywhere in it.
+
+use libfuzzer_sys::fuzz_target;
+use scavenger_miner::mining::meets_difficulty;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(difficulty) = std::str::from_utf8(data) else {
+        return;
+    };
+    let valid_hex = hex::decode(difficulty).ok();
+
+    let zero = [0u8; 64];
+    let fits = valid_hex.as_ref().is_some_and(|bytes| bytes.len() <= zero.len());
+    assert_eq!(meets_difficulty(&zero, difficulty), fits);
+
+    let ones = [0xffu8; 64];
+    if let Some(bytes) = valid_hex {
+        let all_ff = bytes.iter().all(|&b| b == 0xff);
+        assert_eq!(meets_difficulty(&ones, difficulty), fits && all_ff);
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+// A receipt's preimage comes from the server: checking it against what was
+// submitted must never panic, and must accept exactly what build_preimage
+// produces.
+
+use libfuzzer_sys::fuzz_target;
+use scavenger_miner::mining::{build_preimage, preimage_mismatches};
+use scavenger_miner::types::Challenge;
+
+fuzz_target!(|input: (&str, &str, &str, &[u8])| {
+    let (preimage, nonce, address, challenge_json) = input;
+    let Ok(challenge) = serde_json::from_slice::<Challenge>(challenge_json) else {
+        return;
+    };
+    let _ = preimage_mismatches(preimage, nonce, address, &challenge);
+    let built = build_preimage(nonce, address, &challenge);
+    assert!(preimage_mismatches(&built, nonce, address, &challenge).is_empty());
+});
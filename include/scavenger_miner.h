@@ -0,0 +1,94 @@
+#ifndef SCAVENGER_MINER_H
+#define SCAVENGER_MINER_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define SM_NOT_FOUND 0
+
+#define SM_FOUND 1
+
+#define SM_CANCELLED 2
+
+#define SM_ERROR -1
+
+/**
+ * Digest length in bytes
+ */
+#define SM_HASH_LEN 64
+
+/**
+ * Opaque challenge ROM
+ */
+typedef struct SmRom SmRom;
+
+/**
+ * Progress callback: hashes done so far; return false to cancel. May be NULL.
+ */
+typedef bool (*SmProgressFn)(void *user_data, uint64_t hashes_done);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Build the ROM for a challenge's no_pre_mine (NUL-terminated). Allocates
+ * about 1 GB and takes a while. NULL on error; free with sm_rom_free.
+ *
+ * # Safety
+ * `no_pre_mine` must be NULL or a valid NUL-terminated string.
+ */
+struct SmRom *sm_rom_new(const char *no_pre_mine);
+
+/**
+ * # Safety
+ * `rom` must come from sm_rom_new and not be used afterwards. NULL is ignored.
+ */
+void sm_rom_free(struct SmRom *rom);
+
+/**
+ * Hash `len` preimage bytes into `out` (SM_HASH_LEN bytes). Returns 0 or SM_ERROR.
+ *
+ * # Safety
+ * `rom` must be a live ROM, `preimage` valid for `len` bytes and `out`
+ * writable for SM_HASH_LEN bytes.
+ */
+int sm_hash(const struct SmRom *rom, const uint8_t *preimage, size_t len, uint8_t *out);
+
+/**
+ * 1 if the digest passes the hex difficulty, 0 if not, SM_ERROR on bad arguments.
+ *
+ * # Safety
+ * `hash` must be valid for `len` bytes and `difficulty` a NUL-terminated string.
+ */
+int sm_meets_difficulty(const uint8_t *hash, size_t len, const char *difficulty);
+
+/**
+ * Scan nonces `start .. start + count` for `address` against a challenge,
+ * given as the JSON object the API returns under "challenge".
+ *
+ * Returns SM_FOUND (nonce and digest written to `out_nonce` / `out_hash`),
+ * SM_NOT_FOUND, SM_CANCELLED (callback returned false) or SM_ERROR.
+ *
+ * # Safety
+ * `rom` must be a live ROM; strings NUL-terminated; `out_nonce` writable;
+ * `out_hash` writable for SM_HASH_LEN bytes. `user_data` is passed through.
+ */
+int sm_mine_range(const struct SmRom *rom,
+                  const char *address,
+                  const char *challenge_json,
+                  uint64_t start,
+                  uint64_t count,
+                  SmProgressFn progress,
+                  void *user_data,
+                  uint64_t *out_nonce,
+                  uint8_t *out_hash);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SCAVENGER_MINER_H */
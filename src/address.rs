@@ -0,0 +1,306 @@
+// ==================== ADDRESSES ====================
+//
+// Cardano addresses are bech32 with a checksum, so a typo can be caught
+// locally instead of as a rejected registration or, worse, hours of
+// mining for an address nobody holds the keys to.
+//
+// Mining normally runs under a payment address (addr1...). With
+// --address-kind stake it runs under the wallet's stake (reward) address
+// instead, derived from a base address when that is what was given, so
+// every payment address of one wallet maps to the same identity.
+
+use clap::ValueEnum;
+
+const CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut chk: u32 = 1;
+    for v in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+/// Human-readable part and payload bytes of a bech32 string. No length
+/// limit: Cardano base addresses are longer than BIP-173 allows.
+pub fn decode(raw: &str) -> Result<(String, Vec<u8>), String> {
+    if raw.chars().any(|c| c.is_ascii_uppercase()) && raw.chars().any(|c| c.is_ascii_lowercase()) {
+        return Err("mixes upper and lower case".to_string());
+    }
+    let raw = raw.to_ascii_lowercase();
+    let split = raw.rfind('1').ok_or("no '1' separator")?;
+    let (hrp, data) = (&raw[..split], &raw[split + 1..]);
+    if hrp.is_empty() || data.len() < 6 {
+        return Err("too short".to_string());
+    }
+    let values = data
+        .bytes()
+        .map(|c| {
+            CHARSET
+                .iter()
+                .position(|&x| x == c)
+                .map(|v| v as u8)
+                .ok_or_else(|| format!("'{}' is not a bech32 character", c as char))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    let expanded = hrp
+        .bytes()
+        .map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 31))
+        .chain(values.iter().copied());
+    if polymod(expanded) != 1 {
+        return Err("checksum mismatch (typo or truncated copy?)".to_string());
+    }
+
+    // 5-bit groups -> bytes, dropping the 6-symbol checksum
+    let mut bytes = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for &v in &values[..values.len() - 6] {
+        acc = acc << 5 | v as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return Err("invalid padding".to_string());
+    }
+    Ok((hrp.to_string(), bytes))
+}
+
+pub fn encode(hrp: &str, bytes: &[u8]) -> String {
+    // bytes -> 5-bit groups, zero padded
+    let mut values = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for &b in bytes {
+        acc = (acc << 8 | b as u32) & 0xfff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push((acc >> bits & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        values.push((acc << (5 - bits) & 31) as u8);
+    }
+    let expanded = hrp
+        .bytes()
+        .map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 31))
+        .chain(values.iter().copied())
+        .chain([0; 6]);
+    let checksum = polymod(expanded) ^ 1;
+    values.extend((0..6).map(|i| (checksum >> (5 * (5 - i)) & 31) as u8));
+    let data: String = values.iter().map(|&v| CHARSET[v as usize] as char).collect();
+    format!("{}1{}", hrp, data)
+}
+
+/// Bech32 form of raw address bytes (as CIP-30 wallets return them)
+pub fn from_bytes(bytes: &[u8]) -> String {
+    let hrp = match bytes.first() {
+        Some(header) if header & 0x0f == 1 => "addr",
+        _ => "addr_test",
+    };
+    encode(hrp, bytes)
+}
+
+/// Description of a valid payment address ("mainnet base address"), or why it isn't one
+pub fn check(address: &str) -> Result<String, String> {
+    let (hrp, bytes) = decode(address)?;
+    let network = match hrp.as_str() {
+        "addr" => "mainnet",
+        "addr_test" => "testnet",
+        "stake" | "stake_test" => {
+            return Err("this is a stake (reward) address; use a payment address starting with addr1, \
+                        or pass --address-kind stake to mine under it"
+                .to_string())
+        }
+        other => return Err(format!("'{}' is not a Cardano address prefix", other)),
+    };
+    let header = *bytes.first().ok_or("empty address")?;
+    let kind = match header >> 4 {
+        0..=3 => "base address",
+        4 | 5 => "pointer address",
+        6 | 7 => "enterprise address",
+        8 => return Err("Byron-era bootstrap addresses are not supported".to_string()),
+        _ => return Err(format!("unknown address type {}", header >> 4)),
+    };
+    let network_id = header & 0x0f;
+    if (network == "mainnet") != (network_id == 1) {
+        return Err(format!("prefix says {} but the network id is {}", network, network_id));
+    }
+    Ok(format!("{} {}", network, kind))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AddressKind {
+    /// addr1... as given
+    Payment,
+    /// stake1..., given directly or derived from a base address
+    Stake,
+}
+
+/// Description of a valid stake address, or why it isn't one
+pub fn check_stake(address: &str) -> Result<String, String> {
+    let (hrp, bytes) = decode(address)?;
+    let network = match hrp.as_str() {
+        "stake" => "mainnet",
+        "stake_test" => "testnet",
+        "addr" | "addr_test" => return Err("this is a payment address, not a stake address".to_string()),
+        other => return Err(format!("'{}' is not a Cardano address prefix", other)),
+    };
+    let header = *bytes.first().ok_or("empty address")?;
+    if !matches!(header >> 4, 14 | 15) || bytes.len() != 29 {
+        return Err("malformed stake address".to_string());
+    }
+    Ok(format!("{} stake address", network))
+}
+
+/// The stake address a base address delegates to
+pub fn stake_address(address: &str) -> Result<String, String> {
+    check(address)?;
+    let (_, bytes) = decode(address)?;
+    let header = bytes[0];
+    let stake_header = match header >> 4 {
+        // Base addresses: payment credential, then stake credential (key or script)
+        0 | 1 => 0xe0,
+        2 | 3 => 0xf0,
+        4 | 5 => return Err("pointer addresses have no stake credential to derive from".to_string()),
+        _ => return Err("enterprise addresses have no stake part; use a base address (addr1q...) \
+                        or the stake1... address itself"
+            .to_string()),
+    };
+    if bytes.len() != 57 {
+        return Err(format!("base address is {} bytes, expected 57", bytes.len()));
+    }
+    let mut stake = vec![stake_header | (header & 0x0f)];
+    stake.extend_from_slice(&bytes[29..]);
+    let hrp = if header & 0x0f == 1 { "stake" } else { "stake_test" };
+    Ok(encode(hrp, &stake))
+}
+
+/// The address to register and mine under, plus a description for the log
+pub fn resolve(address: &str, kind: AddressKind) -> Result<(String, String), String> {
+    match kind {
+        AddressKind::Payment => check(address).map(|kind| (address.to_string(), kind)),
+        AddressKind::Stake if address.starts_with("stake") => {
+            check_stake(address).map(|kind| (address.to_string(), kind))
+        }
+        AddressKind::Stake => {
+            let stake = stake_address(address)?;
+            let kind = format!("{} (stake part of {})", check_stake(&stake)?, address);
+            Ok((stake, kind))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // CIP-19's test vectors: one payment key hash, one stake key hash
+    const BASE: &str = "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x";
+    const BASE_TEST: &str = "addr_test1qz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgs68faae";
+    const SCRIPT_BASE: &str = "addr1z8phkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gten0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgs9yc0hh";
+    const POINTER: &str = "addr1gx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrzqf96k";
+    const ENTERPRISE: &str = "addr1vx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzers66hrl8";
+    const STAKE: &str = "stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw";
+    const STAKE_TEST: &str = "stake_test1uqehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gssrtvn";
+    const PAYMENT_HASH: &str = "9493315cd92eb5d8c4304e67b7e16ae36d61d34502694657811a2c8e";
+    const STAKE_HASH: &str = "337b62cfff6403a06a3acbc34f8c46003c69fe79a3628cefa9c47251";
+
+    #[test]
+    fn cip19_vectors_decode_and_encode() {
+        let (hrp, bytes) = decode(BASE).unwrap();
+        assert_eq!(hrp, "addr");
+        assert_eq!(hex::encode(&bytes), format!("01{}{}", PAYMENT_HASH, STAKE_HASH));
+        assert_eq!(encode(&hrp, &bytes), BASE);
+        assert_eq!(from_bytes(&bytes), BASE);
+
+        let (hrp, bytes) = decode(STAKE).unwrap();
+        assert_eq!(hrp, "stake");
+        assert_eq!(hex::encode(&bytes), format!("e1{}", STAKE_HASH));
+        assert_eq!(encode(&hrp, &bytes), STAKE);
+
+        for address in [BASE_TEST, SCRIPT_BASE, POINTER, ENTERPRISE, STAKE_TEST] {
+            let (hrp, bytes) = decode(address).unwrap();
+            assert_eq!(encode(&hrp, &bytes), address);
+        }
+        assert_eq!(from_bytes(&decode(BASE_TEST).unwrap().1), BASE_TEST);
+        // Upper case throughout is the same address
+        assert_eq!(decode(&BASE.to_uppercase()).unwrap(), decode(BASE).unwrap());
+    }
+
+    #[test]
+    fn payment_addresses_are_described() {
+        assert_eq!(check(BASE).unwrap(), "mainnet base address");
+        assert_eq!(check(BASE_TEST).unwrap(), "testnet base address");
+        assert_eq!(check(SCRIPT_BASE).unwrap(), "mainnet base address");
+        assert_eq!(check(POINTER).unwrap(), "mainnet pointer address");
+        assert_eq!(check(ENTERPRISE).unwrap(), "mainnet enterprise address");
+        assert_eq!(check_stake(STAKE).unwrap(), "mainnet stake address");
+        assert_eq!(check_stake(STAKE_TEST).unwrap(), "testnet stake address");
+    }
+
+    #[test]
+    fn bad_checksum_and_mixed_case_are_refused() {
+        // One character changed, and one dropped
+        let typo = BASE.replacen("qx2f", "qx2g", 1);
+        assert!(decode(&typo).unwrap_err().contains("checksum"));
+        assert!(check(&BASE[..BASE.len() - 1]).unwrap_err().contains("checksum"));
+        let mixed = format!("addr1Q{}", &BASE[6..]);
+        assert_eq!(decode(&mixed).unwrap_err(), "mixes upper and lower case");
+        assert!(decode("addr1qx2fxv2bmy").unwrap_err().contains("'b'"));
+        assert!(decode("addrqx2fxv2umy").is_err());
+    }
+
+    #[test]
+    fn wrong_network_prefix_is_refused() {
+        // Testnet bytes under the mainnet prefix, with a valid checksum
+        let (_, testnet) = decode(BASE_TEST).unwrap();
+        let relabelled = encode("addr", &testnet);
+        let e = check(&relabelled).unwrap_err();
+        assert!(e.contains("prefix says mainnet but the network id is 0"), "{}", e);
+        let e = check(&encode("bc", &testnet)).unwrap_err();
+        assert!(e.contains("not a Cardano address prefix"), "{}", e);
+    }
+
+    #[test]
+    fn stake_and_payment_addresses_are_not_interchangeable() {
+        let e = check(STAKE).unwrap_err();
+        assert!(e.contains("stake (reward) address"), "{}", e);
+        assert!(resolve(STAKE, AddressKind::Payment).is_err());
+        let e = check_stake(BASE).unwrap_err();
+        assert!(e.contains("payment address"), "{}", e);
+    }
+
+    #[test]
+    fn stake_address_of_a_base_address() {
+        assert_eq!(stake_address(BASE).unwrap(), STAKE);
+        assert_eq!(stake_address(BASE_TEST).unwrap(), STAKE_TEST);
+        assert_eq!(stake_address(SCRIPT_BASE).unwrap(), STAKE);
+        assert!(stake_address(ENTERPRISE).unwrap_err().contains("no stake part"));
+        assert!(stake_address(POINTER).unwrap_err().contains("pointer"));
+    }
+
+    #[test]
+    fn resolve_for_each_kind() {
+        assert_eq!(resolve(BASE, AddressKind::Payment).unwrap().0, BASE);
+        let (stake, kind) = resolve(BASE, AddressKind::Stake).unwrap();
+        assert_eq!(stake, STAKE);
+        assert!(kind.starts_with("mainnet stake address (stake part of addr1"), "{}", kind);
+        assert_eq!(resolve(STAKE, AddressKind::Stake).unwrap().0, STAKE);
+        assert!(resolve(ENTERPRISE, AddressKind::Stake).is_err());
+    }
+}
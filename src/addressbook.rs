@@ -0,0 +1,180 @@
+// ==================== ADDRESS BOOK ====================
+//
+// Labels for the addresses a user mines for (addresses.json in the data
+// dir), so commands take --label home instead of a pasted bech32 string.
+// Registration state, receipts and earnings are not stored here: they are
+// worked out from audit.jsonl and history.jsonl, which already record them.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::address;
+use crate::audit::AuditEntry;
+use crate::store::{self, SolutionStatus, Store};
+use crate::{tls, AddressesAction, Cli, MinerApi, ScavengerAPI};
+
+pub const BOOK_FILE: &str = "addresses.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub label: String,
+    pub address: String,
+    pub added_at: String,
+}
+
+pub struct AddressBook {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl AddressBook {
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(BOOK_FILE);
+        let entries = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        Ok(AddressBook { path, entries })
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&self.entries)?)?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    pub fn get(&self, label: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.label == label)
+    }
+
+    /// Add or relabel; the address must be a valid payment or stake address
+    pub fn add(&mut self, label: &str, raw: &str) -> Result<()> {
+        let raw = raw.trim();
+        if let (Err(e), Err(_)) = (address::check(raw), address::check_stake(raw)) {
+            bail!("{}: {}", raw, e);
+        }
+        if label.trim().is_empty() {
+            bail!("Label cannot be empty");
+        }
+        self.entries.retain(|e| e.label != label);
+        self.entries.push(Entry {
+            label: label.to_string(),
+            address: raw.to_string(),
+            added_at: Utc::now().to_rfc3339(),
+        });
+        self.save()
+    }
+
+    pub fn remove(&mut self, label: &str) -> Result<()> {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.label != label);
+        if self.entries.len() == before {
+            bail!("No address labelled '{}'", label);
+        }
+        self.save()
+    }
+}
+
+/// Add the labels of `theirs` that `ours` lacks; a label on both keeps
+/// ours. How many were added.
+pub fn merge(ours: &mut Vec<Entry>, theirs: Vec<Entry>) -> usize {
+    let before = ours.len();
+    for entry in theirs {
+        if !ours.iter().any(|e| e.label == entry.label) {
+            ours.push(entry);
+        }
+    }
+    ours.len() - before
+}
+
+/// The address for --label
+pub fn lookup(data_dir: &Path, label: &str) -> Result<String> {
+    let book = AddressBook::load(data_dir)?;
+    match book.get(label) {
+        Some(entry) => Ok(entry.address.clone()),
+        None => bail!(
+            "No address labelled '{}'; add it with `scavenger_miner addresses add {} <address>`",
+            label,
+            label
+        ),
+    }
+}
+
+/// `addresses [add|remove]`; without an action, the book with earnings
+/// from the server's STAR table
+pub async fn run(cli: &Cli, action: Option<&AddressesAction>) -> Result<()> {
+    tls::warn_if_insecure();
+    let mut book = AddressBook::load(&cli.data_dir)?;
+    match action {
+        Some(AddressesAction::Add { label, address }) => {
+            book.add(label, address)?;
+            say!("✅ {} = {}", label, address.trim());
+        }
+        Some(AddressesAction::Remove { label }) => {
+            book.remove(label)?;
+            say!("🗑️  Removed {}", label);
+        }
+        None => {
+            let store = Store::open(&cli.data_dir)?;
+            let rates = ScavengerAPI::new(&cli.base_url)?.get_star_rate().await.ok();
+            print(&cli.data_dir, &store, rates.as_deref())?;
+        }
+    }
+    Ok(())
+}
+
+/// The book with registration, receipts and earnings per address.
+/// `rates` is the STAR table; without it earnings are left out.
+pub fn print(data_dir: &Path, store: &Store, rates: Option<&[u64]>) -> Result<()> {
+    let book = AddressBook::load(data_dir)?;
+    if book.entries.is_empty() {
+        say!("📒 The address book is empty");
+        say!("   Add one: scavenger_miner addresses add <label> <address>");
+        return Ok(());
+    }
+
+    let audit: Vec<AuditEntry> = store::read_jsonl(&store.audit_path())?;
+    let registered_at: HashMap<&str, &str> = audit
+        .iter()
+        .filter(|e| e.action == "register" && e.outcome == "ok")
+        .filter_map(|e| Some((e.address.as_deref()?, e.timestamp.as_str())))
+        .collect();
+    let solutions = store.solutions()?;
+
+    say!("📒 Address book ({})", book.path.display());
+    for entry in &book.entries {
+        let accepted: Vec<_> = solutions
+            .iter()
+            .filter(|s| s.address == entry.address && s.status == SolutionStatus::Accepted)
+            .collect();
+        let earned = rates.map(|rates| {
+            accepted
+                .iter()
+                .filter_map(|s| rates.get((s.challenge.day as usize).checked_sub(1)?))
+                .sum::<u64>()
+        });
+        say!();
+        say!("   {}", entry.label);
+        say!("     Address: {}", entry.address);
+        match registered_at.get(entry.address.as_str()) {
+            Some(at) => say!("     Registered: yes ({})", at),
+            None => say!("     Registered: not from this machine"),
+        }
+        say!("     Receipts: {}", accepted.len());
+        match earned {
+            Some(earned) => say!("     Earnings: {} STAR", earned),
+            None => say!("     Earnings: unknown (rate table unavailable)"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// ==================== EXPECTED VALUE ADVISOR ====================
+//
+// Before a ROM is built: given the hash rate this machine measured last
+// time and the challenge's difficulty, how likely is a solution before
+// the challenge stops accepting them? Daemons can skip long shots
+// (--min-success-chance) instead of burning power on them.
+
+use chrono::{DateTime, Utc};
+
+use crate::store::{HashRateRecord, Store};
+use crate::{parse_deadline, timefmt, ChallengeResponse, DifficultyRule};
+
+/// Runs shorter than this don't update the calibration
+pub const CALIBRATION_MIN_SECS: f64 = 30.0;
+
+/// P(at least one solution) in `hashes` attempts at per-hash probability `p`
+pub fn chance(p: f64, hashes: f64) -> f64 {
+    if p >= 1.0 {
+        return 1.0;
+    }
+    // 1 - (1 - p)^n without losing tiny p to rounding
+    -(hashes * (-p).ln_1p()).exp_m1()
+}
+
+/// --min-success-chance: a percentage between 0 and 100
+pub fn parse_percent(raw: &str) -> Result<f64, String> {
+    let value: f64 = raw
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("invalid percentage '{}'", raw))?;
+    if !(0.0..=100.0).contains(&value) {
+        return Err(format!("{} is not between 0 and 100", value));
+    }
+    Ok(value)
+}
+
+pub struct Advice {
+    pub chance: f64,
+    /// Calibrated rate scaled to the current thread count
+    pub rate: f64,
+    pub calibration: HashRateRecord,
+}
+
+/// None without a calibration, a parseable difficulty or time left
+pub fn advise(
+    store: &Store,
+    response: &ChallengeResponse,
+    threads: usize,
+    stop_at: Option<DateTime<Utc>>,
+    rule: DifficultyRule,
+) -> Option<Advice> {
+    let calibration = store.hash_rate().ok().flatten()?;
+    let p = rule.probability(&response.challenge.difficulty)?;
+    let closes = parse_deadline(&response.challenge.latest_submission)?;
+    let closes = stop_at.map_or(closes, |stop| stop.min(closes));
+    let secs = (closes - Utc::now()).num_seconds();
+    if secs <= 0 || calibration.threads == 0 {
+        return None;
+    }
+    let rate = calibration.rate * threads as f64 / calibration.threads as f64;
+    Some(Advice { chance: chance(p, rate * secs as f64), rate, calibration })
+}
+
+pub fn print(advice: &Advice) {
+    say!(
+        "🎯 P(solution before the deadline) ~{:.1}% at {:.0} H/s",
+        advice.chance * 100.0,
+        advice.rate
+    );
+    say!(
+        "   Calibrated at {:.0} H/s on {} thread(s), {}",
+        advice.calibration.rate,
+        advice.calibration.threads,
+        timefmt::timestamp(&advice.calibration.measured_at)
+    );
+}
@@ -0,0 +1,127 @@
+// ==================== API CLIENT ====================
+//
+// Plain async client for the Scavenger Mine API, for tools built on the
+// library. The miner binary builds on it (`with_http`, `http`, `url`),
+// adding audit logging, retries and replay on the same endpoints.
+
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+use serde::de::DeserializeOwned;
+
+use crate::types::{
+    ChallengeResponse, RegistrationResponse, SolutionResponse, TandCResponse, BASE_URL,
+};
+
+/// Scavenger Mine API client
+///
+/// ```no_run
+/// # async fn demo() -> anyhow::Result<()> {
+/// let client = scavenger_miner::Client::new()?;
+/// let response = client.challenge().await?;
+/// println!("{} at difficulty {}", response.challenge.challenge_id, response.challenge.difficulty);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl Client {
+    /// Client for the production API
+    pub fn new() -> Result<Self> {
+        Self::with_base_url(BASE_URL)
+    }
+
+    /// Client for another deployment (staging, a local mock)
+    pub fn with_base_url(base_url: impl Into<String>) -> Result<Self> {
+        Self::with_http(base_url, reqwest::Client::builder())
+    }
+
+    /// Client over an HTTP builder set up elsewhere (TLS backend, extra
+    /// roots, proxies); the API's headers and timeout are added to it
+    pub fn with_http(base_url: impl Into<String>, http: reqwest::ClientBuilder) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36"),
+        );
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json, text/plain, */*"));
+
+        let http = http
+            .default_headers(headers)
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Client { http, base_url: base_url.into().trim_end_matches('/').to_string() })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The endpoint at `path` (e.g. "challenge") under the base URL
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    /// The HTTP client underneath, for callers that send requests
+    /// themselves (the miner binary, to audit and retry them)
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Raw JSON of one endpoint. Error statuses still carry a JSON body
+    /// (a rejected solution, say), so only a non-JSON body is an error.
+    pub(crate) async fn fetch(&self, post: bool, path: &str) -> Result<serde_json::Value> {
+        let url = self.url(path);
+        let request = if post { self.http.post(&url) } else { self.http.get(&url) };
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        serde_json::from_str(&body)
+            .with_context(|| format!("Invalid JSON response ({}): {}", status, body))
+    }
+
+    async fn fetch_as<T: DeserializeOwned>(&self, post: bool, path: &str) -> Result<T> {
+        Ok(serde_json::from_value(self.fetch(post, path).await?)?)
+    }
+
+    /// GET /TandC
+    pub async fn terms(&self) -> Result<TandCResponse> {
+        self.fetch_as(false, "TandC").await
+    }
+
+    /// POST /register/{address}/{signature}/{pubkey}
+    pub async fn register(
+        &self,
+        address: &str,
+        signature: &str,
+        pubkey: &str,
+    ) -> Result<RegistrationResponse> {
+        self.fetch_as(true, &format!("register/{}/{}/{}", address, signature, pubkey))
+            .await
+    }
+
+    /// GET /challenge
+    pub async fn challenge(&self) -> Result<ChallengeResponse> {
+        self.fetch_as(false, "challenge").await
+    }
+
+    /// POST /solution/{address}/{challenge_id}/{nonce}; no receipt means rejected
+    pub async fn submit(
+        &self,
+        address: &str,
+        challenge_id: &str,
+        nonce: &str,
+    ) -> Result<SolutionResponse> {
+        self.fetch_as(true, &format!("solution/{}/{}/{}", address, challenge_id, nonce))
+            .await
+    }
+
+    /// GET /work_to_star_rate: STAR per accepted solution, indexed by day - 1
+    pub async fn star_rate(&self) -> Result<Vec<u64>> {
+        self.fetch_as(false, "work_to_star_rate").await
+    }
+}
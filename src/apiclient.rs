@@ -0,0 +1,295 @@
+// ==================== API CLIENT ====================
+//
+// The library's `Client`, over the TLS settings every client here starts
+// from (tls::builder), with what a mining run adds around each call: the
+// audit log, retries with backoff, schema checks, chaos injection and the
+// coordinator link. `MinerApi` is what a round needs of it, so replays can
+// stand in for the server.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+use scavenger_miner::Client;
+
+use crate::audit::{AuditEntry, AuditLog};
+#[cfg(feature = "chaos")]
+use crate::chaos;
+use crate::{coordinator, metrics, retry, schema, tls};
+use crate::{
+    ChallengeResponse, NoChallenge, RegistrationResponse, SolutionResponse, TandCResponse, BASE_URL,
+};
+
+pub struct ScavengerAPI {
+    api: Client,
+    audit: Option<AuditLog>,
+    /// Set when base_url is a coordinator (--coordinator)
+    coordinator: Option<coordinator::Link>,
+}
+
+impl ScavengerAPI {
+    pub fn new(base_url: &str) -> Result<Self> {
+        let api = Client::with_http(base_url, tls::builder())?;
+        Ok(ScavengerAPI { api, audit: None, coordinator: None })
+    }
+
+    pub fn with_audit_log(mut self, log: AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Mine as one of the base URL's workers, named `worker` in its log
+    pub fn with_coordinator(mut self, worker: String) -> Self {
+        self.coordinator = Some(coordinator::Link::new(worker));
+        self
+    }
+
+    /// Send and read the body, failing on statuses worth a retry; chaos
+    /// builds may fail, delay or mangle it
+    async fn fetch(&self, request: reqwest::RequestBuilder, entry: &mut AuditEntry) -> Result<String> {
+        #[cfg(feature = "chaos")]
+        let injected = chaos::before_request().await?;
+        #[cfg(not(feature = "chaos"))]
+        let injected: Option<(u16, String)> = None;
+        let (status, body) = match injected {
+            Some(injected) => injected,
+            None => {
+                let response = request.send().await?;
+                let status = response.status().as_u16();
+                let body = response.text().await?;
+                #[cfg(feature = "chaos")]
+                let body = chaos::mangle(body);
+                (status, body)
+            }
+        };
+        entry.http_status = Some(status);
+        retry::check_status(status, body)
+    }
+
+    fn audit<T>(&self, mut entry: AuditEntry, result: &Result<T>) {
+        entry.finish(result);
+        if entry.outcome == "error" {
+            metrics::api_error(&entry.action);
+        }
+        if let Some(log) = &self.audit {
+            log.append(&entry);
+        }
+    }
+    
+    pub async fn get_terms(&self) -> Result<TandCResponse> {
+        retry::with_backoff("T&C", || self.get_terms_once()).await
+    }
+
+    async fn get_terms_once(&self) -> Result<TandCResponse> {
+        let mut entry = AuditEntry::new("terms");
+        
+        let result: Result<TandCResponse> = async {
+            let url = self.api.url("TandC");
+            let body = self.fetch(self.api.http().get(&url), &mut entry).await?;
+            decode_body(&body, &mut entry).with_context(|| format!("Failed to fetch T&C: {}", body))
+        }.await;
+        
+        self.audit(entry, &result);
+        result
+    }
+
+    pub fn register_url(&self, address: &str, signature: &str, pubkey: &str) -> String {
+        self.api.url(&format!("register/{}/{}/{}", address, signature, pubkey))
+    }
+
+    pub async fn register(
+        &self,
+        address: &str,
+        signature: &str,
+        pubkey: &str,
+    ) -> Result<RegistrationResponse> {
+        retry::with_backoff("register", || self.register_once(address, signature, pubkey)).await
+    }
+
+    async fn register_once(
+        &self,
+        address: &str,
+        signature: &str,
+        pubkey: &str,
+    ) -> Result<RegistrationResponse> {
+        let mut entry = AuditEntry::new("register");
+        entry.address = Some(address.to_string());
+        
+        let result: Result<RegistrationResponse> = async {
+            let url = self.register_url(address, signature, pubkey);
+            let body = self.fetch(self.api.http().post(&url), &mut entry).await?;
+            let response: RegistrationResponse = decode_body(&body, &mut entry)?;
+            // `fetch` lets refusals (4xx) through, as for submissions: the
+            // answer is a message in place of the receipt
+            let refused = entry.http_status.is_some_and(|status| !(200..300).contains(&status));
+            if refused && response.registration_receipt.is_none() {
+                anyhow::bail!("Registration failed: {}", body);
+            }
+            Ok(response)
+        }.await;
+        
+        self.audit(entry, &result);
+        result
+    }
+}
+
+/// The calls a mining round makes, so recorded traffic can stand in for the server
+pub trait MinerApi {
+    async fn get_challenge(&self) -> Result<ChallengeResponse>;
+    async fn submit_solution(
+        &self,
+        address: &str,
+        challenge_id: &str,
+        nonce: &str,
+    ) -> Result<SolutionResponse>;
+    async fn get_star_rate(&self) -> Result<Vec<u64>>;
+    /// Wait between polls (instant when replaying)
+    async fn pause(&self, duration: Duration);
+    /// Where a solution is POSTed (shown by --dry-run)
+    fn solution_url(&self, address: &str, challenge_id: &str, nonce: &str) -> String {
+        format!("{}/solution/{}/{}/{}", BASE_URL, address, challenge_id, nonce)
+    }
+    /// Nonces to scan, when something else hands them out (a coordinator);
+    /// None lets the round choose. An empty range means nothing is left.
+    async fn claim_nonces(
+        &self,
+        _address: &str,
+        _challenge_id: &str,
+        _count: u64,
+    ) -> Result<Option<std::ops::Range<u64>>> {
+        Ok(None)
+    }
+    /// Why an open challenge should not be mined, when something else
+    /// knows (a coordinator whose fleet already solved it)
+    fn held_back(&self, _challenge_id: &str) -> Option<String> {
+        None
+    }
+    /// The time deadlines are held against (the recording's when replaying)
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Parse a JSON body, keeping the raw value for the audit log
+fn decode_body<T: serde::de::DeserializeOwned>(body: &str, entry: &mut AuditEntry) -> Result<T> {
+    let value: serde_json::Value = serde_json::from_str(body)
+        .with_context(|| format!("Invalid JSON response: {}", body))?;
+    schema::check(&entry.action, &value);
+    entry.response = Some(value.clone());
+    Ok(serde_json::from_value(value)?)
+}
+
+impl MinerApi for ScavengerAPI {
+    fn solution_url(&self, address: &str, challenge_id: &str, nonce: &str) -> String {
+        self.api.url(&format!("solution/{}/{}/{}", address, challenge_id, nonce))
+    }
+
+    async fn get_challenge(&self) -> Result<ChallengeResponse> {
+        retry::with_backoff("challenge", || self.get_challenge_once()).await
+    }
+
+    async fn submit_solution(
+        &self,
+        address: &str,
+        challenge_id: &str,
+        nonce: &str,
+    ) -> Result<SolutionResponse> {
+        retry::submit_with_backoff(|| self.submit_solution_once(address, challenge_id, nonce))
+            .await
+    }
+
+    async fn get_star_rate(&self) -> Result<Vec<u64>> {
+        retry::with_backoff("star rate", || self.get_star_rate_once()).await
+    }
+
+    async fn pause(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    async fn claim_nonces(
+        &self,
+        address: &str,
+        challenge_id: &str,
+        count: u64,
+    ) -> Result<Option<std::ops::Range<u64>>> {
+        let Some(link) = &self.coordinator else {
+            return Ok(None);
+        };
+        let range =
+            link.claim(self.api.http(), self.api.base_url(), address, challenge_id, count).await?;
+        Ok(Some(range))
+    }
+
+    fn held_back(&self, challenge_id: &str) -> Option<String> {
+        self.coordinator.as_ref()?.held_back(challenge_id)
+    }
+}
+
+impl ScavengerAPI {
+    async fn get_challenge_once(&self) -> Result<ChallengeResponse> {
+        let mut entry = AuditEntry::new("challenge");
+        
+        let result: Result<ChallengeResponse> = async {
+            let url = self.api.url("challenge");
+            let body = self.fetch(self.api.http().get(&url), &mut entry).await?;
+            let value: serde_json::Value = decode_body(&body, &mut entry)?;
+            if let Some(idle) = NoChallenge::from_response(&value) {
+                entry.outcome = "idle".to_string();
+                return Err(idle.into());
+            }
+            Ok(serde_json::from_value(value)?)
+        }.await;
+        
+        if let (Some(link), Some(served)) = (&self.coordinator, &entry.response) {
+            link.note(served);
+        }
+        
+        if let Ok(response) = &result {
+            entry.challenge_id = Some(response.challenge.challenge_id.clone());
+        }
+        self.audit(entry, &result);
+        result
+    }
+
+    async fn get_star_rate_once(&self) -> Result<Vec<u64>> {
+        let mut entry = AuditEntry::new("star_rate");
+        
+        let result: Result<Vec<u64>> = async {
+            let url = self.api.url("work_to_star_rate");
+            let body = self.fetch(self.api.http().get(&url), &mut entry).await?;
+            decode_body(&body, &mut entry)
+        }.await;
+        
+        self.audit(entry, &result);
+        result
+    }
+
+    async fn submit_solution_once(
+        &self,
+        address: &str,
+        challenge_id: &str,
+        nonce: &str,
+    ) -> Result<SolutionResponse> {
+        let mut entry = AuditEntry::new("submit");
+        entry.address = Some(address.to_string());
+        entry.challenge_id = Some(challenge_id.to_string());
+        entry.nonce = Some(nonce.to_string());
+        
+        let result: Result<SolutionResponse> = async {
+            let url = self.solution_url(address, challenge_id, nonce);
+            let body = self.fetch(self.api.http().post(&url), &mut entry).await?;
+            decode_body(&body, &mut entry)
+        }.await;
+        
+        if let Ok(response) = &result {
+            if response.crypto_receipt.is_some() {
+                entry.outcome = "accepted".to_string();
+            } else {
+                entry.outcome = "rejected".to_string();
+                entry.detail = Some(response.extra.to_string());
+            }
+        }
+        self.audit(entry, &result);
+        result
+    }
+}
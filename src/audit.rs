@@ -0,0 +1,86 @@
+// ==================== AUDIT LOG ====================
+//
+// Append-only record of every call to the server (terms, registration,
+// challenge fetch, rate table and submission), kept apart from console
+// output so a disputed day can be reconstructed.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::store::append_jsonl;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    pub http_status: Option<u16>,
+    /// "ok", "error", "accepted", "rejected" or "idle" (no challenge)
+    pub outcome: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Raw JSON body, so a day's traffic can be replayed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<serde_json::Value>,
+    pub duration_ms: u64,
+    #[serde(skip)]
+    started: Option<Instant>,
+}
+
+impl AuditEntry {
+    pub fn new(action: &str) -> Self {
+        AuditEntry {
+            timestamp: Utc::now().to_rfc3339(),
+            action: action.to_string(),
+            address: None,
+            challenge_id: None,
+            nonce: None,
+            http_status: None,
+            outcome: String::new(),
+            detail: None,
+            response: None,
+            duration_ms: 0,
+            started: Some(Instant::now()),
+        }
+    }
+
+    /// Fill in outcome and timing from the call result
+    pub fn finish<T>(&mut self, result: &anyhow::Result<T>) {
+        if let Some(started) = self.started {
+            self.duration_ms = started.elapsed().as_millis() as u64;
+        }
+        match result {
+            Ok(_) if self.outcome.is_empty() => self.outcome = "ok".to_string(),
+            Ok(_) => {}
+            Err(e) => {
+                if self.outcome.is_empty() {
+                    self.outcome = "error".to_string();
+                }
+                self.detail = Some(e.to_string());
+            }
+        }
+    }
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        AuditLog { path }
+    }
+
+    pub fn append(&self, entry: &AuditEntry) {
+        if let Err(e) = append_jsonl(&self.path, entry) {
+            say!("⚠️  Failed to write audit log: {}", e);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// ==================== BENCHMARK ====================
+//
+// `bench`: how fast this machine hashes, without a server or an address.
+// The real ROM and mining loop run against a synthetic challenge nobody
+// can solve, for a fixed time. A run of at least 30 s at the protocol ROM
+// size also becomes the calibration --min-success-chance uses, so a new
+// machine can be calibrated before its first challenge.
+
+use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
+
+use crate::store::Store;
+use crate::supervisor::{self, Supervisor};
+use crate::{
+    advisor, experimental_rom_size, limits, mine_challenge, rom_dir, romcache, romfile, timefmt,
+    Challenge, Cli, DifficultyRule, MiningOptions, ROM_SIZE,
+};
+
+/// Any address does; it only goes into the preimage
+const BENCH_ADDRESS: &str = "addr1benchmark";
+
+fn challenge() -> Result<Challenge> {
+    serde_json::from_value(serde_json::json!({
+        "challenge_id": "**BENCH",
+        "day": 0,
+        "challenge_number": 0,
+        // One hash in four billion would solve it; close enough to never
+        "difficulty": "00000000",
+        "no_pre_mine": "0000000000000000000000000000000000000000000000000000000000000000",
+        "latest_submission": "",
+        "no_pre_mine_hour": "",
+    }))
+    .context("Cannot build the benchmark challenge")
+}
+
+/// `bench`, at --rom-size and with --threads as the profile allows
+pub async fn run(cli: &Cli, duration: Duration) -> Result<()> {
+    let rom_size = experimental_rom_size(cli)?.unwrap_or(ROM_SIZE);
+    let on_disk = romfile::enable(cli.rom_storage, rom_dir(cli), rom_size)?;
+    limits::check(cli.ignore_limits, rom_size, on_disk)?;
+    let threads = cli.profile.tuning().threads(cli.threads);
+    measure(&Store::open(&cli.data_dir)?, threads, rom_size, duration).await
+}
+
+async fn measure(store: &Store, threads: usize, rom_size: usize, duration: Duration) -> Result<()> {
+    let challenge = challenge()?;
+    let mut supervisor = Supervisor::new();
+    supervisor.spawn("signals", supervisor::signals);
+    let mut options = MiningOptions {
+        start_nonce: 0,
+        max_iterations: u64::MAX,
+        threads,
+        sprint: None,
+        work_hours: None,
+        stop_at: None,
+        power_guard: None,
+        deadline: None,
+        expires_at: None,
+        rom_size,
+        difficulty_rule: DifficultyRule::default(),
+        cancel: supervisor.token(),
+    };
+    say!(
+        "⏱️  Benchmark: {} thread(s) for {}",
+        threads,
+        timefmt::humanize(chrono::Duration::from_std(duration).unwrap_or_default())
+    );
+
+    let report = supervisor
+        .run(async {
+            let ctx = romcache::build(&challenge.no_pre_mine, &options).await?;
+            // The clock starts once the ROM is ready
+            options.stop_at = Some(Instant::now() + duration);
+            Some(mine_challenge(&ctx, BENCH_ADDRESS, &challenge, &options))
+        })
+        .await;
+    let Some(report) = report else {
+        say!("🛑 Benchmark cancelled");
+        return Ok(());
+    };
+
+    say!();
+    match report.hash_rate {
+        Some(rate) => {
+            let per_thread = rate / threads as f64;
+            say!("📊 {:.0} H/s on {} thread(s) ({:.0} H/s per thread)", rate, threads, per_thread);
+            store.record_hash_rate(rate, threads)?;
+            say!("   Saved as the calibration for --min-success-chance");
+        }
+        None => say!(
+            "📊 {} hashes; run at least {:.0}s at the protocol ROM size for a usable rate",
+            report.scanned,
+            advisor::CALIBRATION_MIN_SECS
+        ),
+    }
+    Ok(())
+}
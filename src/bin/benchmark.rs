@@ -1,4 +1,3 @@
-use num_cpus;
 use std::time::Instant;
 
 fn main() {
@@ -1,5 +1,4 @@
 use ashmaize::{hash, Rom, RomGenerationType};
-use hex;
 
 fn main() {
     println!("🧪 Testing AshMaize Integration\n");
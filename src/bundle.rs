@@ -0,0 +1,485 @@
+// ==================== SEALED SYNC ====================
+//
+// `sync push` / `sync pull`: a user's machines (the laptop that registered,
+// the rig that mines) share registration state and history through one file
+// on storage they need not trust: a directory, an S3 bucket or a WebDAV
+// share. History, receipts, the address book, the audit log (which holds
+// the registrations) and the settings file (config.toml, profile and all,
+// wherever --config keeps it) are packed into a bundle and sealed with
+// ChaCha20-Poly1305 under a key stretched from the user's passphrase with
+// Argon2id, so the storage only ever sees ciphertext, and a wrong
+// passphrase or a tampered file fails to open. Both directions merge rather
+// than copy: log lines and address labels one side lacks are added, other
+// files only where they are missing, so no machine's records are lost and
+// nothing local is overwritten; a rig restored from a bundle gets the
+// settings, one that has its own keeps them. A push writes conditionally (If-Match on
+// the ETag, or a lock and hash check in a directory), so two machines
+// pushing at once cannot drop each other's records.
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use crate::addressbook::{self, Entry};
+use crate::hdwallet;
+use crate::sync::{self, Remote};
+use crate::{tls, SyncAction};
+
+/// What a bundle carries: what another machine needs to know this user,
+/// not this machine's logs or ROM
+const BUNDLED: &[&str] = &[
+    "history.jsonl",
+    "scans.jsonl",
+    "challenges.jsonl",
+    "rates.jsonl",
+    "addresses.json",
+    "audit.jsonl",
+    "receipts",
+    "payouts",
+];
+
+/// The settings file's name in a bundle; on each machine it is wherever
+/// --config points
+const CONFIG_ENTRY: &str = "config.toml";
+
+/// The bundle's name on the remote
+const BUNDLE_FILE: &str = "scavenger-sync.bin";
+
+/// Merge-and-write rounds a push makes before giving up on a busy remote
+const PUSH_TRIES: usize = 5;
+
+/// Passphrase for sync push/pull, instead of the prompt
+pub const PASSPHRASE_ENV: &str = "SCAVENGER_SYNC_PASSPHRASE";
+
+/// A new bundle's passphrase is all that protects it
+const MIN_PASSPHRASE: usize = 12;
+
+/// Format tag; the salt and nonce follow it
+const MAGIC: &[u8; 8] = b"SCVSYNC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Path in the data dir -> contents
+type Files = BTreeMap<String, Vec<u8>>;
+
+/// Where this machine keeps what a bundle carries
+struct Local<'a> {
+    data_dir: &'a Path,
+    config: &'a Path,
+}
+
+impl Local<'_> {
+    /// Where a file of the bundle lives here
+    fn path(&self, relative: &str) -> PathBuf {
+        match relative {
+            CONFIG_ENTRY => self.config.to_path_buf(),
+            _ => self.data_dir.join(relative),
+        }
+    }
+}
+
+/// `sync push` / `sync pull` against --remote
+pub async fn run(
+    data_dir: &Path,
+    config: &Path,
+    action: &SyncAction,
+    remote: Option<&Remote>,
+) -> Result<()> {
+    tls::warn_if_insecure();
+    let Some(remote) = remote else {
+        bail!("Name the bundle's place with --remote or SCAVENGER_SYNC_REMOTE");
+    };
+    let local = Local { data_dir, config };
+    match action {
+        SyncAction::Push => push(&local, remote).await,
+        SyncAction::Pull => pull(remote, &local).await,
+    }
+}
+
+/// Merge this machine's state into the remote bundle, creating it on the
+/// first push. The write only lands if the bundle is still the one merged
+/// into; when another machine pushed in between, merge again on top of it.
+async fn push(local: &Local<'_>, remote: &Remote) -> Result<()> {
+    let mut existing = remote.get_file(BUNDLE_FILE).await?;
+    let passphrase = passphrase(existing.is_none())?;
+    for _ in 0..PUSH_TRIES {
+        let mut files = match &existing {
+            Some(stored) => open(&stored.body, &passphrase)?,
+            None => Files::new(),
+        };
+        let added = merge(&mut files, read_local(local)?)?;
+        let sealed = seal(&files, &passphrase)?;
+        if remote.put_file(BUNDLE_FILE, sealed, existing.as_ref()).await? {
+            say!(
+                "🔒 Pushed {} file(s) to {}, {} record(s) new from this machine",
+                files.len(),
+                remote,
+                added
+            );
+            return Ok(());
+        }
+        say!("🔁 Another machine pushed to {} meanwhile; merging again", remote);
+        existing = remote.get_file(BUNDLE_FILE).await?;
+    }
+    bail!("{} kept changing under this push; try again", remote)
+}
+
+/// Merge the remote bundle into the data dir (and the settings file)
+async fn pull(remote: &Remote, local: &Local<'_>) -> Result<()> {
+    let Some(stored) = remote.get_file(BUNDLE_FILE).await? else {
+        bail!("{} holds no bundle yet; run `sync push` on the other machine first", remote);
+    };
+    let theirs = open(&stored.body, &passphrase(false)?)?;
+    let before = read_local(local)?;
+    let mut files = before.clone();
+    let added = merge(&mut files, theirs)?;
+    for (relative, contents) in &files {
+        let old = before.get(relative);
+        if old == Some(contents) {
+            continue;
+        }
+        let path = local.path(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let written = match old {
+            // Merged logs only grow, and appending is safe beside a running miner
+            Some(old) if relative.ends_with(".jsonl") => fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(&contents[old.len()..])),
+            _ => {
+                let tmp = path.with_extension("sync.tmp");
+                fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, &path))
+            }
+        };
+        written.with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    say!("🔓 Pulled {} new record(s) from {} into {}", added, remote, local.data_dir.display());
+    Ok(())
+}
+
+/// From SCAVENGER_SYNC_PASSPHRASE, else typed in without echo (twice for a
+/// new bundle, whose passphrase cannot be recovered)
+fn passphrase(new: bool) -> Result<Zeroizing<String>> {
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => Zeroizing::new(passphrase),
+        Err(_) => {
+            say!("\n🔑 Sync passphrase (not shown; the same on every machine):");
+            let passphrase = read_line()?;
+            if new {
+                say!("🔑 Once more, to be sure - it cannot be recovered:");
+                if *read_line()? != *passphrase {
+                    bail!("The passphrases differ");
+                }
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        bail!("The passphrase is empty");
+    }
+    if new && passphrase.chars().count() < MIN_PASSPHRASE {
+        bail!("A new bundle needs a passphrase of at least {} characters", MIN_PASSPHRASE);
+    }
+    Ok(passphrase)
+}
+
+fn read_line() -> Result<Zeroizing<String>> {
+    let line = hdwallet::read_hidden().context("Cannot read the passphrase")?;
+    Ok(Zeroizing::new(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+fn read_local(local: &Local) -> Result<Files> {
+    let mut files = Files::new();
+    let mut found = sync::local_files(local.data_dir, BUNDLED)?;
+    if local.config.is_file() {
+        found.push((CONFIG_ENTRY.to_string(), local.config.to_path_buf()));
+    }
+    for (relative, path) in found {
+        let contents =
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        files.insert(relative, contents);
+    }
+    Ok(files)
+}
+
+/// Fold `theirs` into `ours`; how many lines, labels and files were added
+fn merge(ours: &mut Files, theirs: Files) -> Result<usize> {
+    let mut added = 0;
+    for (relative, contents) in theirs {
+        let Some(mine) = ours.get_mut(&relative) else {
+            ours.insert(relative, contents);
+            added += 1;
+            continue;
+        };
+        if relative == addressbook::BOOK_FILE {
+            let mut entries: Vec<Entry> =
+                serde_json::from_slice(mine).context("Cannot parse the local address book")?;
+            let theirs: Vec<Entry> =
+                serde_json::from_slice(&contents).context("Cannot parse the bundled address book")?;
+            let labels = addressbook::merge(&mut entries, theirs);
+            if labels > 0 {
+                *mine = serde_json::to_string_pretty(&entries)?.into_bytes();
+                added += labels;
+            }
+        } else if relative.ends_with(".jsonl") {
+            added += merge_lines(mine, &contents);
+        }
+        // Anything else (a receipt, the settings) is the same on both sides
+        // or ours stands
+    }
+    Ok(added)
+}
+
+/// Append the lines of `theirs` that `mine` lacks, in their order
+fn merge_lines(mine: &mut Vec<u8>, theirs: &[u8]) -> usize {
+    let mut known: HashSet<Vec<u8>> = mine.split(|&b| b == b'\n').map(<[u8]>::to_vec).collect();
+    let mut added = 0;
+    for line in theirs.split(|&b| b == b'\n') {
+        if line.is_empty() || !known.insert(line.to_vec()) {
+            continue;
+        }
+        if !mine.is_empty() && !mine.ends_with(b"\n") {
+            mine.push(b'\n');
+        }
+        mine.extend_from_slice(line);
+        mine.push(b'\n');
+        added += 1;
+    }
+    added
+}
+
+/// Per file: path length (u16), path, contents length (u64), contents
+fn pack(files: &Files) -> Zeroizing<Vec<u8>> {
+    let mut packed = Zeroizing::new(Vec::new());
+    for (relative, contents) in files {
+        packed.extend((relative.len() as u16).to_be_bytes());
+        packed.extend(relative.as_bytes());
+        packed.extend((contents.len() as u64).to_be_bytes());
+        packed.extend(contents);
+    }
+    packed
+}
+
+fn unpack(mut packed: &[u8]) -> Result<Files> {
+    fn take<'a>(packed: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+        if packed.len() < n {
+            bail!("The bundle is cut short");
+        }
+        let (head, rest) = packed.split_at(n);
+        *packed = rest;
+        Ok(head)
+    }
+
+    let mut files = Files::new();
+    while !packed.is_empty() {
+        let len = u16::from_be_bytes(take(&mut packed, 2)?.try_into().expect("2 bytes"));
+        let relative = String::from_utf8(take(&mut packed, len as usize)?.to_vec())
+            .context("The bundle names a file in something other than UTF-8")?;
+        if relative != CONFIG_ENTRY && !sync::is_synced(&relative, BUNDLED) {
+            bail!("The bundle holds {}, which is not miner state", relative);
+        }
+        let len = u64::from_be_bytes(take(&mut packed, 8)?.try_into().expect("8 bytes"));
+        let len = usize::try_from(len).context("The bundle is cut short")?;
+        files.insert(relative, take(&mut packed, len)?.to_vec());
+    }
+    Ok(files)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| anyhow!("Cannot derive the key: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_slice())))
+}
+
+/// MAGIC, salt and nonce, then the packed files encrypted; the header is
+/// authenticated with them
+fn seal(files: &Files, passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut salt)
+        .and_then(|_| getrandom::getrandom(&mut nonce))
+        .map_err(|e| anyhow!("No randomness for the bundle: {}", e))?;
+    let mut sealed = MAGIC.to_vec();
+    sealed.extend(salt);
+    sealed.extend(nonce);
+    let payload = Payload { msg: &pack(files), aad: &sealed };
+    let encrypted = cipher(passphrase, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), payload)
+        .map_err(|_| anyhow!("Cannot encrypt the bundle"))?;
+    sealed.extend(encrypted);
+    Ok(sealed)
+}
+
+fn open(sealed: &[u8], passphrase: &str) -> Result<Files> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if sealed.len() < header || !sealed.starts_with(MAGIC) {
+        bail!("The remote {} is not a sync bundle", BUNDLE_FILE);
+    }
+    let (head, encrypted) = sealed.split_at(header);
+    let (salt, nonce) = head[MAGIC.len()..].split_at(SALT_LEN);
+    let payload = Payload { msg: encrypted, aad: head };
+    let packed = cipher(passphrase, salt)?
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| anyhow!("Cannot open the bundle: wrong passphrase, or it was tampered with"))?;
+    unpack(&Zeroizing::new(packed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery";
+
+    fn files(entries: &[(&str, &str)]) -> Files {
+        entries
+            .iter()
+            .map(|(name, contents)| (name.to_string(), contents.as_bytes().to_vec()))
+            .collect()
+    }
+
+    fn sample() -> Files {
+        files(&[
+            ("history.jsonl", "{\"a\":1}\n{\"a\":2}\n"),
+            ("receipts/D01.json", "{\"receipt\":true}"),
+        ])
+    }
+
+    #[test]
+    fn sealed_bundle_opens_to_the_same_files() {
+        let sealed = seal(&sample(), PASSPHRASE).unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(open(&sealed, PASSPHRASE).unwrap(), sample());
+    }
+
+    #[test]
+    fn wrong_passphrase_is_refused() {
+        let sealed = seal(&sample(), PASSPHRASE).unwrap();
+        let error = open(&sealed, "correct horse battery!").unwrap_err();
+        assert!(error.to_string().contains("wrong passphrase"), "{}", error);
+    }
+
+    #[test]
+    fn tampering_is_refused() {
+        let sealed = seal(&sample(), PASSPHRASE).unwrap();
+        let mut body = sealed.clone();
+        *body.last_mut().unwrap() ^= 1;
+        assert!(open(&body, PASSPHRASE).is_err());
+        // The nonce is part of the authenticated header
+        let mut header = sealed.clone();
+        header[MAGIC.len() + SALT_LEN] ^= 1;
+        assert!(open(&header, PASSPHRASE).is_err());
+        let mut truncated = sealed;
+        truncated.truncate(MAGIC.len() + SALT_LEN + NONCE_LEN + 4);
+        assert!(open(&truncated, PASSPHRASE).is_err());
+        assert!(open(b"not a bundle", PASSPHRASE).is_err());
+    }
+
+    #[test]
+    fn merge_adds_only_what_is_missing() {
+        let mut ours = files(&[
+            ("history.jsonl", "{\"a\":1}\n{\"a\":2}"),
+            ("receipts/D01.json", "ours"),
+        ]);
+        let theirs = files(&[
+            ("history.jsonl", "{\"a\":2}\n{\"a\":3}\n{\"a\":1}\n"),
+            ("receipts/D01.json", "theirs"),
+            ("receipts/D02.json", "theirs"),
+        ]);
+        assert_eq!(merge(&mut ours, theirs.clone()).unwrap(), 2);
+        assert_eq!(ours["history.jsonl"], b"{\"a\":1}\n{\"a\":2}\n{\"a\":3}\n");
+        assert_eq!(ours["receipts/D01.json"], b"ours");
+        assert_eq!(ours["receipts/D02.json"], b"theirs");
+        // Merging the same thing again changes nothing
+        let before = ours.clone();
+        assert_eq!(merge(&mut ours, theirs).unwrap(), 0);
+        assert_eq!(ours, before);
+    }
+
+    #[test]
+    fn merge_unions_address_labels() {
+        let book = |entries: &[Entry]| serde_json::to_vec(entries).unwrap();
+        let entry = |label: &str, address: &str| Entry {
+            label: label.to_string(),
+            address: address.to_string(),
+            added_at: "2025-11-01T00:00:00Z".to_string(),
+        };
+        let mut ours = Files::new();
+        ours.insert(addressbook::BOOK_FILE.to_string(), book(&[entry("laptop", "addr1a")]));
+        let mut theirs = Files::new();
+        theirs.insert(
+            addressbook::BOOK_FILE.to_string(),
+            book(&[entry("laptop", "addr1c"), entry("rig", "addr1b")]),
+        );
+        assert_eq!(merge(&mut ours, theirs).unwrap(), 1);
+        let merged: Vec<Entry> = serde_json::from_slice(&ours[addressbook::BOOK_FILE]).unwrap();
+        let labels: Vec<_> =
+            merged.iter().map(|e| (e.label.as_str(), e.address.as_str())).collect();
+        // A label on both sides keeps ours
+        assert_eq!(labels, [("laptop", "addr1a"), ("rig", "addr1b")]);
+    }
+
+    #[test]
+    fn bundle_holds_only_miner_state() {
+        let packed = pack(&files(&[("../.ssh/authorized_keys", "key")]));
+        assert!(unpack(&packed).is_err());
+        assert_eq!(unpack(&pack(&sample())).unwrap(), sample());
+    }
+
+    #[test]
+    fn settings_travel_but_never_overwrite() {
+        let dir = std::env::temp_dir().join(format!("scavenger-settings-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("data")).unwrap();
+        fs::write(dir.join("data/history.jsonl"), "{\"a\":1}\n").unwrap();
+        let config = dir.join("elsewhere.toml");
+        let local = Local { data_dir: &dir.join("data"), config: &config };
+
+        // No settings file here: nothing to carry
+        assert!(!read_local(&local).unwrap().contains_key(CONFIG_ENTRY));
+        fs::write(&config, "profile = \"sbc\"\n").unwrap();
+        let ours = read_local(&local).unwrap();
+        assert_eq!(ours[CONFIG_ENTRY], b"profile = \"sbc\"\n");
+        assert_eq!(local.path(CONFIG_ENTRY), config);
+        assert_eq!(local.path("history.jsonl"), dir.join("data/history.jsonl"));
+        // It survives the bundle, and is the only file outside the data dir
+        assert_eq!(unpack(&pack(&ours)).unwrap(), ours);
+        assert!(unpack(&pack(&files(&[("elsewhere.toml", "")]))).is_err());
+
+        // A machine without settings gets them; one with its own keeps them
+        let mut bare = Files::new();
+        assert_eq!(merge(&mut bare, ours.clone()).unwrap(), 2);
+        assert_eq!(bare[CONFIG_ENTRY], ours[CONFIG_ENTRY]);
+        let mut configured = files(&[(CONFIG_ENTRY, "threads = 8\n")]);
+        merge(&mut configured, ours).unwrap();
+        assert_eq!(configured[CONFIG_ENTRY], b"threads = 8\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn stale_push_is_not_written() {
+        let dir = std::env::temp_dir().join(format!("scavenger-bundle-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let remote = Remote::Dir(dir.clone());
+        assert!(remote.put_file(BUNDLE_FILE, b"first".to_vec(), None).await.unwrap());
+        // Creating it again is a lost race too
+        assert!(!remote.put_file(BUNDLE_FILE, b"again".to_vec(), None).await.unwrap());
+
+        let read = remote.get_file(BUNDLE_FILE).await.unwrap().unwrap();
+        assert!(remote.put_file(BUNDLE_FILE, b"second".to_vec(), Some(&read)).await.unwrap());
+        // `read` is now out of date
+        assert!(!remote.put_file(BUNDLE_FILE, b"third".to_vec(), Some(&read)).await.unwrap());
+        assert_eq!(fs::read(dir.join(BUNDLE_FILE)).unwrap(), b"second");
+        assert!(!dir.join(format!("{}.lock", BUNDLE_FILE)).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
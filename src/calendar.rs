@@ -0,0 +1,139 @@
+// ==================== EVENT CALENDAR ====================
+//
+// `calendar`: the event day by day, for "how are we doing" at a glance.
+// Accepted challenges come from the solution history, missed ones from the
+// challenge archive (seen, never accepted, past latest_submission), and
+// the length of the event from the STAR rate table, which has one entry
+// per day; "today" is the day of the current challenge. Without the
+// server the calendar stops at the last day we saw and says nothing about
+// what remains.
+
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::store::{SolutionStatus, Store};
+use crate::{parse_deadline, tls, Cli, MinerApi, ScavengerAPI};
+
+#[derive(Default)]
+struct Day {
+    accepted: BTreeSet<u32>,
+    missed: BTreeSet<u32>,
+    /// Seen and still open for submissions
+    open: BTreeSet<u32>,
+}
+
+/// "#3 #7 #12", or "-" for none
+fn numbers(set: &BTreeSet<u32>) -> String {
+    if set.is_empty() {
+        return "-".to_string();
+    }
+    set.iter().map(|n| format!("#{}", n)).collect::<Vec<_>>().join(" ")
+}
+
+/// `calendar`, with the event's length and today from the server when it answers
+pub async fn run(cli: &Cli) -> Result<()> {
+    tls::warn_if_insecure();
+    let api = ScavengerAPI::new(&cli.base_url)?;
+    let rates = api.get_star_rate().await.ok();
+    let current_day = api.get_challenge().await.ok().map(|r| r.challenge.day);
+    print(&Store::open(&cli.data_dir)?, rates.as_deref(), current_day)
+}
+
+/// `rates` is the STAR table and `current_day` the current challenge's day,
+/// when the server could be asked
+pub fn print(store: &Store, rates: Option<&[u64]>, current_day: Option<u32>) -> Result<()> {
+    let solutions = store.solutions()?;
+    let challenges = store.challenges()?;
+
+    let mut days: BTreeMap<u32, Day> = BTreeMap::new();
+    for solution in solutions.iter().filter(|s| s.status == SolutionStatus::Accepted) {
+        let challenge = &solution.challenge;
+        days.entry(challenge.day).or_default().accepted.insert(challenge.challenge_number);
+    }
+    let now = Utc::now();
+    for record in &challenges {
+        let challenge = &record.challenge;
+        let day = days.entry(challenge.day).or_default();
+        if day.accepted.contains(&challenge.challenge_number) {
+            continue;
+        }
+        match parse_deadline(&challenge.latest_submission) {
+            Some(closes) if closes > now => day.open.insert(challenge.challenge_number),
+            _ => day.missed.insert(challenge.challenge_number),
+        };
+    }
+
+    let last_seen = days.keys().next_back().copied().unwrap_or(0);
+    let today = current_day.unwrap_or(last_seen).max(last_seen);
+    let event_days = rates.map_or(today, |rates| (rates.len() as u32).max(today));
+    if event_days == 0 {
+        say!("📭 Nothing to show yet: no challenges seen and no rate table from the server");
+        return Ok(());
+    }
+
+    say!("📅 Event calendar");
+    say!();
+    say!("   {:>4}  {:<6}  {:<24}  {:<24}  {:>6}", "Day", "", "Accepted", "Missed", "STAR");
+    let (mut solved_days, mut lost_days, mut accepted, mut missed) = (0, 0, 0, 0);
+    let mut earned = 0u64;
+    for number in 1..=event_days {
+        let day = days.get(&number);
+        let rate = rates.and_then(|rates| rates.get(number as usize - 1).copied());
+        let solved = day.map_or(0, |d| d.accepted.len());
+        let mark = if solved > 0 {
+            solved_days += 1;
+            "solved"
+        } else if number == today {
+            "today"
+        } else if number < today {
+            lost_days += 1;
+            "missed"
+        } else {
+            ""
+        };
+        accepted += solved;
+        missed += day.map_or(0, |d| d.missed.len());
+        let star = match rate {
+            Some(rate) if solved > 0 => {
+                earned += rate * solved as u64;
+                (rate * solved as u64).to_string()
+            }
+            _ => String::new(),
+        };
+        let (done, lost) = match day {
+            Some(day) => {
+                let mut done = numbers(&day.accepted);
+                if !day.open.is_empty() {
+                    done = format!("{} (open: {})", done, numbers(&day.open));
+                }
+                (done, numbers(&day.missed))
+            }
+            // Before we started mining, or still to come
+            None if number < today => ("-".to_string(), "not mined".to_string()),
+            None => (String::new(), String::new()),
+        };
+        let row = format!("   {:>4}  {:<6}  {:<24}  {:<24}  {:>6}", number, mark, done, lost, star);
+        say!("{}", row.trim_end());
+    }
+
+    say!();
+    say!("   Accepted: {} challenge(s) on {} of {} day(s) so far", accepted, solved_days, today);
+    say!(
+        "   Missed: {} challenge(s) seen but not accepted, {} day(s) with nothing",
+        missed,
+        lost_days
+    );
+    match rates {
+        Some(_) => {
+            say!("   Earned: {} STAR", earned);
+            say!(
+                "   Remaining: {} day(s) after today, of {}",
+                event_days.saturating_sub(today),
+                event_days
+            );
+        }
+        None => say!("   Remaining: unknown (rate table unavailable)"),
+    }
+    Ok(())
+}
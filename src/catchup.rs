@@ -0,0 +1,206 @@
+// ==================== CATCH-UP ====================
+//
+// The server only ever hands out the current challenge, but an earlier one
+// keeps taking solutions until its latest_submission. After downtime the
+// archive knows which of those we saw, never solved and could still
+// submit; at startup they are listed and, when the user agrees (or
+// --catch-up says so for daemons and headless runs), mined before the
+// current challenge: the best paid first, then whichever closes soonest.
+// Each is an ordinary round whose challenge comes from the archive instead
+// of the server. Challenges we never saw cannot be recovered this way.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::round::{self, RoundSettings};
+use crate::store::{ChallengeRecord, Store};
+use crate::{console, parse_deadline, timefmt, ChallengeResponse, MinerApi, SolutionResponse};
+
+/// Less time than this left is not worth a ROM build
+const MIN_TIME_LEFT: Duration = Duration::from_secs(10 * 60);
+
+pub struct Missed {
+    record: ChallengeRecord,
+    closes: DateTime<Utc>,
+    /// STAR for a solution, when the rate table is known
+    reward: Option<u64>,
+}
+
+/// The archived challenge as the server served it
+fn response(record: &ChallengeRecord) -> Result<ChallengeResponse> {
+    Ok(serde_json::from_value(serde_json::json!({
+        "code": record.code,
+        "challenge": record.challenge,
+        "mining_period_ends": record.mining_period_ends,
+    }))?)
+}
+
+/// Archived challenges other than `current_id` that this address could
+/// still solve and submit, in the order to mine them
+fn missed(
+    store: &Store,
+    settings: &RoundSettings,
+    current_id: &str,
+    rates: Option<&[u64]>,
+) -> Result<Vec<Missed>> {
+    let cutoff = Utc::now() + chrono::Duration::from_std(MIN_TIME_LEFT)?;
+    let mut missed = Vec::new();
+    for record in store.challenges()? {
+        if record.challenge.challenge_id == current_id {
+            continue;
+        }
+        let Some(closes) = parse_deadline(&record.challenge.latest_submission) else {
+            continue;
+        };
+        // Past the mining period a round would skip it anyway
+        let ends = parse_deadline(&record.mining_period_ends).unwrap_or(closes);
+        let closes = closes.min(ends);
+        if closes < cutoff || round::payee(store, settings, &response(&record)?).is_err() {
+            continue;
+        }
+        let day = record.challenge.day as usize;
+        let reward = rates.and_then(|rates| rates.get(day.checked_sub(1)?).copied());
+        missed.push(Missed { record, closes, reward });
+    }
+    missed.sort_by_key(|m| (std::cmp::Reverse(m.reward), m.closes));
+    Ok(missed)
+}
+
+/// List what can still be caught up on and decide whether to: by asking
+/// when `interactive`, else as --catch-up (`auto`) says
+pub async fn offer<A: MinerApi>(
+    api: &A,
+    store: &Store,
+    settings: &RoundSettings,
+    interactive: bool,
+    auto: bool,
+) -> Vec<Missed> {
+    let current = match api.get_challenge().await {
+        Ok(current) => current,
+        Err(e) => {
+            say!("⚠️  Cannot check for missed challenges: {}", e);
+            return Vec::new();
+        }
+    };
+    let rates = api.get_star_rate().await.ok();
+    let missed =
+        match missed(store, settings, &current.challenge.challenge_id, rates.as_deref()) {
+            Ok(missed) => missed,
+            Err(e) => {
+                say!("⚠️  Cannot check for missed challenges: {}", e);
+                return Vec::new();
+            }
+        };
+    if missed.is_empty() {
+        return missed;
+    }
+
+    say!("\n🕰️  {} earlier challenge(s) still take solutions:", missed.len());
+    for m in &missed {
+        let left = timefmt::humanize(m.closes - Utc::now());
+        let reward = m.reward.map_or(String::new(), |star| format!(", {} STAR", star));
+        say!(
+            "   {} (day {}, #{}) closes in {}{}",
+            m.record.challenge.challenge_id,
+            m.record.challenge.day,
+            m.record.challenge.challenge_number,
+            left,
+            reward
+        );
+    }
+    say!("   Each needs its own ROM build before the current challenge gets one");
+    let yes = if interactive {
+        say!("\nMine them first? (y/n)");
+        let input = console::read_line(None).ok().flatten().unwrap_or_default();
+        input.trim().eq_ignore_ascii_case("y")
+    } else {
+        auto
+    };
+    if !yes {
+        if !interactive {
+            say!("   Pass --catch-up to mine them before the current challenge");
+        }
+        return Vec::new();
+    }
+    missed
+}
+
+/// Mine each missed challenge in turn, until cancelled
+pub async fn mine<A: MinerApi>(
+    api: &A,
+    store: &Store,
+    settings: &RoundSettings,
+    missed: Vec<Missed>,
+) {
+    for m in missed {
+        if settings.cancel.is_cancelled() {
+            return;
+        }
+        let id = m.record.challenge.challenge_id.clone();
+        let pinned = match response(&m.record) {
+            Ok(response) => Pinned { api, response: Mutex::new(Some(response)) },
+            Err(e) => {
+                say!("⚠️  Cannot catch up on {}: {}", id, e);
+                continue;
+            }
+        };
+        say!("\n🕰️  Catching up on {}", id);
+        match round::run_round(&pinned, store, settings).await {
+            Ok(outcome) => say!("   Catch-up on {} finished: {:?}", id, outcome),
+            Err(e) => say!("⚠️  Catch-up on {} failed: {}", id, e),
+        }
+    }
+}
+
+/// The server, except that the first challenge fetched is an archived one.
+/// Later fetches (a round waiting out a challenge it skips) get the real,
+/// current one.
+struct Pinned<'a, A> {
+    api: &'a A,
+    response: Mutex<Option<ChallengeResponse>>,
+}
+
+impl<A: MinerApi> MinerApi for Pinned<'_, A> {
+    async fn get_challenge(&self) -> Result<ChallengeResponse> {
+        let pinned = self.response.lock().unwrap().take();
+        match pinned {
+            Some(response) => Ok(response),
+            None => self.api.get_challenge().await,
+        }
+    }
+
+    async fn submit_solution(&self, a: &str, c: &str, n: &str) -> Result<SolutionResponse> {
+        self.api.submit_solution(a, c, n).await
+    }
+
+    async fn get_star_rate(&self) -> Result<Vec<u64>> {
+        self.api.get_star_rate().await
+    }
+
+    async fn pause(&self, duration: Duration) {
+        self.api.pause(duration).await
+    }
+
+    fn solution_url(&self, address: &str, challenge_id: &str, nonce: &str) -> String {
+        self.api.solution_url(address, challenge_id, nonce)
+    }
+
+    async fn claim_nonces(
+        &self,
+        address: &str,
+        challenge_id: &str,
+        count: u64,
+    ) -> Result<Option<std::ops::Range<u64>>> {
+        self.api.claim_nonces(address, challenge_id, count).await
+    }
+
+    fn held_back(&self, challenge_id: &str) -> Option<String> {
+        self.api.held_back(challenge_id)
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        self.api.now()
+    }
+}
@@ -0,0 +1,127 @@
+// ==================== FAULT INJECTION ====================
+//
+// Dev builds only (--features chaos). The retry, back-off and daemon paths
+// only run when the server misbehaves, which it rarely does on demand; this
+// makes the API client misbehave instead, so a local soak run exercises
+// them for hours. Configured from the environment, e.g.
+//
+//   SCAVENGER_CHAOS="timeout=0.05,5xx=0.1,malformed=0.05,slow=0.2,delay=5s"
+//
+// Each rate is the chance per request; `delay` is how long a slow or
+// timed-out request takes (default 5s). Unset means no faults.
+
+use anyhow::Result;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::retry::Transient;
+use crate::timefmt;
+
+#[derive(Debug, Default)]
+pub struct Chaos {
+    timeout: f64,
+    server_error: f64,
+    malformed: f64,
+    slow: f64,
+    delay: Duration,
+}
+
+impl Chaos {
+    fn parse(raw: &str) -> Result<Self, String> {
+        let mut chaos = Chaos { delay: Duration::from_secs(5), ..Default::default() };
+        for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or(format!("'{}' is not key=value", part))?;
+            if key == "delay" {
+                chaos.delay = timefmt::parse_duration(value)?;
+                continue;
+            }
+            let rate: f64 = value.parse().map_err(|_| format!("'{}' is not a rate", value))?;
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!("{} rate {} is not between 0 and 1", key, rate));
+            }
+            match key {
+                "timeout" => chaos.timeout = rate,
+                "5xx" => chaos.server_error = rate,
+                "malformed" => chaos.malformed = rate,
+                "slow" => chaos.slow = rate,
+                _ => {
+                    return Err(format!(
+                        "unknown fault '{}' (timeout, 5xx, malformed, slow, delay)",
+                        key
+                    ))
+                }
+            }
+        }
+        Ok(chaos)
+    }
+}
+
+fn config() -> Option<&'static Chaos> {
+    static CHAOS: OnceLock<Option<Chaos>> = OnceLock::new();
+    CHAOS
+        .get_or_init(|| {
+            let raw = std::env::var("SCAVENGER_CHAOS").ok()?;
+            match Chaos::parse(&raw) {
+                Ok(chaos) => Some(chaos),
+                Err(e) => {
+                    say!("⚠️  SCAVENGER_CHAOS ignored: {}", e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Uniform in [0, 1); the std hasher keys are random enough for this
+fn roll() -> f64 {
+    (RandomState::new().build_hasher().finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Startup notice, so a chaos build is never mistaken for a broken server
+pub fn announce() {
+    if let Some(chaos) = config() {
+        say!(
+            "🐒 Chaos mode: timeout {:.0}%, 5xx {:.0}%, malformed {:.0}%, slow {:.0}% (delay {}s)",
+            chaos.timeout * 100.0,
+            chaos.server_error * 100.0,
+            chaos.malformed * 100.0,
+            chaos.slow * 100.0,
+            chaos.delay.as_secs()
+        );
+    }
+}
+
+/// Before a request: maybe wait, fail like a timeout, or stand in for the
+/// server with an error response (status, body)
+pub async fn before_request() -> Result<Option<(u16, String)>> {
+    let Some(chaos) = config() else {
+        return Ok(None);
+    };
+    if roll() < chaos.slow {
+        tokio::time::sleep(chaos.delay).await;
+    }
+    if roll() < chaos.timeout {
+        tokio::time::sleep(chaos.delay).await;
+        let timeout = "error sending request: operation timed out (chaos)".to_string();
+        return Err(Transient(timeout).into());
+    }
+    if roll() < chaos.server_error {
+        let status = [500, 502, 503, 504][(roll() * 4.0) as usize];
+        let body = "<html><body>Service Unavailable (chaos)</body></html>";
+        return Ok(Some((status, body.to_string())));
+    }
+    Ok(None)
+}
+
+/// After a response: maybe cut the body off mid-JSON
+pub fn mangle(body: String) -> String {
+    match config() {
+        Some(chaos) if roll() < chaos.malformed => {
+            let cut = (body.len() as f64 * roll()) as usize;
+            body.chars().take(cut).collect()
+        }
+        _ => body,
+    }
+}
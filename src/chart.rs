@@ -0,0 +1,47 @@
+// ==================== TERMINAL CHARTS ====================
+//
+// One-line sparklines for hash rate and difficulty in the status and
+// history commands. Block elements where the console can draw them, an
+// ASCII ramp elsewhere; no TUI needed.
+
+use crate::console;
+
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_RAMP: [char; 8] = ['_', '.', ':', '-', '=', '+', '*', '#'];
+
+/// Scale `values` between their min and max, one character each.
+/// Long series keep only the most recent `width` values.
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let ramp = if console::unicode() { &BLOCKS } else { &ASCII_RAMP };
+    let (min, max) = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    values
+        .iter()
+        .map(|&v| {
+            if !v.is_finite() {
+                ' '
+            } else if max <= min {
+                ramp[ramp.len() / 2]
+            } else {
+                let level = (v - min) / (max - min) * (ramp.len() - 1) as f64;
+                ramp[level.round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// Sparkline with its range, e.g. "▁▃▅█  (1200 .. 1850)"
+pub fn labelled(values: &[f64], width: usize, unit: &str) -> String {
+    let shown = &values[values.len().saturating_sub(width)..];
+    let (min, max) = shown
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if min > max {
+        return "(no data)".to_string();
+    }
+    format!("{}  ({:.0} .. {:.0}{})", sparkline(values, width), min, max, unit)
+}
@@ -0,0 +1,253 @@
+// ==================== CONFIG CHECK ====================
+//
+// `config check`: everything startup would trip over, found before the
+// miner is rolled out to a rack of machines. Prints the effective settings
+// with where each came from (flag > environment > default), then every
+// problem, and exits non-zero if any of them would stop or break a run.
+
+use anyhow::{bail, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory};
+use std::path::Path;
+
+use crate::configfile::ConfigFile;
+use crate::romfile::RomStorage;
+use crate::share::Share;
+use crate::{address, addressbook, limits, s3, sync, workhours, Cli, Mode, ROM_SIZE};
+
+#[derive(Default)]
+struct Findings {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Findings {
+    fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(message.into());
+    }
+
+    fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+}
+
+pub fn check(cli: &Cli, matches: &ArgMatches, file: Option<&ConfigFile>) -> Result<()> {
+    print_settings(matches, file);
+
+    let mut findings = Findings::default();
+    let daemon = cli.mode == Mode::Daemon;
+    let tuning = cli.profile.tuning();
+    let cpus = num_cpus::get();
+
+    say!();
+    say!("📱 Profile {:?}", cli.profile);
+    say!("   Threads: {} (requested {})", tuning.threads(cli.threads), cli.threads);
+    if let Some(guard) = &tuning.power_guard {
+        say!("   Pauses above {:.0}°C or below {}% battery", guard.max_temp_c, guard.min_battery);
+    }
+    say!("   Status file every {}s (daemon)", tuning.status_interval.as_secs());
+
+    let labelled = match &cli.label {
+        Some(label) => match addressbook::lookup(&cli.data_dir, label) {
+            Ok(address) => Some(address),
+            Err(e) => {
+                findings.error(format!("--label: {}", e));
+                None
+            }
+        },
+        None => None,
+    };
+    match labelled.as_deref().or(cli.address.as_deref()).map(str::trim) {
+        Some(raw) => match address::resolve(raw, cli.address_kind) {
+            Ok((resolved, kind)) => {
+                say!("📍 Address: {}", resolved);
+                say!("   {}", kind);
+                match Share::from_cli(cli, &resolved) {
+                    Ok(Some(share)) => share.print(),
+                    Ok(None) => {}
+                    Err(e) => findings.error(format!("--share: {}", e)),
+                }
+            }
+            Err(e) => findings.error(format!("address {}: {}", raw, e)),
+        },
+        None if cli.label.is_some() => {}
+        None if daemon => findings.error("daemon mode needs --address, --label or SCAVENGER_ADDRESS"),
+        None => findings.warn("no address set; it will be prompted for"),
+    }
+
+    if cli.threads == 0 {
+        findings.error("--threads must be at least 1");
+    } else if cli.threads > cpus {
+        findings.warn(format!("--threads {} is more than the {} CPU(s) here", cli.threads, cpus));
+    }
+    if let Some(max) = tuning.max_threads.filter(|&max| cli.threads > max) {
+        findings.warn(format!("profile {:?} caps --threads {} to {}", cli.profile, cli.threads, max));
+    }
+    if cli.sprint_threads == Some(0) {
+        findings.error("--sprint-threads must be at least 1");
+    }
+    if cli.sprint_threads.is_some() && cli.sprint_minutes.is_none() {
+        findings.warn("--sprint-threads has no effect without --sprint-minutes");
+    }
+    if cli.sprint_minutes == Some(0) {
+        findings.warn("--sprint-minutes 0 never starts a sprint");
+    }
+    match cli.work_hours {
+        Some(hours) => {
+            let threads = tuning.threads(cli.threads);
+            let work = workhours::Yield { hours, percent: cli.work_yield };
+            let yielded = work.threads(threads);
+            say!("   Work hours {}: {} of {} threads step aside", hours, yielded, threads);
+            if yielded == 0 {
+                findings.warn(format!(
+                    "--work-yield {}% of {} thread(s) rounds to none; work hours change nothing",
+                    cli.work_yield, threads
+                ));
+            }
+        }
+        None if matches.value_source("work_yield") != Some(ValueSource::DefaultValue) => {
+            findings.warn("--work-yield has no effect without --work-hours");
+        }
+        None => {}
+    }
+    if cli.max_hashes == Some(0) {
+        findings.error("--max-hashes 0 would never hash anything");
+    }
+    if cli.max_duration.is_some_and(|d| d.is_zero()) {
+        findings.error("--max-duration 0 would stop before starting");
+    }
+    if daemon && cli.confirm {
+        findings.warn("--confirm needs a terminal and is ignored in daemon mode");
+    } else if cli.headless && cli.confirm {
+        findings.warn("--confirm reads stdin and is ignored with --headless");
+    }
+    if daemon && cli.keep_mining {
+        findings.warn("--loop has no effect in daemon mode, which always loops");
+    }
+    if cli.insecure {
+        findings.warn("--insecure: TLS verification is off, the server's identity is not checked");
+    }
+
+    check_data_dir(&cli.data_dir, &mut findings);
+    if let Some(remote) = &cli.state_remote {
+        check_remote(remote, &mut findings);
+    }
+    if let Some(recording) = &cli.replay {
+        if !recording.is_file() {
+            findings.error(format!("--replay {} does not exist", recording.display()));
+        }
+    }
+
+    let rom_size = cli.rom_size.unwrap_or(ROM_SIZE);
+    if rom_size != ROM_SIZE {
+        let message = format!(
+            "--rom-size {} is not the protocol ROM; solutions will not be submitted",
+            limits::size(rom_size as u64)
+        );
+        if cli.breaks_submissions_ack {
+            findings.warn(message);
+        } else {
+            findings.error(message + " (needs --i-know-this-breaks-submissions)");
+        }
+    }
+
+    let limits = limits::detect();
+    let on_disk = cli.rom_storage == RomStorage::Mmap;
+    if let Some(cap) = limits::shortfall(&limits, rom_size, on_disk) {
+        let message = format!(
+            "memory is limited to {} but the ROM needs about {}",
+            limits::size(cap),
+            limits::size(limits::rom_requirement(rom_size))
+        );
+        if cli.ignore_limits {
+            findings.warn(message + " (--ignore-limits)");
+        } else {
+            findings.error(message);
+        }
+    }
+
+    say!();
+    for warning in &findings.warnings {
+        say!("⚠️  {}", warning);
+    }
+    for error in &findings.errors {
+        say!("❌ {}", error);
+    }
+    if !findings.errors.is_empty() {
+        bail!("{} problem(s) in the configuration", findings.errors.len());
+    }
+    say!("✅ Configuration OK ({} warning(s))", findings.warnings.len());
+    Ok(())
+}
+
+/// Every option with its effective value and where it came from
+fn print_settings(matches: &ArgMatches, file: Option<&ConfigFile>) {
+    say!("⚙️  Effective configuration (flag > env > config file > default)");
+    if let Some(file) = file {
+        say!("   Config file: {}", file.path.display());
+    }
+    for arg in Cli::command().get_arguments() {
+        let id = arg.get_id().as_str();
+        if matches!(id, "help" | "version") {
+            continue;
+        }
+        let value = matches
+            .get_raw(id)
+            .map(|values| {
+                values.map(|v| v.to_string_lossy().into_owned()).collect::<Vec<_>>().join(",")
+            })
+            .unwrap_or_else(|| "-".to_string());
+        let source = match matches.value_source(id) {
+            Some(ValueSource::CommandLine) => "flag".to_string(),
+            Some(ValueSource::EnvVariable) => {
+                arg.get_env().map_or("env".to_string(), |env| env.to_string_lossy().into_owned())
+            }
+            Some(ValueSource::DefaultValue) if file.is_some_and(|f| f.sets(id)) => {
+                "config file".to_string()
+            }
+            Some(ValueSource::DefaultValue) => "default".to_string(),
+            _ => "unset".to_string(),
+        };
+        say!("   {:<16} {:<28} ({})", id, value, source);
+    }
+}
+
+fn check_data_dir(dir: &Path, findings: &mut Findings) {
+    if !dir.exists() {
+        let parent = dir.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        if !parent.is_dir() {
+            findings.error(format!("data dir {} cannot be created: {} is missing", dir.display(), parent.display()));
+        }
+        return;
+    }
+    if !dir.is_dir() {
+        findings.error(format!("data dir {} is not a directory", dir.display()));
+        return;
+    }
+    let probe = dir.join(".config-check");
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+        }
+        Err(e) => findings.error(format!("data dir {} is not writable: {}", dir.display(), e)),
+    }
+}
+
+fn check_remote(remote: &sync::Remote, findings: &mut Findings) {
+    match remote {
+        sync::Remote::Dir(dir) => {
+            if !dir.is_dir() {
+                findings.warn(format!("state remote {} does not exist yet", dir.display()));
+            }
+        }
+        sync::Remote::S3 { bucket, .. } => {
+            if let Err(e) = s3::S3Client::from_env(bucket) {
+                findings.error(format!("state remote {}: {:#}", remote, e));
+            }
+        }
+        sync::Remote::WebDav(_) => findings.error(format!(
+            "state remote {}: a WebDAV share only takes `sync push` and `sync pull`",
+            remote
+        )),
+    }
+}
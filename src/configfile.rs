@@ -0,0 +1,202 @@
+// ==================== CONFIG FILE ====================
+//
+// Settings shared by every run on a box, in config.toml instead of a long
+// command line on each machine. Keys are the long flag names (dashes or
+// underscores) and their values become the flags' defaults, so the order
+// is flag > SCAVENGER_* environment > config file > built-in default, and
+// every value goes through the same parser as the flag would. The file is
+// read from --config / SCAVENGER_CONFIG, else ./config.toml if there is
+// one; `init` writes a commented template.
+
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::Cli;
+
+pub const DEFAULT_PATH: &str = "config.toml";
+
+pub struct ConfigFile {
+    pub path: PathBuf,
+    /// Arg id -> values, as the command line would have passed them
+    values: BTreeMap<String, Vec<String>>,
+}
+
+/// --config from the raw arguments, before clap sees them: the file
+/// supplies clap's defaults, so it has to be read first
+fn requested_path() -> Option<PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|a| a.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    std::env::var_os("SCAVENGER_CONFIG").filter(|p| !p.is_empty()).map(PathBuf::from)
+}
+
+fn scalar(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(n) => Some(n.to_string()),
+        toml::Value::Float(x) => Some(x.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// A setting as command line values; lists repeat the flag
+fn to_strings(key: &str, value: &toml::Value) -> Result<Vec<String>> {
+    let strings = match value {
+        toml::Value::Array(items) => items.iter().map(scalar).collect(),
+        value => scalar(value).map(|s| vec![s]),
+    };
+    strings.with_context(|| format!("'{}' must be a string, number, boolean or list of them", key))
+}
+
+impl ConfigFile {
+    /// The config file, if one was asked for or ./config.toml exists
+    pub fn load(command: &clap::Command) -> Result<Option<Self>> {
+        let (path, explicit) = match requested_path() {
+            Some(path) => (path, true),
+            None => (PathBuf::from(DEFAULT_PATH), false),
+        };
+        if !explicit && !path.is_file() {
+            return Ok(None);
+        }
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read config file {}", path.display()))?;
+        Self::parse(command, &path, &raw).map(Some)
+    }
+
+    pub fn parse(command: &clap::Command, path: &Path, raw: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(raw)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        let mut values = BTreeMap::new();
+        for (key, value) in &table {
+            let long = key.replace('_', "-");
+            let arg = command
+                .get_arguments()
+                .find(|arg| {
+                    arg.get_long() == Some(long.as_str())
+                        || arg.get_all_aliases().is_some_and(|a| a.contains(&long.as_str()))
+                })
+                .filter(|arg| !matches!(arg.get_id().as_str(), "config" | "help" | "version"));
+            if long == "insecure" {
+                bail!("{}: --insecure can only be given on the command line", path.display());
+            }
+            let Some(arg) = arg else {
+                bail!("{}: unknown setting '{}' (keys are the long flag names)", path.display(), key);
+            };
+            let strings = to_strings(key, value).with_context(|| path.display().to_string())?;
+            values.insert(arg.get_id().to_string(), strings);
+        }
+        Ok(ConfigFile { path: path.to_path_buf(), values })
+    }
+
+    /// The command with the file's values as defaults
+    pub fn apply(&self, mut command: clap::Command) -> clap::Command {
+        for (id, values) in &self.values {
+            command = command.mut_arg(id, |arg| arg.default_values(values.clone()));
+        }
+        command
+    }
+
+    pub fn sets(&self, id: &str) -> bool {
+        self.values.contains_key(id)
+    }
+}
+
+fn template(cli: &Cli) -> String {
+    let address = match &cli.address {
+        Some(address) => format!("address = \"{}\"", address.trim()),
+        None => "# address = \"addr1...\"".to_string(),
+    };
+    format!(
+        r#"# Scavenger miner settings for this machine. Keys are the long flag
+# names; a flag or SCAVENGER_* variable still overrides any of them.
+# `scavenger_miner config check` shows what ends up in effect.
+
+# Cardano address to mine for
+{address}
+
+# Mining threads
+threads = {threads}
+
+# Hours the machine is needed for other work (local time): a share of the
+# threads steps aside then and is taken back after
+# work_hours = "mon-fri 09:00-18:00"
+# work_yield = 50
+
+# Hashes per run before giving up on a challenge (iterations)
+# max_hashes = 1000000
+
+# interactive or daemon
+# mode = "daemon"
+
+# Where solutions, scans and receipts are kept
+# data_dir = "{data_dir}"
+
+# ROM in RAM (fastest) or in a memory-mapped file, for less RAM than 1 GB free
+# rom_storage = "mmap"
+# rom_dir = "/var/tmp"
+
+# Scavenger Mine API
+# base_url = "{base_url}"
+
+# How a hash is checked against the difficulty, should the server's rule
+# turn out not to be the digest prefix: prefix, below, zero-bits, zero-bytes
+# difficulty_rule = "prefix"
+
+# Tries per API call through outages and 5xx answers, backing off in between
+# api_retries = 5
+
+# Logging: text or json, and plain / ascii output for dumb terminals
+# log_format = "text"
+# plain = false
+# ascii = false
+
+# Mining for friends: take turns with other addresses (labels or addresses,
+# NAME=WEIGHT), per challenge or per time slice
+# share = ["alice=2", "bob"]
+# share_by = "slice"
+# share_slice = "1h"
+
+# Several rigs on one address: one coordinates, the others mine its ranges
+# coordinate = "0.0.0.0:9300"
+# coordinator = "http://rig-01:9300"
+
+# Daemons: first mine earlier challenges that still take solutions
+# catch_up = true
+
+# Prometheus metrics at http://<addr>/metrics
+# metrics_addr = "127.0.0.1:9184"
+
+# Full-screen dashboard while mining (interactive runs in a terminal)
+# tui = true
+"#,
+        address = address,
+        threads = cli.threads,
+        data_dir = cli.data_dir.display(),
+        base_url = cli.base_url,
+    )
+}
+
+/// `init`: write a template config file
+pub fn init(cli: &Cli, force: bool) -> Result<()> {
+    let path = &cli.config;
+    if path.exists() && !force {
+        bail!("{} already exists (--force to overwrite)", path.display());
+    }
+    std::fs::write(path, template(cli))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    say!("✅ Wrote {}", path.display());
+    say!("   Edit it, then `scavenger_miner config check` to see the result");
+    Ok(())
+}
+
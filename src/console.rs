@@ -0,0 +1,424 @@
+// ==================== CONSOLE ====================
+//
+// All human-facing output goes through here (via `say!`). Legacy Windows
+// consoles garble emoji and box drawing, and a progress line wider than
+// the terminal wraps on every refresh, so we detect what the terminal can
+// do once and adapt. Plain mode (--plain) goes further for screen readers
+// and log files: no symbols, no box art, no carriage-return rewriting.
+// JSON mode (containers) is plain output wrapped as one object per line.
+// A command whose result goes to stdout as JSON (`mine --json`) sends all
+// of this to stderr instead, so the result can be piped as is.
+// While the dashboard (--tui) owns the screen, lines are captured for its
+// log pane instead of printed. During a round every line is also appended,
+// timestamped, to that challenge's own log file (see `Segment`).
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+struct Console {
+    /// Emoji and box drawing render correctly
+    unicode: bool,
+    /// ANSI escape sequences are understood
+    ansi: bool,
+    /// Sequential, undecorated output
+    plain: bool,
+    /// Plain output as JSON lines for log collectors
+    json: bool,
+}
+
+static CONSOLE: OnceLock<Console> = OnceLock::new();
+
+// A progress line is on screen and not yet terminated
+static PROGRESS_ACTIVE: AtomicBool = AtomicBool::new(false);
+static PROGRESS_LEN: AtomicUsize = AtomicUsize::new(0);
+
+// Plain mode emits progress as ordinary lines, so keep them sparse
+static LAST_PLAIN_PROGRESS: Mutex<Option<Instant>> = Mutex::new(None);
+const PLAIN_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+const BANNER_WIDTH: usize = 62;
+
+/// stdout is reserved for a machine-readable result
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// println! to wherever human output goes
+macro_rules! emit {
+    ($($arg:tt)*) => {
+        if TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// The current challenge's log file
+static SEGMENT: Mutex<Option<File>> = Mutex::new(None);
+
+/// Copies every line to a per-challenge log file until dropped, so a
+/// post-mortem of one challenge needn't dig through weeks of output
+pub struct Segment(());
+
+impl Segment {
+    pub fn open(path: &Path) -> io::Result<Segment> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        *SEGMENT.lock().unwrap() = Some(file);
+        Ok(Segment(()))
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        *SEGMENT.lock().unwrap() = None;
+    }
+}
+
+/// Append to the challenge log, one timestamped entry per non-empty line
+fn segment_line(text: &str) {
+    let mut segment = SEGMENT.lock().unwrap();
+    let Some(file) = segment.as_mut() else {
+        return;
+    };
+    let ts = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f %:z");
+    for text in text.lines().map(str::trim_end).filter(|l| !l.trim().is_empty()) {
+        // A full disk must not stop the miner; the main output still has it
+        let _ = writeln!(file, "{} {}", ts, text);
+    }
+}
+
+/// Lines held while the dashboard is up, and how many it has been given
+static CAPTURED: Mutex<Option<(Vec<String>, usize)>> = Mutex::new(None);
+
+/// Hold every line from now on instead of printing it
+#[cfg(feature = "tui")]
+pub fn begin_capture() {
+    end_progress();
+    *CAPTURED.lock().unwrap() = Some((Vec::new(), 0));
+}
+
+/// Lines captured since the last call
+#[cfg(feature = "tui")]
+pub fn take_captured() -> Vec<String> {
+    match CAPTURED.lock().unwrap().as_mut() {
+        Some((held, given)) => {
+            let new = held[*given..].to_vec();
+            *given = held.len();
+            new
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Print again, starting with everything held, so the scrollback has it
+#[cfg(feature = "tui")]
+pub fn end_capture() {
+    let held = CAPTURED.lock().unwrap().take();
+    for text in held.into_iter().flat_map(|(held, _)| held) {
+        emit!("{}", text);
+    }
+}
+
+/// Send human output to stderr from now on, leaving stdout to the result
+pub fn use_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Detect capabilities; call once at startup before printing
+pub fn init(force_ascii: bool, plain: bool, json: bool) {
+    let plain = plain || json;
+    let ansi = !plain && enable_ansi();
+    let unicode = !force_ascii && !plain && detect_unicode();
+    let _ = CONSOLE.set(Console { unicode, ansi, plain, json });
+}
+
+fn get() -> &'static Console {
+    CONSOLE.get_or_init(|| Console {
+        unicode: detect_unicode(),
+        ansi: enable_ansi(),
+        plain: false,
+        json: false,
+    })
+}
+
+#[cfg(windows)]
+fn enable_ansi() -> bool {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, SetConsoleOutputCP,
+        ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_OUTPUT_HANDLE,
+    };
+
+    const CP_UTF8: u32 = 65001;
+    // SAFETY: plain Win32 calls on the process' own stdout handle
+    unsafe {
+        SetConsoleOutputCP(CP_UTF8);
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            return false;
+        }
+        SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
+
+#[cfg(not(windows))]
+fn enable_ansi() -> bool {
+    std::env::var("TERM").map_or(true, |term| term != "dumb")
+}
+
+#[cfg(windows)]
+fn detect_unicode() -> bool {
+    // conhost can't draw emoji even with the UTF-8 code page; modern hosts announce themselves
+    ["WT_SESSION", "TERM_PROGRAM", "ConEmuANSI"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some())
+}
+
+#[cfg(not(windows))]
+fn detect_unicode() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+/// Block elements and emoji can be drawn
+pub fn unicode() -> bool {
+    get().unicode
+}
+
+/// Terminal width, re-read on every call so resizes are picked up
+pub fn width() -> usize {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(w), _)| w as usize)
+        .unwrap_or(80)
+}
+
+/// ASCII stand-ins for the symbols we print
+fn ascii_symbol(c: char) -> Option<&'static str> {
+    Some(match c {
+        '✅' => "[OK]",
+        '❌' => "[FAIL]",
+        '⚠' => "[WARN]",
+        '⏭' => ">>",
+        '⭐' => "*",
+        '🎉' => "*",
+        '⛏' => "[MINE]",
+        '🧪' => "[DRY RUN]",
+        '💾' => "[SAVED]",
+        '🔬' => "[REPORT]",
+        '🏁' => "[SPRINT]",
+        '⏰' | '⏱' => "[TIME]",
+        '🟢' => "[ON]",
+        '🔴' => "[OFF]",
+        '🕒' => "[SOON]",
+        '🛠' => "[MAINT]",
+        '🌡' => "[HOT]",
+        '▶' => ">",
+        '📱' => "[PROFILE]",
+        '❔' => "[?]",
+        '═' => "=",
+        '─' => "-",
+        '║' | '│' => "|",
+        '╔' | '╗' | '╚' | '╝' => "+",
+        _ => return None,
+    })
+}
+
+/// Emoji, dingbats and invisible joiners that legacy consoles can't render
+fn is_decorative(c: char) -> bool {
+    matches!(c as u32, 0x2190..=0x2BFF | 0x1F000..=0x1FAFF | 0xFE0F | 0x200D)
+}
+
+/// Rewrite a line for the current console
+pub fn adapt(text: &str) -> String {
+    if get().unicode {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut dropped = false;
+    for c in text.chars() {
+        if let Some(replacement) = ascii_symbol(c) {
+            out.push_str(replacement);
+        } else if is_decorative(c) {
+            dropped = true;
+            continue;
+        } else if !(dropped && c == ' ') {
+            // The space that separated a dropped symbol from its text goes with it
+            out.push(c);
+        }
+        dropped = false;
+    }
+    out
+}
+
+/// Approximate display width: emoji take two cells, selectors none
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0xFE0F | 0x200D => 0,
+            0x1F000..=0x1FAFF => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn end_progress() {
+    if PROGRESS_ACTIVE.swap(false, Ordering::SeqCst) {
+        emit!();
+    }
+}
+
+pub fn line(text: &str) {
+    segment_line(text);
+    if let Some((held, _)) = CAPTURED.lock().unwrap().as_mut() {
+        held.push(adapt(text));
+        return;
+    }
+    if get().json {
+        json_line(text);
+        return;
+    }
+    end_progress();
+    emit!("{}", adapt(text));
+}
+
+/// {"ts":..,"level":..,"msg":..}; the level comes from the leading symbol
+fn json_line(text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    let level = match text.chars().next() {
+        Some('❌') => "error",
+        Some('⚠') => "warn",
+        _ => "info",
+    };
+    // The level already says what the symbols did; separators vanish entirely
+    let msg: String = text
+        .chars()
+        .filter(|&c| !is_decorative(c) && ascii_symbol(c).is_none())
+        .collect();
+    let msg = msg.trim();
+    if msg.is_empty() {
+        return;
+    }
+    let mut record = serde_json::json!({
+        "ts": chrono::Utc::now().to_rfc3339(),
+        "level": level,
+        "msg": msg,
+    });
+    if let Some(id) = crate::machine::id() {
+        record["machine_id"] = id.into();
+    }
+    emit!("{}", record);
+}
+
+/// Boxed section header
+pub fn banner(lines: &[&str]) {
+    if get().plain {
+        for text in lines {
+            line(&format!("== {} ==", adapt(text).trim()));
+        }
+        return;
+    }
+    let horizontal = "═".repeat(BANNER_WIDTH);
+    line(&format!("╔{}╗", horizontal));
+    for text in lines {
+        let text = adapt(text);
+        let pad = BANNER_WIDTH.saturating_sub(display_width(&text) + 2);
+        line(&format!("║  {}{}║", text, " ".repeat(pad)));
+    }
+    line(&format!("╚{}╝", horizontal));
+}
+
+/// Rewrite the single status line in place, never wider than the terminal
+pub fn progress(text: &str) {
+    // The dashboard draws its own
+    if CAPTURED.lock().unwrap().is_some() {
+        return;
+    }
+    if get().plain {
+        let mut last = LAST_PLAIN_PROGRESS.lock().unwrap();
+        if last.is_some_and(|t| t.elapsed() < PLAIN_PROGRESS_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+        line(text.trim());
+        return;
+    }
+    let text = adapt(text);
+    let max = width().saturating_sub(1);
+    let text: String = if display_width(&text) > max {
+        let mut used = 0;
+        text.chars()
+            .take_while(|c| {
+                used += display_width(c.encode_utf8(&mut [0; 4]));
+                used <= max
+            })
+            .collect()
+    } else {
+        text
+    };
+
+    let len = display_width(&text);
+    let previous = PROGRESS_LEN.swap(len, Ordering::SeqCst);
+    let mut out: Box<dyn Write> = if TO_STDERR.load(Ordering::Relaxed) {
+        Box::new(io::stderr().lock())
+    } else {
+        Box::new(io::stdout().lock())
+    };
+    if get().ansi {
+        let _ = write!(out, "\r{}\x1b[K", text);
+    } else {
+        // Blank out leftovers of a longer previous line
+        let _ = write!(out, "\r{}{}", text, " ".repeat(previous.saturating_sub(len)));
+    }
+    let _ = out.flush();
+    PROGRESS_ACTIVE.store(true, Ordering::SeqCst);
+}
+
+// One thread reads stdin for the rest of the run and queues the lines, so
+// a prompt that gives up waiting leaves no read behind to take the answer
+// meant for the next one
+static STDIN_LINES: OnceLock<Mutex<mpsc::Receiver<io::Result<String>>>> = OnceLock::new();
+
+/// The next line typed, including its newline, waiting at most `timeout`
+/// (None: as long as it takes); Ok(None) on timeout, an empty line at EOF
+pub fn read_line(timeout: Option<Duration>) -> io::Result<Option<String>> {
+    let lines = STDIN_LINES.get_or_init(|| {
+        let (lines, received) = mpsc::channel();
+        let reader = move || loop {
+            let mut line = String::new();
+            let read = io::stdin().read_line(&mut line);
+            let done = !matches!(read, Ok(n) if n > 0);
+            if lines.send(read.map(|_| line)).is_err() || done {
+                return;
+            }
+        };
+        if let Err(e) = std::thread::Builder::new().name("stdin".into()).spawn(reader) {
+            say!("⚠️  Cannot read the keyboard: {}", e);
+        }
+        Mutex::new(received)
+    });
+    let lines = lines.lock().unwrap_or_else(|e| e.into_inner());
+    let next = match timeout {
+        Some(timeout) => lines.recv_timeout(timeout),
+        None => lines.recv().map_err(mpsc::RecvTimeoutError::from),
+    };
+    match next {
+        Ok(line) => line.map(Some),
+        Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+        // Past EOF
+        Err(mpsc::RecvTimeoutError::Disconnected) => Ok(Some(String::new())),
+    }
+}
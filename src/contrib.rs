@@ -0,0 +1,187 @@
+// ==================== CONTRIBUTIONS ====================
+//
+// Groups that mine one address from several machines split rewards
+// off-chain by work done. Each machine records its --worker name with the
+// ranges it scans; for every accepted solution the hashes each worker put
+// into that challenge are summed into a contribution report, and a split
+// policy turns the report into a payout suggestion.
+
+use anyhow::Result;
+use chrono::Utc;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::store::{self, ScanRecord, SolutionRecord, SolutionStatus, Store};
+
+/// Scans recorded without --worker or a machine ID
+pub const UNNAMED: &str = "(unnamed)";
+
+#[derive(Debug, Serialize)]
+pub struct WorkerShare {
+    pub worker: String,
+    pub hashes: u64,
+    /// Fraction of the challenge's hashes, 0.0..=1.0
+    pub share: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Contribution {
+    pub challenge_id: String,
+    pub address: String,
+    pub nonce: String,
+    pub total_hashes: u64,
+    pub workers: Vec<WorkerShare>,
+}
+
+/// Scans of the challenge `solution` solved, oldest first
+fn solution_scans<'a>(
+    solution: &'a SolutionRecord,
+    scans: &'a [ScanRecord],
+) -> impl DoubleEndedIterator<Item = &'a ScanRecord> {
+    scans.iter().filter(|s| {
+        s.challenge_id == solution.challenge.challenge_id && s.address == solution.address
+    })
+}
+
+fn hashes_per_worker<'a>(scans: impl Iterator<Item = &'a ScanRecord>) -> BTreeMap<&'a str, u64> {
+    let mut per_worker: BTreeMap<&str, u64> = BTreeMap::new();
+    for scan in scans {
+        // Unnamed workers are still told apart by machine
+        let worker = scan.worker.as_deref().or(scan.machine_id.as_deref()).unwrap_or(UNNAMED);
+        *per_worker.entry(worker).or_default() += scan.count;
+    }
+    per_worker
+}
+
+/// Shares by weight; equal weights give an equal split
+fn shares(per_worker: BTreeMap<&str, u64>, weight: impl Fn(u64) -> u64) -> Vec<WorkerShare> {
+    let total: u64 = per_worker.values().map(|&h| weight(h)).sum();
+    let mut workers: Vec<WorkerShare> = per_worker
+        .into_iter()
+        .map(|(worker, hashes)| WorkerShare {
+            worker: worker.to_string(),
+            hashes,
+            share: if total == 0 { 0.0 } else { weight(hashes) as f64 / total as f64 },
+        })
+        .collect();
+    workers.sort_by_key(|w| std::cmp::Reverse(w.hashes));
+    workers
+}
+
+/// Hashes per worker that went into the challenge `solution` solved
+pub fn contribution(solution: &SolutionRecord, scans: &[ScanRecord]) -> Contribution {
+    let workers = shares(hashes_per_worker(solution_scans(solution, scans)), |h| h);
+    Contribution {
+        challenge_id: solution.challenge.challenge_id.clone(),
+        address: solution.address.clone(),
+        nonce: solution.nonce.clone(),
+        total_hashes: workers.iter().map(|w| w.hashes).sum(),
+        workers,
+    }
+}
+
+// ==================== SPLIT POLICIES ====================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitPolicy {
+    /// By hashes over the whole challenge
+    Proportional,
+    /// By hashes in the last N scanned ranges before the solution (--split-last-n)
+    LastN,
+    /// The same share for every worker that scanned the challenge
+    Equal,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Split {
+    pub policy: SplitPolicy,
+    pub last_n: usize,
+}
+
+/// Suggested shares of one accepted solution's reward
+#[derive(Debug, Serialize)]
+pub struct PayoutSuggestion {
+    pub policy: SplitPolicy,
+    /// Ranges considered, for last-n
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_n: Option<usize>,
+    pub generated_at: String,
+    pub challenge_id: String,
+    pub address: String,
+    pub nonce: String,
+    pub payouts: Vec<WorkerShare>,
+}
+
+impl Split {
+    pub fn suggest(&self, solution: &SolutionRecord, scans: &[ScanRecord]) -> PayoutSuggestion {
+        let payouts = match self.policy {
+            SplitPolicy::Proportional => {
+                shares(hashes_per_worker(solution_scans(solution, scans)), |h| h)
+            }
+            SplitPolicy::LastN => {
+                let mut recent: Vec<&ScanRecord> =
+                    solution_scans(solution, scans).rev().take(self.last_n).collect();
+                recent.reverse();
+                shares(hashes_per_worker(recent.into_iter()), |h| h)
+            }
+            SplitPolicy::Equal => shares(hashes_per_worker(solution_scans(solution, scans)), |_| 1),
+        };
+        PayoutSuggestion {
+            policy: self.policy,
+            last_n: (self.policy == SplitPolicy::LastN).then_some(self.last_n),
+            generated_at: Utc::now().to_rfc3339(),
+            challenge_id: solution.challenge.challenge_id.clone(),
+            address: solution.address.clone(),
+            nonce: solution.nonce.clone(),
+            payouts,
+        }
+    }
+}
+
+/// One report per accepted solution in `data_dir`, counting the scans of
+/// `data_dir` and of the other workers' data dirs in `include`
+pub fn reports(data_dir: &Path, include: &[PathBuf]) -> Result<Vec<Contribution>> {
+    let store = Store::open(data_dir)?;
+    let mut scans: Vec<ScanRecord> = Vec::new();
+    for dir in std::iter::once(data_dir).chain(include.iter().map(PathBuf::as_path)) {
+        scans.extend(store::read_jsonl::<ScanRecord>(&dir.join(store::SCANS_FILE))?);
+    }
+    Ok(store
+        .solutions()?
+        .iter()
+        .filter(|s| s.status == SolutionStatus::Accepted)
+        .map(|s| contribution(s, &scans))
+        .collect())
+}
+
+pub fn print(data_dir: &Path, include: &[PathBuf], json: bool) -> Result<()> {
+    let reports = reports(data_dir, include)?;
+    if json {
+        for report in &reports {
+            println!("{}", serde_json::to_string(report)?);
+        }
+        return Ok(());
+    }
+    if reports.is_empty() {
+        say!("📭 No accepted solutions yet");
+        return Ok(());
+    }
+    for report in &reports {
+        say!();
+        say!("🧾 {} (nonce {})", report.challenge_id, report.nonce);
+        say!("   Address: {}", report.address);
+        say!("   Hashes: {}", report.total_hashes);
+        for share in &report.workers {
+            say!(
+                "     {:<20} {:>14} {:>6.2}%",
+                share.worker,
+                share.hashes,
+                share.share * 100.0
+            );
+        }
+    }
+    Ok(())
+}
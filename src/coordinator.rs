@@ -0,0 +1,668 @@
+// ==================== COORDINATOR ====================
+//
+// Rigs mining one address each start from their own time-based nonce, so
+// their ranges run into each other and the same nonces get hashed twice.
+// `--coordinate ADDR` runs one process as the coordinator: it alone talks
+// to the API, serves its workers (`--coordinator URL`) the current
+// challenge, hands every round a range of nonces no other worker gets and
+// submits whatever they find. Workers speak the API's own paths to it, so
+// a worker is an ordinary miner whose base URL is the coordinator, plus one
+// POST /nonces per round. Once a solution from the fleet is accepted, the
+// challenge is served marked as solved and the workers wait for the next.
+// Ranges are not leased: a worker that dies leaves its range unscanned.
+// Claims and solutions are only taken for challenges the coordinator served
+// and that still accept solutions, and what it has tracked for a challenge
+// is dropped once that has passed. A solution is forwarded only if its
+// nonce was handed out and its digest meets the difficulty here, which
+// means building the challenge's ROM when its first solution arrives.
+//
+// Like /metrics it is plain HTTP without authentication: bind it to a
+// private network.
+
+use anyhow::{bail, Context, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    build_preimage, build_rom_sized, hash, initial_nonce, metrics, parse_deadline, retry,
+    Challenge, ChallengeResponse, DifficultyRule, MinerApi, NoChallenge, Rom, NB_INSTRS, NB_LOOPS,
+    ROM_SIZE,
+};
+
+/// How long a fetched challenge is served before the API is asked again
+const CHALLENGE_TTL: Duration = Duration::from_secs(15);
+
+/// The STAR table changes once a day at most
+const RATES_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A worker that stalls before finishing its request head is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest request head taken; the coordinator's own requests are far shorter
+const MAX_HEAD: usize = 8 * 1024;
+
+/// Most nonces one claim can take
+const MAX_CLAIM: u64 = 1 << 40;
+
+// ---------- Worker side ----------
+
+/// A worker's view of its coordinator
+pub struct Link {
+    worker: String,
+    /// Challenge ID -> why the coordinator wants it left alone
+    held: Mutex<HashMap<String, String>>,
+}
+
+impl Link {
+    /// `worker` names this machine in the coordinator's log
+    pub fn new(worker: String) -> Self {
+        Link { worker, held: Mutex::new(HashMap::new()) }
+    }
+
+    /// Remember what the coordinator said about a challenge it served
+    pub fn note(&self, served: &serde_json::Value) {
+        let Some(id) = served["challenge"]["challenge_id"].as_str() else {
+            return;
+        };
+        let mut held = self.held.lock().unwrap();
+        match served["fleet_solved"].as_str() {
+            Some(reason) => held.insert(id.to_string(), reason.to_string()),
+            None => held.remove(id),
+        };
+    }
+
+    pub fn held_back(&self, challenge_id: &str) -> Option<String> {
+        let reason = self.held.lock().unwrap().get(challenge_id)?.clone();
+        Some(format!("Challenge {} already solved by the fleet ({})", challenge_id, reason))
+    }
+
+    /// Up to `count` nonces of `challenge_id` for this worker alone; empty
+    /// when the fleet is done with it
+    pub async fn claim(
+        &self,
+        client: &reqwest::Client,
+        base_url: &str,
+        address: &str,
+        challenge_id: &str,
+        count: u64,
+    ) -> Result<Range<u64>> {
+        let url = format!("{}/nonces/{}/{}", base_url, address, challenge_id);
+        let count = count.clamp(1, MAX_CLAIM).to_string();
+        retry::with_backoff("nonce claim", || async {
+            let request = client.post(&url).query(&[("count", &count), ("worker", &self.worker)]);
+            let response = request.send().await?;
+            let status = response.status();
+            let body = retry::check_status(status.as_u16(), response.text().await?)?;
+            let value: serde_json::Value = serde_json::from_str(&body)
+                .with_context(|| format!("Invalid answer from the coordinator: {}", body))?;
+            if !status.is_success() {
+                bail!("The coordinator refused: {}", value["message"].as_str().unwrap_or(&body));
+            }
+            match (value["start"].as_u64(), value["end"].as_u64()) {
+                (Some(start), Some(end)) if start <= end => Ok(start..end),
+                _ => bail!("Invalid nonce range from the coordinator: {}", body),
+            }
+        })
+        .await
+    }
+}
+
+// ---------- Coordinator side ----------
+
+#[derive(Default)]
+struct State {
+    challenge: Option<(Instant, serde_json::Value)>,
+    rates: Option<(Instant, Vec<u64>)>,
+    /// Challenge ID -> the challenge as served, until it stops taking solutions
+    known: HashMap<String, Challenge>,
+    /// Challenge ID -> first nonce not handed out yet
+    next: HashMap<String, u64>,
+    /// Challenge ID -> which solution settled it
+    solved: HashMap<String, String>,
+    /// (challenge ID, nonce) -> the API's answer, for workers that retry
+    answers: HashMap<(String, String), serde_json::Value>,
+}
+
+struct Coordinator<A> {
+    api: A,
+    address: String,
+    first_nonce: u64,
+    rule: DifficultyRule,
+    /// ROM_SIZE; smaller in tests
+    rom_size: usize,
+    /// The ROM solutions were last checked against, with its seed
+    rom: tokio::sync::Mutex<Option<(String, Arc<Rom>)>>,
+    state: RefCell<State>,
+}
+
+type Reply = (u16, serde_json::Value);
+
+fn message(status: u16, text: impl std::fmt::Display) -> Reply {
+    (status, serde_json::json!({ "message": text.to_string() }))
+}
+
+/// The challenge as the API served it
+fn challenge_value(response: &ChallengeResponse) -> serde_json::Value {
+    serde_json::json!({
+        "code": response.code.as_str(),
+        "challenge": response.challenge,
+        "mining_period_ends": response.mining_period_ends,
+    })
+}
+
+/// Query strings from reqwest: '+' for spaces and %XX escapes
+fn decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match raw.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    out.push(byte);
+                    i += 2;
+                }
+                None => out.push(b'%'),
+            },
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn param<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+}
+
+impl<A: MinerApi> Coordinator<A> {
+    async fn route(&self, method: &str, target: &str, peer: SocketAddr) -> Reply {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, parts.as_slice()) {
+            ("GET", ["challenge"]) => self.challenge().await,
+            ("GET", ["work_to_star_rate"]) => self.rates().await,
+            ("POST", ["nonces", address, challenge_id]) => {
+                self.refresh_for(challenge_id).await;
+                let worker = param(query, "worker").map(decode);
+                let worker = worker.unwrap_or_else(|| peer.ip().to_string());
+                match param(query, "count").and_then(|c| c.parse::<u64>().ok()) {
+                    Some(count) if (1..=MAX_CLAIM).contains(&count) => {
+                        self.claim(address, challenge_id, count, &worker)
+                    }
+                    _ => message(400, format!("count must be 1 to {}", MAX_CLAIM)),
+                }
+            }
+            ("POST", ["solution", address, challenge_id, nonce]) => {
+                self.refresh_for(challenge_id).await;
+                self.submit(address, challenge_id, nonce, peer).await
+            }
+            _ => message(404, "not found; this is a coordinator, mine with --coordinator"),
+        }
+    }
+
+    async fn challenge(&self) -> Reply {
+        let cached = self.state.borrow().challenge.clone();
+        let mut served = match cached {
+            Some((at, value)) if at.elapsed() < CHALLENGE_TTL => value,
+            _ => match self.api.get_challenge().await {
+                Ok(response) => {
+                    let value = challenge_value(&response);
+                    self.state.borrow_mut().challenge = Some((Instant::now(), value.clone()));
+                    self.learn(&response.challenge);
+                    value
+                }
+                // Workers go idle on the same answer
+                Err(e) => match NoChallenge::find(&e) {
+                    Some(idle) => return (200, idle.to_value()),
+                    None => return message(502, format!("Cannot fetch the challenge: {}", e)),
+                },
+            },
+        };
+        let id = served["challenge"]["challenge_id"].as_str().unwrap_or_default().to_string();
+        if let Some(reason) = self.state.borrow().solved.get(&id) {
+            served["fleet_solved"] = reason.clone().into();
+        }
+        (200, served)
+    }
+
+    async fn rates(&self) -> Reply {
+        let cached = self.state.borrow().rates.clone();
+        match cached {
+            Some((at, rates)) if at.elapsed() < RATES_TTL => (200, rates.into()),
+            _ => match self.api.get_star_rate().await {
+                Ok(rates) => {
+                    self.state.borrow_mut().rates = Some((Instant::now(), rates.clone()));
+                    (200, rates.into())
+                }
+                Err(e) => message(502, format!("Cannot fetch the rate table: {}", e)),
+            },
+        }
+    }
+
+    /// Remember a challenge the API served, and forget every challenge
+    /// that no longer takes solutions along with what was tracked for it
+    fn learn(&self, challenge: &Challenge) {
+        let now = self.api.now();
+        let mut state = self.state.borrow_mut();
+        let State { known, next, solved, answers, .. } = &mut *state;
+        known.insert(challenge.challenge_id.clone(), challenge.clone());
+        known.retain(|_, challenge| !closed(challenge, now));
+        next.retain(|id, _| known.contains_key(id));
+        solved.retain(|id, _| known.contains_key(id));
+        answers.retain(|(id, _), _| known.contains_key(id));
+    }
+
+    /// A worker may name a challenge served before this coordinator
+    /// started; fetch the current one before calling it unknown
+    async fn refresh_for(&self, challenge_id: &str) {
+        if !self.state.borrow().known.contains_key(challenge_id) {
+            let _ = self.challenge().await;
+        }
+    }
+
+    /// The challenge, if it was served here and still takes solutions
+    fn open_challenge(&self, challenge_id: &str) -> Result<Challenge, Reply> {
+        let state = self.state.borrow();
+        let Some(challenge) = state.known.get(challenge_id) else {
+            return Err(message(404, format!("challenge {} was not served here", challenge_id)));
+        };
+        if closed(challenge, self.api.now()) {
+            return Err(message(410, format!("challenge {} takes no more solutions", challenge_id)));
+        }
+        Ok(challenge.clone())
+    }
+
+    fn claim(&self, address: &str, challenge_id: &str, count: u64, worker: &str) -> Reply {
+        if address != self.address {
+            return message(403, format!("this coordinator mines for {}", self.address));
+        }
+        if let Err(reply) = self.open_challenge(challenge_id) {
+            return reply;
+        }
+        let mut state = self.state.borrow_mut();
+        let solved = state.solved.contains_key(challenge_id);
+        let next = state.next.entry(challenge_id.to_string()).or_insert(self.first_nonce);
+        let start = *next;
+        let end = if solved { start } else { start.saturating_add(count) };
+        *next = end;
+        if start < end {
+            say!("📦 {}: nonces {}..{} of {}", worker, start, end, challenge_id);
+        }
+        (200, serde_json::json!({ "start": start, "end": end }))
+    }
+
+    async fn submit(
+        &self,
+        address: &str,
+        challenge_id: &str,
+        nonce: &str,
+        peer: SocketAddr,
+    ) -> Reply {
+        if address != self.address {
+            return message(403, format!("this coordinator mines for {}", self.address));
+        }
+        let challenge = match self.open_challenge(challenge_id) {
+            Ok(challenge) => challenge,
+            Err(reply) => return reply,
+        };
+        let key = (challenge_id.to_string(), nonce.to_string());
+        if let Some(answer) = self.state.borrow().answers.get(&key) {
+            return (200, answer.clone());
+        }
+        if let Err(reply) = self.check(&challenge, nonce).await {
+            let reason = reply.1["message"].as_str().unwrap_or_default();
+            say!("🚫 {} sent nonce {} for {}: {}", peer.ip(), nonce, challenge_id, reason);
+            return reply;
+        }
+        say!("📤 {} found nonce {} for {}, submitting", peer.ip(), nonce, challenge_id);
+        let response = match self.api.submit_solution(address, challenge_id, nonce).await {
+            Ok(response) => response,
+            Err(e) => return message(502, format!("Cannot submit: {}", e)),
+        };
+        let accepted = response.accepted();
+        let answer = serde_json::to_value(&response).unwrap_or_default();
+        let mut state = self.state.borrow_mut();
+        state.answers.insert(key, answer.clone());
+        if accepted {
+            say!("🎉 Solution for {} accepted; workers move on", challenge_id);
+            metrics::solution_accepted();
+            let reason = format!("nonce {} from {}", nonce, peer.ip());
+            state.solved.entry(challenge_id.to_string()).or_insert(reason);
+        } else {
+            say!("📋 Solution for {} not accepted: {}", challenge_id, response.extra);
+        }
+        (200, answer)
+    }
+
+    /// A worker's solution before it goes out under this address: a nonce
+    /// this coordinator handed out, whose digest meets the difficulty
+    async fn check(&self, challenge: &Challenge, nonce: &str) -> Result<(), Reply> {
+        let value = match u64::from_str_radix(nonce, 16) {
+            Ok(value) if nonce.len() == 16 => value,
+            _ => return Err(message(400, "the nonce must be 16 hex digits")),
+        };
+        let next = self.state.borrow().next.get(&challenge.challenge_id).copied();
+        if !(self.first_nonce..next.unwrap_or(self.first_nonce)).contains(&value) {
+            return Err(message(403, "that nonce was never handed out"));
+        }
+        let rom = self.rom(&challenge.no_pre_mine).await;
+        let preimage = build_preimage(nonce, &self.address, challenge);
+        let digest = hash(preimage.as_bytes(), &rom, NB_LOOPS, NB_INSTRS);
+        if !self.rule.meets(&digest, &challenge.difficulty) {
+            return Err(message(422, "the digest does not meet the difficulty"));
+        }
+        Ok(())
+    }
+
+    /// The ROM for `seed`, built on first use; the previous one is freed first
+    async fn rom(&self, seed: &str) -> Arc<Rom> {
+        let mut cached = self.rom.lock().await;
+        if let Some((built_for, rom)) = &*cached {
+            if built_for == seed {
+                return Arc::clone(rom);
+            }
+        }
+        *cached = None;
+        say!("🔧 Building the challenge ROM to check the fleet's solutions...");
+        let (owned, size) = (seed.to_string(), self.rom_size);
+        let rom = tokio::task::spawn_blocking(move || build_rom_sized(owned.as_bytes(), size))
+            .await
+            .expect("ROM build panicked");
+        let rom = Arc::new(rom);
+        *cached = Some((seed.to_string(), Arc::clone(&rom)));
+        rom
+    }
+}
+
+/// Past latest_submission (a challenge without a readable one stays open)
+fn closed(challenge: &Challenge, now: chrono::DateTime<chrono::Utc>) -> bool {
+    parse_deadline(&challenge.latest_submission).is_some_and(|t| now >= t)
+}
+
+/// Bind now, so a taken port fails at startup rather than in the background
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Cannot listen on --coordinate {}", addr))?;
+    say!("🧭 Coordinating workers on http://{}", listener.local_addr()?);
+    say!("   Start them with --coordinator http://<this host>:{}", listener.local_addr()?.port());
+    Ok(listener)
+}
+
+/// Serve workers for `address` until cancelled; their solutions are held
+/// to `rule`
+pub async fn serve<A: MinerApi + 'static>(
+    api: A,
+    address: &str,
+    listener: TcpListener,
+    rule: DifficultyRule,
+    cancel: CancellationToken,
+) {
+    let coordinator = Rc::new(Coordinator {
+        api,
+        address: address.to_string(),
+        first_nonce: initial_nonce(),
+        rule,
+        rom_size: ROM_SIZE,
+        rom: tokio::sync::Mutex::new(None),
+        state: RefCell::new(State::default()),
+    });
+    // MinerApi futures need not be Send, so requests run as local tasks
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(async move {
+            loop {
+                let (stream, peer) = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(_) => continue,
+                    },
+                    _ = cancel.cancelled() => return,
+                };
+                let coordinator = Rc::clone(&coordinator);
+                tokio::task::spawn_local(async move {
+                    let _ = answer(&coordinator, stream, peer).await;
+                });
+            }
+        })
+        .await;
+}
+
+async fn answer<A: MinerApi>(
+    coordinator: &Coordinator<A>,
+    mut stream: TcpStream,
+    peer: SocketAddr,
+) -> Result<()> {
+    // Everything is in the request line; headers and body are ignored
+    let head = tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await??;
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some(method), Some(target)) => coordinator.route(method, target, peer).await,
+        _ => message(400, "bad request"),
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        410 => "Gone",
+        422 => "Unprocessable Entity",
+        _ => "Bad Gateway",
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// The request up to the blank line ending its headers, however many
+/// reads it arrives in
+async fn read_head(stream: &mut (impl AsyncRead + Unpin)) -> Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        if head.len() > MAX_HEAD {
+            bail!("Request head over {} bytes", MAX_HEAD);
+        }
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            bail!("Connection closed mid-request");
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayApi;
+
+    const ADDRESS: &str = "addr1qcoordinated";
+
+    /// Under the prefix rule every digest meets this, and almost none meet "00000000"
+    const EASY: &str = "ffffffff";
+
+    fn open(id: &str, difficulty: &str, latest_submission: &str) -> Challenge {
+        serde_json::from_value(serde_json::json!({
+            "challenge_id": id,
+            "day": 1,
+            "challenge_number": 1,
+            "difficulty": difficulty,
+            "no_pre_mine": "fd651ac2725e3b9d804cc8df7aa2c2d0f3d3d9ac4b2e35bcc4fd1b0ff1b2c3a4",
+            "latest_submission": latest_submission,
+            "no_pre_mine_hour": "548571128",
+        }))
+        .unwrap()
+    }
+
+    /// Serving c1 and c2, both open
+    fn coordinator() -> Coordinator<ReplayApi> {
+        let coordinator = Coordinator {
+            api: ReplayApi::from_entries(Vec::new()),
+            address: ADDRESS.to_string(),
+            first_nonce: 1_000,
+            rule: DifficultyRule::Prefix,
+            rom_size: 1024 * 1024,
+            rom: tokio::sync::Mutex::new(None),
+            state: RefCell::new(State::default()),
+        };
+        coordinator.learn(&open("c1", EASY, "2099-01-01T00:00:00Z"));
+        coordinator.learn(&open("c2", EASY, "2099-01-01T00:00:00Z"));
+        coordinator
+    }
+
+    fn nonce(value: u64) -> String {
+        format!("{:016x}", value)
+    }
+
+    fn range(reply: Reply) -> Range<u64> {
+        assert_eq!(reply.0, 200, "{}", reply.1);
+        reply.1["start"].as_u64().unwrap()..reply.1["end"].as_u64().unwrap()
+    }
+
+    #[test]
+    fn claims_never_overlap() {
+        let coordinator = coordinator();
+        let claims = [("rig-a", 10), ("rig-b", 5), ("rig-a", 7), ("rig-c", 1), ("rig-b", 64)];
+        let ranges: Vec<Range<u64>> = claims
+            .iter()
+            .map(|&(worker, count)| range(coordinator.claim(ADDRESS, "c1", count, worker)))
+            .collect();
+        for (i, a) in ranges.iter().enumerate() {
+            assert_eq!(a.end - a.start, claims[i].1);
+            for b in &ranges[i + 1..] {
+                assert!(a.end <= b.start || b.end <= a.start, "{:?} overlaps {:?}", a, b);
+            }
+        }
+        assert_eq!(ranges[0].start, 1_000);
+        assert_eq!(ranges.last().unwrap().end, 1_000 + 87);
+        // Each challenge has its own nonce space
+        assert_eq!(range(coordinator.claim(ADDRESS, "c2", 3, "rig-a")), 1_000..1_003);
+    }
+
+    #[test]
+    fn solved_challenge_hands_out_nothing() {
+        let coordinator = coordinator();
+        range(coordinator.claim(ADDRESS, "c1", 10, "rig-a"));
+        coordinator.state.borrow_mut().solved.insert("c1".into(), "nonce 1 from rig-a".into());
+        assert!(range(coordinator.claim(ADDRESS, "c1", 10, "rig-b")).is_empty());
+        assert!(!range(coordinator.claim(ADDRESS, "c2", 10, "rig-b")).is_empty());
+    }
+
+    #[test]
+    fn claims_for_another_address_are_refused() {
+        let coordinator = coordinator();
+        assert_eq!(coordinator.claim("addr1qsomeoneelse", "c1", 10, "rig-a").0, 403);
+        assert_eq!(range(coordinator.claim(ADDRESS, "c1", 1, "rig-a")), 1_000..1_001);
+    }
+
+    #[tokio::test]
+    async fn route_checks_the_claim_size() {
+        let coordinator = coordinator();
+        let peer: SocketAddr = "10.0.0.7:40000".parse().unwrap();
+        let target = format!("/nonces/{}/c1?worker=rig%2D1+east&count=", ADDRESS);
+        for count in ["0", "x", &(MAX_CLAIM + 1).to_string()] {
+            let reply = coordinator.route("POST", &format!("{}{}", target, count), peer).await;
+            assert_eq!(reply.0, 400, "count={}", count);
+        }
+        let reply = coordinator.route("POST", &format!("{}{}", target, 4), peer).await;
+        assert_eq!(range(reply), 1_000..1_004);
+        assert_eq!(coordinator.route("GET", "/nowhere", peer).await.0, 404);
+    }
+
+    #[test]
+    fn unknown_and_closed_challenges_are_refused() {
+        let coordinator = coordinator();
+        assert_eq!(coordinator.claim(ADDRESS, "made-up", 10, "rig-a").0, 404);
+        coordinator.state.borrow_mut().known.insert(
+            "c0".into(),
+            open("c0", EASY, "2000-01-01T00:00:00Z"),
+        );
+        assert_eq!(coordinator.claim(ADDRESS, "c0", 10, "rig-a").0, 410);
+        assert!(coordinator.state.borrow().next.keys().all(|id| id != "made-up" && id != "c0"));
+    }
+
+    #[test]
+    fn closed_challenges_are_forgotten() {
+        let coordinator = coordinator();
+        range(coordinator.claim(ADDRESS, "c1", 10, "rig-a"));
+        coordinator.state.borrow_mut().solved.insert("c1".into(), "nonce 1 from rig-a".into());
+        coordinator.learn(&open("c1", EASY, "2000-01-01T00:00:00Z"));
+        let state = coordinator.state.borrow();
+        assert!(!state.known.contains_key("c1") && state.known.contains_key("c2"));
+        assert!(state.next.is_empty() && state.solved.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_checked_solutions_are_submitted() {
+        let coordinator = coordinator();
+        coordinator.learn(&open("hard", "00000000", "2099-01-01T00:00:00Z"));
+        let peer: SocketAddr = "10.0.0.7:40000".parse().unwrap();
+        range(coordinator.claim(ADDRESS, "c1", 10, "rig-a"));
+        range(coordinator.claim(ADDRESS, "hard", 10, "rig-a"));
+
+        let submit = |id: &'static str, nonce: String| {
+            let coordinator = &coordinator;
+            async move { coordinator.submit(ADDRESS, id, &nonce, peer).await.0 }
+        };
+        assert_eq!(submit("made-up", nonce(1_000)).await, 404);
+        assert_eq!(submit("c1", "3e8".to_string()).await, 400);
+        assert_eq!(submit("c1", nonce(999)).await, 403);
+        assert_eq!(submit("c1", nonce(1_010)).await, 403);
+        assert_eq!(submit("c2", nonce(1_000)).await, 403);
+        assert_eq!(submit("hard", nonce(1_000)).await, 422);
+        // Passes every check and goes to the API, which has nothing recorded
+        assert_eq!(submit("c1", nonce(1_009)).await, 502);
+    }
+
+    #[tokio::test]
+    async fn request_head_may_arrive_in_pieces() {
+        let first = &b"POST /nonces/a/c1?count=4 HT"[..];
+        let mut pieces = first.chain(&b"TP/1.1\r\nHost: x\r\n\r\n"[..]);
+        let head = read_head(&mut pieces).await.unwrap();
+        assert!(head.starts_with("POST /nonces/a/c1?count=4 HTTP/1.1\r\n"));
+
+        assert!(read_head(&mut &b"GET /challenge HTTP/1.1\r\n"[..]).await.is_err());
+        let endless = vec![b'a'; MAX_HEAD * 2];
+        assert!(read_head(&mut &endless[..]).await.is_err());
+    }
+
+    #[test]
+    fn decodes_query_strings() {
+        assert_eq!(decode("rig-1"), "rig-1");
+        assert_eq!(decode("rig+1%2Feast"), "rig 1/east");
+        assert_eq!(decode("caf%C3%A9"), "café");
+        // Broken escapes stay as they were
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz1"), "%zz1");
+    }
+
+    #[test]
+    fn finds_query_parameters() {
+        let query = "count=64&worker=rig-a&workers=3";
+        assert_eq!(param(query, "count"), Some("64"));
+        assert_eq!(param(query, "worker"), Some("rig-a"));
+        assert_eq!(param(query, "workers"), Some("3"));
+        assert_eq!(param(query, "work"), None);
+        assert_eq!(param("", "count"), None);
+        assert_eq!(param("count", "count"), None);
+    }
+}
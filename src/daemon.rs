@@ -0,0 +1,235 @@
+// ==================== DAEMON ====================
+//
+// Unattended mode for containers and services: no prompts, no TTY, one
+// round after another until the time budget runs out or the instance is
+// reclaimed (or Ctrl+C / SIGINT arrives). Everything is configured up front through flags or their
+// SCAVENGER_* environment variables. --loop runs the same loop from an
+// interactive session, after its one-time prompts.
+//
+// A failed round starts a recovery: the challenge endpoint is probed with
+// a wait that doubles up to MAX_RETRY_DELAY, and the first answer goes
+// straight back to mining. Outages can last hours, so the daemon never
+// gives up; past OUTAGE_ALERT_AFTER the notifier hears it is down, then
+// every OUTAGE_HEARTBEAT that it still is, and once more when it is back.
+// Once a day between rounds the reward schedule is checked for changes.
+//
+// No challenge at all (the event not started yet, paused or over) is not
+// an outage: the daemon goes "event idle", says so once, polls slowly (no
+// later than the start the server announces, if it does) and mines again
+// as soon as a challenge appears.
+
+use anyhow::Result;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::notify::Priority;
+use crate::round::{self, RoundSettings};
+use crate::store::Store;
+use crate::sync::{self, Remote};
+use crate::{memory, parse_deadline, rates, spot, status, timefmt, MinerApi, NoChallenge};
+
+/// Nonces per round when no --max-hashes is given
+pub const ROUND_HASHES: u64 = 1_000_000;
+
+/// First wait after a failed round (server down, network gone); doubles
+/// with every failed attempt after it
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Longest wait between attempts, however long the outage
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// Down this long before the notifier hears of it; blips pass quietly
+const OUTAGE_ALERT_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// Between "still down" reminders
+const OUTAGE_HEARTBEAT: Duration = Duration::from_secs(60 * 60);
+
+/// Longest wait between checks while there is no challenge
+const EVENT_IDLE_MAX_POLL: Duration = Duration::from_secs(15 * 60);
+
+/// Wait before the next attempt after `failures` failures in a row
+pub fn retry_delay(failures: u32) -> Duration {
+    let doublings = failures.saturating_sub(1).min(16);
+    RETRY_DELAY.saturating_mul(1 << doublings).min(MAX_RETRY_DELAY)
+}
+
+fn humanize(duration: Duration) -> String {
+    timefmt::humanize(chrono::Duration::from_std(duration).unwrap_or_default())
+}
+
+pub async fn run<A: MinerApi>(
+    api: &A,
+    store: &Store,
+    settings: &RoundSettings,
+    data_dir: &Path,
+    remote: Option<&Remote>,
+) -> Result<()> {
+    say!("🤖 Mining round after round for {}", settings.address);
+    let mut rounds = 0u64;
+    let mut rates_checked: Option<Instant> = None;
+    loop {
+        if rates_checked.is_none_or(|at| at.elapsed() >= rates::CHECK_EVERY) {
+            rates::check(api, store, settings.notifier.as_ref()).await;
+            rates_checked = Some(Instant::now());
+        }
+        rounds += 1;
+        match round::run_round(api, store, settings).await {
+            Ok(outcome) => say!("Round {} finished: {:?}", rounds, outcome),
+            Err(e) => match NoChallenge::find(&e) {
+                Some(idle) => event_idle(api, settings, idle).await,
+                None => {
+                    say!("❌ Round {} failed: {}", rounds, e);
+                    recover(api, settings, &e).await;
+                }
+            },
+        }
+        // Once per round: a leak shows up as a staircase, one step per ROM rotation
+        let usage = memory::sample();
+        if !usage.is_empty() {
+            say!("🧠 Memory: {}", usage);
+        }
+        if let Some(remote) = remote {
+            sync::checkpoint(data_dir, remote).await;
+        }
+        if stopping(settings) {
+            say!("⏹️  Stopping after {} round(s)", rounds);
+            return Ok(());
+        }
+    }
+}
+
+/// After a failed round: wait, probe the API and back off until it answers
+/// (or mining should stop). One failed round on its own is retried once
+/// the first wait is over.
+async fn recover<A: MinerApi>(api: &A, settings: &RoundSettings, first_error: &anyhow::Error) {
+    let started = Instant::now();
+    let mut failures = 1;
+    let mut last_error = first_error.to_string();
+    let mut alerted: Option<Instant> = None;
+    loop {
+        if stopping(settings) {
+            return;
+        }
+        let due = match alerted {
+            None => started.elapsed() >= OUTAGE_ALERT_AFTER,
+            Some(at) => at.elapsed() >= OUTAGE_HEARTBEAT,
+        };
+        if due {
+            if let Some(notifier) = &settings.notifier {
+                let (title, priority) = match alerted {
+                    None => ("Scavenger API down", Priority::High),
+                    Some(_) => ("Scavenger API still down", Priority::Normal),
+                };
+                let message = format!(
+                    "Down for {}, {} attempts; retrying every {}\nLast error: {}",
+                    humanize(started.elapsed()),
+                    failures,
+                    humanize(retry_delay(failures)),
+                    last_error
+                );
+                notifier.try_send(title, &message, priority).await;
+            }
+            alerted = Some(Instant::now());
+        }
+
+        let delay = retry_delay(failures);
+        say!("   Retrying in {}", humanize(delay));
+        status::set_phase("retrying", None);
+        tokio::select! {
+            _ = api.pause(delay) => {}
+            _ = settings.cancel.cancelled() => return,
+        }
+        if stopping(settings) {
+            return;
+        }
+        match api.get_challenge().await {
+            // An answer without a challenge is still an answer
+            Err(e) if NoChallenge::find(&e).is_none() => {
+                failures += 1;
+                last_error = e.to_string();
+                say!(
+                    "❌ API still unreachable ({} attempts, down for {}): {}",
+                    failures,
+                    humanize(started.elapsed()),
+                    e
+                );
+            }
+            _ => {
+                if failures > 1 {
+                    let down = humanize(started.elapsed());
+                    say!("✅ API reachable again after {} ({} attempts), back to mining", down, failures);
+                    if let (Some(notifier), Some(_)) = (&settings.notifier, alerted) {
+                        let message = format!("Back after {} down; mining resumed", down);
+                        notifier.try_send("Scavenger API back", &message, Priority::Normal).await;
+                    }
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// No challenge to mine: check back slowly until there is one (or mining
+/// should stop). Any other error ends the wait for the next round to
+/// deal with.
+async fn event_idle<A: MinerApi>(api: &A, settings: &RoundSettings, first: &NoChallenge) {
+    let started = Instant::now();
+    let mut idle = first.clone();
+    let mut poll = idle.code.poll_interval();
+    say!("\n😴 Event idle: {}", idle);
+    say!(
+        "   Checking every {}, backing off to {}, until a challenge appears",
+        humanize(poll),
+        humanize(EVENT_IDLE_MAX_POLL)
+    );
+    if let Some(notifier) = &settings.notifier {
+        notifier.try_send("Event idle", &idle.to_string(), Priority::Normal).await;
+    }
+    loop {
+        status::set_phase("event idle", None);
+        // Not past the announced start, if there is one
+        let until_start = idle
+            .starts_at
+            .as_deref()
+            .and_then(parse_deadline)
+            .and_then(|at| (at - chrono::Utc::now()).to_std().ok());
+        let wait = until_start.map_or(poll, |until| until.min(poll));
+        tokio::select! {
+            _ = api.pause(wait) => {}
+            _ = settings.cancel.cancelled() => return,
+        }
+        if stopping(settings) {
+            return;
+        }
+        match api.get_challenge().await {
+            Ok(_) => {
+                let idle_for = humanize(started.elapsed());
+                say!("✅ A challenge is up after {} idle, back to mining", idle_for);
+                if let Some(notifier) = &settings.notifier {
+                    let message = format!("Idle for {}; mining resumed", idle_for);
+                    notifier.try_send("Event resumed", &message, Priority::Normal).await;
+                }
+                return;
+            }
+            Err(e) => match NoChallenge::find(&e) {
+                Some(now) => {
+                    if now.code != idle.code || now.starts_at != idle.starts_at {
+                        say!("😴 Still idle: {}", now);
+                    }
+                    idle = now.clone();
+                }
+                None => {
+                    say!("⚠️  Checking for a challenge failed while idle: {}", e);
+                    return;
+                }
+            },
+        }
+        poll = (poll * 2).min(EVENT_IDLE_MAX_POLL.max(poll));
+    }
+}
+
+fn stopping(settings: &RoundSettings) -> bool {
+    settings.stop_at.is_some_and(|t| Instant::now() >= t)
+        || spot::termination_pending()
+        || settings.cancel.is_cancelled()
+}
@@ -0,0 +1,640 @@
+// ==================== MINING ENGINE ====================
+//
+// The CPU mining loop of a round: worker threads claiming nonce batches on
+// a shared counter and hashing them through the challenge's MiningContext,
+// with the final sprint, work hours and power pausing on top, and a render
+// thread for the progress line or the TUI.
+
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "tui")]
+use crate::tui;
+use crate::{advisor, console, histogram, metrics, power, spot, status, timefmt, workhours};
+use crate::{build_preimage, Challenge, DifficultyRule, MiningContext};
+
+/// Final sprint window: extra threads join the last minutes before the deadline
+/// (at the same priority as the rest; the sprint does not renice anything)
+pub struct SprintPlan {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub threads: usize,
+}
+
+impl SprintPlan {
+    pub fn new(deadline: DateTime<Utc>, minutes: u64, threads: usize) -> Self {
+        Self {
+            starts_at: deadline - chrono::Duration::minutes(minutes as i64),
+            ends_at: deadline,
+            threads,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        let now = Utc::now();
+        now >= self.starts_at && now < self.ends_at
+    }
+
+    pub fn is_over(&self) -> bool {
+        Utc::now() >= self.ends_at
+    }
+}
+
+/// A nonce that met the difficulty, with everything needed to re-check it
+#[derive(Clone)]
+pub struct Solution {
+    pub nonce: String,
+    pub preimage: String,
+    pub hash: [u8; 64],
+    pub found_at: DateTime<Utc>,
+}
+
+/// What a mining run produced, including how much nonce space it covered
+pub struct MiningReport {
+    pub solution: Option<Solution>,
+    pub scanned: u64,
+    /// Average H/s, when the run was a fair measurement of the protocol ROM
+    pub hash_rate: Option<f64>,
+}
+
+pub struct MiningOptions {
+    pub start_nonce: u64,
+    pub max_iterations: u64,
+    pub threads: usize,
+    pub sprint: Option<SprintPlan>,
+    /// Threads to step aside during --work-hours
+    pub work_hours: Option<workhours::Yield>,
+    /// Hard stop from --max-duration
+    pub stop_at: Option<Instant>,
+    /// Pause while the device is too hot or low on battery
+    pub power_guard: Option<power::PowerGuard>,
+    /// mining_period_ends: mining stops there; also the countdown and the
+    /// time-to-solution estimate
+    pub deadline: Option<DateTime<Utc>>,
+    /// latest_submission: past this the challenge is stale
+    pub expires_at: Option<DateTime<Utc>>,
+    /// ROM_SIZE unless overridden with --rom-size
+    pub rom_size: usize,
+    /// --difficulty-rule: what a digest must do to count as a solution
+    pub difficulty_rule: DifficultyRule,
+    /// Checked between hashes; cancelled on Ctrl+C, the time budget,
+    /// spot reclaim or a stale challenge
+    pub cancel: CancellationToken,
+}
+
+impl MiningOptions {
+    pub fn out_of_time(&self) -> bool {
+        self.stop_at.is_some_and(|t| Instant::now() >= t)
+    }
+
+    pub fn expired(&self) -> bool {
+        self.expires_at.is_some_and(|t| Utc::now() >= t)
+    }
+
+    pub fn period_over(&self) -> bool {
+        self.deadline.is_some_and(|t| Utc::now() >= t)
+    }
+
+    /// Why mining should stop now, if it should
+    pub fn stop_reason(&self) -> Option<&'static str> {
+        if spot::termination_pending() {
+            Some("⚠️  Instance is being reclaimed, mining stopped")
+        } else if self.out_of_time() {
+            Some("⏱️  Time budget reached, mining stopped")
+        } else if self.expired() {
+            Some("⌛ Challenge no longer accepts solutions, mining stopped")
+        } else if self.period_over() {
+            Some("⌛ Mining period ended (mining_period_ends), mining stopped")
+        } else if self.cancel.is_cancelled() {
+            Some("🛑 Mining cancelled")
+        } else {
+            None
+        }
+    }
+}
+
+/// State shared between mining threads
+pub struct SharedProgress {
+    pub next: AtomicU64,
+    pub hashes: AtomicU64,
+    pub live_workers: AtomicUsize,
+    pub stop: AtomicBool,
+    /// Set by the power guard; workers idle until it clears
+    pub paused: AtomicBool,
+    /// Paused from the dashboard; same, until resumed there
+    pub held: AtomicBool,
+    /// During work hours, the first worker to idle (the rest idle too);
+    /// usize::MAX outside them
+    pub yielding_from: AtomicUsize,
+    /// Hashes per worker
+    pub worker_hashes: Vec<AtomicU64>,
+    pub found: Mutex<Option<(u64, [u8; 64])>>,
+    /// Per-hash latency of each batch
+    pub latency: histogram::LatencyHistogram,
+}
+
+impl SharedProgress {
+    pub fn new(workers: usize) -> Self {
+        SharedProgress {
+            next: AtomicU64::new(0),
+            hashes: AtomicU64::new(0),
+            live_workers: AtomicUsize::new(0),
+            stop: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            held: AtomicBool::new(false),
+            yielding_from: AtomicUsize::new(usize::MAX),
+            worker_hashes: (0..workers).map(|_| AtomicU64::new(0)).collect(),
+            found: Mutex::new(None),
+            latency: histogram::LatencyHistogram::new(),
+        }
+    }
+
+    /// Whether workers should idle instead of hashing
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed) || self.held.load(Ordering::Relaxed)
+    }
+
+    /// Keep the first solution and tell every worker to stop
+    pub fn record_found(&self, nonce: u64, hash: [u8; 64]) {
+        let mut found = self.found.lock().unwrap();
+        if found.is_none() {
+            *found = Some((nonce, hash));
+        }
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Nonces claimed per worker per round-trip on the shared counter
+const BATCH_SIZE: u64 = 64;
+
+// Sensors change slowly and reading sysfs isn't free
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Mine this long before trusting the measured rate for an estimate
+const ESTIMATE_AFTER: Duration = Duration::from_secs(30);
+
+// Expected time beyond this multiple of the time left is a long shot
+const HOPELESS_FACTOR: f64 = 10.0;
+
+/// Expected time to a solution against the time left, once the rate is known
+fn report_estimate(difficulty: &str, rate: f64, options: &MiningOptions) {
+    let p = options.difficulty_rule.probability(difficulty);
+    let Some(p) = p.filter(|&p| p > 0.0 && rate > 0.0) else {
+        return;
+    };
+    let expected_secs = 1.0 / p / rate;
+    let expected = chrono::Duration::seconds(expected_secs.min(i64::MAX as f64 / 1e3) as i64);
+    say!(
+        "\n📉 Expected time to a solution: {} at {:.0} H/s",
+        timefmt::humanize(expected),
+        rate
+    );
+    let left = options.deadline.map(|d| d - Utc::now());
+    let Some(left) = left.filter(|left| left.num_seconds() > 0) else {
+        return;
+    };
+    say!("   Time left in this challenge: {}", timefmt::humanize(left));
+    if expected_secs > left.num_seconds() as f64 * HOPELESS_FACTOR {
+        say!(
+            "🐢 That is over {:.0}x the time left - on this hardware this challenge is a long shot",
+            HOPELESS_FACTOR
+        );
+    }
+}
+
+
+/// What the mining loop has to say. A separate thread renders these, so a
+/// slow terminal or log sink can't hold up the loop that paces the workers.
+pub enum MiningEvent {
+    Progress { hashes: u64, rate: f64, elapsed: f64 },
+    /// The rate has settled; time for the time-to-solution estimate
+    Estimate { rate: f64 },
+    Paused(String),
+    Resumed,
+    SprintStarted(usize),
+    SprintEnded(usize),
+    /// Threads left mining
+    WorkHoursStarted(usize),
+    /// Threads taken back
+    WorkHoursEnded(usize),
+}
+
+impl MiningEvent {
+    /// Whether `later` makes this one stale: the same setting, a newer value
+    fn superseded_by(&self, later: &MiningEvent) -> bool {
+        use MiningEvent::*;
+        matches!(
+            (self, later),
+            (Estimate { .. }, Estimate { .. })
+                | (Paused(_) | Resumed, Paused(_) | Resumed)
+                | (SprintStarted(_) | SprintEnded(_), SprintStarted(_) | SprintEnded(_))
+                | (
+                    WorkHoursStarted(_) | WorkHoursEnded(_),
+                    WorkHoursStarted(_) | WorkHoursEnded(_)
+                )
+        )
+    }
+}
+
+// Room for a few seconds of events; beyond that the mining loop stops queueing
+const EVENT_QUEUE: usize = 32;
+
+/// The mining loop's end of the render queue, which never waits on the
+/// renderer: a stalled terminal must not hold up pausing or the sprint. With
+/// the queue full, a progress update is dropped, as the next one supersedes
+/// it, and a change of state (paused, sprinting...) is held, replacing any
+/// held change of the same setting, until there is room.
+struct Events {
+    pub queue: SyncSender<MiningEvent>,
+    /// Changes of state not queued yet, oldest first, one per setting
+    pub held: Vec<MiningEvent>,
+}
+
+impl Events {
+    fn new(queue: SyncSender<MiningEvent>) -> Self {
+        Events { queue, held: Vec::new() }
+    }
+
+    /// Queue held changes while there is room; true once none is left
+    fn flush(&mut self) -> bool {
+        while !self.held.is_empty() {
+            let event = self.held.remove(0);
+            match self.queue.try_send(event) {
+                Ok(()) => {}
+                Err(TrySendError::Full(event)) => {
+                    self.held.insert(0, event);
+                    return false;
+                }
+                // The renderer is gone; there is nobody to show them to
+                Err(TrySendError::Disconnected(_)) => self.held.clear(),
+            }
+        }
+        true
+    }
+
+    fn emit(&mut self, event: MiningEvent) {
+        if let MiningEvent::Progress { .. } = event {
+            // Not ahead of a held change, which would then show out of order
+            if self.flush() {
+                let _ = self.queue.try_send(event);
+            }
+            return;
+        }
+        self.held.retain(|held| !held.superseded_by(&event));
+        self.held.push(event);
+        self.flush();
+    }
+
+    /// Mining is over: hand what is still held to the renderer, which is
+    /// about to be waited for anyway
+    fn finish(self) {
+        for event in self.held {
+            let _ = self.queue.send(event);
+        }
+    }
+}
+
+pub fn render_events(events: Receiver<MiningEvent>, difficulty: &str, options: &MiningOptions) {
+    for event in events {
+        render_event(event, difficulty, options);
+    }
+}
+
+pub fn render_event(event: MiningEvent, difficulty: &str, options: &MiningOptions) {
+    let deadline = options.deadline;
+    match event {
+        MiningEvent::Progress { hashes, rate, elapsed } => {
+            status::mining(hashes, rate);
+            let left = deadline.map(|d| d - Utc::now()).filter(|left| left.num_seconds() > 0);
+            let ends = left.map(|left| format!(" | Ends in: {}", timefmt::humanize(left)));
+            console::progress(&format!(
+                "   ⛏️  Iteration: {:>10} | Rate: {:>8.0} H/s | Time: {:>6.1}s{}",
+                hashes, rate, elapsed, ends.unwrap_or_default()));
+        }
+        MiningEvent::Estimate { rate } => report_estimate(difficulty, rate, options),
+        MiningEvent::Paused(reason) => say!("\n🌡️  Pausing: {}", reason),
+        MiningEvent::Resumed => say!("\n▶️  Resuming mining"),
+        MiningEvent::SprintStarted(threads) => {
+            say!("\n🏁 Final sprint: raising to {} threads", threads)
+        }
+        MiningEvent::SprintEnded(threads) => {
+            say!("\n⏰ Deadline passed, back to {} threads", threads)
+        }
+        MiningEvent::WorkHoursStarted(threads) => {
+            say!("\n💼 Work hours: down to {} threads", threads)
+        }
+        MiningEvent::WorkHoursEnded(threads) => {
+            say!("\n🌙 Work hours over: {} threads back", threads)
+        }
+    }
+}
+
+fn run_worker(
+    ctx: &MiningContext,
+    address: &str,
+    challenge: &Challenge,
+    options: &MiningOptions,
+    shared: &SharedProgress,
+    worker: usize,
+    sprint: Option<&SprintPlan>,
+) {
+    let max_iterations = options.max_iterations;
+
+    // Sprint workers stay parked until the sprint window opens
+    if let Some(plan) = sprint {
+        while !plan.is_active() {
+            if shared.stop.load(Ordering::Relaxed)
+                || plan.is_over()
+                || options.cancel.is_cancelled()
+                || shared.next.load(Ordering::Relaxed) >= max_iterations
+            {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    loop {
+        if shared.stop.load(Ordering::Relaxed) {
+            return;
+        }
+        if sprint.is_some_and(|plan| plan.is_over()) || options.cancel.is_cancelled() {
+            return;
+        }
+        if shared.is_paused() || worker >= shared.yielding_from.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(50));
+            continue;
+        }
+
+        let base = shared.next.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+        if base >= max_iterations {
+            return;
+        }
+        let end = (base + BATCH_SIZE).min(max_iterations);
+        if !hash_batch(ctx, address, challenge, options, shared, worker, base..end) {
+            return;
+        }
+    }
+}
+
+/// Hash one claimed batch on this thread; false once the worker should stop
+/// (a solution was found or mining was cancelled)
+fn hash_batch(
+    ctx: &MiningContext,
+    address: &str,
+    challenge: &Challenge,
+    options: &MiningOptions,
+    shared: &SharedProgress,
+    worker: usize,
+    batch: std::ops::Range<u64>,
+) -> bool {
+    let batch_started = Instant::now();
+    let mut batch_hashes = 0;
+
+    for i in batch {
+        // Per hash, so a stop lands within one hash, not one batch
+        if options.cancel.is_cancelled() {
+            shared.latency.record_batch(batch_started.elapsed(), batch_hashes);
+            return false;
+        }
+        let nonce = options.start_nonce.wrapping_add(i);
+        let nonce_hex = format!("{:016x}", nonce);
+
+        // Build preimage
+        let preimage = build_preimage(&nonce_hex, address, challenge);
+
+        // Hash with AshMaize
+        let hash = ctx.hash(&preimage);
+        shared.hashes.fetch_add(1, Ordering::Relaxed);
+        shared.worker_hashes[worker].fetch_add(1, Ordering::Relaxed);
+        batch_hashes += 1;
+
+        // Check difficulty
+        if options.difficulty_rule.meets(&hash, &challenge.difficulty) {
+            shared.record_found(nonce, hash);
+            shared.latency.record_batch(batch_started.elapsed(), batch_hashes);
+            return false;
+        }
+    }
+    shared.latency.record_batch(batch_started.elapsed(), batch_hashes);
+    true
+}
+
+/// Recompute the digest from scratch to make sure workers didn't report garbage
+fn verify_solution(
+    ctx: &MiningContext,
+    solution: &Solution,
+    difficulty: &str,
+    rule: DifficultyRule,
+) -> bool {
+    let hash = ctx.hash(&solution.preimage);
+    hash == solution.hash && rule.meets(&hash, difficulty)
+}
+
+pub fn mine_challenge(
+    ctx: &MiningContext,
+    address: &str,
+    challenge: &Challenge,
+    options: &MiningOptions,
+) -> MiningReport {
+    say!("\n🔨 Mining started");
+    say!("   Challenge ID: {}", challenge.challenge_id);
+    say!("   Difficulty: {}", challenge.difficulty);
+    say!("   Max iterations: {}", options.max_iterations);
+    if let Some(stop_at) = options.stop_at {
+        let left = stop_at.saturating_duration_since(Instant::now());
+        say!("   Time budget: {} left", timefmt::humanize(chrono::Duration::seconds(left.as_secs() as i64)));
+    }
+    say!("   Threads: {}", options.threads);
+    if let Some(plan) = &options.sprint {
+        say!(
+            "   Final sprint: {} threads from {}",
+            plan.threads,
+            timefmt::local(plan.starts_at)
+        );
+    }
+    
+    let start = Instant::now();
+    let random_start = options.start_nonce;
+    
+    say!("   Starting nonce: 0x{:016x}", random_start);
+
+    let max_iterations = options.max_iterations;
+    let base_threads = options.threads.max(1);
+    let sprint_threads = options
+        .sprint
+        .as_ref()
+        .map_or(0, |plan| plan.threads.saturating_sub(base_threads));
+    let cpu_workers = base_threads + sprint_threads;
+    // Workers from `kept` on step aside during work hours
+    let work_hours = options.work_hours.filter(|work| work.threads(base_threads) > 0);
+    let kept = work_hours.map_or(cpu_workers, |work| base_threads - work.threads(base_threads));
+    let shared = SharedProgress::new(cpu_workers);
+    if let Some(work) = &work_hours {
+        say!(
+            "   Work hours: {}, when {} of {} threads step aside",
+            work.hours,
+            base_threads - kept,
+            base_threads
+        );
+        if work.in_effect() {
+            shared.yielding_from.store(kept, Ordering::Relaxed);
+        }
+    }
+    let (queue, rendered) = mpsc::sync_channel(EVENT_QUEUE);
+    let mut events = Events::new(queue);
+    // Hashes already added to the metrics counter
+    let mut counted = 0;
+
+    std::thread::scope(|s| {
+        let renderer = s.spawn(|| {
+            #[cfg(feature = "tui")]
+            if tui::enabled() {
+                return tui::run(rendered, &shared, challenge, options, start);
+            }
+            render_events(rendered, &challenge.difficulty, options)
+        });
+
+        for worker in 0..cpu_workers {
+            let sprint = if worker < base_threads { None } else { options.sprint.as_ref() };
+            let shared = &shared;
+            shared.live_workers.fetch_add(1, Ordering::SeqCst);
+            s.spawn(move || {
+                run_worker(ctx, address, challenge, options, shared, worker, sprint);
+                shared.live_workers.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+
+        // Progress report every second
+        let mut last_report = Instant::now();
+        let mut last_power_check: Option<Instant> = None;
+        let mut estimated = false;
+        let mut sprinting = false;
+        while shared.live_workers.load(Ordering::SeqCst) > 0 {
+            std::thread::sleep(Duration::from_millis(100));
+
+            if !options.cancel.is_cancelled() && options.stop_reason().is_some() {
+                options.cancel.cancel();
+            }
+
+            if let Some(guard) = &options.power_guard {
+                if last_power_check.is_none_or(|t| t.elapsed() >= POWER_CHECK_INTERVAL) {
+                    last_power_check = Some(Instant::now());
+                    let paused = shared.paused.load(Ordering::Relaxed);
+                    match guard.pause_reason(&power::read(), paused) {
+                        Some(reason) if !paused => {
+                            shared.paused.store(true, Ordering::Relaxed);
+                            events.emit(MiningEvent::Paused(reason));
+                        }
+                        None if paused => {
+                            shared.paused.store(false, Ordering::Relaxed);
+                            events.emit(MiningEvent::Resumed);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if let Some(plan) = &options.sprint {
+                if !sprinting && sprint_threads > 0 && plan.is_active() {
+                    sprinting = true;
+                    events.emit(MiningEvent::SprintStarted(plan.threads));
+                } else if sprinting && plan.is_over() {
+                    sprinting = false;
+                    events.emit(MiningEvent::SprintEnded(base_threads));
+                }
+            }
+
+            if let Some(work) = &work_hours {
+                let in_hours = work.in_effect();
+                let from = if in_hours { kept } else { usize::MAX };
+                if shared.yielding_from.swap(from, Ordering::Relaxed) != from {
+                    let running = if sprinting { cpu_workers } else { base_threads };
+                    let event = if in_hours {
+                        MiningEvent::WorkHoursStarted(kept.min(running))
+                    } else {
+                        MiningEvent::WorkHoursEnded(running.saturating_sub(kept))
+                    };
+                    events.emit(event);
+                }
+            }
+
+            if last_report.elapsed().as_secs() >= 1 && !shared.stop.load(Ordering::Relaxed) {
+                let elapsed = start.elapsed().as_secs_f64();
+                let done = shared.hashes.load(Ordering::Relaxed);
+                let rate = done as f64 / elapsed;
+                events.emit(MiningEvent::Progress { hashes: done, rate, elapsed });
+                metrics::hashes(done - counted);
+                counted = done;
+                status::latency(shared.latency.snapshot());
+                if !estimated && start.elapsed() >= ESTIMATE_AFTER {
+                    estimated = true;
+                    events.emit(MiningEvent::Estimate { rate });
+                }
+                last_report = Instant::now();
+            }
+        }
+
+        // Let the renderer drain before the summary below is printed
+        events.finish();
+        let _ = renderer.join();
+    });
+
+    let hashes = shared.hashes.load(Ordering::Relaxed);
+    metrics::hashes(hashes - counted);
+    let scanned = shared.next.load(Ordering::Relaxed).min(max_iterations);
+    let elapsed = start.elapsed().as_secs_f64();
+    let hash_rate = (elapsed >= advisor::CALIBRATION_MIN_SECS
+        && ctx.is_protocol())
+        .then(|| hashes as f64 / elapsed);
+    let latency = shared.latency.snapshot();
+    status::latency(latency.clone());
+    if latency.total() > 0 {
+        say!("\n📶 Per-hash latency: {}", latency.summary());
+    }
+    if let Some((nonce, hash)) = shared.found.into_inner().unwrap() {
+        let nonce_hex = format!("{:016x}", nonce);
+        let elapsed = start.elapsed();
+        say!("\n✅ FOUND VALID NONCE!");
+        say!("   Nonce: 0x{}", nonce_hex);
+        say!("   Nonce (dec): {}", nonce);
+        say!("   Hash: {}", hex::encode(&hash[..8]));
+        say!("   Time: {:.2}s", elapsed.as_secs_f64());
+        say!("   Rate: {:.0} H/s", hashes as f64 / elapsed.as_secs_f64());
+
+        let solution = Solution {
+            preimage: build_preimage(&nonce_hex, address, challenge),
+            nonce: nonce_hex,
+            hash,
+            found_at: Utc::now(),
+        };
+        if !verify_solution(ctx, &solution, &challenge.difficulty, options.difficulty_rule) {
+            say!("❌ Local verification failed, discarding nonce");
+            return MiningReport { solution: None, scanned, hash_rate };
+        }
+        say!("   Verified locally: ✅");
+        metrics::solution_found();
+        #[cfg(feature = "tui")]
+        tui::solution_found(&challenge.challenge_id, &solution.nonce);
+        return MiningReport { solution: Some(solution), scanned, hash_rate };
+    }
+    
+    if let Some(reason) = options.stop_reason().filter(|_| scanned < max_iterations) {
+        say!("\n{}", reason);
+    }
+    say!("\n❌ No valid nonce found in {} iterations", hashes);
+    MiningReport { solution: None, scanned, hash_rate }
+}
+
+/// Start with a time-based nonce to avoid collisions between machines
+pub fn initial_nonce() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,169 @@
+// ==================== C FFI ====================
+//
+// extern "C" surface of the miner core for C/C++ mining frameworks:
+// build a ROM, hash a preimage, check a difficulty, and scan a nonce range
+// with a progress/cancel callback. Header: include/scavenger_miner.h
+// (regenerate with `cbindgen --config cbindgen.toml --output include/scavenger_miner.h`).
+//
+// All functions are single-threaded; a ROM is read-only once built, so
+// callers may share one between threads and split the nonce space.
+
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::mining;
+use crate::types::Challenge;
+
+pub const SM_NOT_FOUND: c_int = 0;
+pub const SM_FOUND: c_int = 1;
+pub const SM_CANCELLED: c_int = 2;
+pub const SM_ERROR: c_int = -1;
+
+/// Digest length in bytes
+pub const SM_HASH_LEN: usize = 64;
+
+/// Nonces hashed between progress callbacks
+const CALLBACK_EVERY: u64 = 1024;
+
+/// Opaque challenge ROM
+pub struct SmRom(ashmaize::Rom);
+
+/// Progress callback: hashes done so far; return false to cancel. May be NULL.
+pub type SmProgressFn = Option<extern "C" fn(user_data: *mut c_void, hashes_done: u64) -> bool>;
+
+unsafe fn str_arg<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+/// Build the ROM for a challenge's no_pre_mine (NUL-terminated). Allocates
+/// about 1 GB and takes a while. NULL on error; free with sm_rom_free.
+///
+/// # Safety
+/// `no_pre_mine` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sm_rom_new(no_pre_mine: *const c_char) -> *mut SmRom {
+    let Some(seed) = str_arg(no_pre_mine) else {
+        return std::ptr::null_mut();
+    };
+    catch_unwind(|| Box::into_raw(Box::new(SmRom(mining::build_rom(seed.as_bytes())))))
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// # Safety
+/// `rom` must come from sm_rom_new and not be used afterwards. NULL is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn sm_rom_free(rom: *mut SmRom) {
+    if !rom.is_null() {
+        drop(Box::from_raw(rom));
+    }
+}
+
+/// Hash `len` preimage bytes into `out` (SM_HASH_LEN bytes). Returns 0 or SM_ERROR.
+///
+/// # Safety
+/// `rom` must be a live ROM, `preimage` valid for `len` bytes and `out`
+/// writable for SM_HASH_LEN bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sm_hash(
+    rom: *const SmRom,
+    preimage: *const u8,
+    len: usize,
+    out: *mut u8,
+) -> c_int {
+    if rom.is_null() || preimage.is_null() || out.is_null() {
+        return SM_ERROR;
+    }
+    let bytes = std::slice::from_raw_parts(preimage, len);
+    let Ok(preimage) = std::str::from_utf8(bytes) else {
+        return SM_ERROR;
+    };
+    let digest = mining::hash_preimage(&(*rom).0, preimage);
+    std::ptr::copy_nonoverlapping(digest.as_ptr(), out, SM_HASH_LEN);
+    0
+}
+
+/// 1 if the digest passes the hex difficulty, 0 if not, SM_ERROR on bad arguments.
+///
+/// # Safety
+/// `hash` must be valid for `len` bytes and `difficulty` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sm_meets_difficulty(
+    hash: *const u8,
+    len: usize,
+    difficulty: *const c_char,
+) -> c_int {
+    let Some(difficulty) = str_arg(difficulty) else {
+        return SM_ERROR;
+    };
+    if hash.is_null() {
+        return SM_ERROR;
+    }
+    let hash = std::slice::from_raw_parts(hash, len);
+    mining::meets_difficulty(hash, difficulty) as c_int
+}
+
+/// Scan nonces `start .. start + count` for `address` against a challenge,
+/// given as the JSON object the API returns under "challenge".
+///
+/// Returns SM_FOUND (nonce and digest written to `out_nonce` / `out_hash`),
+/// SM_NOT_FOUND, SM_CANCELLED (callback returned false) or SM_ERROR.
+///
+/// # Safety
+/// `rom` must be a live ROM; strings NUL-terminated; `out_nonce` writable;
+/// `out_hash` writable for SM_HASH_LEN bytes. `user_data` is passed through.
+#[no_mangle]
+pub unsafe extern "C" fn sm_mine_range(
+    rom: *const SmRom,
+    address: *const c_char,
+    challenge_json: *const c_char,
+    start: u64,
+    count: u64,
+    progress: SmProgressFn,
+    user_data: *mut c_void,
+    out_nonce: *mut u64,
+    out_hash: *mut u8,
+) -> c_int {
+    let (Some(address), Some(challenge_json)) = (str_arg(address), str_arg(challenge_json)) else {
+        return SM_ERROR;
+    };
+    if rom.is_null() || out_nonce.is_null() || out_hash.is_null() {
+        return SM_ERROR;
+    }
+    let Ok(challenge) = serde_json::from_str::<Challenge>(challenge_json) else {
+        return SM_ERROR;
+    };
+    let rom = &(*rom).0;
+
+    let scan = catch_unwind(AssertUnwindSafe(|| {
+        for i in 0..count {
+            if i % CALLBACK_EVERY == 0 && i > 0 {
+                if let Some(progress) = progress {
+                    if !progress(user_data, i) {
+                        return Err(SM_CANCELLED);
+                    }
+                }
+            }
+            let nonce = start.wrapping_add(i);
+            let preimage = mining::build_preimage(&format!("{:016x}", nonce), address, &challenge);
+            let digest = mining::hash_preimage(rom, &preimage);
+            if mining::meets_difficulty(&digest, &challenge.difficulty) {
+                return Ok(Some((nonce, digest)));
+            }
+        }
+        Ok(None)
+    }));
+
+    match scan {
+        Ok(Ok(Some((nonce, digest)))) => {
+            *out_nonce = nonce;
+            std::ptr::copy_nonoverlapping(digest.as_ptr(), out_hash, SM_HASH_LEN);
+            SM_FOUND
+        }
+        Ok(Ok(None)) => SM_NOT_FOUND,
+        Ok(Err(code)) => code,
+        Err(_) => SM_ERROR,
+    }
+}
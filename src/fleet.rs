@@ -0,0 +1,153 @@
+// ==================== FLEET FILE ====================
+//
+// A dozen rigs with a config.toml each drift apart. A fleet file keeps all
+// of them in one place: top-level keys are shared by every host,
+// [coordinator] names the one host that takes that role (`host = "..."`)
+// and its settings, plain keys under [workers] apply to every other host
+// and [workers.<host>] to that host alone, later sections winning.
+// `fleet render --host <name>` merges them into the config.toml for one
+// host, checked against the same flags as any config file, so deployment
+// tooling only ever copies a rendered file. Workers get --worker set to
+// their host name unless the file names them otherwise.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::configfile::ConfigFile;
+
+pub const DEFAULT_PATH: &str = "fleet.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Coordinator,
+    Worker,
+}
+
+struct Fleet {
+    shared: toml::Table,
+    coordinator: Option<(String, toml::Table)>,
+    /// Settings under [workers] itself
+    workers: toml::Table,
+    hosts: toml::Table,
+}
+
+fn table(value: toml::Value, name: &str) -> Result<toml::Table> {
+    match value {
+        toml::Value::Table(table) => Ok(table),
+        _ => bail!("[{}] must be a table", name),
+    }
+}
+
+impl Fleet {
+    fn parse(raw: &str) -> Result<Self> {
+        let mut shared: toml::Table = toml::from_str(raw)?;
+        let coordinator = match shared.remove("coordinator") {
+            Some(value) => {
+                let mut settings = table(value, "coordinator")?;
+                let host = match settings.remove("host") {
+                    Some(toml::Value::String(host)) if !host.is_empty() => host,
+                    _ => bail!("[coordinator] needs host = \"<name>\""),
+                };
+                Some((host, settings))
+            }
+            None => None,
+        };
+        let mut workers = match shared.remove("workers") {
+            Some(value) => table(value, "workers")?,
+            None => toml::Table::new(),
+        };
+        let names: Vec<String> =
+            workers.iter().filter(|(_, v)| v.is_table()).map(|(k, _)| k.clone()).collect();
+        let mut hosts = toml::Table::new();
+        for name in names {
+            hosts.insert(name.clone(), workers.remove(&name).unwrap());
+        }
+        if let Some((host, _)) = &coordinator {
+            if hosts.contains_key(host) {
+                bail!("{} is both the coordinator and a worker", host);
+            }
+        }
+        Ok(Fleet { shared, coordinator, workers, hosts })
+    }
+
+    fn role(&self, host: &str) -> Option<Role> {
+        match &self.coordinator {
+            Some((coordinator, _)) if coordinator == host => Some(Role::Coordinator),
+            _ if self.hosts.contains_key(host) => Some(Role::Worker),
+            _ => None,
+        }
+    }
+
+    fn host_names(&self) -> Vec<&str> {
+        let coordinator = self.coordinator.iter().map(|(host, _)| host.as_str());
+        coordinator.chain(self.hosts.keys().map(String::as_str)).collect()
+    }
+
+    /// Shared keys, then the role's, then the host's own
+    fn effective(&self, host: &str, role: Role) -> toml::Table {
+        let mut merged = toml::Table::new();
+        let mut layers = vec![&self.shared];
+        match role {
+            Role::Coordinator => layers.extend(self.coordinator.iter().map(|(_, t)| t)),
+            Role::Worker => {
+                layers.push(&self.workers);
+                layers.extend(self.hosts.get(host).and_then(toml::Value::as_table));
+            }
+        }
+        for layer in layers {
+            merged.extend(layer.iter().map(|(k, v)| (k.replace('-', "_"), v.clone())));
+        }
+        if role == Role::Worker && !merged.contains_key("worker") {
+            merged.insert("worker".to_string(), toml::Value::String(host.to_string()));
+        }
+        merged
+    }
+}
+
+/// `fleet render`: the config.toml for `host`, to `output` or stdout
+pub fn render(
+    command: &clap::Command,
+    fleet_path: &Path,
+    host: &str,
+    output: Option<&Path>,
+) -> Result<()> {
+    let raw = std::fs::read_to_string(fleet_path)
+        .with_context(|| format!("Cannot read fleet file {}", fleet_path.display()))?;
+    let fleet = Fleet::parse(&raw)
+        .with_context(|| format!("Invalid fleet file {}", fleet_path.display()))?;
+    let Some(role) = fleet.role(host) else {
+        bail!(
+            "{} is not in {} (hosts: {})",
+            host,
+            fleet_path.display(),
+            fleet.host_names().join(", ")
+        );
+    };
+
+    let settings = fleet.effective(host, role);
+    let body = toml::to_string(&settings)?;
+    let label = format!("{} for {}", fleet_path.display(), host);
+    ConfigFile::parse(command, Path::new(&label), &body)?;
+
+    let role = match role {
+        Role::Coordinator => "coordinator",
+        Role::Worker => "worker",
+    };
+    let rendered = format!(
+        "# Rendered from {} for {} ({}). Edit the fleet file and render again\n\
+         # rather than editing this one.\n\n{}",
+        fleet_path.display(),
+        host,
+        role,
+        body
+    );
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            say!("✅ Wrote {} for {} ({})", path.display(), host, role);
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+// ==================== FORENSICS ====================
+//
+// When the server rejects a solution that passed our local check, or
+// accepts it with a receipt for some other preimage, dump everything
+// needed to tell a format bug from a timing or parameter drift.
+// `verify-receipts` re-checks every stored receipt the same way.
+
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+
+use crate::store::{SolutionRecord, Store};
+use crate::{
+    parse_deadline, preimage_mismatches, DifficultyRule, NB_INSTRS, NB_LOOPS, ROM_MIXING_NUMBERS,
+    ROM_PRE_SIZE, ROM_SIZE,
+};
+
+pub fn build_report(
+    record: &SolutionRecord,
+    found_at: DateTime<Utc>,
+    submitted_at: Option<DateTime<Utc>>,
+    rule: DifficultyRule,
+) -> String {
+    let challenge = &record.challenge;
+    let hash = hex::decode(&record.hash).unwrap_or_default();
+    let deadline = parse_deadline(&record.mining_period_ends);
+    let mut out = String::new();
+
+    // Writing to a String cannot fail
+    let title = match record.receipt_problems.is_empty() {
+        true => "REJECTED SOLUTION",
+        false => "RECEIPT MISMATCH",
+    };
+    let _ = writeln!(out, "SCAVENGER MINER - {} REPORT", title);
+    let _ = writeln!(out, "Generated: {}", Utc::now().to_rfc3339());
+
+    let _ = writeln!(out, "\n== Outcome ==");
+    let _ = writeln!(out, "Status: {:?}", record.status);
+    if let Some(response) = &record.response {
+        let _ = writeln!(out, "Server response: {}", response);
+    }
+    for problem in &record.receipt_problems {
+        let _ = writeln!(out, "Receipt mismatch: {}", problem);
+    }
+
+    let _ = writeln!(out, "\n== Timing ==");
+    let _ = writeln!(out, "Found at:          {}", found_at.to_rfc3339());
+    match submitted_at {
+        Some(t) => { let _ = writeln!(out, "Submitted at:      {}", t.to_rfc3339()); }
+        None => { let _ = writeln!(out, "Submitted at:      (not submitted)"); }
+    }
+    let _ = writeln!(out, "Deadline:          {}", record.mining_period_ends);
+    let _ = writeln!(out, "Latest submission: {}", challenge.latest_submission);
+    if let (Some(deadline), Some(submitted)) = (deadline, submitted_at) {
+        let margin = (deadline - submitted).num_seconds();
+        let _ = writeln!(out, "Margin to deadline: {}s", margin);
+    }
+
+    let _ = writeln!(out, "\n== Challenge ==");
+    let _ = writeln!(out, "challenge_id:     {}", challenge.challenge_id);
+    let _ = writeln!(out, "day:              {}", challenge.day);
+    let _ = writeln!(out, "challenge_number: {}", challenge.challenge_number);
+    let _ = writeln!(out, "difficulty:       {}", challenge.difficulty);
+    let _ = writeln!(out, "no_pre_mine:      {}", challenge.no_pre_mine);
+    let _ = writeln!(out, "no_pre_mine_hour: {}", challenge.no_pre_mine_hour);
+
+    let _ = writeln!(out, "\n== Preimage ==");
+    let _ = writeln!(out, "Address: {}", record.address);
+    let _ = writeln!(out, "Nonce:   {}", record.nonce);
+    let _ = writeln!(out, "String:  {}", record.preimage);
+    let _ = writeln!(out, "Bytes:   {}", hex::encode(record.preimage.as_bytes()));
+    let _ = writeln!(out, "Length:  {} bytes", record.preimage.len());
+    let _ = writeln!(out, "Layout:");
+    let fields = [
+        ("nonce", record.nonce.as_str()),
+        ("address", record.address.as_str()),
+        ("challenge_id", challenge.challenge_id.as_str()),
+        ("difficulty", challenge.difficulty.as_str()),
+        ("no_pre_mine", challenge.no_pre_mine.as_str()),
+        ("latest_submission", challenge.latest_submission.as_str()),
+        ("no_pre_mine_hour", challenge.no_pre_mine_hour.as_str()),
+    ];
+    let mut offset = 0;
+    for (name, value) in fields {
+        let _ = writeln!(out, "  [{:>4}..{:>4}] {}", offset, offset + value.len(), name);
+        offset += value.len();
+    }
+
+    let _ = writeln!(out, "\n== ROM / hash parameters ==");
+    let _ = writeln!(out, "Seed (no_pre_mine as UTF-8): {}", hex::encode(challenge.no_pre_mine.as_bytes()));
+    let _ = writeln!(out, "Generation: TwoStep pre_size={} mixing_numbers={}", ROM_PRE_SIZE, ROM_MIXING_NUMBERS);
+    let _ = writeln!(out, "ROM size: {}", ROM_SIZE);
+    let _ = writeln!(out, "Loops: {}  Instructions: {}", NB_LOOPS, NB_INSTRS);
+
+    let _ = writeln!(out, "\n== Digest ==");
+    let _ = writeln!(out, "{}", record.hash);
+
+    let _ = writeln!(out, "\n== Difficulty comparison ==");
+    for line in rule.trace(&hash, &challenge.difficulty) {
+        let _ = writeln!(out, "  {}", line);
+    }
+
+    let _ = writeln!(out, "\n== Hints ==");
+    if !record.receipt_problems.is_empty() {
+        let _ = writeln!(
+            out,
+            "- RECEIPT: the server signed a different preimage; compare it with the String above"
+        );
+    }
+    let late = matches!((deadline, submitted_at), (Some(d), Some(s)) if s > d);
+    if late {
+        let _ = writeln!(out, "- TIMING: submitted after mining_period_ends");
+    }
+    if !rule.meets(&hash, &challenge.difficulty) {
+        let _ = writeln!(out, "- LOCAL: digest does not meet difficulty locally either");
+    } else if !late {
+        let _ = writeln!(
+            out,
+            "- Local check passes: suspect preimage format or ROM/hash parameter drift"
+        );
+    }
+
+    out
+}
+
+/// `verify-receipts`: every stored receipt against the solution it was for
+pub fn verify_receipts(store: &Store) -> Result<()> {
+    let (mut checked, mut mismatched) = (0, 0);
+    for record in store.solutions()? {
+        let receipt = record.response.as_ref().map(|r| &r["crypto_receipt"]["preimage"]);
+        let Some(preimage) = receipt.and_then(|p| p.as_str()) else {
+            continue;
+        };
+        checked += 1;
+        let problems =
+            preimage_mismatches(preimage, &record.nonce, &record.address, &record.challenge);
+        if problems.is_empty() {
+            continue;
+        }
+        mismatched += 1;
+        say!("❌ {} nonce {}", record.challenge.challenge_id, record.nonce);
+        for problem in problems {
+            say!("   {}", problem);
+        }
+    }
+    if checked == 0 {
+        say!("No receipts to check yet");
+    } else if mismatched > 0 {
+        bail!(
+            "{} of {} receipt(s) are not for the solution that was submitted",
+            mismatched,
+            checked
+        );
+    } else {
+        say!("✅ All {} receipt(s) match the solutions that were submitted", checked);
+    }
+    Ok(())
+}
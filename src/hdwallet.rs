@@ -0,0 +1,340 @@
+// ==================== RECOVERY PHRASE ====================
+//
+// `register --mnemonic`: register straight from the wallet's 12-24 word
+// recovery phrase, no browser and no key file. The phrase is checked
+// against the BIP39 English list and its checksum, turned into the root
+// key the way Yoroi, Eternl and other Shelley wallets do (CIP-3 Icarus:
+// PBKDF2 over the entropy), and the keys are derived along CIP-1852:
+// m/1852'/1815'/<account>'/0/<index> to pay, m/1852'/1815'/<account>'/2/0
+// to stake (BIP32-Ed25519). That gives the same base address the wallet
+// shows, and the key to sign the T&C with (signature::SigningKey).
+//
+// The phrase comes from SCAVENGER_MNEMONIC or a prompt that does not echo
+// it. Neither it nor any key derived from it is ever written anywhere:
+// everything lives in memory for the one registration and is wiped when
+// dropped.
+
+use anyhow::{bail, Context, Result};
+use curve25519_dalek::{EdwardsPoint, Scalar};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha512};
+use std::io::{self, Write};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::address;
+use crate::console;
+use crate::signature::{blake2b_224, SigningKey};
+
+const WORDS: &str = include_str!("bip39_english.txt");
+
+/// Recovery phrase for --mnemonic, instead of the prompt
+pub const PHRASE_ENV: &str = "SCAVENGER_MNEMONIC";
+
+/// CIP-1852 purpose and Cardano's coin type, both hardened
+const PURPOSE: u32 = 0x8000_0000 | 1852;
+const COIN_TYPE: u32 = 0x8000_0000 | 1815;
+const HARDENED: u32 = 0x8000_0000;
+
+/// The entropy behind a phrase, once its words and checksum check out
+fn entropy(phrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let list: Vec<&str> = WORDS.split_whitespace().collect();
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if ![12, 15, 18, 21, 24].contains(&words.len()) {
+        bail!("The phrase has {} words; recovery phrases have 12, 15, 18, 21 or 24", words.len());
+    }
+    let mut bits = Zeroizing::new(Vec::with_capacity(words.len() * 11));
+    for (i, word) in words.iter().enumerate() {
+        let Ok(index) = list.binary_search(&word.to_lowercase().as_str()) else {
+            bail!("Word {} of the phrase is not in the BIP39 English list", i + 1);
+        };
+        bits.extend((0..11).rev().map(|bit| (index >> bit) & 1 == 1));
+    }
+    let checksum_bits = words.len() / 3;
+    let (data, checksum) = bits.split_at(bits.len() - checksum_bits);
+    let entropy: Zeroizing<Vec<u8>> = Zeroizing::new(
+        data.chunks(8).map(|byte| byte.iter().fold(0u8, |acc, &b| acc << 1 | b as u8)).collect(),
+    );
+    let hash = Sha256::digest(entropy.as_slice());
+    let expected = (0..checksum_bits).map(|i| (hash[i / 8] >> (7 - i % 8)) & 1 == 1);
+    if !expected.eq(checksum.iter().copied()) {
+        bail!("The phrase's checksum does not match: a word is wrong or out of order");
+    }
+    Ok(entropy)
+}
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    Zeroizing::new(mac.finalize().into_bytes().into())
+}
+
+/// PBKDF2-HMAC-SHA512 into `out`
+fn pbkdf2(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]) {
+    for (block, chunk) in out.chunks_mut(64).enumerate() {
+        let index = (block as u32 + 1).to_be_bytes();
+        let mut u = hmac_sha512(password, &[salt, &index]);
+        let mut t = u.clone();
+        for _ in 1..rounds {
+            u = hmac_sha512(password, &[u.as_slice()]);
+            t.iter_mut().zip(u.iter()).for_each(|(t, u)| *t ^= u);
+        }
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+}
+
+/// A BIP32-Ed25519 extended private key with its chain code
+struct XPrv {
+    /// kL then kR
+    key: [u8; 64],
+    chain: [u8; 32],
+}
+
+impl Drop for XPrv {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.chain.zeroize();
+    }
+}
+
+impl XPrv {
+    /// CIP-3 Icarus root key, with no passphrase as wallets create them
+    fn root(entropy: &[u8]) -> XPrv {
+        let mut seed = Zeroizing::new([0u8; 96]);
+        pbkdf2(b"", entropy, 4096, seed.as_mut_slice());
+        seed[0] &= 0b1111_1000;
+        seed[31] &= 0b0001_1111;
+        seed[31] |= 0b0100_0000;
+        XPrv {
+            key: seed[..64].try_into().expect("96 bytes"),
+            chain: seed[64..].try_into().expect("96 bytes"),
+        }
+    }
+
+    fn public(&self) -> [u8; 32] {
+        let scalar: [u8; 32] = self.key[..32].try_into().expect("64 bytes");
+        EdwardsPoint::mul_base(&Scalar::from_bytes_mod_order(scalar)).compress().to_bytes()
+    }
+
+    /// Child `index` (hardened from 2^31), BIP32-Ed25519 V2
+    fn child(&self, index: u32) -> XPrv {
+        let serial = index.to_le_bytes();
+        let (z, c) = if index >= HARDENED {
+            (
+                hmac_sha512(&self.chain, &[&[0x00], &self.key, &serial]),
+                hmac_sha512(&self.chain, &[&[0x01], &self.key, &serial]),
+            )
+        } else {
+            let public = self.public();
+            (
+                hmac_sha512(&self.chain, &[&[0x02], &public, &serial]),
+                hmac_sha512(&self.chain, &[&[0x03], &public, &serial]),
+            )
+        };
+        let mut key = [0u8; 64];
+        // kL + 8 * ZL (ZL is the first 28 bytes), then kR + ZR mod 2^256
+        let mut carry = 0u16;
+        for i in 0..32 {
+            // The bits shifted out of each byte travel on in the carry
+            let zl = if i < 28 { (z[i] as u16) << 3 } else { 0 };
+            let sum = self.key[i] as u16 + zl + carry;
+            key[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        let mut carry = 0u16;
+        for i in 32..64 {
+            let sum = self.key[i] as u16 + z[i] as u16 + carry;
+            key[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        XPrv { key, chain: c[32..].try_into().expect("64 bytes") }
+    }
+
+    fn signing_key(&self) -> Result<SigningKey> {
+        SigningKey::extended(&self.key, &self.public()).map_err(anyhow::Error::msg)
+    }
+}
+
+/// One address of a wallet and the keys behind it
+pub struct Wallet {
+    payment: XPrv,
+    stake: XPrv,
+}
+
+impl Wallet {
+    /// Account `account`, payment address `index`
+    pub fn from_phrase(phrase: &str, account: u32, index: u32) -> Result<Wallet> {
+        if account >= HARDENED || index >= HARDENED {
+            bail!("Account and address index must be below 2^31");
+        }
+        let root = XPrv::root(&entropy(phrase)?);
+        let account = root.child(PURPOSE).child(COIN_TYPE).child(HARDENED | account);
+        Ok(Wallet { payment: account.child(0).child(index), stake: account.child(2).child(0) })
+    }
+
+    /// The base address: payment key hash, then stake key hash
+    pub fn address(&self, mainnet: bool) -> String {
+        let mut bytes = vec![if mainnet { 0x01 } else { 0x00 }];
+        bytes.extend(blake2b_224(&self.payment.public()));
+        bytes.extend(blake2b_224(&self.stake.public()));
+        address::from_bytes(&bytes)
+    }
+
+    pub fn payment_key(&self) -> Result<SigningKey> {
+        self.payment.signing_key()
+    }
+
+    pub fn stake_key(&self) -> Result<SigningKey> {
+        self.stake.signing_key()
+    }
+}
+
+/// The phrase from SCAVENGER_MNEMONIC, else typed in without echo
+pub fn read_phrase() -> Result<Zeroizing<String>> {
+    if let Ok(phrase) = std::env::var(PHRASE_ENV) {
+        return Ok(Zeroizing::new(phrase));
+    }
+    say!("\n🔑 Enter the wallet's recovery phrase (not shown; never saved):");
+    read_hidden().context("Cannot read the recovery phrase")
+}
+
+/// A line from stdin, not echoed while it is typed
+pub fn read_hidden() -> io::Result<Zeroizing<String>> {
+    io::stdout().flush().ok();
+    let echo = Echo::off();
+    // Through the shared reader, which an earlier prompt may have started
+    let read = console::read_line(None);
+    drop(echo);
+    say!();
+    read.map(|line| Zeroizing::new(line.unwrap_or_default()))
+}
+
+/// Terminal echo switched off until dropped
+struct Echo {
+    #[cfg(unix)]
+    saved: Option<libc::termios>,
+    #[cfg(windows)]
+    saved: Option<u32>,
+}
+
+impl Echo {
+    #[cfg(unix)]
+    fn off() -> Echo {
+        // SAFETY: termios calls on the process' own stdin
+        unsafe {
+            let mut mode: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut mode) != 0 {
+                return Echo { saved: None };
+            }
+            let saved = mode;
+            mode.c_lflag &= !libc::ECHO;
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &mode);
+            Echo { saved: Some(saved) }
+        }
+    }
+
+    #[cfg(windows)]
+    fn off() -> Echo {
+        use windows_sys::Win32::System::Console::{
+            GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_ECHO_INPUT, STD_INPUT_HANDLE,
+        };
+        // SAFETY: plain Win32 calls on the process' own stdin handle
+        unsafe {
+            let handle = GetStdHandle(STD_INPUT_HANDLE);
+            let mut mode = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                return Echo { saved: None };
+            }
+            SetConsoleMode(handle, mode & !ENABLE_ECHO_INPUT);
+            Echo { saved: Some(mode) }
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn off() -> Echo {
+        Echo {}
+    }
+}
+
+impl Drop for Echo {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(saved) = &self.saved {
+            // SAFETY: restores the mode read in `off`
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, saved);
+            }
+        }
+        #[cfg(windows)]
+        if let Some(mode) = self.saved {
+            use windows_sys::Win32::System::Console::{
+                GetStdHandle, SetConsoleMode, STD_INPUT_HANDLE,
+            };
+            // SAFETY: restores the mode read in `off`
+            unsafe {
+                SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), mode);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entropy, Wallet, XPrv};
+
+    /// CIP-3's Icarus test vector
+    const ICARUS: &str =
+        "eight country switch draw meat scout mystery blade tip drift useless good keep usage title";
+    /// The phrase behind CIP-19's test keys
+    const CIP19: &str = "test walk nut penalty hip pave soap entry language right filter choice";
+
+    #[test]
+    fn icarus_root_key() {
+        let root = XPrv::root(&entropy(ICARUS).unwrap());
+        let xprv = [root.key.as_slice(), root.chain.as_slice()].concat();
+        assert_eq!(
+            hex::encode(xprv),
+            "c065afd2832cd8b087c4d9ab7011f481ee1e0721e78ea5dd609f3ab3f156d245\
+             d176bd8fd4ec60b4731c3918a2a72a0226c0cd119ec35b47e4d55884667f552a\
+             23f7fdcd4a10c6cd2c7393ac61d877873e248f417634aa3d812af327ffe9d620"
+        );
+    }
+
+    #[test]
+    fn payment_key_and_address() {
+        let wallet = Wallet::from_phrase(CIP19, 0, 0).unwrap();
+        // addr_vk1w0l2sr2zgfm26ztc6nl9xy8ghsk5sh6ldwemlpmp9xylzy4dtf7st80zhd
+        let payment = "73fea80d424276ad0978d4fe5310e8bc2d485f5f6bb3bf87612989f112ad5a7d";
+        assert_eq!(hex::encode(wallet.payment.public()), payment);
+        assert_eq!(wallet.payment_key().unwrap().pubkey(), payment);
+        // CIP-19's base addresses share this payment part; the stake part is
+        // the phrase's own m/1852'/1815'/0'/2/0 key
+        assert_eq!(
+            wallet.address(true),
+            "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3jcu5d8ps7zex2k2xt3uqxgjqnnj83ws8lhrn648jjxtwqfjkjv7"
+        );
+        assert_eq!(
+            wallet.address(false),
+            "addr_test1qz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3jcu5d8ps7zex2k2xt3uqxgjqnnj83ws8lhrn648jjxtwq2ytjqp"
+        );
+        assert_eq!(
+            hex::encode(wallet.stake.public()),
+            "2c041c9c6a676ac54d25e2fdce44c56581e316ae43adc4c7bf17f23214d8d892"
+        );
+    }
+
+    #[test]
+    fn bad_phrases_are_refused() {
+        // Two words swapped: every word is valid, the checksum is not
+        let swapped = ICARUS.replacen("eight country", "country eight", 1);
+        let e = entropy(&swapped).unwrap_err();
+        assert!(e.to_string().contains("checksum"), "{}", e);
+        let e = entropy(&ICARUS.replace("title", "titel")).unwrap_err();
+        assert!(e.to_string().contains("Word 15"), "{}", e);
+        let e = entropy("eight country switch").unwrap_err();
+        assert!(e.to_string().contains("3 words"), "{}", e);
+        // Upper case is the same phrase
+        assert_eq!(*entropy(&ICARUS.to_uppercase()).unwrap(), *entropy(ICARUS).unwrap());
+    }
+}
@@ -0,0 +1,100 @@
+// ==================== LATENCY HISTOGRAM ====================
+//
+// A mean hash rate hides the stalls operators care about: page faults on
+// a swapping ROM, thermal throttling, a noisy neighbour. Workers time each
+// batch and file its per-hash latency into fixed log-spaced buckets, which
+// the status file exports and the status command draws.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Bucket upper bounds in microseconds per hash; one overflow bucket follows
+pub const BOUNDS_US: [u64; 11] =
+    [50, 100, 200, 500, 1_000, 2_000, 5_000, 10_000, 20_000, 50_000, 100_000];
+
+pub struct LatencyHistogram {
+    counts: [AtomicU64; BOUNDS_US.len() + 1],
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        LatencyHistogram { counts: std::array::from_fn(|_| AtomicU64::new(0)) }
+    }
+
+    /// A batch of `hashes` took `elapsed`
+    pub fn record_batch(&self, elapsed: Duration, hashes: u64) {
+        if hashes == 0 {
+            return;
+        }
+        let per_hash = elapsed.as_micros() as u64 / hashes;
+        let bucket = BOUNDS_US
+            .iter()
+            .position(|&bound| per_hash <= bound)
+            .unwrap_or(BOUNDS_US.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            bounds_us: BOUNDS_US.to_vec(),
+            counts: self.counts.iter().map(|c| c.load(Ordering::Relaxed)).collect(),
+        }
+    }
+}
+
+/// Batch counts per bucket; `counts` has one more entry than `bounds_us`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub bounds_us: Vec<u64>,
+    pub counts: Vec<u64>,
+}
+
+impl Snapshot {
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Upper bound of the bucket holding quantile `q` (0.0..=1.0); None past the last bound
+    pub fn quantile_us(&self, q: f64) -> Option<u64> {
+        let target = (self.total() as f64 * q).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return self.bounds_us.get(i).copied();
+            }
+        }
+        None
+    }
+
+    /// "p50 <=1.0 ms, p90 <=2.0 ms, p99 <=5.0 ms"
+    pub fn summary(&self) -> String {
+        let last = self.bounds_us.last().copied().unwrap_or(0);
+        let show = |q: f64| match self.quantile_us(q) {
+            Some(us) => format!("<={:.1} ms", us as f64 / 1_000.0),
+            None => format!(">{:.0} ms", last as f64 / 1_000.0),
+        };
+        format!("p50 {}, p90 {}, p99 {}", show(0.5), show(0.9), show(0.99))
+    }
+
+    /// One row per non-empty bucket with a proportional bar
+    pub fn rows(&self, width: usize) -> Vec<String> {
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut lower = 0;
+        let mut rows = Vec::new();
+        for (i, &count) in self.counts.iter().enumerate() {
+            let label = match self.bounds_us.get(i) {
+                Some(&upper) => format!("{:>6}-{:<6}", lower, upper),
+                None => format!("{:>6}+      ", lower),
+            };
+            lower = self.bounds_us.get(i).copied().unwrap_or(lower);
+            if count == 0 {
+                continue;
+            }
+            let bar = "#".repeat(((count * width as u64).div_ceil(max)) as usize);
+            rows.push(format!("{} µs {:<width$} {}", label, bar, count, width = width));
+        }
+        rows
+    }
+}
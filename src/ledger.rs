@@ -0,0 +1,182 @@
+// ==================== LEDGER ====================
+//
+// `register --ledger`: register with the key on a Ledger, which never
+// leaves it. The Cardano app derives the address along CIP-1852 (as for
+// --mnemonic) and, after the T&C message is checked and approved on the
+// device's screen, signs it as CIP-8 asks (app 7.1 or later). It answers
+// with the signature, its public key and the address it put in the
+// protected header, and the COSE_Sign1 is assembled here around exactly
+// that header, the same one signature::SigningKey writes. The first Ledger
+// found on USB HID is used (ledger-transport-hid).
+
+use anyhow::{bail, Context, Result};
+use ledger_transport::APDUCommand;
+use ledger_transport_hid::hidapi::HidApi;
+use ledger_transport_hid::TransportNativeHID;
+
+use crate::{address, signature, signpage};
+
+/// The Cardano app's instruction class and the instructions used
+const CLA: u8 = 0xd7;
+const INS_GET_VERSION: u8 = 0x00;
+const INS_DERIVE_ADDRESS: u8 = 0x11;
+const INS_SIGN_MESSAGE: u8 = 0x24;
+
+/// DERIVE_ADDRESS: answer with the address rather than show it
+const P1_RETURN: u8 = 0x01;
+/// SIGN_MESSAGE stages
+const P1_INIT: u8 = 0x01;
+const P1_CHUNK: u8 = 0x02;
+const P1_CONFIRM: u8 = 0x03;
+
+/// CIP-8 message signing arrived in the Cardano app 7.1
+const MIN_VERSION: (u8, u8) = (7, 1);
+
+/// Message bytes in the first chunk, which the device shows (half as
+/// many when it shows them as hex), and in each chunk after it
+const FIRST_CHUNK: usize = 198;
+const CHUNK: usize = 250;
+
+/// CIP-1852 purpose and Cardano's coin type, both hardened
+const PURPOSE: u32 = 0x8000_0000 | 1852;
+const COIN_TYPE: u32 = 0x8000_0000 | 1815;
+const HARDENED: u32 = 0x8000_0000;
+
+/// Address types and where their credentials come from, as the app numbers them
+const BASE_ADDRESS: u8 = 0x00;
+const REWARD_ADDRESS: u8 = 0x0e;
+const PAYMENT_PATH: u8 = 0x11;
+const PAYMENT_NONE: u8 = 0x13;
+const STAKING_KEY_PATH: u8 = 0x22;
+
+/// CIP-8 address field: the whole address, not just a key hash
+const ADDRESS_FIELD: u8 = 0x01;
+
+/// A Ledger running the Cardano app, and the address on it to use
+pub struct Ledger {
+    transport: TransportNativeHID,
+    account: u32,
+    index: u32,
+    mainnet: bool,
+}
+
+impl Ledger {
+    /// The first Ledger connected, checked for a Cardano app that signs messages
+    pub fn open(account: u32, index: u32, mainnet: bool) -> Result<Ledger> {
+        if account >= HARDENED || index >= HARDENED {
+            bail!("Account and address index must be below 2^31");
+        }
+        let hid = HidApi::new().context("Cannot use USB HID")?;
+        let transport = TransportNativeHID::new(&hid)
+            .context("No Ledger found: connect it, unlock it and open the Cardano app")?;
+        let ledger = Ledger { transport, account, index, mainnet };
+        let version = ledger.exchange(INS_GET_VERSION, 0, Vec::new())?;
+        let [major, minor, patch, ..] = version[..] else {
+            bail!("The Ledger's version answer is too short");
+        };
+        say!("🔐 Ledger with the Cardano app {}.{}.{}", major, minor, patch);
+        if (major, minor) < MIN_VERSION {
+            bail!(
+                "Signing messages needs the Cardano app {}.{} or later; update it in Ledger Live",
+                MIN_VERSION.0,
+                MIN_VERSION.1
+            );
+        }
+        Ok(ledger)
+    }
+
+    /// The base address, computed on the device
+    pub fn address(&self) -> Result<String> {
+        let bytes = self.exchange(INS_DERIVE_ADDRESS, P1_RETURN, self.address_params(false))?;
+        Ok(address::from_bytes(&bytes))
+    }
+
+    /// Sign `message` for the base address, or with the stake key for the
+    /// stake address; waits for the owner to approve it on the device
+    pub fn sign(&self, message: &str, stake: bool) -> Result<signpage::Signed> {
+        let bytes = message.as_bytes();
+        let ascii = bytes.iter().all(|b| (0x20..0x7f).contains(b))
+            && message.trim() == message
+            && !message.contains("  ");
+        let signing_path = if stake { self.path(2, 0) } else { self.path(0, self.index) };
+
+        let mut init = (bytes.len() as u32).to_be_bytes().to_vec();
+        init.extend(signing_path);
+        init.push(0x00); // the payload as is, not hashed
+        init.push(ascii as u8);
+        init.push(ADDRESS_FIELD);
+        init.extend(self.address_params(stake));
+        self.exchange(INS_SIGN_MESSAGE, P1_INIT, init)?;
+
+        // At least one chunk, even for an empty message
+        let first = if ascii { FIRST_CHUNK } else { FIRST_CHUNK / 2 };
+        let (head, tail) = bytes.split_at(first.min(bytes.len()));
+        for chunk in std::iter::once(head).chain(tail.chunks(CHUNK)) {
+            let mut data = (chunk.len() as u32).to_be_bytes().to_vec();
+            data.extend(chunk);
+            self.exchange(INS_SIGN_MESSAGE, P1_CHUNK, data)?;
+        }
+
+        say!("\n🔐 Check the message on the Ledger and approve it there...");
+        let answer = self.exchange(INS_SIGN_MESSAGE, P1_CONFIRM, Vec::new())?;
+        if answer.len() < 100 {
+            bail!("The Ledger's signature answer is too short");
+        }
+        let (signature, rest) = answer.split_at(64);
+        let (pubkey, rest) = rest.split_at(32);
+        let (size, rest) = rest.split_at(4);
+        let size = u32::from_be_bytes(size.try_into().expect("4 bytes")) as usize;
+        let Some(address) = rest.get(..size) else {
+            bail!("The Ledger's signature answer is cut short");
+        };
+        Ok(signpage::Signed {
+            signature: signature::cose_sign1(message, address, signature),
+            pubkey: hex::encode(pubkey),
+        })
+    }
+
+    /// m/1852'/1815'/<account>'/<role>/<index>, as the app reads paths
+    fn path(&self, role: u32, index: u32) -> Vec<u8> {
+        let steps = [PURPOSE, COIN_TYPE, HARDENED | self.account, role, index];
+        let mut path = vec![steps.len() as u8];
+        path.extend(steps.iter().flat_map(|step| step.to_be_bytes()));
+        path
+    }
+
+    /// The base address, or with `stake` the stake (reward) address
+    fn address_params(&self, stake: bool) -> Vec<u8> {
+        let network = self.mainnet as u8;
+        let mut params = if stake {
+            vec![REWARD_ADDRESS, network, PAYMENT_NONE]
+        } else {
+            let mut params = vec![BASE_ADDRESS, network, PAYMENT_PATH];
+            params.extend(self.path(0, self.index));
+            params
+        };
+        params.push(STAKING_KEY_PATH);
+        params.extend(self.path(2, 0));
+        params
+    }
+
+    /// One command to the Cardano app; its answer, unless it refused
+    fn exchange(&self, ins: u8, p1: u8, data: Vec<u8>) -> Result<Vec<u8>> {
+        let command = APDUCommand { cla: CLA, ins, p1, p2: 0, data };
+        let answer = self.transport.exchange(&command).context("Lost the Ledger")?;
+        match answer.retcode() {
+            0x9000 => Ok(answer.data().to_vec()),
+            code => bail!("The Ledger refused: {}", refusal(code)),
+        }
+    }
+}
+
+fn refusal(code: u16) -> String {
+    match code {
+        0x6e09 => "rejected on the device".to_string(),
+        0x6e10 => "the Cardano app's policy does not allow it".to_string(),
+        0x6e11 | 0x5515 => "the device is locked; unlock it".to_string(),
+        0x6e00 | 0x6d00 | 0x6511 | 0x6e01 => "open the Cardano app on it first".to_string(),
+        0x6e07 => "the app did not accept the request".to_string(),
+        0x6e08 => "the app does not allow that derivation path".to_string(),
+        code => format!("status 0x{:04x}", code),
+    }
+}
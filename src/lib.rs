@@ -0,0 +1,87 @@
+//! Core Scavenger Mine protocol pieces, shared by the miner binary, the
+//! bench/test tools, the fuzz targets and the language bindings.
+//!
+//! # Stable API
+//!
+//! The items re-exported at the crate root are the supported surface:
+//! [`Client`], [`Challenge`] and the other wire types, [`MiningContext`],
+//! [`Miner`] with [`MinerEvent`] and [`Solution`], the embedding handle and
+//! its status types, plus the difficulty helpers. They
+//! follow semver: anything that breaks them bumps the minor version while
+//! we are on 0.x, and returned structs and enums are `#[non_exhaustive]`
+//! so new fields and events are not breaking. The `api`, `mining` and
+//! `types` modules stay public for the bindings and for tools that want
+//! the lower-level pieces, but are not covered.
+//!
+//! Tools doing what the miner binary does start from the same parts: the
+//! binary's API client is built on [`Client`] (`Client::with_http`) and
+//! adds audit logging, retries and replay; its mining loop hashes through
+//! [`MiningContext`] as [`Miner`] does, and adds sprints, power pausing and
+//! the ROM cache. Both sit on [`build_preimage`] and [`meets_difficulty`].
+//!
+//! ```
+//! use scavenger_miner::{meets_difficulty, success_probability};
+//!
+//! let hash = [0x00, 0x0a, 0xff, 0xff];
+//! assert!(meets_difficulty(&hash, "000FFFFF"));
+//! assert_eq!(success_probability("00FFFFFF"), Some(1.0 / 256.0));
+//! ```
+//!
+//! # Features
+//!
+//! - `cli` (default): everything the miner binary needs
+//! - `native-tls` (default) / `rustls`: TLS backend for the API client;
+//!   `rustls-native-roots` makes rustls trust the OS certificate store too
+//! - `lite`: the miner binary with rustls and none of the optional
+//!   extras, for small instances and static musl builds
+//!   (`--no-default-features --features lite`)
+//! - `tui` (default): `--tui`, a full-screen dashboard for interactive mining
+//! - `client`: the async API [`Client`]
+//! - `hash`: AshMaize hashing and [`Miner`]; pulls in the 1 GB ROM
+//! - `embed`: async `MinerHandle` with pause/stop and a status channel,
+//!   for GUIs embedding the engine
+//! - `wasm`: wasm-bindgen exports for verifying solutions in browsers and
+//!   Node (add `hash` to recompute digests)
+//! - `ffi`: extern "C" API for C/C++ (include/scavenger_miner.h)
+//! - `python`: PyO3 module with the API client and mining primitives
+//!
+//! wasm build:
+//! `cargo build --lib --target wasm32-unknown-unknown --no-default-features --features wasm`
+//!
+//! python build (see pyproject.toml): `maturin develop --release`
+
+pub mod mining;
+pub mod types;
+
+#[cfg(feature = "client")]
+pub mod api;
+
+#[cfg(feature = "hash")]
+mod miner;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+pub use mining::{build_preimage, meets_difficulty, success_probability, DifficultyRule};
+pub use types::{
+    parse_deadline, Challenge, ChallengeCode, ChallengeResponse, CryptoReceipt,
+    RegistrationReceipt, RegistrationResponse, SolutionResponse, TandCResponse, BASE_URL,
+};
+
+#[cfg(feature = "client")]
+pub use api::Client;
+
+#[cfg(feature = "hash")]
+pub use mining::MiningContext;
+
+#[cfg(feature = "hash")]
+pub use miner::{Miner, MinerEvent, Solution};
+
+#[cfg(feature = "embed")]
+pub use miner::{MinerHandle, MinerState, MinerStatus};
@@ -0,0 +1,175 @@
+// ==================== RESOURCE LIMITS ====================
+//
+// The ROM alone is a gigabyte. In a container with a smaller memory limit
+// the kernel OOM-kills the miner minutes into ROM generation, with nothing
+// in the logs to say why. Read the limits up front, report them, and
+// refuse to start when the ROM cannot fit.
+
+use anyhow::{bail, Result};
+
+use crate::ROM_PRE_SIZE;
+
+/// Heap, thread stacks and HTTP client on top of the ROM buffers
+const RUNTIME_OVERHEAD: u64 = 128 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+pub struct Limits {
+    /// cgroup memory limit, None when unlimited or unknown
+    pub memory_limit: Option<u64>,
+    pub memory_available: Option<u64>,
+    /// RLIMIT_AS
+    pub address_space: Option<u64>,
+    /// RLIMIT_MEMLOCK (ulimit -l)
+    pub locked_memory: Option<u64>,
+    /// RLIMIT_NOFILE (ulimit -n)
+    pub open_files: Option<u64>,
+}
+
+/// Memory a ROM of `rom_size` bytes needs while it is being built
+pub fn rom_requirement(rom_size: usize) -> u64 {
+    (rom_size + ROM_PRE_SIZE.min(rom_size)) as u64 + RUNTIME_OVERHEAD
+}
+
+/// Parse "1G", "256M", "64k" or plain bytes (binary multiples)
+pub fn parse_size(raw: &str) -> Result<usize, String> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (digits, unit) = raw.split_at(split);
+    let shift = match unit.trim().to_ascii_lowercase().trim_end_matches("ib").trim_end_matches('b') {
+        "" => 0,
+        "k" => 10,
+        "m" => 20,
+        "g" => 30,
+        other => return Err(format!("unknown size unit '{}' in '{}' (use K, M or G)", other, raw)),
+    };
+    let value: usize = digits
+        .parse()
+        .map_err(|_| format!("invalid size '{}' (e.g. 64M, 1G)", raw))?;
+    value
+        .checked_shl(shift)
+        .filter(|&bytes| bytes > 0 && bytes >> shift == value)
+        .ok_or_else(|| format!("size '{}' out of range", raw))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn detect() -> Limits {
+    Limits {
+        memory_limit: cgroup_memory_limit(),
+        memory_available: meminfo_available(),
+        address_space: proc_limit("Max address space"),
+        locked_memory: proc_limit("Max locked memory"),
+        open_files: proc_limit("Max open files"),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn detect() -> Limits {
+    Limits::default()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn cgroup_memory_limit() -> Option<u64> {
+    use std::fs;
+
+    // cgroup v2: our own group first, then the root of the namespace
+    let own = fs::read_to_string("/proc/self/cgroup").ok().and_then(|groups| {
+        groups
+            .lines()
+            .find_map(|line| line.strip_prefix("0::").map(|path| path.trim().to_string()))
+    });
+    let mut candidates = Vec::new();
+    if let Some(path) = own {
+        candidates.push(format!("/sys/fs/cgroup{}/memory.max", path.trim_end_matches('/')));
+    }
+    candidates.push("/sys/fs/cgroup/memory.max".to_string());
+    // cgroup v1 reports "unlimited" as a huge page-aligned number
+    candidates.push("/sys/fs/cgroup/memory/memory.limit_in_bytes".to_string());
+
+    candidates.iter().find_map(|path| {
+        let raw = fs::read_to_string(path).ok()?;
+        let raw = raw.trim();
+        if raw == "max" {
+            return Some(None);
+        }
+        let bytes: u64 = raw.parse().ok()?;
+        Some((bytes < 1 << 60).then_some(bytes))
+    })?
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn meminfo_available() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Soft limit from /proc/self/limits; None when unlimited
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn proc_limit(name: &str) -> Option<u64> {
+    let limits = std::fs::read_to_string("/proc/self/limits").ok()?;
+    let line = limits.lines().find(|l| l.starts_with(name))?;
+    line[name.len()..].split_whitespace().next()?.parse().ok()
+}
+
+pub fn size(bytes: u64) -> String {
+    if bytes >= 1 << 30 {
+        format!("{:.1} GiB", bytes as f64 / (1u64 << 30) as f64)
+    } else {
+        format!("{} MiB", bytes >> 20)
+    }
+}
+
+fn show(value: Option<u64>) -> String {
+    value.map_or_else(|| "unlimited".to_string(), size)
+}
+
+/// The hard cap (cgroup or address space) when it is below what the ROM
+/// needs. A ROM on disk (--rom-storage mmap) is paged within the cgroup
+/// limit, so only the address space counts then.
+pub fn shortfall(limits: &Limits, rom_size: usize, on_disk: bool) -> Option<u64> {
+    let memory_limit = limits.memory_limit.filter(|_| !on_disk);
+    [memory_limit, limits.address_space]
+        .into_iter()
+        .flatten()
+        .min()
+        .filter(|&cap| cap < rom_requirement(rom_size))
+}
+
+/// Print the report; error when the ROM cannot fit and `ignore` is not set
+pub fn check(ignore: bool, rom_size: usize, on_disk: bool) -> Result<()> {
+    let limits = detect();
+    let needed = rom_requirement(rom_size);
+
+    say!("🧮 Resource limits (ROM needs ~{})", size(needed));
+    say!("   Memory limit (cgroup): {}", show(limits.memory_limit));
+    if let Some(available) = limits.memory_available {
+        say!("   Available memory: {}", size(available));
+    }
+    say!("   Address space (ulimit -v): {}", show(limits.address_space));
+    say!("   Locked memory (ulimit -l): {}", show(limits.locked_memory));
+    if let Some(files) = limits.open_files {
+        say!("   Open files (ulimit -n): {}", files);
+    }
+
+    if let Some(cap) = shortfall(&limits, rom_size, on_disk) {
+        if ignore {
+            say!("⚠️  ROM needs {} but the limit is {}; continuing (--ignore-limits)", size(needed), size(cap));
+        } else {
+            bail!(
+                "ROM needs about {} but this process is limited to {}. Raise the container \
+                 memory limit (e.g. docker run --memory 2g) or pass --ignore-limits",
+                size(needed),
+                size(cap)
+            );
+        }
+    } else if on_disk {
+        if limits.memory_available.is_some_and(|available| available < needed) {
+            say!("   Less free memory than the ROM: it is paged from disk, expect a lower hash rate");
+        }
+    } else if limits.memory_available.is_some_and(|available| available < needed) {
+        // Not fatal: the kernel may reclaim caches or swap
+        say!("⚠️  Less free memory than the ROM needs, expect swapping (or --rom-storage mmap)");
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+// ==================== MACHINE ID ====================
+//
+// Rigs that share one payout address are hard to tell apart afterwards:
+// --worker is optional and typed by hand. So every machine gets an ID of
+// its own, made up on the first run and kept in machine_id in the data
+// dir, and everything it reports carries it: metrics labels, the status
+// file, notifications, JSON log lines and the scans and solutions it
+// records. The file is left out of state sync, so a data dir restored on
+// another host gets a new ID; --machine-id sets one outright, for
+// containers whose data dir does not outlive them.
+
+use anyhow::{bail, Context, Result};
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+use std::sync::OnceLock;
+
+const MACHINE_ID_FILE: &str = "machine_id";
+
+static ID: OnceLock<String> = OnceLock::new();
+
+/// This machine's ID, once `init` has run
+pub fn id() -> Option<&'static str> {
+    ID.get().map(String::as_str)
+}
+
+/// 16 random hex digits; std seeds every RandomState from the OS
+fn generate() -> String {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    hasher.write_u128(now.map_or(0, |d| d.as_nanos()));
+    format!("{:016x}", hasher.finish())
+}
+
+/// --machine-id: something that reads well in a label and a file name
+pub fn parse(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    let valid = raw.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if raw.is_empty() || raw.len() > 64 || !valid {
+        return Err(format!("'{}' is not 1-64 letters, digits, '-' or '_'", raw));
+    }
+    Ok(raw.to_string())
+}
+
+/// The ID from --machine-id, else the one saved in `data_dir`, else a new
+/// one saved there
+pub fn init(data_dir: &Path, explicit: Option<&str>) -> Result<&'static str> {
+    let id = match explicit {
+        Some(id) => id.to_string(),
+        None => load_or_create(data_dir)?,
+    };
+    Ok(ID.get_or_init(|| id))
+}
+
+fn load_or_create(data_dir: &Path) -> Result<String> {
+    let path = data_dir.join(MACHINE_ID_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(saved) => match parse(&saved) {
+            Ok(id) => return Ok(id),
+            Err(e) => bail!("{}: {} (delete it for a new ID)", path.display(), e),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("Cannot read {}", path.display())),
+    }
+    std::fs::create_dir_all(data_dir)?;
+    let id = generate();
+    std::fs::write(&path, format!("{}\n", id))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(id)
+}
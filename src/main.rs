@@ -1,477 +1,1196 @@
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT, ACCEPT};
-use serde::{Deserialize, Serialize};
-use anyhow::{Result, Context};
-use std::time::Instant;
+use anyhow::Result;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::time::{Duration, Instant};
 use std::io::{self, Write};
 
 // Import AshMaize từ dependency
-use ashmaize::{hash, Rom, RomGenerationType};
+use ashmaize::{hash, Rom};
 
-const BASE_URL: &str = "https://scavenger.prod.gd.midnighttge.io";
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+compile_error!("the miner needs a TLS backend: enable `native-tls` (default) or `rustls`");
 
-// ==================== API STRUCTURES ====================
+// ==================== ALLOCATOR ====================
+//
+// ROM generation makes a few huge allocations and mining a steady churn of
+// small strings; the system allocator handles both fine on Linux but holds
+// on to freed pages on Windows. Either feature swaps it out for the miner.
+// On Unix it sits behind romfile::FileBacked, which hands the ROM to a
+// file mapping with --rom-storage mmap and everything else through.
 
-#[derive(Debug, Deserialize)]
-struct TandCResponse {
-    version: String,
-    content: String,
-    message: String,
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("features `jemalloc` and `mimalloc` both set a global allocator; enable one");
+
+#[cfg(feature = "jemalloc")]
+type Allocator = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+type Allocator = mimalloc::MiMalloc;
+
+#[cfg(not(any(feature = "jemalloc", feature = "mimalloc")))]
+type Allocator = std::alloc::System;
+
+#[cfg(unix)]
+#[global_allocator]
+static ALLOCATOR: romfile::FileBacked<Allocator> = romfile::FileBacked(Allocator {});
+
+#[cfg(not(unix))]
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator {};
+
+/// println! through the console backend (ASCII fallback, progress-line aware)
+macro_rules! say {
+    () => { $crate::console::line("") };
+    ($($arg:tt)*) => { $crate::console::line(&format!($($arg)*)) };
 }
 
-#[derive(Debug, Deserialize)]
-struct RegistrationResponse {
-    #[serde(rename = "registrationReceipt")]
-    registration_receipt: Option<RegistrationReceipt>,
-    #[serde(flatten)]
-    extra: serde_json::Value,
+mod address;
+mod advisor;
+mod apiclient;
+mod addressbook;
+mod audit;
+mod bench;
+mod bundle;
+mod calendar;
+mod catchup;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod chart;
+mod config;
+mod configfile;
+mod console;
+mod contrib;
+mod coordinator;
+mod daemon;
+mod engine;
+mod fleet;
+mod forensics;
+mod hdwallet;
+#[cfg(feature = "ledger")]
+mod ledger;
+mod histogram;
+mod limits;
+mod machine;
+mod memory;
+mod metrics;
+mod notify;
+mod power;
+mod profile;
+mod provision;
+mod rates;
+mod register;
+mod replay;
+mod retry;
+mod romcache;
+mod romfile;
+mod round;
+mod s3;
+mod schema;
+mod service;
+mod session;
+mod share;
+mod signature;
+mod signpage;
+mod spot;
+mod status;
+mod store;
+mod submitqueue;
+mod supervisor;
+mod sync;
+mod timefmt;
+mod tls;
+mod trend;
+#[cfg(feature = "tui")]
+mod tui;
+mod wallets;
+mod webdav;
+mod window;
+mod workhours;
+use apiclient::{MinerApi, ScavengerAPI};
+use engine::{initial_nonce, mine_challenge, MiningOptions, Solution, SprintPlan};
+use audit::AuditLog;
+use round::{EngineChoice, RoundOutcome, RoundSettings};
+use store::{SolutionStatus, Store};
+use supervisor::Supervisor;
+
+use scavenger_miner::mining::{
+    build_preimage, build_rom_sized, preimage_mismatches, DifficultyRule, MiningContext, NB_INSTRS,
+    NB_LOOPS, ROM_MIXING_NUMBERS, ROM_PRE_SIZE, ROM_SIZE,
+};
+use scavenger_miner::types::{
+    already_submitted, parse_deadline, Challenge, ChallengeCode, ChallengeResponse, CryptoReceipt,
+    NoChallenge, RegistrationResponse, SolutionResponse, TandCResponse, BASE_URL,
+};
+
+// ==================== CLI ====================
+//
+// Every flag a container needs also reads a SCAVENGER_* environment
+// variable, so `docker run -e ...` is all the configuration there is.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Prompts, one round, waits for ENTER at the end
+    Interactive,
+    /// No prompts or TTY, rounds back to back until stopped
+    Daemon,
 }
 
-#[derive(Debug, Deserialize)]
-struct RegistrationReceipt {
-    preimage: String,
-    signature: String,
-    timestamp: String,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    Text,
+    /// One JSON object per line on stdout
+    Json,
 }
 
-#[derive(Debug, Deserialize)]
-struct ChallengeResponse {
-    code: String,
-    challenge: Challenge,
-    #[serde(rename = "mining_period_ends")]
-    mining_period_ends: String,
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Exit 0 if the daemon in --data-dir is alive and making progress, 1 otherwise
+    Healthcheck {
+        /// Status file older than this means the process is gone
+        #[arg(long, value_name = "DURATION", default_value = "2m", value_parser = timefmt::parse_duration)]
+        max_age: Duration,
+
+        /// No progress for this long means the miner is stuck
+        #[arg(long, value_name = "DURATION", default_value = "30m", value_parser = timefmt::parse_duration)]
+        max_stall: Duration,
+    },
+    /// List labelled addresses with registration, receipts and earnings
+    Addresses {
+        #[command(subcommand)]
+        action: Option<AddressesAction>,
+    },
+    /// Hashes each --worker contributed to every accepted solution
+    Contributions {
+        /// Data dirs of the other workers (e.g. pulled from their remotes)
+        #[arg(long, value_name = "DIR")]
+        include: Vec<std::path::PathBuf>,
+
+        /// One JSON report per line
+        #[arg(long)]
+        json: bool,
+    },
+    /// What the daemon in --data-dir is doing, with a hash rate chart
+    Status {
+        /// The status file as one JSON object on stdout
+        #[arg(long)]
+        json: bool,
+    },
+    /// Solutions per day beside the difficulty of each day
+    History,
+    /// Days of the event with the challenges accepted and missed, and how many remain
+    Calendar,
+    /// Check that every stored receipt is for the nonce, address and challenge submitted
+    VerifyReceipts,
+    /// Difficulty and expected hashes of every archived challenge
+    DifficultyTrend,
+    /// Check the reward schedule now and list every version seen
+    Rates,
+    /// Mine one round --headless (needs --address or --label), then exit
+    Mine {
+        /// The result as one JSON object on stdout; progress goes to stderr
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the current challenge and, once calibrated, the chance of solving it
+    Challenge {
+        /// One JSON object on stdout
+        #[arg(long)]
+        json: bool,
+    },
+    /// Measure this machine's hash rate on the real ROM; no server or address needed
+    Bench {
+        /// How long to hash once the ROM is built
+        #[arg(long, value_name = "DURATION", default_value = "60s", value_parser = timefmt::parse_duration)]
+        duration: Duration,
+    },
+    /// Sign the Terms & Conditions and register the address
+    Register {
+        /// Check the signature locally and report what is wrong; send nothing
+        #[arg(long)]
+        verify_only: bool,
+
+        /// Signature (or the whole signData result) instead of the signing page
+        #[arg(long, value_name = "HEX")]
+        signature: Option<String>,
+
+        /// Public key, when --signature is only the COSE_Sign1
+        #[arg(long, value_name = "HEX")]
+        pubkey: Option<String>,
+
+        /// Sign here with this cardano-cli signing key (payment.skey) instead
+        /// of a browser wallet
+        #[arg(
+            long,
+            value_name = "FILE",
+            env = "SCAVENGER_SKEY",
+            conflicts_with_all = ["signature", "pubkey"]
+        )]
+        skey: Option<std::path::PathBuf>,
+
+        /// Derive the address and key from the wallet's recovery phrase
+        /// (SCAVENGER_MNEMONIC, else prompted for) and sign with it; nothing
+        /// is saved
+        #[arg(long, group = "derive", conflicts_with_all = ["signature", "pubkey", "skey"])]
+        mnemonic: bool,
+
+        /// Derive the address and sign on a Ledger (Cardano app 7.1 or later)
+        #[cfg(feature = "ledger")]
+        #[arg(
+            long,
+            group = "derive",
+            conflicts_with_all = ["signature", "pubkey", "skey", "mnemonic"]
+        )]
+        ledger: bool,
+
+        /// Wallet account for --mnemonic or --ledger
+        #[arg(long, value_name = "N", default_value_t = 0, requires = "derive")]
+        account: u32,
+
+        /// Payment address index within the account for --mnemonic or --ledger
+        #[arg(long, value_name = "N", default_value_t = 0, requires = "derive")]
+        address_index: u32,
+
+        /// Derive a testnet address with --mnemonic or --ledger
+        #[arg(long, requires = "derive")]
+        testnet: bool,
+    },
+    /// Windows: start the daemon at logon or boot with the current flags (Task Scheduler)
+    InstallTask {
+        /// Task name
+        #[arg(long, default_value = "ScavengerMiner")]
+        name: String,
+
+        #[arg(long, value_enum, default_value_t = service::TaskTrigger::Logon)]
+        trigger: service::TaskTrigger,
+
+        /// Print the task XML instead of registering it
+        #[arg(long)]
+        print: bool,
+    },
+    /// macOS: run the daemon at login and keep it alive with the current flags (launchd)
+    InstallLaunchd {
+        /// LaunchAgent label, also the plist file name
+        #[arg(long, default_value = "com.scavenger.miner")]
+        label: String,
+
+        /// Scheduling priority from -20 to 20; higher yields more CPU to other apps
+        #[arg(long, default_value_t = 10, allow_negative_numbers = true)]
+        nice: i32,
+
+        /// Print the plist instead of installing it
+        #[arg(long)]
+        print: bool,
+    },
+    /// Fetch a config file, check it, write it to --config and start the daemon
+    Provision {
+        /// Where to download the config file from
+        #[arg(long, value_name = "URL")]
+        from_url: String,
+
+        /// Expected SHA-256 of the file, from a source other than URL. Without
+        /// it the first word of <URL>.sha256 is used, which catches a broken
+        /// download but not a tampered one; --start service requires it.
+        #[arg(long, value_name = "HEX")]
+        sha256: Option<String>,
+
+        /// How to start the daemon once the file is in place
+        #[arg(long, value_enum, default_value_t = provision::Start::Background)]
+        start: provision::Start,
+    },
+    /// Write a commented config.toml template (to --config) for this machine
+    Init {
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Settings for several rigs kept in one fleet file
+    Fleet {
+        #[command(subcommand)]
+        action: FleetAction,
+    },
+    /// Share registrations, history, receipts and settings with your other machines,
+    /// encrypted with a passphrase (SCAVENGER_SYNC_PASSPHRASE or a prompt)
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+
+        /// Where the encrypted bundle lives: a directory, s3://bucket/prefix
+        /// or a WebDAV URL (credentials in WEBDAV_USERNAME, WEBDAV_PASSWORD)
+        #[arg(
+            long,
+            global = true,
+            value_name = "DIR|S3_URL|WEBDAV_URL",
+            env = "SCAVENGER_SYNC_REMOTE",
+            value_parser = sync::Remote::parse
+        )]
+        remote: Option<sync::Remote>,
+    },
 }
 
-#[derive(Debug, Deserialize)]
-struct Challenge {
-    challenge_id: String,
-    day: u32,
-    challenge_number: u32,
-    difficulty: String,
-    no_pre_mine: String,
-    latest_submission: String,
-    no_pre_mine_hour: String,
+#[derive(Debug, Subcommand)]
+enum AddressesAction {
+    /// Label an address (replaces an existing label)
+    Add { label: String, address: String },
+    Remove { label: String },
 }
 
-#[derive(Debug, Deserialize)]
-struct SolutionResponse {
-    crypto_receipt: Option<CryptoReceipt>,
-    #[serde(flatten)]
-    extra: serde_json::Value,
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    /// Validate the flags and environment, print the effective settings, exit 1 on problems
+    Check,
 }
 
-#[derive(Debug, Deserialize)]
-struct CryptoReceipt {
-    preimage: String,
-    timestamp: String,
-    signature: String,
+#[derive(Debug, Subcommand)]
+enum SyncAction {
+    /// Merge this machine's state into the bundle, creating it on the first push
+    Push,
+    /// Merge the bundle into --data-dir and --config; local files are never overwritten
+    Pull,
 }
 
-// ==================== API CLIENT ====================
+#[derive(Debug, Subcommand)]
+enum FleetAction {
+    /// Print one host's effective config.toml: shared keys, its role's, then its own
+    Render {
+        /// Host name: the coordinator's host or a [workers.<name>] section
+        #[arg(long)]
+        host: String,
 
-struct ScavengerAPI {
-    client: reqwest::Client,
+        /// Fleet file with [coordinator] and [workers.*] sections
+        #[arg(long, value_name = "FILE", default_value = fleet::DEFAULT_PATH)]
+        fleet: std::path::PathBuf,
+
+        /// Write here instead of stdout
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
 }
 
-impl ScavengerAPI {
-    fn new() -> Result<Self> {
-        let mut headers = HeaderMap::new();
-        
-        headers.insert(
-            USER_AGENT,
-            HeaderValue::from_static(
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36"
-            ),
-        );
-        
-        headers.insert(
-            ACCEPT,
-            HeaderValue::from_static("application/json, text/plain, */*"),
-        );
-        
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .context("Failed to build HTTP client")?;
-        
-        Ok(ScavengerAPI { client })
-    }
-    
-    async fn get_terms(&self) -> Result<TandCResponse> {
-        let url = format!("{}/TandC", BASE_URL);
-        let response = self.client.get(&url).send().await?;
-        
-        if !response.status().is_success() {
-            let body = response.text().await?;
-            anyhow::bail!("Failed to fetch T&C: {}", body);
-        }
-        
-        Ok(response.json().await?)
-    }
+#[derive(Debug, Parser)]
+#[command(name = "scavenger_miner", version, about = "Scavenger Mine client powered by AshMaize")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// interactive or daemon
+    #[arg(long, value_enum, env = "SCAVENGER_MODE", default_value_t = Mode::Interactive)]
+    mode: Mode,
+
+    /// Never read stdin: no prompts, no ENTER at the end, --confirm ignored.
+    /// Exit status: 0 accepted, 2 no solution, 3 rejected, 4 not delivered
+    #[arg(long, env = "SCAVENGER_HEADLESS")]
+    headless: bool,
+
+    /// Interactive and `mine`: keep going after each challenge, fetching the
+    /// next one and mining again until stopped (daemon mode always does)
+    #[arg(long = "loop", env = "SCAVENGER_LOOP")]
+    keep_mining: bool,
+
+    /// Daemon and headless: first mine earlier challenges that were missed
+    /// but still take solutions (interactive runs ask instead)
+    #[arg(long, env = "SCAVENGER_CATCH_UP")]
+    catch_up: bool,
+
+    /// Settings file (TOML, keys are flag names); flags and SCAVENGER_* override it
+    #[arg(long, value_name = "FILE", env = "SCAVENGER_CONFIG", default_value = configfile::DEFAULT_PATH)]
+    config: std::path::PathBuf,
+
+    /// TLS stack for HTTPS when the build has both (default: native)
+    #[arg(long, value_enum, value_name = "BACKEND", env = "SCAVENGER_TLS")]
+    tls: Option<tls::TlsBackend>,
+
+    /// Also trust the certificates in this PEM file (a TLS-inspecting proxy's
+    /// root); repeat for more files
+    #[arg(long, value_name = "PEM", env = "SCAVENGER_EXTRA_CA_CERT", value_delimiter = ',')]
+    extra_ca_cert: Vec<std::path::PathBuf>,
+
+    /// Skip TLS certificate verification, to debug a proxy. Needs
+    /// SCAVENGER_INSECURE_CONFIRM; refused for unattended runs (daemon,
+    /// headless, --loop, fleet roles, provision and the service installers)
+    #[arg(long)]
+    insecure: bool,
+
+    /// Scavenger Mine API to talk to
+    #[arg(long, value_name = "URL", env = "SCAVENGER_BASE_URL", default_value = BASE_URL)]
+    base_url: String,
+
+    /// Mine as a worker of the coordinator at this URL (nonce ranges from it,
+    /// solutions submitted through it) instead of talking to the API
+    #[arg(long, value_name = "URL", env = "SCAVENGER_COORDINATOR", conflicts_with = "coordinate")]
+    coordinator: Option<String>,
+
+    /// Coordinate --coordinator workers from this address instead of mining:
+    /// fetch challenges, hand out disjoint nonce ranges, submit what they find
+    #[arg(long, value_name = "ADDR", env = "SCAVENGER_COORDINATE")]
+    coordinate: Option<std::net::SocketAddr>,
+
+    /// Tries per API call when the server is unreachable, times out or answers
+    /// 5xx/429, waiting longer after each (1 = no retries)
+    #[arg(
+        long,
+        value_name = "N",
+        env = "SCAVENGER_API_RETRIES",
+        default_value_t = retry::DEFAULT_ATTEMPTS,
+        value_parser = clap::value_parser!(u32).range(1..=20)
+    )]
+    api_retries: u32,
+
+    /// Cardano address to mine for (skips the prompt; required in daemon mode)
+    #[arg(long, env = "SCAVENGER_ADDRESS")]
+    address: Option<String>,
 
-    async fn register(
-        &self,
-        address: &str,
-        signature: &str,
-        pubkey: &str,
-    ) -> Result<RegistrationResponse> {
-        let url = format!(
-            "{}/register/{}/{}/{}",
-            BASE_URL, address, signature, pubkey
+    /// Use the address with this label in the address book
+    #[arg(long, env = "SCAVENGER_LABEL", conflicts_with = "address")]
+    label: Option<String>,
+
+    /// Take turns with another payout address: an address-book label or an
+    /// address, with a weight (default 1). Repeat for more; the --address or
+    /// --label one takes part with weight 1 unless listed
+    #[arg(
+        long,
+        value_name = "NAME[=WEIGHT]",
+        env = "SCAVENGER_SHARE",
+        value_delimiter = ',',
+        value_parser = share::parse
+    )]
+    share: Vec<(String, u32)>,
+
+    /// How --share takes turns: per challenge, or per --share-slice of time
+    #[arg(
+        long,
+        value_enum,
+        env = "SCAVENGER_SHARE_BY",
+        default_value_t = share::ShareBy::Challenge
+    )]
+    share_by: share::ShareBy,
+
+    /// Length of one address's turn with --share-by slice
+    #[arg(
+        long,
+        value_name = "DURATION",
+        env = "SCAVENGER_SHARE_SLICE",
+        default_value = "1h",
+        value_parser = share::parse_slice
+    )]
+    share_slice: Duration,
+
+    /// Name of this machine in contribution reports when several mine one address
+    #[arg(long, value_name = "NAME", env = "SCAVENGER_WORKER")]
+    worker: Option<String>,
+
+    /// ID this machine reports in metrics, status, notifications and records
+    /// (default: made up once and kept in the data dir)
+    #[arg(long, value_name = "ID", env = "SCAVENGER_MACHINE_ID", value_parser = machine::parse)]
+    machine_id: Option<String>,
+
+    /// After each accepted solution, write a payout suggestion to payouts/ using this policy
+    #[arg(long, value_enum, env = "SCAVENGER_SPLIT_POLICY")]
+    split_policy: Option<contrib::SplitPolicy>,
+
+    /// Ranges counted by --split-policy last-n
+    #[arg(long, value_name = "N", env = "SCAVENGER_SPLIT_LAST_N", default_value_t = 10)]
+    split_last_n: usize,
+
+    /// Register and mine under the payment address or the wallet's stake address
+    #[arg(long, value_enum, env = "SCAVENGER_ADDRESS_KIND", default_value_t = address::AddressKind::Payment)]
+    address_kind: address::AddressKind,
+
+    /// Log format (default: json in daemon mode, text otherwise)
+    #[arg(long, value_enum, env = "SCAVENGER_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Hardware preset: standard, low-memory (phones, Termux) or sbc (Raspberry Pi)
+    #[arg(long, value_enum, env = "SCAVENGER_PROFILE", default_value_t = profile::Profile::Standard)]
+    profile: profile::Profile,
+
+    /// Number of mining threads (CPU limit)
+    #[arg(long, env = "SCAVENGER_THREADS", default_value_t = 1)]
+    threads: usize,
+
+    /// Start a final sprint this many minutes before mining_period_ends: the
+    /// thread count goes up to --sprint-threads until the deadline. Only the
+    /// thread count changes; the process keeps its scheduling priority
+    #[arg(long, value_name = "MINUTES", env = "SCAVENGER_SPRINT_MINUTES")]
+    sprint_minutes: Option<u64>,
+
+    /// Thread count during the final sprint, above the --threads CPU limit
+    /// (default: all CPU cores)
+    #[arg(long, value_name = "N", env = "SCAVENGER_SPRINT_THREADS")]
+    sprint_threads: Option<usize>,
+
+    /// Hours the machine is needed for other work, in local time, e.g.
+    /// "mon-fri 09:00-18:00" or "22:00-06:00"; --work-yield of the threads
+    /// (sprint threads included) step aside then and come back after
+    #[arg(
+        long,
+        value_name = "[DAYS ]HH:MM-HH:MM",
+        env = "SCAVENGER_WORK_HOURS",
+        value_parser = workhours::parse
+    )]
+    work_hours: Option<workhours::WorkHours>,
+
+    /// Percentage of the threads yielded during --work-hours
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        env = "SCAVENGER_WORK_YIELD",
+        default_value = "50",
+        value_parser = advisor::parse_percent
+    )]
+    work_yield: f64,
+
+    /// Wallet to list first on the registration signing page
+    #[arg(long, value_enum, env = "SCAVENGER_WALLET")]
+    wallet: Option<wallets::Wallet>,
+
+    /// Verify found nonces locally and print the request instead of POSTing it
+    #[arg(long, env = "SCAVENGER_DRY_RUN")]
+    dry_run: bool,
+
+    /// Show the full solution and ask before submitting
+    #[arg(long)]
+    confirm: bool,
+
+    /// Seconds to wait for the confirmation answer before auto-submitting
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    confirm_timeout: u64,
+
+    /// Force plain ASCII symbols instead of emoji and box drawing
+    #[arg(long)]
+    ascii: bool,
+
+    /// Screen-reader and log friendly output: no emoji, box art or in-place updates
+    #[arg(long)]
+    plain: bool,
+
+    /// Full-screen dashboard while mining: per-thread rates, deadlines,
+    /// solutions; p pauses, q quits (interactive runs in a terminal)
+    #[cfg(feature = "tui")]
+    #[arg(long, env = "SCAVENGER_TUI")]
+    tui: bool,
+
+    /// Re-run the mining flow against a recorded audit log instead of the server
+    #[arg(long, value_name = "AUDIT_JSONL")]
+    replay: Option<std::path::PathBuf>,
+
+    /// Only replay entries from this UTC day (YYYY-MM-DD)
+    #[arg(long, value_name = "DAY", requires = "replay")]
+    replay_day: Option<String>,
+
+    /// Stop mining cleanly after this long, counted from startup (e.g. 45m, 2h, 1h30m)
+    #[arg(
+        long,
+        value_name = "DURATION",
+        env = "SCAVENGER_MAX_DURATION",
+        value_parser = timefmt::parse_duration
+    )]
+    max_duration: Option<Duration>,
+
+    /// Stop mining cleanly after this many hashes (per round in daemon mode;
+    /// `mine` defaults to 1000000)
+    #[arg(long, visible_alias = "iterations", value_name = "N", env = "SCAVENGER_MAX_HASHES")]
+    max_hashes: Option<u64>,
+
+    /// ROM size for experiments, e.g. 64M. Solutions from anything but the
+    /// protocol's 1G don't verify on the server and are never submitted
+    #[arg(long, value_name = "SIZE", env = "SCAVENGER_ROM_SIZE", value_parser = limits::parse_size)]
+    rom_size: Option<usize>,
+
+    /// Acknowledge that a non-protocol --rom-size makes every solution unsubmittable
+    #[arg(long = "i-know-this-breaks-submissions")]
+    breaks_submissions_ack: bool,
+
+    /// Where the ROM lives: ram, or mmap for a file the OS pages in and out
+    /// (for machines with less free RAM than the ROM; slower)
+    #[arg(long, value_enum, env = "SCAVENGER_ROM_STORAGE", default_value_t = romfile::RomStorage::Ram)]
+    rom_storage: romfile::RomStorage,
+
+    /// Directory for the --rom-storage mmap file (default: --data-dir)
+    #[arg(long, value_name = "DIR", env = "SCAVENGER_ROM_DIR")]
+    rom_dir: Option<std::path::PathBuf>,
+
+    /// Daemon mode: skip challenges whose chance of a solution before they
+    /// close is below this percentage (needs one earlier run to calibrate)
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        env = "SCAVENGER_MIN_SUCCESS_CHANCE",
+        value_parser = advisor::parse_percent
+    )]
+    min_success_chance: Option<f64>,
+
+    /// Server acceptance limit: after an accepted solution, wait for the next challenge or day
+    #[arg(
+        long,
+        value_enum,
+        env = "SCAVENGER_ACCEPTANCE_RULE",
+        default_value_t = store::AcceptanceRule::PerChallenge
+    )]
+    acceptance_rule: store::AcceptanceRule,
+
+    /// ROM while waiting for the next challenge: keep (instant start when the seed repeats,
+    /// ~1 GB held) or drop (nothing held, full rebuild)
+    #[arg(
+        long,
+        value_enum,
+        env = "SCAVENGER_IDLE_ROM",
+        default_value_t = round::RomRetention::Drop
+    )]
+    idle_rom: round::RomRetention,
+
+    /// While waiting for the next challenge, poll less often, backing off up to this
+    #[arg(
+        long,
+        value_name = "DURATION",
+        env = "SCAVENGER_IDLE_POLL_MAX",
+        default_value = "5m",
+        value_parser = timefmt::parse_duration
+    )]
+    idle_poll_max: Duration,
+
+    /// How a hash is checked against the difficulty: the digest prefix at or
+    /// below it (prefix), the digest below it as a number (below), or as
+    /// many leading zero bits / bytes as it has (zero-bits, zero-bytes)
+    #[arg(
+        long,
+        value_enum,
+        env = "SCAVENGER_DIFFICULTY_RULE",
+        default_value_t = DifficultyRule::Prefix
+    )]
+    difficulty_rule: DifficultyRule,
+
+    /// Push solutions and heartbeats to a phone:
+    /// ntfy://<topic>, https://<ntfy server>/<topic> or pushover://<user key>@<app token>
+    #[arg(
+        long,
+        value_name = "URL",
+        env = "SCAVENGER_NOTIFY",
+        value_parser = notify::Notifier::parse
+    )]
+    notify: Option<notify::Notifier>,
+
+    /// Heartbeat interval for --notify in daemon and --loop mode: state, hash
+    /// rate and solutions so far (0 = solutions only)
+    #[arg(
+        long,
+        visible_alias = "heartbeat-every",
+        value_name = "DURATION",
+        env = "SCAVENGER_NOTIFY_EVERY",
+        default_value = "6h",
+        value_parser = timefmt::parse_duration
+    )]
+    notify_every: Duration,
+
+    /// Serve Prometheus metrics at http://ADDR/metrics, e.g. 0.0.0.0:9184
+    #[arg(long, value_name = "ADDR", env = "SCAVENGER_METRICS_ADDR")]
+    metrics_addr: Option<std::net::SocketAddr>,
+
+    /// Start even when the memory limits look too small for the ROM
+    #[arg(long, env = "SCAVENGER_IGNORE_LIMITS")]
+    ignore_limits: bool,
+
+    /// Watch this cloud's metadata service for spot/preemption notices
+    #[arg(long, value_name = "CLOUD", env = "SCAVENGER_SPOT")]
+    spot: Option<spot::Cloud>,
+
+    /// Where state outlives the instance: a directory (e.g. a network volume) or
+    /// s3://bucket/prefix. Seeds a fresh data dir at startup, receives the state
+    /// after each run and on spot termination
+    #[arg(
+        long,
+        value_name = "DIR|S3_URL",
+        env = "SCAVENGER_STATE_REMOTE",
+        value_parser = sync::Remote::parse
+    )]
+    state_remote: Option<sync::Remote>,
+
+    /// Directory for history and receipts
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "SCAVENGER_DATA_DIR",
+        default_value = store::DEFAULT_DATA_DIR
+    )]
+    data_dir: std::path::PathBuf,
+}
+
+/// Where --rom-storage mmap keeps its file
+fn rom_dir(cli: &Cli) -> &std::path::Path {
+    cli.rom_dir.as_deref().unwrap_or(&cli.data_dir)
+}
+
+/// The --rom-size override, when it differs from the protocol ROM
+fn experimental_rom_size(cli: &Cli) -> Result<Option<usize>> {
+    let Some(size) = cli.rom_size.filter(|&size| size != ROM_SIZE) else {
+        return Ok(None);
+    };
+    if !cli.breaks_submissions_ack {
+        anyhow::bail!(
+            "--rom-size {} differs from the protocol ROM ({}); the server rejects every \
+             solution mined with it. Pass --i-know-this-breaks-submissions to experiment anyway",
+            limits::size(size as u64),
+            limits::size(ROM_SIZE as u64)
         );
-        
-        let response = self.client.post(&url).send().await?;
-        
-        if !response.status().is_success() {
-            let body = response.text().await?;
-            anyhow::bail!("Registration failed: {}", body);
-        }
-        
-        Ok(response.json().await?)
     }
+    say!(
+        "🧪 Experimental ROM of {}: solutions are recorded as not submittable",
+        limits::size(size as u64)
+    );
+    Ok(Some(size))
+}
+
+// ==================== MAIN ====================
 
-    async fn get_challenge(&self) -> Result<ChallengeResponse> {
-        let url = format!("{}/challenge", BASE_URL);
-        let response = self.client.get(&url).send().await?;
-        Ok(response.json().await?)
+/// The server (or --coordinator), every call audited
+fn api_client(cli: &Cli, store: &Store) -> Result<ScavengerAPI> {
+    let mut api = ScavengerAPI::new(cli.coordinator.as_deref().unwrap_or(&cli.base_url))?
+        .with_audit_log(AuditLog::new(store.audit_path()));
+    if cli.coordinator.is_some() {
+        let worker = cli.worker.clone().or(machine::id().map(str::to_string)).unwrap_or_default();
+        api = api.with_coordinator(worker);
     }
+    Ok(api)
+}
 
-    async fn submit_solution(
-        &self,
-        address: &str,
-        challenge_id: &str,
-        nonce: &str,
-    ) -> Result<SolutionResponse> {
-        let url = format!(
-            "{}/solution/{}/{}/{}",
-            BASE_URL, address, challenge_id, nonce
-        );
-        
-        let response = self.client.post(&url).send().await?;
-        Ok(response.json().await?)
+/// Send solutions left undelivered, by this run or an earlier one, in the background
+fn spawn_submit_queue(cli: &Cli, store: &Store, supervisor: &mut Supervisor) -> Result<()> {
+    if cli.dry_run {
+        return Ok(());
+    }
+    match submitqueue::pending(store) {
+        0 => {}
+        n => say!("📮 {} solution(s) waiting in the submission queue", n),
     }
+    let (api, store) = (api_client(cli, store)?, Store::open(&cli.data_dir)?);
+    supervisor.spawn("submit queue", move |cancel| submitqueue::service(api, store, cancel));
+    Ok(())
+}
+
+/// Heartbeats for --notify, for runs that go on unattended
+fn spawn_heartbeats(cli: &Cli, supervisor: &mut Supervisor) {
+    let (Some(notifier), false) = (cli.notify.clone(), cli.notify_every.is_zero()) else {
+        return;
+    };
+    say!("📲 Notifying {} (heartbeat every {})", notifier, timefmt::humanize(
+        chrono::Duration::from_std(cli.notify_every).unwrap_or_default()
+    ));
+    let every = cli.notify_every;
+    supervisor.spawn("notify", move |cancel| notify::heartbeats(notifier, every, cancel));
+}
 
-    async fn get_star_rate(&self) -> Result<Vec<u64>> {
-        let url = format!("{}/work_to_star_rate", BASE_URL);
-        let response = self.client.get(&url).send().await?;
-        Ok(response.json().await?)
+/// Exit status of a headless round, for scripts and service managers:
+/// 0 accepted (or not meant to be submitted), 1 error, 2 no solution within
+/// the budget, 3 rejected by the server, 4 found but not delivered
+fn exit_code(outcome: &RoundOutcome) -> i32 {
+    match outcome {
+        RoundOutcome::Solution(SolutionStatus::Accepted)
+        | RoundOutcome::Solution(SolutionStatus::AcceptedNoReceipt)
+        | RoundOutcome::Solution(SolutionStatus::DryRun)
+        | RoundOutcome::Solution(SolutionStatus::NotSubmittable) => 0,
+        RoundOutcome::NotFound | RoundOutcome::Solution(SolutionStatus::Cancelled) => 2,
+        RoundOutcome::Solution(SolutionStatus::Rejected) => 3,
+        RoundOutcome::Solution(SolutionStatus::SubmitFailed) => 4,
     }
 }
 
-// ==================== MINING LOGIC ====================
+/// `mine --json`: the round's outcome, exit status and solution record
+fn print_result(store: &Store, address: &str, result: &Result<RoundOutcome>) {
+    let value = match result {
+        Ok(outcome) => {
+            let (name, solution) = match outcome {
+                RoundOutcome::Solution(status) => {
+                    // The round has just recorded it
+                    let solutions = store.solutions().unwrap_or_default();
+                    let solution = solutions.into_iter().rev().find(|s| s.address == address);
+                    (serde_json::to_value(status).unwrap_or_default(), solution)
+                }
+                RoundOutcome::NotFound => (serde_json::json!("not_found"), None),
+            };
+            serde_json::json!({
+                "outcome": name,
+                "exit_code": exit_code(outcome),
+                "solution": solution,
+            })
+        }
+        Err(e) => serde_json::json!({
+            "outcome": "error",
+            "exit_code": 1,
+            "error": format!("{:#}", e),
+        }),
+    };
+    println!("{}", value);
+}
 
-struct MiningContext {
-    rom: Rom,
-    nb_loops: u32,
-    nb_instrs: u32,
+fn wait_for_enter() {
+    say!();
+    console::banner(&["Press ENTER to exit..."]);
+    
+    io::stdout().flush().unwrap();
+    let _ = console::read_line(None);
 }
 
-impl MiningContext {
-    fn new(no_pre_mine: &str, nb_loops: u32, nb_instrs: u32) -> Self {
-        println!("🔧 Initializing AshMaize ROM...");
-        println!("   Seed: {}...", &no_pre_mine[..16.min(no_pre_mine.len())]);
-        println!("   Loops: {}", nb_loops);
-        println!("   Instructions: {}", nb_instrs);
-        
-        // ROM parameters
-        const PRE_SIZE: usize = 16 * 1024 * 1024;        // 16 MB
-        const ROM_SIZE: usize = 1024 * 1024 * 1024; // 1 GB
-        
-        let rom = Rom::new(
-            no_pre_mine.as_bytes(),
-            RomGenerationType::TwoStep {
-                pre_size: PRE_SIZE,
-                mixing_numbers: 4,
-            },
-            ROM_SIZE,
-        );
-        
-        println!("✅ ROM initialized ({} MB)", ROM_SIZE / 1_024 / 1_024);
-        
-        Self { rom, nb_loops, nb_instrs }
+fn main() -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let result = runtime.block_on(run());
+    // Dropping the runtime joins its blocking threads, and an abandoned ROM
+    // build would hold Ctrl+C up until the whole ROM is done
+    if romcache::abandoned_running() {
+        runtime.shutdown_background();
     }
-    
-    fn hash(&self, preimage: &str) -> [u8; 64] {
-        hash(preimage.as_bytes(), &self.rom, self.nb_loops, self.nb_instrs)
+    result
+}
+
+/// The unattended role this run has, if any, for refusing --insecure: the
+/// subcommands that install or start a daemon, then the modes nobody watches
+fn unattended_role(cli: &Cli) -> Option<&'static str> {
+    match &cli.command {
+        Some(Command::Provision { .. }) => return Some("for provision, which installs a daemon"),
+        Some(Command::InstallTask { .. }) => return Some("for install-task"),
+        Some(Command::InstallLaunchd { .. }) => return Some("for install-launchd"),
+        Some(Command::Mine { .. }) => return Some("for `mine`"),
+        _ => {}
+    }
+    if cli.coordinate.is_some() {
+        Some("for the coordinator (--coordinate)")
+    } else if cli.coordinator.is_some() {
+        Some("for fleet workers (--coordinator)")
+    } else if cli.mode == Mode::Daemon {
+        Some("in daemon mode")
+    } else if cli.headless {
+        Some("for headless runs")
+    } else {
+        cli.keep_mining.then_some("with --loop")
     }
 }
 
-fn meets_difficulty(hash: &[u8], difficulty: &str) -> bool {
-    let diff_bytes = match hex::decode(difficulty) {
-        Ok(bytes) => bytes,
-        Err(_) => return false,
+async fn run() -> Result<()> {
+    let started = Instant::now();
+    let file = configfile::ConfigFile::load(&Cli::command())?;
+    let command = match &file {
+        Some(file) => file.apply(Cli::command()),
+        None => Cli::command(),
     };
-    
-    for i in 0..4.min(diff_bytes.len()) {
-        if i >= hash.len() {
-            return false;
+    let matches = command.get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    tls::init(cli.tls, &cli.extra_ca_cert, cli.insecure, unattended_role(&cli))?;
+    retry::init(cli.api_retries);
+
+    // Only a mining run logs JSON; `mine --json` keeps stdout for its result
+    let mines = matches!(cli.command, None | Some(Command::Mine { .. }));
+    if matches!(cli.command, Some(Command::Mine { json: true })) {
+        console::use_stderr();
+    }
+    console::init(cli.ascii, cli.plain, mines && json_logs(&cli));
+
+    match &cli.command {
+        Some(Command::Healthcheck { max_age, max_stall }) => {
+            status::healthcheck(&cli.data_dir, *max_age, *max_stall)
+        }
+        Some(Command::Config { action: ConfigAction::Check }) => {
+            config::check(&cli, &matches, file.as_ref())
+        }
+        Some(Command::Fleet { action: FleetAction::Render { host, fleet, output } }) => {
+            fleet::render(&Cli::command(), fleet, host, output.as_deref())
+        }
+        Some(Command::Provision { from_url, sha256, start }) => {
+            tls::warn_if_insecure();
+            provision::run(&cli, from_url, sha256.as_deref(), *start).await
+        }
+        Some(Command::Init { force }) => configfile::init(&cli, *force),
+        Some(Command::InstallTask { name, trigger, print }) => {
+            service::install_task(&cli, name, *trigger, *print)
+        }
+        Some(Command::InstallLaunchd { label, nice, print }) => {
+            service::install_launchd(&cli, label, *nice, *print)
+        }
+        Some(Command::Status { json }) => {
+            status::print(&cli.data_dir.join(store::STATUS_FILE), *json)
         }
-        if hash[i] < diff_bytes[i] {
-            return true;
+        Some(Command::History) => {
+            trend::history(&Store::open(&cli.data_dir)?, cli.difficulty_rule)
         }
-        if hash[i] > diff_bytes[i] {
-            return false;
+        Some(Command::Rates) => rates::run(&cli).await,
+        Some(Command::Calendar) => calendar::run(&cli).await,
+        Some(Command::VerifyReceipts) => forensics::verify_receipts(&Store::open(&cli.data_dir)?),
+        Some(Command::DifficultyTrend) => {
+            trend::print(&Store::open(&cli.data_dir)?, cli.difficulty_rule)
         }
+        Some(Command::Contributions { include, json }) => {
+            contrib::print(&cli.data_dir, include, *json)
+        }
+        Some(Command::Sync { action, remote }) => {
+            bundle::run(&cli.data_dir, &cli.config, action, remote.as_ref()).await
+        }
+        Some(Command::Addresses { action }) => addressbook::run(&cli, action.as_ref()).await,
+        Some(Command::Challenge { json }) => round::show_challenge(&cli, *json).await,
+        Some(Command::Bench { duration }) => bench::run(&cli, *duration).await,
+        Some(Command::Register { .. }) => register::command(&cli).await,
+        None | Some(Command::Mine { .. }) => mine(cli, started).await,
     }
-    true
 }
 
-fn build_preimage(
-    nonce: &str,
-    address: &str,
-    challenge: &Challenge,
-) -> String {
-    format!(
-        "{}{}{}{}{}{}{}",
-        nonce,
-        address,
-        challenge.challenge_id,
-        challenge.difficulty,
-        challenge.no_pre_mine,
-        challenge.latest_submission,
-        challenge.no_pre_mine_hour
-    )
+/// Whether a mining run logs JSON lines: --log-format, else daemons do
+fn json_logs(cli: &Cli) -> bool {
+    cli.log_format.map_or(cli.mode == Mode::Daemon, |format| format == LogFormat::Json)
 }
 
-fn mine_challenge(
-    address: &str,
-    challenge: &Challenge,
-    max_iterations: u64,
-) -> Option<String> {
-    println!("\n🔨 Mining started");
-    println!("   Challenge ID: {}", challenge.challenge_id);
-    println!("   Difficulty: {}", challenge.difficulty);
-    println!("   Max iterations: {}", max_iterations);
-    
-    // Initialize AshMaize
-    const NB_LOOPS: u32 = 8;
-    const NB_INSTRS: u32 = 256;
-    let ctx = MiningContext::new(&challenge.no_pre_mine, NB_LOOPS, NB_INSTRS);
-    
-    let start = Instant::now();
-    let mut last_report = Instant::now();
-    
-    // Start with random nonce to avoid collisions
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let random_start = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    
-    println!("   Starting nonce: 0x{:016x}", random_start);
-    
-    for i in 0..max_iterations {
-        let nonce = random_start.wrapping_add(i);
-        let nonce_hex = format!("{:016x}", nonce);
+/// --label, else --address, else the address a phrase or device derived;
+/// None when nothing names one
+fn given_address(cli: &Cli, derived: Option<&String>) -> Result<Option<String>> {
+    let labelled = match &cli.label {
+        Some(label) => Some(addressbook::lookup(&cli.data_dir, label)?),
+        None => None,
+    };
+    let given = labelled.as_ref().or(cli.address.as_ref()).or(derived);
+    Ok(given.map(|address| address.trim().to_string()))
+}
 
-        // Build preimage
-        let preimage = build_preimage(&nonce_hex, address, challenge);
-        
-        // Hash with AshMaize
-        let hash = ctx.hash(&preimage);
-        
-        // Check difficulty
-        if meets_difficulty(&hash, &challenge.difficulty) {
-            let elapsed = start.elapsed();
-            println!("\n✅ FOUND VALID NONCE!");
-            println!("   Nonce: 0x{}", nonce_hex);
-            println!("   Nonce (dec): {}", nonce);
-            println!("   Hash: {}", hex::encode(&hash[..8]));
-            println!("   Time: {:.2}s", elapsed.as_secs_f64());
-            println!("   Rate: {:.0} H/s", i as f64 / elapsed.as_secs_f64());
-            return Some(nonce_hex);
+/// The address to run under for --address-kind, printed with what it is
+fn resolve_address(cli: &Cli, address: String) -> Result<String> {
+    match address::resolve(&address, cli.address_kind) {
+        Ok((resolved, kind)) => {
+            say!("\n📍 Address: {}", resolved);
+            say!("   {}", kind);
+            Ok(resolved)
         }
-        
-        // Progress report every second
-        if last_report.elapsed().as_secs() >= 1 {
-            let elapsed = start.elapsed().as_secs_f64();
-            let rate = i as f64 / elapsed;
-            print!("\r   ⛏️  Iteration: {:>10} | Rate: {:>8.0} H/s | Time: {:>6.1}s", 
-                i, rate, elapsed);
-            io::stdout().flush().unwrap();
-            last_report = Instant::now();
+        // Payment addresses are passed through; the server has the last word
+        Err(e) if cli.address_kind == address::AddressKind::Payment => {
+            say!("\n📍 Address: {}", address);
+            say!("⚠️  {}", e);
+            Ok(address)
         }
+        Err(e) => anyhow::bail!("Cannot mine under a stake address: {}", e),
     }
-    
-    println!("\n❌ No valid nonce found in {} iterations", max_iterations);
-    None
 }
 
-// ==================== REGISTRATION ====================
+/// A mining run: interactive, headless (`mine`), daemon or coordinator
+async fn mine(cli: Cli, started: Instant) -> Result<()> {
+    let daemon = cli.mode == Mode::Daemon;
+    // Headless (and `mine`) is the interactive round minus the prompts, for
+    // systemd units, containers and scripts: stdin is never read
+    let headless = cli.headless || matches!(cli.command, Some(Command::Mine { .. }));
+    let json_result = matches!(cli.command, Some(Command::Mine { json: true }));
 
-async fn interactive_register(
-    api: &ScavengerAPI,
-    address: &str,
-) -> Result<()> {
-    println!("\n╔══════════════════════════════════════════════════════════════╗");
-    println!("║                  📝 REGISTRATION PROCESS                    ║");
-    println!("╚══════════════════════════════════════════════════════════════╝");
+    console::banner(&["🌙 SCAVENGER MINER v0.2.0", "Powered by AshMaize Algorithm"]);
+    #[cfg(feature = "chaos")]
+    chaos::announce();
+    tls::warn_if_insecure();
+    say!();
     
-    println!("\n📄 Fetching Terms & Conditions...");
-    let tandc = api.get_terms().await?;
-    println!("✅ Got T&C version: {}", tandc.version);
-    
-    println!("\n📋 Message to sign:");
-    println!("────────────────────────────────────────────────────────────────");
-    println!("{}", tandc.message);
-    println!("────────────────────────────────────────────────────────────────");
-    
-    println!("\n🔐 How to sign with Cardano wallet:");
-    println!("════════════════════════════════════════════════════════════════");
-    println!("1. Open your Cardano wallet in browser (Nami/Eternl/Yoroi)");
-    println!("2. Open Developer Tools (Press F12)");
-    println!("3. Go to Console tab");
-    println!("4. Copy and paste this code:\n");
-    
-    println!("const api = await cardano.nami.enable();");
-    println!("const addrs = await api.getUsedAddresses();");
-    println!("const msg = \"{}\";", tandc.message.replace("\"", "\\\""));
-    println!("const signed = await api.signData(addrs[0], Buffer.from(msg).toString('hex'));");
-    println!("console.log('Signature:', signed.signature);");
-    println!("console.log('Pubkey:', signed.key);");
-    
-    println!("\n════════════════════════════════════════════════════════════════");
-    println!("5. Copy the outputs and paste below\n");
-    
-    println!("Enter signature:");
-    let mut signature = String::new();
-    io::stdin().read_line(&mut signature)?;
-    let signature = signature.trim().to_string();
-    
-    println!("Enter public key:");
-    let mut pubkey = String::new();
-    io::stdin().read_line(&mut pubkey)?;
-    let pubkey = pubkey.trim().to_string();
-    
-    if pubkey.len() != 64 {
-        anyhow::bail!("Invalid pubkey length: {} (expected 64)", pubkey.len());
+    if let Some(recording) = &cli.replay {
+        return replay::run(recording, cli.replay_day.as_deref(), &cli.data_dir).await;
     }
     
-    println!("\n📤 Registering...");
-    let result = api.register(address, &signature, &pubkey).await?;
-    
-    if let Some(receipt) = result.registration_receipt {
-        println!("✅ Registration successful!");
-        println!("   Timestamp: {}", receipt.timestamp);
-    } else {
-        println!("✅ Registration completed");
+    if let Some(remote) = &cli.state_remote {
+        match sync::pull(remote, &cli.data_dir).await {
+            Ok(0) => {}
+            Ok(n) => say!("📥 Restored {} file(s) from {}", n, remote),
+            Err(e) => say!("⚠️  Failed to restore state from {}: {}", remote, e),
+        }
     }
+    let tuning = cli.profile.tuning();
+    let split = cli
+        .split_policy
+        .map(|policy| contrib::Split { policy, last_n: cli.split_last_n.max(1) });
+    if let Some(max) = tuning.max_threads {
+        say!("📱 Profile {:?}: at most {} thread(s)", cli.profile, max);
+    }
+    if let Some(guard) = &tuning.power_guard {
+        say!("   Pausing above {:.0}°C or below {}% battery", guard.max_temp_c, guard.min_battery);
+    }
+    say!();
     
-    Ok(())
-}
-
-// ==================== MAIN ====================
-fn wait_for_enter() {
-    println!("\n╔══════════════════════════════════════════════════════════════╗");
-    println!("║            Press ENTER to exit...                            ║");
-    println!("╚══════════════════════════════════════════════════════════════╝");
-    
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).ok();
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    println!("╔══════════════════════════════════════════════════════════════╗");
-    println!("║              🌙 SCAVENGER MINER v0.2.0                      ║");
-    println!("║           Powered by AshMaize Algorithm                     ║");
-    println!("╚══════════════════════════════════════════════════════════════╝\n");
-    
-    let api = ScavengerAPI::new()?;
-    
-    // TODO: Replace with your Cardano address
-    println!("Enter your Cardano address:");
-    let mut my_address = String::new();
-    io::stdin().read_line(&mut my_address)?;
-    let my_address = my_address.trim();
-    
-    println!("\n📍 Address: {}", my_address);
-    
-    // Registration (optional)
-    println!("\nDo you want to register? (y/n)");
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+    let store = Store::open(&cli.data_dir)?;
+    say!("🖥️  Machine {}", machine::init(&cli.data_dir, cli.machine_id.as_deref())?);
+    let mut supervisor = Supervisor::new();
+    supervisor.spawn("signals", supervisor::signals);
+    if let Some(cloud) = cli.spot {
+        supervisor.spawn("spot", move |cancel| spot::watch(cloud, cancel));
+    }
+    if let Some(addr) = cli.metrics_addr {
+        let listener = metrics::bind(addr).await?;
+        supervisor.spawn("metrics", move |cancel| metrics::serve(listener, cancel));
+    }
+    let cancel = supervisor.token();
+    #[cfg(feature = "tui")]
+    if cli.tui {
+        tui::enable(!daemon && !headless && !json_logs(&cli) && !cli.plain, cancel.clone());
+    }
+    let api = api_client(&cli, &store)?;
     
-    if input.trim().to_lowercase() == "y" {
-        match interactive_register(&api, my_address).await {
-            Ok(_) => println!("\n✅ Registration successful!"),
-            Err(e) => {
-                println!("\n⚠️  Registration failed: {}", e);
-                println!("   Continuing to mining...");
-            }
+    let my_address = match given_address(&cli, None)? {
+        Some(address) => address,
+        None if daemon => {
+            anyhow::bail!("Daemon mode needs an address (--address, --label or SCAVENGER_ADDRESS)")
         }
-    } else {
-        println!("⏭️  Skipping registration");
+        None if headless => {
+            anyhow::bail!("Headless runs need an address (--address, --label or SCAVENGER_ADDRESS)")
+        }
+        None => {
+            say!("Enter your Cardano address:");
+            let my_address = console::read_line(None)?.unwrap_or_default();
+            my_address.trim().to_string()
+        }
+    };
+    let my_address = resolve_address(&cli, my_address)?;
+    let my_address = my_address.as_str();
+    let share = share::Share::from_cli(&cli, my_address)?;
+    if let Some(share) = &share {
+        share.print();
     }
+    let work_hours =
+        cli.work_hours.map(|hours| workhours::Yield { hours, percent: cli.work_yield });
     
-    // Get challenge
-    println!("\n╔══════════════════════════════════════════════════════════════╗");
-    println!("║                  📡 FETCHING CHALLENGE                       ║");
-    println!("╚══════════════════════════════════════════════════════════════╝");
-    
-    let challenge_response = api.get_challenge().await?;
-    println!("\n✅ Challenge received:");
-    println!("   ID: {}", challenge_response.challenge.challenge_id);
-    println!("   Day: {}", challenge_response.challenge.day);
-    println!("   Challenge #: {}", challenge_response.challenge.challenge_number);
-    println!("   Difficulty: {}", challenge_response.challenge.difficulty);
-    println!("   Deadline: {}", challenge_response.mining_period_ends);
-    
-    // Mining
-    println!("\n╔══════════════════════════════════════════════════════════════╗");
-    println!("║                      ⛏️  MINING                              ║");
-    println!("╚══════════════════════════════════════════════════════════════╝");
-    
-    println!("\nHow many hashes to try?");
-    println!("  100000     = Quick test (~few minutes)");
-    println!("  1000000    = Medium test");
-    println!("  100000000  = Serious mining (hours)");
-    println!("\nEnter number:");
+    if let Some(addr) = cli.coordinate {
+        let listener = coordinator::bind(addr).await?;
+        let rule = cli.difficulty_rule;
+        supervisor.run(coordinator::serve(api, my_address, listener, rule, cancel)).await;
+        return Ok(());
+    }
+    let rom_size = experimental_rom_size(&cli)?;
+    let on_disk = romfile::enable(cli.rom_storage, rom_dir(&cli), rom_size.unwrap_or(ROM_SIZE))?;
+    limits::check(cli.ignore_limits, rom_size.unwrap_or(ROM_SIZE), on_disk)?;
+    let idle = round::IdlePolicy {
+        rom: cli.idle_rom,
+        max_poll: Some(cli.idle_poll_max),
+    };
+    spawn_submit_queue(&cli, &store, &mut supervisor)?;
     
-    let mut iterations_input = String::new();
-    io::stdin().read_line(&mut iterations_input)?;
-    let max_iterations: u64 = iterations_input
-        .trim()
-        .parse()
-        .unwrap_or(100_000);
+    if daemon {
+        if cli.confirm {
+            say!("⚠️  --confirm needs a terminal, ignored in daemon mode");
+        }
+        let settings = RoundSettings {
+            address: my_address.to_string(),
+            max_iterations: Some(daemon::ROUND_HASHES),
+            max_hashes: cli.max_hashes,
+            stop_at: cli.max_duration.map(|d| started + d),
+            threads: tuning.threads(cli.threads),
+            sprint_minutes: cli.sprint_minutes,
+            sprint_threads: cli.sprint_threads.or(tuning.max_threads).map(|n| tuning.threads(n)),
+            work_hours,
+            dry_run: cli.dry_run,
+            confirm: false,
+            confirm_timeout: 0,
+            engine: EngineChoice::AshMaize,
+            resume_scan: true,
+            power_guard: tuning.power_guard,
+            worker: cli.worker.clone(),
+            split,
+            rom_size,
+            min_success_chance: cli.min_success_chance.map(|percent| percent / 100.0),
+            acceptance: cli.acceptance_rule,
+            difficulty_rule: cli.difficulty_rule,
+            share,
+            idle,
+            notifier: cli.notify.clone(),
+            cancel: cancel.clone(),
+        };
+        let (status_path, interval) = (store.status_path(), tuning.status_interval);
+        supervisor.spawn("status", move |cancel| {
+            status::write_periodically(status_path, interval, cancel)
+        });
+        spawn_heartbeats(&cli, &mut supervisor);
+        let missed = catchup::offer(&api, &store, &settings, false, cli.catch_up).await;
+        let rounds = async {
+            catchup::mine(&api, &store, &settings, missed).await;
+            daemon::run(&api, &store, &settings, &cli.data_dir, cli.state_remote.as_ref()).await
+        };
+        return supervisor.run(rounds).await;
+    }
     
-    if let Some(nonce) = mine_challenge(
-        my_address,
-        &challenge_response.challenge,
-        max_iterations,
-    ) {
-        // Submit solution
-        println!("\n╔══════════════════════════════════════════════════════════════╗");
-        println!("║                  📤 SUBMITTING SOLUTION                      ║");
-        println!("╚══════════════════════════════════════════════════════════════╝");
-        
-        let result = api.submit_solution(
-            my_address,
-            &challenge_response.challenge.challenge_id,
-            &nonce,
-        ).await?;
+    // Registration (optional); headless runs leave it to the `register` command
+    if !headless {
+        say!("\nDo you want to register? (y/n)");
+        let input = console::read_line(None)?.unwrap_or_default();
         
-        if let Some(receipt) = result.crypto_receipt {
-            println!("\n🎉🎉🎉 SOLUTION ACCEPTED! 🎉🎉🎉");
-            println!("   Timestamp: {}", receipt.timestamp);
-            
-            // Check reward
-            if let Ok(rates) = api.get_star_rate().await {
-                let day = challenge_response.challenge.day as usize;
-                if day > 0 && day <= rates.len() {
-                    println!("\n⭐ REWARD: {} STAR tokens!", rates[day - 1]);
+        if input.trim().to_lowercase() == "y" {
+            let registration =
+                register::Registration {
+                    wallet: cli.wallet,
+                    dry_run: cli.dry_run,
+                    ..Default::default()
+                };
+            match register::run(&api, my_address, &registration).await {
+                Ok(_) => say!("\n✅ Registration successful!"),
+                Err(e) => {
+                    say!("\n⚠️  Registration failed: {}", e);
+                    say!("   Continuing to mining...");
                 }
             }
         } else {
-            println!("\n📋 Solution submitted");
-            println!("   Response: {:?}", result.extra);
+            say!("⏭️  Skipping registration");
         }
     }
     
-    println!("\n╔══════════════════════════════════════════════════════════════╗");
-    println!("║                    ✅ PROGRAM COMPLETE                       ║");
-    println!("╚══════════════════════════════════════════════════════════════╝");
-    wait_for_enter();
-    Ok(())
-}
\ No newline at end of file
+    let settings = RoundSettings {
+        address: my_address.to_string(),
+        max_iterations: cli.keep_mining.then_some(daemon::ROUND_HASHES),
+        max_hashes: cli.max_hashes.or(headless.then_some(daemon::ROUND_HASHES)),
+        stop_at: cli.max_duration.map(|d| started + d),
+        threads: tuning.threads(cli.threads),
+        sprint_minutes: cli.sprint_minutes,
+        sprint_threads: cli.sprint_threads.or(tuning.max_threads).map(|n| tuning.threads(n)),
+        work_hours,
+        dry_run: cli.dry_run,
+        confirm: cli.confirm && !headless,
+        confirm_timeout: cli.confirm_timeout,
+        engine: EngineChoice::AshMaize,
+        // Round after round on one challenge continues where the last stopped
+        resume_scan: cli.keep_mining,
+        power_guard: tuning.power_guard,
+        worker: cli.worker.clone(),
+        split,
+        rom_size,
+        min_success_chance: None,
+        acceptance: cli.acceptance_rule,
+        difficulty_rule: cli.difficulty_rule,
+        share,
+        idle,
+        notifier: cli.notify.clone(),
+        cancel: cancel.clone(),
+    };
+    let missed = catchup::offer(&api, &store, &settings, !headless, cli.catch_up).await;
+    if cli.keep_mining {
+        spawn_heartbeats(&cli, &mut supervisor);
+        let rounds = async {
+            catchup::mine(&api, &store, &settings, missed).await;
+            daemon::run(&api, &store, &settings, &cli.data_dir, cli.state_remote.as_ref()).await
+        };
+        return supervisor.run(rounds).await;
+    }
+    let (result, interrupted) = supervisor
+        .run(async {
+            catchup::mine(&api, &store, &settings, missed).await;
+            let result = round::run_round(&api, &store, &settings).await;
+            if let Some(remote) = &cli.state_remote {
+                sync::checkpoint(&cli.data_dir, remote).await;
+            }
+            (result, cancel.is_cancelled())
+        })
+        .await;
+    if json_result {
+        print_result(&store, &settings.address, &result);
+    }
+    if interrupted {
+        // Ctrl+C, or nobody is at the keyboard of a reclaimed VM
+        return result.map(|_| ());
+    }
+    let outcome = result?;
+    
+    say!();
+    console::banner(&["✅ PROGRAM COMPLETE"]);
+    if !headless {
+        wait_for_enter();
+        return Ok(());
+    }
+    match exit_code(&outcome) {
+        0 => Ok(()),
+        code => std::process::exit(code),
+    }
+}
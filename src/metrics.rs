@@ -0,0 +1,169 @@
+// ==================== PROMETHEUS METRICS ====================
+//
+// --metrics-addr serves /metrics in the Prometheus text format, for farms
+// watched from Grafana. Counters live here as atomics bumped where things
+// happen (hashes per progress tick, solutions, failed API calls, ROM
+// builds); the hash rate is read from the status snapshot when scraped.
+// Every sample is labelled with the machine ID, so rigs mining for one
+// address stay apart in a shared Prometheus.
+// Like the signing page, the server is a few lines on a bare TcpListener:
+// one GET, one response, connection closed.
+//
+// Bind to 127.0.0.1 or a private interface: the numbers say nothing
+// secret, but there is no authentication.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+use crate::{machine, status};
+
+static HASHES: AtomicU64 = AtomicU64::new(0);
+static SOLUTIONS_FOUND: AtomicU64 = AtomicU64::new(0);
+static SOLUTIONS_ACCEPTED: AtomicU64 = AtomicU64::new(0);
+static ROM_BUILDS: AtomicU64 = AtomicU64::new(0);
+/// Seconds of the last ROM build, as f64 bits
+static ROM_INIT_SECONDS: AtomicU64 = AtomicU64::new(0);
+/// Failed API calls per action (challenge, submit, register)
+static API_ERRORS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// A scraper that stalls mid-request doesn't get to hold a task forever
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn hashes(count: u64) {
+    HASHES.fetch_add(count, Ordering::Relaxed);
+}
+
+pub fn solution_found() {
+    SOLUTIONS_FOUND.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn solution_accepted() {
+    SOLUTIONS_ACCEPTED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn api_error(action: &str) {
+    *API_ERRORS.lock().unwrap().entry(action.to_string()).or_default() += 1;
+}
+
+pub fn rom_built(took: Duration) {
+    ROM_BUILDS.fetch_add(1, Ordering::Relaxed);
+    ROM_INIT_SECONDS.store(took.as_secs_f64().to_bits(), Ordering::Relaxed);
+}
+
+/// The exposition text for one scrape
+fn render() -> String {
+    let mut out = String::new();
+    let machine = machine::id().map(|id| format!("machine_id=\"{}\"", id));
+    // Samples are (labels without braces, value)
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (labels, value) in samples {
+            let labels: Vec<&str> = machine
+                .iter()
+                .map(String::as_str)
+                .chain(Some(labels.as_str()).filter(|l| !l.is_empty()))
+                .collect();
+            if labels.is_empty() {
+                let _ = writeln!(out, "{} {}", name, value);
+            } else {
+                let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+            }
+        }
+    };
+    let one = |value: String| [(String::new(), value)];
+    let count = |counter: &AtomicU64| one(counter.load(Ordering::Relaxed).to_string());
+
+    metric("scavenger_hashes_total", "counter", "Hashes computed", &count(&HASHES));
+    let rate = status::current().map_or(0.0, |s| s.hash_rate);
+    metric(
+        "scavenger_hashrate",
+        "gauge",
+        "Hashes per second in the current run",
+        &one(rate.to_string()),
+    );
+    metric(
+        "scavenger_solutions_found_total",
+        "counter",
+        "Solutions found and verified locally",
+        &count(&SOLUTIONS_FOUND),
+    );
+    metric(
+        "scavenger_solutions_accepted_total",
+        "counter",
+        "Solutions the server accepted",
+        &count(&SOLUTIONS_ACCEPTED),
+    );
+    let errors: Vec<(String, String)> = API_ERRORS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(action, n)| (format!("action=\"{}\"", action), n.to_string()))
+        .collect();
+    metric("scavenger_api_errors_total", "counter", "Failed API calls", &errors);
+    metric("scavenger_rom_builds_total", "counter", "ROMs built", &count(&ROM_BUILDS));
+    let rom_seconds = f64::from_bits(ROM_INIT_SECONDS.load(Ordering::Relaxed));
+    metric(
+        "scavenger_rom_init_seconds",
+        "gauge",
+        "How long the last ROM took to build",
+        &one(rom_seconds.to_string()),
+    );
+    out
+}
+
+/// Bind now, so a taken port fails at startup rather than in the background
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Cannot listen on --metrics-addr {}", addr))?;
+    say!("📈 Prometheus metrics on http://{}/metrics", listener.local_addr()?);
+    Ok(listener)
+}
+
+/// Answer scrapes until cancelled; spawn on the runtime
+pub async fn serve(listener: TcpListener, cancel: CancellationToken) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            },
+            _ = cancel.cancelled() => return,
+        };
+        tokio::spawn(async move {
+            let _ = tokio::time::timeout(REQUEST_TIMEOUT, answer(stream)).await;
+        });
+    }
+}
+
+async fn answer(mut stream: TcpStream) -> Result<()> {
+    // Only the request line matters; headers and body are ignored
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let head = String::from_utf8_lossy(&buf[..n]);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (status, content_type, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            ("200 OK", "text/plain; version=0.0.4; charset=utf-8", render())
+        }
+        _ => ("404 Not Found", "text/plain", "not found; try /metrics\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
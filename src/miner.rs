@@ -0,0 +1,352 @@
+// ==================== MINER ====================
+//
+// One challenge, one address: owns the ROM, scans a nonce range on a few
+// threads and reports through MinerEvent. The CLI runs its own loop on top
+// of the same primitives for sprints, power pausing and the status line.
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::mining::{build_preimage, DifficultyRule, MiningContext, ROM_SIZE};
+use crate::types::Challenge;
+
+/// Nonces a worker claims at a time
+const BATCH_SIZE: u64 = 64;
+
+/// How often `mine` reports progress
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A nonce that passed the difficulty, with what's needed to re-check it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Solution {
+    /// 16 hex digits, as submitted
+    pub nonce: String,
+    pub preimage: String,
+    pub hash: [u8; 64],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MinerEvent {
+    /// Hashes done so far in this `mine` call
+    Progress { hashes: u64 },
+    Found(Solution),
+}
+
+/// Searches one challenge for one address
+///
+/// ```no_run
+/// use scavenger_miner::{Miner, MinerEvent};
+/// # fn demo(challenge: scavenger_miner::Challenge) {
+/// // Builds the 1 GB ROM
+/// let miner = Miner::new("addr1q...", challenge).threads(4);
+/// let solution = miner.mine(0..1_000_000, |event| {
+///     if let MinerEvent::Progress { hashes } = event {
+///         println!("{} hashes", hashes);
+///     }
+///     true // keep going
+/// });
+/// if let Some(solution) = solution {
+///     println!("nonce {}", solution.nonce);
+/// }
+/// # }
+/// ```
+pub struct Miner {
+    address: String,
+    challenge: Challenge,
+    ctx: MiningContext,
+    threads: usize,
+    rule: DifficultyRule,
+}
+
+impl Miner {
+    /// Builds the challenge ROM, which takes a while and allocates ROM_SIZE bytes
+    pub fn new(address: impl Into<String>, challenge: Challenge) -> Self {
+        let ctx = MiningContext::new(&challenge.no_pre_mine, ROM_SIZE);
+        Miner {
+            address: address.into(),
+            challenge,
+            ctx,
+            threads: 1,
+            rule: DifficultyRule::default(),
+        }
+    }
+
+    /// Worker threads for `mine` (default 1)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// How a digest is held against the difficulty (default Prefix)
+    pub fn difficulty_rule(mut self, rule: DifficultyRule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn challenge(&self) -> &Challenge {
+        &self.challenge
+    }
+
+    /// Hash one nonce; Some if it passes the difficulty
+    pub fn try_nonce(&self, nonce: u64) -> Option<Solution> {
+        let nonce = format!("{:016x}", nonce);
+        let preimage = build_preimage(&nonce, &self.address, &self.challenge);
+        let hash = self.ctx.hash(&preimage);
+        self.rule
+            .meets(&hash, &self.challenge.difficulty)
+            .then_some(Solution { nonce, preimage, hash })
+    }
+
+    /// Scan `nonces` until a solution turns up, the range runs out or
+    /// `on_event` returns false. Events arrive on the calling thread.
+    pub fn mine(
+        &self,
+        nonces: Range<u64>,
+        on_event: impl FnMut(MinerEvent) -> bool,
+    ) -> Option<Solution> {
+        self.run(nonces, &Control::default(), on_event)
+    }
+
+    fn run(
+        &self,
+        nonces: Range<u64>,
+        control: &Control,
+        mut on_event: impl FnMut(MinerEvent) -> bool,
+    ) -> Option<Solution> {
+        let next = AtomicU64::new(nonces.start);
+        let hashes = AtomicU64::new(0);
+        let found: Mutex<Option<Solution>> = Mutex::new(None);
+
+        std::thread::scope(|s| {
+            let workers: Vec<_> = (0..self.threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        while !control.stop.load(Ordering::Relaxed) {
+                            if control.paused.load(Ordering::Relaxed) {
+                                std::thread::sleep(Duration::from_millis(50));
+                                continue;
+                            }
+                            let base = next.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+                            if base >= nonces.end {
+                                return;
+                            }
+                            for nonce in base..(base.saturating_add(BATCH_SIZE)).min(nonces.end) {
+                                let solution = self.try_nonce(nonce);
+                                hashes.fetch_add(1, Ordering::Relaxed);
+                                if let Some(solution) = solution {
+                                    found.lock().unwrap().get_or_insert(solution);
+                                    control.stop.store(true, Ordering::Relaxed);
+                                    return;
+                                }
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            let mut last_report = Instant::now();
+            while !workers.iter().all(|w| w.is_finished()) {
+                std::thread::sleep(Duration::from_millis(50));
+                if last_report.elapsed() >= PROGRESS_INTERVAL {
+                    last_report = Instant::now();
+                    let progress = MinerEvent::Progress { hashes: hashes.load(Ordering::Relaxed) };
+                    if !on_event(progress) {
+                        control.stop.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+        });
+
+        let solution = found.into_inner().unwrap()?;
+        on_event(MinerEvent::Found(solution.clone()));
+        Some(solution)
+    }
+}
+
+/// Flags the workers poll between hashes
+#[derive(Default)]
+struct Control {
+    stop: AtomicBool,
+    paused: AtomicBool,
+}
+
+// ==================== EMBEDDING ====================
+//
+// Async wrapper for GUIs and dashboards: the search runs on a blocking
+// thread and the frontend drives it through a MinerHandle and watches a
+// MinerStatus channel, instead of shelling out to the CLI.
+
+#[cfg(feature = "embed")]
+pub use handle::{MinerHandle, MinerState, MinerStatus};
+
+#[cfg(feature = "embed")]
+mod handle {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::sync::watch;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum MinerState {
+        Mining,
+        Paused,
+        /// A solution is in MinerStatus::solution
+        Found,
+        /// Range scanned without a solution
+        Exhausted,
+        Stopped,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct MinerStatus {
+        pub state: MinerState,
+        pub hashes: u64,
+        /// Over the last progress interval
+        pub hash_rate: f64,
+        pub solution: Option<Solution>,
+    }
+
+    struct Shared {
+        control: Control,
+        status: watch::Sender<MinerStatus>,
+    }
+
+    /// A running search; dropping it stops the workers, as `stop` does
+    /// without waiting (`finished` lets the search run to its end)
+    ///
+    /// ```no_run
+    /// # async fn demo(challenge: scavenger_miner::Challenge) {
+    /// use scavenger_miner::{Miner, MinerState};
+    ///
+    /// let miner = Miner::build("addr1q...", challenge).await.threads(4);
+    /// let handle = miner.start(0..u64::MAX);
+    /// let mut status = handle.status();
+    /// while status.changed().await.is_ok() {
+    ///     let now = status.borrow().clone();
+    ///     println!("{:?} {:.0} H/s", now.state, now.hash_rate);
+    ///     if now.state != MinerState::Mining {
+    ///         break;
+    ///     }
+    /// }
+    /// let solution = handle.stop().await;
+    /// # }
+    /// ```
+    pub struct MinerHandle {
+        shared: Arc<Shared>,
+        /// Taken by `finished`
+        task: Option<tokio::task::JoinHandle<Option<Solution>>>,
+    }
+
+    impl Miner {
+        /// `Miner::new` without blocking the async runtime on the ROM
+        pub async fn build(address: impl Into<String>, challenge: Challenge) -> Self {
+            let address = address.into();
+            tokio::task::spawn_blocking(move || Miner::new(address, challenge))
+                .await
+                .expect("ROM build panicked")
+        }
+
+        /// Mine `nonces` on a blocking thread. Needs a tokio runtime.
+        pub fn start(self, nonces: Range<u64>) -> MinerHandle {
+            let (status, _) = watch::channel(MinerStatus {
+                state: MinerState::Mining,
+                hashes: 0,
+                hash_rate: 0.0,
+                solution: None,
+            });
+            let shared = Arc::new(Shared { control: Control::default(), status });
+
+            let worker = Arc::clone(&shared);
+            let task = tokio::task::spawn_blocking(move || {
+                let mut last = (Instant::now(), 0u64);
+                let solution = self.run(nonces, &worker.control, |event| {
+                    if let MinerEvent::Progress { hashes } = event {
+                        let rate = (hashes - last.1) as f64 / last.0.elapsed().as_secs_f64();
+                        last = (Instant::now(), hashes);
+                        worker.status.send_modify(|s| {
+                            s.hashes = hashes;
+                            s.hash_rate = rate;
+                        });
+                    }
+                    true
+                });
+                let stopped = worker.control.stop.load(Ordering::Relaxed);
+                worker.status.send_modify(|s| {
+                    s.hash_rate = 0.0;
+                    s.state = match (&solution, stopped) {
+                        (Some(_), _) => MinerState::Found,
+                        (None, true) => MinerState::Stopped,
+                        (None, false) => MinerState::Exhausted,
+                    };
+                    s.solution = solution.clone();
+                });
+                solution
+            });
+            MinerHandle { shared, task: Some(task) }
+        }
+    }
+
+    impl MinerHandle {
+        /// Live status; `changed()` fires on every progress tick and state change
+        pub fn status(&self) -> watch::Receiver<MinerStatus> {
+            self.shared.status.subscribe()
+        }
+
+        /// Workers idle (no CPU) until `resume`
+        pub fn pause(&self) {
+            self.shared.control.paused.store(true, Ordering::Relaxed);
+            self.set_state_if(MinerState::Mining, MinerState::Paused);
+        }
+
+        pub fn resume(&self) {
+            self.shared.control.paused.store(false, Ordering::Relaxed);
+            self.set_state_if(MinerState::Paused, MinerState::Mining);
+        }
+
+        /// Stop the workers and wait for them; a solution found in the meantime is kept
+        pub async fn stop(self) -> Option<Solution> {
+            self.cancel();
+            self.finished().await
+        }
+
+        /// Wait for the search to end on its own
+        pub async fn finished(mut self) -> Option<Solution> {
+            let task = self.task.take()?;
+            task.await.ok().flatten()
+        }
+
+        fn cancel(&self) {
+            self.shared.control.stop.store(true, Ordering::Relaxed);
+            self.shared.control.paused.store(false, Ordering::Relaxed);
+        }
+
+        fn set_state_if(&self, from: MinerState, to: MinerState) {
+            self.shared.status.send_if_modified(|s| {
+                let change = s.state == from;
+                if change {
+                    s.state = to;
+                }
+                change
+            });
+        }
+    }
+
+    impl Drop for MinerHandle {
+        /// Without this a forgotten handle would keep every worker busy until
+        /// the range (often 0..u64::MAX) ran out
+        fn drop(&mut self) {
+            if self.task.is_some() {
+                self.cancel();
+            }
+        }
+    }
+}
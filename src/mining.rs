@@ -0,0 +1,537 @@
+// ==================== PROOF OF WORK ====================
+//
+// Preimage layout and the difficulty check. Both run on server-supplied
+// strings, and the fuzz targets under fuzz/ hold them to never panicking.
+
+use crate::types::Challenge;
+
+// ROM parameters
+pub const ROM_PRE_SIZE: usize = 16 * 1024 * 1024;   // 16 MB
+pub const ROM_SIZE: usize = 1024 * 1024 * 1024;     // 1 GB
+pub const ROM_MIXING_NUMBERS: usize = 4;
+
+// Hash parameters
+pub const NB_LOOPS: u32 = 8;
+pub const NB_INSTRS: u32 = 256;
+
+/// The challenge ROM, seeded with no_pre_mine (allocates ROM_SIZE bytes)
+///
+/// Single-threaded: ashmaize only exposes `Rom::new`, and the TwoStep
+/// mixing reads across the whole pre-ROM, so segments can't be generated
+/// independently and stitched together. Parallel generation (a
+/// --rom-gen-threads option) needs chunked generation upstream first.
+#[cfg(feature = "hash")]
+pub fn build_rom(seed: &[u8]) -> ashmaize::Rom {
+    build_rom_sized(seed, ROM_SIZE)
+}
+
+/// A ROM of another size; digests from it don't verify on the server
+#[cfg(feature = "hash")]
+pub fn build_rom_sized(seed: &[u8], size: usize) -> ashmaize::Rom {
+    ashmaize::Rom::new(
+        seed,
+        ashmaize::RomGenerationType::TwoStep {
+            pre_size: ROM_PRE_SIZE.min(size),
+            mixing_numbers: ROM_MIXING_NUMBERS,
+        },
+        size,
+    )
+}
+
+/// AshMaize digest of a preimage with the protocol's loop/instruction counts
+///
+/// CPU only: an OpenCL or CUDA kernel would need the ROM bytes on the
+/// device, and ashmaize keeps them private behind `Rom`, with `hash` the
+/// only way in. A GPU backend waits on upstream exposing them, and on a
+/// kernel checked digest for digest against this function; until then
+/// there is no device to dispatch to or fall back from.
+#[cfg(feature = "hash")]
+pub fn hash_preimage(rom: &ashmaize::Rom, preimage: &str) -> [u8; 64] {
+    ashmaize::hash(preimage.as_bytes(), rom, NB_LOOPS, NB_INSTRS)
+}
+
+/// What a challenge is hashed with: the ROM built from its no_pre_mine, or
+/// no ROM at all for `instant`. Shared between threads; the miner binary
+/// keeps the last one around for the next challenge with the same seed.
+#[cfg(feature = "hash")]
+pub struct MiningContext {
+    engine: HashEngine,
+    /// no_pre_mine the ROM was built from
+    seed: String,
+    rom_size: usize,
+}
+
+#[cfg(feature = "hash")]
+enum HashEngine {
+    AshMaize(ashmaize::Rom),
+    /// Every digest is all zeros, so the first nonce wins (replays and tests)
+    Instant,
+}
+
+#[cfg(feature = "hash")]
+impl MiningContext {
+    /// Builds the ROM: ROM_SIZE for digests the server accepts, anything
+    /// smaller for tests. Takes a while and allocates `rom_size` bytes.
+    pub fn new(no_pre_mine: &str, rom_size: usize) -> Self {
+        MiningContext {
+            engine: HashEngine::AshMaize(build_rom_sized(no_pre_mine.as_bytes(), rom_size)),
+            seed: no_pre_mine.to_string(),
+            rom_size,
+        }
+    }
+
+    /// No ROM, every digest all zeros
+    pub fn instant() -> Self {
+        MiningContext { engine: HashEngine::Instant, seed: String::new(), rom_size: 0 }
+    }
+
+    pub fn seed(&self) -> &str {
+        &self.seed
+    }
+
+    pub fn rom_size(&self) -> usize {
+        self.rom_size
+    }
+
+    /// Whether digests are the ones the server recomputes (an AshMaize
+    /// ROM of ROM_SIZE)
+    pub fn is_protocol(&self) -> bool {
+        matches!(self.engine, HashEngine::AshMaize(_)) && self.rom_size == ROM_SIZE
+    }
+
+    pub fn hash(&self, preimage: &str) -> [u8; 64] {
+        match &self.engine {
+            HashEngine::AshMaize(rom) => hash_preimage(rom, preimage),
+            HashEngine::Instant => [0; 64],
+        }
+    }
+}
+
+/// Every field where a preimage departs from what `build_preimage` makes of
+/// `nonce`, `address` and `challenge`; empty when it matches. Each field is
+/// read where `build_preimage` puts it, so one of another length shifts the
+/// fields after it and they are reported too. Meant for the preimage echoed
+/// in a server receipt: a 200 with a receipt for a different nonce, address
+/// or challenge would otherwise pass unnoticed.
+pub fn preimage_mismatches(
+    preimage: &str,
+    nonce: &str,
+    address: &str,
+    challenge: &Challenge,
+) -> Vec<String> {
+    let fields = [
+        ("nonce", nonce),
+        ("address", address),
+        ("challenge_id", challenge.challenge_id.as_str()),
+        ("difficulty", challenge.difficulty.as_str()),
+        ("no_pre_mine", challenge.no_pre_mine.as_str()),
+        ("latest_submission", challenge.latest_submission.as_str()),
+        ("no_pre_mine_hour", challenge.no_pre_mine_hour.as_str()),
+    ];
+    let mut problems = Vec::new();
+    let mut rest = preimage;
+    for (name, expected) in fields {
+        // Same length in chars as expected, so a multi-byte char can't be split
+        let found: String = rest.chars().take(expected.chars().count()).collect();
+        // Hex case is not significant in the nonce
+        let same = if name == "nonce" {
+            found.eq_ignore_ascii_case(expected)
+        } else {
+            found == expected
+        };
+        if !same {
+            problems.push(format!("{}: expected '{}', found '{}'", name, expected, found));
+        }
+        rest = &rest[found.len()..];
+    }
+    if !rest.is_empty() {
+        problems.push(format!(
+            "{} unexpected byte(s) after no_pre_mine_hour: '{}'",
+            rest.len(),
+            rest
+        ));
+    }
+    problems
+}
+
+/// How a digest is held against a challenge's difficulty. The server's
+/// rule is not documented, so each reading of it is a variant; `Prefix`,
+/// the one mined with so far, stays the default. Callers pick one and pass
+/// it along; the free functions below always use `Prefix`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[non_exhaustive]
+pub enum DifficultyRule {
+    /// The digest, as a big-endian number as wide as the difficulty, at or
+    /// below it
+    #[default]
+    Prefix,
+    /// The digest, as a big-endian number, strictly below the difficulty
+    /// (as wide as the difficulty is)
+    Below,
+    /// At least as many leading zero bits as the difficulty has
+    ZeroBits,
+    /// At least as many leading zero bytes as the difficulty has
+    ZeroBytes,
+}
+
+fn leading_zero_bits(bytes: &[u8]) -> u32 {
+    let mut bits = 0;
+    for &b in bytes {
+        bits += b.leading_zeros();
+        if b != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+fn leading_zero_bytes(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|&&b| b == 0).count()
+}
+
+/// The difficulty's bytes; None for invalid hex and for an empty target,
+/// which has nothing to compare and so must not pass every digest
+fn decode_target(difficulty: &str) -> Option<Vec<u8>> {
+    hex::decode(difficulty).ok().filter(|bytes| !bytes.is_empty())
+}
+
+/// `hash` against `target`, both read as big-endian numbers over every byte
+/// of the target; None when the hash is narrower than the target
+fn compare_be(hash: &[u8], target: &[u8]) -> Option<std::cmp::Ordering> {
+    // Equal-length slices order lexicographically, which is big-endian order
+    Some(hash.get(..target.len())?.cmp(target))
+}
+
+impl DifficultyRule {
+    /// As --difficulty-rule spells it
+    pub fn name(self) -> &'static str {
+        match self {
+            DifficultyRule::Prefix => "prefix",
+            DifficultyRule::Below => "below",
+            DifficultyRule::ZeroBits => "zero-bits",
+            DifficultyRule::ZeroBytes => "zero-bytes",
+        }
+    }
+
+    /// Fails closed: invalid hex, an empty target and a target wider than
+    /// the hash meet nothing
+    pub fn meets(self, hash: &[u8], difficulty: &str) -> bool {
+        let Some(diff_bytes) = decode_target(difficulty) else {
+            return false;
+        };
+        if diff_bytes.len() > hash.len() {
+            return false;
+        }
+        match self {
+            DifficultyRule::Prefix => compare_be(hash, &diff_bytes)
+                .is_some_and(|order| order != std::cmp::Ordering::Greater),
+            DifficultyRule::Below => {
+                compare_be(hash, &diff_bytes) == Some(std::cmp::Ordering::Less)
+            }
+            DifficultyRule::ZeroBits => leading_zero_bits(hash) >= leading_zero_bits(&diff_bytes),
+            DifficultyRule::ZeroBytes => {
+                leading_zero_bytes(hash) >= leading_zero_bytes(&diff_bytes)
+            }
+        }
+    }
+
+    /// Chance that one random digest passes; None for invalid hex or an
+    /// empty target
+    pub fn probability(self, difficulty: &str) -> Option<f64> {
+        let diff_bytes = decode_target(difficulty)?;
+        Some(match self {
+            // (target + 1) / 256^width: starting the fold at 1 adds the 1
+            // at the least significant place
+            DifficultyRule::Prefix => {
+                diff_bytes.iter().rev().fold(1.0, |acc, &b| (acc + b as f64) / 256.0)
+            }
+            // target / 256^width, most significant byte last in
+            DifficultyRule::Below => {
+                diff_bytes.iter().rev().fold(0.0, |acc, &b| (acc + b as f64) / 256.0)
+            }
+            DifficultyRule::ZeroBits => 0.5f64.powi(leading_zero_bits(&diff_bytes) as i32),
+            DifficultyRule::ZeroBytes => {
+                (1.0 / 256f64).powi(leading_zero_bytes(&diff_bytes) as i32)
+            }
+        })
+    }
+
+    /// Step-by-step explanation of `meets`, for reports
+    pub fn trace(self, hash: &[u8], difficulty: &str) -> Vec<String> {
+        let diff_bytes = match hex::decode(difficulty) {
+            Ok(bytes) if bytes.is_empty() => return vec!["target is empty => FAIL".to_string()],
+            Ok(bytes) => bytes,
+            Err(e) => return vec![format!("difficulty is not valid hex: {}", e)],
+        };
+        if hash.len() < diff_bytes.len() {
+            return vec![format!(
+                "hash has {} bytes, target {} => FAIL",
+                hash.len(),
+                diff_bytes.len()
+            )];
+        }
+        let verdict = |pass: bool| if pass { "PASS" } else { "FAIL" };
+        match self {
+            DifficultyRule::Prefix => {
+                let mut trace = Vec::new();
+                for (i, (&h, &d)) in hash.iter().zip(&diff_bytes).enumerate() {
+                    let (relation, outcome) = match h.cmp(&d) {
+                        std::cmp::Ordering::Less => ("<", "PASS"),
+                        std::cmp::Ordering::Greater => (">", "FAIL"),
+                        std::cmp::Ordering::Equal => ("==", "next byte"),
+                    };
+                    trace.push(format!(
+                        "byte {}: hash {:02x} {} target {:02x} => {}",
+                        i, h, relation, d, outcome
+                    ));
+                    if h != d {
+                        return trace;
+                    }
+                }
+                trace.push("all target bytes equal => PASS".to_string());
+                trace
+            }
+            DifficultyRule::Below => {
+                vec![format!(
+                    "hash {} < target {} => {}",
+                    hex::encode(&hash[..diff_bytes.len()]),
+                    hex::encode(&diff_bytes),
+                    verdict(self.meets(hash, difficulty))
+                )]
+            }
+            DifficultyRule::ZeroBits => vec![format!(
+                "hash has {} leading zero bits, target needs {} => {}",
+                leading_zero_bits(hash),
+                leading_zero_bits(&diff_bytes),
+                verdict(self.meets(hash, difficulty))
+            )],
+            DifficultyRule::ZeroBytes => vec![format!(
+                "hash has {} leading zero bytes, target needs {} => {}",
+                leading_zero_bytes(hash),
+                leading_zero_bytes(&diff_bytes),
+                verdict(self.meets(hash, difficulty))
+            )],
+        }
+    }
+}
+
+/// Whether `hash` meets `difficulty` under the default (Prefix) rule
+pub fn meets_difficulty(hash: &[u8], difficulty: &str) -> bool {
+    DifficultyRule::Prefix.meets(hash, difficulty)
+}
+
+/// Chance that one random digest passes `meets_difficulty`; None for invalid hex
+pub fn success_probability(difficulty: &str) -> Option<f64> {
+    DifficultyRule::Prefix.probability(difficulty)
+}
+
+/// Step-by-step explanation of `meets_difficulty`, for reports
+pub fn difficulty_trace(hash: &[u8], difficulty: &str) -> Vec<String> {
+    DifficultyRule::Prefix.trace(hash, difficulty)
+}
+
+pub fn build_preimage(
+    nonce: &str,
+    address: &str,
+    challenge: &Challenge,
+) -> String {
+    format!(
+        "{}{}{}{}{}{}{}",
+        nonce,
+        address,
+        challenge.challenge_id,
+        challenge.difficulty,
+        challenge.no_pre_mine,
+        challenge.latest_submission,
+        challenge.no_pre_mine_hour
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DifficultyRule::{Below, Prefix, ZeroBits, ZeroBytes};
+    use super::{build_preimage, meets_difficulty, preimage_mismatches};
+    use crate::types::Challenge;
+
+    const NONCE: &str = "00000000deadbeef";
+    const ADDRESS: &str = "addr1qmismatch";
+
+    fn challenge() -> Challenge {
+        Challenge {
+            challenge_id: "**D05C10".into(),
+            day: 5,
+            challenge_number: 10,
+            difficulty: "0000FFFF".into(),
+            no_pre_mine: "fd651ac2725e3b9d".into(),
+            latest_submission: "2025-11-03T23:59:59Z".into(),
+            no_pre_mine_hour: "548571128".into(),
+        }
+    }
+
+    #[test]
+    fn prefix_compares_every_target_byte() {
+        // Differs from the target only after the 4th byte
+        let target = "000fffff00000010";
+        assert!(Prefix.meets(&[0x00, 0x0f, 0xff, 0xff, 0x00, 0x00, 0x00, 0x0f], target));
+        assert!(Prefix.meets(&[0x00, 0x0f, 0xff, 0xff, 0x00, 0x00, 0x00, 0x10], target));
+        assert!(!Prefix.meets(&[0x00, 0x0f, 0xff, 0xff, 0x00, 0x00, 0x00, 0x11], target));
+        assert!(!Prefix.meets(&[0x00, 0x0f, 0xff, 0xff, 0x01, 0x00, 0x00, 0x00], target));
+        // An earlier smaller byte wins whatever follows
+        assert!(Prefix.meets(&[0x00, 0x0f, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff], target));
+    }
+
+    #[test]
+    fn equal_passes_at_or_below_but_not_strictly_below() {
+        let hash = [0x12, 0x34, 0x56, 0x78, 0x9a];
+        assert!(Prefix.meets(&hash, "123456789a"));
+        assert!(!Below.meets(&hash, "123456789a"));
+        assert!(Below.meets(&hash, "123456789b"));
+    }
+
+    #[test]
+    fn only_the_targets_width_of_the_hash_counts() {
+        let mut hash = [0xffu8; 64];
+        hash[..3].copy_from_slice(&[0x00, 0x00, 0x01]);
+        assert!(Prefix.meets(&hash, "000001"));
+        assert!(!Below.meets(&hash, "000001"));
+        assert!(Below.meets(&hash, "000002"));
+    }
+
+    #[test]
+    fn hash_narrower_than_target_fails() {
+        assert!(!Prefix.meets(&[0x00; 4], "ffffffffff"));
+        assert!(!Below.meets(&[0x00; 4], "ffffffffff"));
+    }
+
+    #[test]
+    fn extreme_targets() {
+        let zero = [0u8; 64];
+        let ones = [0xffu8; 64];
+        assert!(Prefix.meets(&zero, &"00".repeat(64)));
+        assert!(!Prefix.meets(&ones, &"00".repeat(64)));
+        assert!(Prefix.meets(&ones, &"ff".repeat(64)));
+        assert!(!Prefix.meets(&ones, &format!("{}fe", "ff".repeat(63))));
+        assert!(!Below.meets(&zero, &"00".repeat(64)));
+        // Nothing to compare against: fail closed under every rule
+        assert!(!Prefix.meets(&ones, ""));
+        assert!(!Prefix.meets(&zero, ""));
+        assert!(!Below.meets(&zero, ""));
+        assert!(!ZeroBits.meets(&zero, ""));
+        assert!(!ZeroBytes.meets(&zero, ""));
+        assert_eq!(Prefix.probability(""), None);
+        assert!(!meets_difficulty(&zero, ""));
+    }
+
+    #[test]
+    fn case_and_bad_hex() {
+        assert!(Prefix.meets(&[0x00, 0x0a, 0xff, 0xff, 0xab], "000AFFFFAB"));
+        assert!(!Prefix.meets(&[0x00; 8], "000"));
+        assert!(!Prefix.meets(&[0x00; 8], "00zz"));
+    }
+
+    #[test]
+    fn probability_covers_the_full_width() {
+        assert_eq!(Prefix.probability("00ffffff"), Some(1.0 / 256.0));
+        assert_eq!(Prefix.probability("00000000ff"), Some(1.0 / 256f64.powi(4)));
+        assert_eq!(Below.probability("0000000100"), Some(1.0 / 256f64.powi(4)));
+        assert_eq!(Prefix.probability(&"ff".repeat(64)), Some(1.0));
+        assert_eq!(Prefix.probability("xyz"), None);
+    }
+
+    #[test]
+    fn trace_agrees_with_meets() {
+        let target = "000fffff00000010";
+        let hashes: [&[u8]; 4] = [
+            &[0x00, 0x0f, 0xff, 0xff, 0x00, 0x00, 0x00, 0x10],
+            &[0x00, 0x0f, 0xff, 0xff, 0x00, 0x00, 0x00, 0x11],
+            &[0x00, 0x0f, 0xff],
+            &[0x01],
+        ];
+        for hash in hashes {
+            let trace = Prefix.trace(hash, target);
+            let verdict = trace.last().unwrap();
+            assert_eq!(Prefix.meets(hash, target), verdict.ends_with("PASS"), "{:?}", trace);
+        }
+        let trace = Prefix.trace(hashes[1], target);
+        assert_eq!(trace.len(), 8);
+        assert_eq!(trace[7], "byte 7: hash 11 > target 10 => FAIL");
+    }
+
+    #[test]
+    fn matching_preimage_has_no_mismatches() {
+        let preimage = build_preimage(NONCE, ADDRESS, &challenge());
+        assert!(preimage_mismatches(&preimage, NONCE, ADDRESS, &challenge()).is_empty());
+        // Hex case is not significant in the nonce
+        let upper = build_preimage(&NONCE.to_uppercase(), ADDRESS, &challenge());
+        assert!(preimage_mismatches(&upper, NONCE, ADDRESS, &challenge()).is_empty());
+    }
+
+    /// Changes one input of build_preimage: nonce, address or challenge
+    type Change = fn(&mut String, &mut String, &mut Challenge);
+
+    #[test]
+    fn each_mismatched_field_is_named() {
+        let fields: [(&str, Change); 7] = [
+            ("nonce", |n, _, _| *n = "00000000deadbeee".into()),
+            ("address", |_, a, _| *a = "addr1qmismatcx".into()),
+            ("challenge_id", |_, _, c| c.challenge_id = "**D05C11".into()),
+            ("difficulty", |_, _, c| c.difficulty = "0000FFFE".into()),
+            ("no_pre_mine", |_, _, c| c.no_pre_mine = "fd651ac2725e3b9e".into()),
+            ("latest_submission", |_, _, c| c.latest_submission = "2025-11-03T23:59:58Z".into()),
+            ("no_pre_mine_hour", |_, _, c| c.no_pre_mine_hour = "548571129".into()),
+        ];
+        for (name, change) in fields {
+            let (mut nonce, mut address) = (NONCE.to_string(), ADDRESS.to_string());
+            let mut served = challenge();
+            change(&mut nonce, &mut address, &mut served);
+            let preimage = build_preimage(&nonce, &address, &served);
+            let problems = preimage_mismatches(&preimage, NONCE, ADDRESS, &challenge());
+            assert_eq!(problems.len(), 1, "{}: {:?}", name, problems);
+            assert!(problems[0].starts_with(&format!("{}: expected", name)), "{:?}", problems);
+        }
+    }
+
+    #[test]
+    fn every_mismatch_is_reported() {
+        let mut served = challenge();
+        served.challenge_id = "**D05C11".into();
+        served.no_pre_mine_hour = "548571129".into();
+        let preimage = build_preimage("00000000deadbeee", ADDRESS, &served);
+        let problems = preimage_mismatches(&preimage, NONCE, ADDRESS, &challenge());
+        assert_eq!(
+            problems,
+            [
+                "nonce: expected '00000000deadbeef', found '00000000deadbeee'",
+                "challenge_id: expected '**D05C10', found '**D05C11'",
+                "no_pre_mine_hour: expected '548571128', found '548571129'",
+            ]
+        );
+    }
+
+    #[test]
+    fn length_differences_show_up() {
+        let preimage = build_preimage(NONCE, ADDRESS, &challenge());
+        let long = format!("{}00", preimage);
+        let problems = preimage_mismatches(&long, NONCE, ADDRESS, &challenge());
+        assert_eq!(problems, ["2 unexpected byte(s) after no_pre_mine_hour: '00'"]);
+        let short = &preimage[..preimage.len() - 3];
+        let problems = preimage_mismatches(short, NONCE, ADDRESS, &challenge());
+        assert_eq!(problems, ["no_pre_mine_hour: expected '548571128', found '548571'"]);
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn context_hashes_as_the_rom_does() {
+        use super::{build_rom_sized, hash_preimage, MiningContext};
+        let preimage = build_preimage(NONCE, ADDRESS, &challenge());
+        let ctx = MiningContext::new("fd651ac2", 1024 * 1024);
+        let rom = build_rom_sized(b"fd651ac2", 1024 * 1024);
+        assert_eq!(ctx.hash(&preimage), hash_preimage(&rom, &preimage));
+        assert_eq!((ctx.seed(), ctx.rom_size()), ("fd651ac2", 1024 * 1024));
+        assert!(!ctx.is_protocol());
+        let instant = MiningContext::instant();
+        assert_eq!(instant.hash(&preimage), [0; 64]);
+        assert!(!instant.is_protocol());
+    }
+}
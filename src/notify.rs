@@ -0,0 +1,199 @@
+// ==================== PHONE NOTIFICATIONS ====================
+//
+// A miner in a closet is out of sight; a push to the phone is the least
+// effort way to keep an eye on it. Two hosted services, nothing to run:
+//   ntfy      --notify ntfy://<topic> or a full https://<server>/<topic>
+//   Pushover  --notify pushover://<user key>@<app token>
+// Sparse on purpose: every solution, plus a heartbeat every --notify-every
+// (default 6 h) with the state, hash rate and solutions so far. A dead rig
+// cannot say so, so each heartbeat says when the next is due: silence
+// past that time means the rig is gone. A failed push is logged and
+// dropped; notifications never hold up mining.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::status::{self, Status};
+use crate::store::{SolutionRecord, SolutionStatus};
+use crate::{chart, limits, machine, parse_deadline, timefmt, tls};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub enum Notifier {
+    /// Topic URL
+    Ntfy(String),
+    Pushover { user: String, token: String },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Notifier {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let raw = raw.trim();
+        if let Some(topic) = raw.strip_prefix("ntfy://") {
+            if topic.is_empty() || topic.contains('/') {
+                return Err(format!("'{}' needs a topic name, e.g. ntfy://my-miner", raw));
+            }
+            return Ok(Notifier::Ntfy(format!("https://ntfy.sh/{}", topic)));
+        }
+        if let Some(rest) = raw.strip_prefix("pushover://") {
+            return match rest.split_once('@') {
+                Some((user, token)) if !user.is_empty() && !token.is_empty() => {
+                    Ok(Notifier::Pushover { user: user.to_string(), token: token.to_string() })
+                }
+                _ => Err("use pushover://<user key>@<app token>".to_string()),
+            };
+        }
+        if raw.starts_with("https://") || raw.starts_with("http://") {
+            return Ok(Notifier::Ntfy(raw.trim_end_matches('/').to_string()));
+        }
+        Err(format!("'{}' is not ntfy://, pushover:// or an ntfy topic URL", raw))
+    }
+
+    pub async fn send(&self, title: &str, message: &str, priority: Priority) -> Result<()> {
+        let client = tls::builder().timeout(TIMEOUT).build()?;
+        // Several rigs may push to one topic
+        let message = match machine::id() {
+            Some(id) => format!("{}\nMachine {}", message, id),
+            None => message.to_string(),
+        };
+        let message = message.as_str();
+        let response = match self {
+            Notifier::Ntfy(url) => {
+                let priority = match priority {
+                    Priority::Low => "2",
+                    Priority::Normal => "3",
+                    Priority::High => "4",
+                };
+                client
+                    .post(url)
+                    .header("Title", title)
+                    .header("Priority", priority)
+                    .body(message.to_string())
+                    .send()
+                    .await?
+            }
+            Notifier::Pushover { user, token } => {
+                let priority = match priority {
+                    Priority::Low => "-1",
+                    Priority::Normal => "0",
+                    Priority::High => "1",
+                };
+                client
+                    .post("https://api.pushover.net/1/messages.json")
+                    .form(&[
+                        ("token", token.as_str()),
+                        ("user", user.as_str()),
+                        ("title", title),
+                        ("message", message),
+                        ("priority", priority),
+                    ])
+                    .send()
+                    .await?
+            }
+        };
+        if !response.status().is_success() {
+            bail!("HTTP {}", response.status());
+        }
+        Ok(())
+    }
+
+    /// Send, logging instead of failing
+    pub async fn try_send(&self, title: &str, message: &str, priority: Priority) {
+        if let Err(e) = self.send(title, message, priority).await {
+            say!("⚠️  Notification failed: {}", e);
+        }
+    }
+}
+
+impl std::fmt::Display for Notifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Notifier::Ntfy(url) => write!(f, "ntfy {}", url),
+            Notifier::Pushover { .. } => write!(f, "Pushover"),
+        }
+    }
+}
+
+/// Alert for a submitted solution
+pub async fn solution(notifier: &Notifier, record: &SolutionRecord) {
+    let (title, priority) = match record.status {
+        SolutionStatus::Accepted => ("Solution accepted", Priority::Normal),
+        SolutionStatus::AcceptedNoReceipt => {
+            ("Solution most likely accepted, no receipt", Priority::Normal)
+        }
+        SolutionStatus::Rejected => ("Solution rejected", Priority::High),
+        SolutionStatus::SubmitFailed => ("Solution not delivered", Priority::High),
+        _ => return,
+    };
+    let message = format!(
+        "{} (day {}, #{})\nnonce {}\n{}",
+        record.challenge.challenge_id,
+        record.challenge.day,
+        record.challenge.challenge_number,
+        record.nonce,
+        record.address
+    );
+    notifier.try_send(title, &message, priority).await;
+}
+
+/// No progress for this long outside the idle phases is a stall
+const STALL_AFTER: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Phases that legitimately make no progress for hours
+const IDLE_PHASES: [&str; 2] = ["waiting", "retrying"];
+
+/// Title, body and priority of a heartbeat
+fn heartbeat(status: &Status, up: Duration, every: Duration) -> (&'static str, String, Priority) {
+    let now = Utc::now();
+    let humanize = |d: Duration| timefmt::humanize(chrono::Duration::from_std(d).unwrap_or_default());
+    let mut message = format!("{} at {:.0} H/s", status.phase, status.hash_rate);
+    if let Some(id) = &status.challenge_id {
+        message.push_str(&format!(" on {}", id));
+    }
+    message.push_str(&format!("\nUp {}, {} accepted", humanize(up), status.accepted));
+    if let Some(rss) = status.memory.and_then(|m| m.rss) {
+        message.push_str(&format!(", RSS {}", limits::size(rss)));
+    }
+    if !status.rate_history.is_empty() {
+        message.push('\n');
+        message.push_str(&chart::sparkline(&status.rate_history, 30));
+    }
+    let next = now + chrono::Duration::from_std(every).unwrap_or_default();
+    message.push_str(&format!("\nNext heartbeat by {}", timefmt::local(next)));
+
+    let stalled = parse_deadline(&status.updated_at)
+        .map(|t| now - t)
+        .filter(|idle| *idle > STALL_AFTER && !IDLE_PHASES.contains(&status.phase.as_str()));
+    match stalled {
+        Some(idle) => {
+            let message = format!("No progress for {}\n{}", timefmt::humanize(idle), message);
+            ("Miner stalled", message, Priority::High)
+        }
+        None => ("Miner heartbeat", message, Priority::Low),
+    }
+}
+
+/// Heartbeat every `every` until cancelled; spawn on the runtime
+pub async fn heartbeats(notifier: Notifier, every: Duration, cancel: CancellationToken) {
+    let started = Instant::now();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(every) => {}
+            _ = cancel.cancelled() => return,
+        }
+        let Some(status) = status::current() else {
+            continue;
+        };
+        let (title, message, priority) = heartbeat(&status, started.elapsed(), every);
+        notifier.try_send(title, &message, priority).await;
+    }
+}
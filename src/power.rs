@@ -0,0 +1,85 @@
+// ==================== POWER GUARD ====================
+//
+// Phones and passively cooled boards throttle, or shut down, long before
+// a desktop would. Read battery and temperature from sysfs (Linux and
+// Android/Termux) and pause hashing while the device is too hot or the
+// battery is running low off the charger.
+
+/// Resume only once readings are this far back inside the limits
+const BATTERY_MARGIN: u8 = 5;
+const TEMP_MARGIN_C: f32 = 5.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PowerGuard {
+    /// Pause below this charge while discharging
+    pub min_battery: u8,
+    /// Pause above this temperature (hottest thermal zone)
+    pub max_temp_c: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct Reading {
+    pub battery: Option<u8>,
+    pub charging: bool,
+    pub temp_c: Option<f32>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn read() -> Reading {
+    use std::fs;
+
+    let read = |path: std::path::PathBuf| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+
+    let mut reading = Reading::default();
+    if let Ok(supplies) = fs::read_dir("/sys/class/power_supply") {
+        for supply in supplies.flatten() {
+            let path = supply.path();
+            if read(path.join("type")).as_deref() != Some("Battery") {
+                continue;
+            }
+            reading.battery = read(path.join("capacity")).and_then(|c| c.parse().ok());
+            reading.charging = matches!(
+                read(path.join("status")).as_deref(),
+                Some("Charging" | "Full")
+            );
+            break;
+        }
+    }
+    if let Ok(zones) = fs::read_dir("/sys/class/thermal") {
+        // Millidegrees; the hottest zone is the one that throttles
+        reading.temp_c = zones
+            .flatten()
+            .filter(|zone| zone.file_name().to_string_lossy().starts_with("thermal_zone"))
+            .filter_map(|zone| read(zone.path().join("temp"))?.parse::<i64>().ok())
+            .filter(|&milli| milli > 0)
+            .max()
+            .map(|milli| milli as f32 / 1000.0);
+    }
+    reading
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn read() -> Reading {
+    Reading::default()
+}
+
+impl PowerGuard {
+    /// Why hashing should be paused right now, if it should
+    pub fn pause_reason(&self, reading: &Reading, paused: bool) -> Option<String> {
+        // Hysteresis so a reading hovering at the limit doesn't flap
+        let (battery_limit, temp_limit) = if paused {
+            (self.min_battery.saturating_add(BATTERY_MARGIN), self.max_temp_c - TEMP_MARGIN_C)
+        } else {
+            (self.min_battery, self.max_temp_c)
+        };
+        if let Some(temp) = reading.temp_c.filter(|&t| t > temp_limit) {
+            return Some(format!("temperature {:.0}°C above {:.0}°C", temp, temp_limit));
+        }
+        match reading.battery {
+            Some(level) if !reading.charging && level < battery_limit => {
+                Some(format!("battery at {}% and discharging", level))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+// ==================== DEVICE PROFILES ====================
+//
+// Presets for hardware that can't take the desktop defaults. The ROM size
+// is fixed by the protocol, so profiles can only trim what surrounds it:
+// thread counts, how hard we push a device that throttles, and how often
+// we write to storage that wears out.
+
+use clap::ValueEnum;
+use std::time::Duration;
+
+use crate::power::PowerGuard;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Profile {
+    /// Desktop and server defaults
+    Standard,
+    /// Phones (Termux) and small boards: few threads, pause when hot or on low battery
+    LowMemory,
+    /// Raspberry Pi and other SD-card boards: pause before firmware throttling, write rarely
+    Sbc,
+}
+
+pub struct Tuning {
+    /// Cap for both --threads and --sprint-threads
+    pub max_threads: Option<usize>,
+    pub power_guard: Option<PowerGuard>,
+    /// How often the daemon rewrites status.json
+    pub status_interval: Duration,
+}
+
+impl Profile {
+    pub fn tuning(self) -> Tuning {
+        match self {
+            Profile::Standard => Tuning {
+                max_threads: None,
+                power_guard: None,
+                status_interval: Duration::from_secs(15),
+            },
+            Profile::LowMemory => Tuning {
+                // Every extra worker is more heat than hashes on a phone SoC
+                max_threads: Some(2),
+                power_guard: Some(PowerGuard { min_battery: 30, max_temp_c: 70.0 }),
+                status_interval: Duration::from_secs(15),
+            },
+            Profile::Sbc => Tuning {
+                max_threads: None,
+                // The Pi firmware starts throttling at 80°C; back off before it does
+                power_guard: Some(PowerGuard { min_battery: 0, max_temp_c: 75.0 }),
+                // SD cards wear out; a probe can live with a minute-old status
+                status_interval: Duration::from_secs(60),
+            },
+        }
+    }
+}
+
+impl Tuning {
+    pub fn threads(&self, requested: usize) -> usize {
+        self.max_threads.map_or(requested, |max| requested.min(max))
+    }
+}
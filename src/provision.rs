@@ -0,0 +1,201 @@
+// ==================== PROVISIONING ====================
+//
+// `provision --from-url`: a new rig in one line of cloud-init or one
+// Ansible task. The config file (e.g. one rendered by `fleet render`) is
+// downloaded, checked against a SHA-256 (--sha256, else the first word of
+// <URL>.sha256, as sha256sum writes it), run through `config check` as a
+// daemon would be and only then written to --config, so a bad file never
+// replaces a working one. Then the daemon is started: in the background by
+// default, so the provisioning step returns, or in this process for
+// container entrypoints, or as the OS's own job where there is an
+// installer.
+//
+// A <URL>.sha256 comes from the same server as the file, so it only
+// catches a corrupt or cut-short download: whoever can change the file can
+// change its checksum too. Only --sha256, taken from somewhere else,
+// authenticates the file, and installing a service requires it.
+
+use anyhow::{bail, Context, Result};
+use clap::{CommandFactory, FromArgMatches, ValueEnum};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::configfile::ConfigFile;
+use crate::{config, retry, service, tls, Cli, Mode};
+
+/// Where the background daemon's output goes, in the data dir
+const LOG_FILE: &str = "daemon.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Start {
+    /// Detached, output to daemon.log in the data dir; provision returns
+    Background,
+    /// Become the daemon (container entrypoints, systemd ExecStart)
+    Exec,
+    /// Install it as a launchd agent (macOS) or scheduled task (Windows)
+    Service,
+    /// Only write the file
+    None,
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    retry::with_backoff("download", || async {
+        let response = client.get(url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            retry::check_status(status.as_u16(), response.text().await?)?;
+            bail!("{} answered HTTP {}", url, status.as_u16());
+        }
+        Ok(response.bytes().await?.to_vec())
+    })
+    .await
+}
+
+/// --sha256, else the digest published beside the file
+async fn expected_digest(
+    client: &reqwest::Client,
+    url: &str,
+    given: Option<&str>,
+) -> Result<String> {
+    let digest = match given {
+        Some(digest) => digest.to_string(),
+        None => {
+            let sums_url = format!("{}.sha256", url);
+            let sums = download(client, &sums_url).await.with_context(|| {
+                format!("No checksum: pass --sha256 or publish {}", sums_url)
+            })?;
+            let sums = String::from_utf8_lossy(&sums);
+            sums.split_whitespace().next().unwrap_or_default().to_string()
+        }
+    };
+    let digest = digest.trim().to_ascii_lowercase();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("'{}' is not a SHA-256 (64 hex digits)", digest);
+    }
+    Ok(digest)
+}
+
+/// Write beside the target and rename, so a crash leaves the old file
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, contents)
+        .with_context(|| format!("Failed to write {}", partial.display()))?;
+    std::fs::rename(&partial, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+pub async fn run(cli: &Cli, url: &str, sha256: Option<&str>, start: Start) -> Result<()> {
+    if start == Start::Service && sha256.is_none() {
+        bail!(
+            "--start service installs a job that runs at every boot: pass --sha256 with the \
+             digest from somewhere other than {}",
+            url
+        );
+    }
+    let client = tls::builder().timeout(Duration::from_secs(30)).build()?;
+    say!("📥 Fetching {}", url);
+    let body = download(&client, url).await?;
+    let expected = expected_digest(&client, url, sha256).await?;
+    let actual = hex::encode(Sha256::digest(&body));
+    if actual != expected {
+        bail!("Checksum mismatch for {}: expected {}, got {}", url, expected, actual);
+    }
+    say!("   SHA-256 {} matches", actual);
+    if sha256.is_none() {
+        say!("⚠️  The checksum came from the same server as the file: it rules out a broken");
+        say!("   download, not a tampered one. Pass --sha256 to authenticate the file.");
+    }
+    let raw = String::from_utf8(body).context("The config file is not UTF-8")?;
+
+    // The flags of this run over the new file, as the daemon will see them
+    let file = ConfigFile::parse(&Cli::command(), &cli.config, &raw)?;
+    let matches = file.apply(Cli::command()).try_get_matches_from(std::env::args_os())?;
+    let mut provisioned = Cli::from_arg_matches(&matches)?;
+    provisioned.mode = Mode::Daemon;
+    say!();
+    config::check(&provisioned, &matches, Some(&file))
+        .context("Not writing a config file the daemon would fail on")?;
+
+    let unchanged = std::fs::read_to_string(&cli.config).is_ok_and(|old| old == raw);
+    if unchanged {
+        say!("   {} is already this file", cli.config.display());
+    } else {
+        write_atomic(&cli.config, raw.as_bytes())?;
+        say!("✅ Wrote {}", cli.config.display());
+    }
+
+    // The daemon may start in another directory: hand it the file by its
+    // absolute path (daemon_args passes on provisioned.config)
+    provisioned.config = cli
+        .config
+        .canonicalize()
+        .with_context(|| format!("Cannot resolve {}", cli.config.display()))?;
+    match start {
+        Start::Background => background(&provisioned),
+        Start::Exec => exec(&provisioned),
+        Start::Service if cfg!(windows) => {
+            let trigger = service::TaskTrigger::Boot;
+            service::install_task(&provisioned, "ScavengerMiner", trigger, false)
+        }
+        Start::Service if cfg!(target_os = "macos") => {
+            service::install_launchd(&provisioned, "com.scavenger.miner", 10, false)
+        }
+        Start::Service => {
+            bail!("No service installer for this OS; use --start exec from a systemd unit")
+        }
+        Start::None => {
+            let exe = service::executable()?;
+            let args = service::daemon_args(&provisioned)?.join(" ");
+            say!("   Start the daemon: {} {}", exe.display(), args);
+            Ok(())
+        }
+    }
+}
+
+/// The daemon with the flags that were checked above (data dir, address,
+/// threads...), as the service installers would run it
+fn daemon(cli: &Cli) -> Result<Command> {
+    let mut command = Command::new(service::executable()?);
+    command.args(service::daemon_args(cli)?);
+    Ok(command)
+}
+
+fn background(cli: &Cli) -> Result<()> {
+    std::fs::create_dir_all(&cli.data_dir)?;
+    let log_path = cli.data_dir.join(LOG_FILE);
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Cannot open {}", log_path.display()))?;
+    let mut command = daemon(cli)?;
+    command.stdin(Stdio::null()).stdout(log.try_clone()?).stderr(log);
+    // Its own process group, so the provisioning shell's Ctrl-C or exit
+    // doesn't reach it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let child = command.spawn().context("Cannot start the daemon")?;
+    say!("🚀 Daemon started (pid {}), logging to {}", child.id(), log_path.display());
+    Ok(())
+}
+
+fn exec(cli: &Cli) -> Result<()> {
+    let mut command = daemon(cli)?;
+    say!("🚀 Starting the daemon");
+    #[cfg(unix)]
+    {
+        let e = std::os::unix::process::CommandExt::exec(&mut command);
+        Err(e).context("Cannot start the daemon")
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status().context("Cannot start the daemon")?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
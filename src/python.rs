@@ -0,0 +1,159 @@
+// ==================== PYTHON ====================
+//
+// PyO3 module for scripting strategies and poking at difficulty data from
+// notebooks. Same protocol code as the miner; API responses come back as
+// plain dicts/lists (via the json module) so they drop into pandas as-is.
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::mining;
+use crate::types::{Challenge, ChallengeResponse, SolutionResponse, BASE_URL};
+
+fn runtime_error(e: impl std::fmt::Display) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+/// serde value -> Python object, through the json module
+fn to_py(py: Python<'_>, value: &impl serde::Serialize) -> PyResult<PyObject> {
+    let text = serde_json::to_string(value).map_err(runtime_error)?;
+    Ok(py.import("json")?.call_method1("loads", (text,))?.unbind())
+}
+
+/// Python dict (the "challenge" object from the API) -> Challenge
+fn challenge_from_py(py: Python<'_>, challenge: &Bound<'_, PyAny>) -> PyResult<Challenge> {
+    let text: String = py
+        .import("json")?
+        .call_method1("dumps", (challenge,))?
+        .extract()?;
+    serde_json::from_str(&text).map_err(|e| PyValueError::new_err(format!("bad challenge: {}", e)))
+}
+
+/// Blocking client for the Scavenger Mine API
+#[pyclass(name = "Client")]
+struct PyClient {
+    api: crate::Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl PyClient {
+    fn fetch(&self, py: Python<'_>, post: bool, path: &str) -> PyResult<serde_json::Value> {
+        py.allow_threads(|| self.runtime.block_on(self.api.fetch(post, path)))
+            .map_err(|e| runtime_error(format!("{:#}", e)))
+    }
+}
+
+#[pymethods]
+impl PyClient {
+    #[new]
+    #[pyo3(signature = (base_url = BASE_URL.to_string()))]
+    fn new(base_url: String) -> PyResult<Self> {
+        let api = crate::Client::with_base_url(base_url).map_err(runtime_error)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(runtime_error)?;
+        Ok(PyClient { api, runtime })
+    }
+
+    /// GET /challenge
+    fn challenge(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let value = self.fetch(py, false, "challenge")?;
+        // Validate against the wire types so scripts fail the same way the miner would
+        serde_json::from_value::<ChallengeResponse>(value.clone()).map_err(runtime_error)?;
+        to_py(py, &value)
+    }
+
+    /// POST /solution/{address}/{challenge_id}/{nonce}
+    fn submit(&self, py: Python<'_>, address: &str, challenge_id: &str, nonce: &str) -> PyResult<PyObject> {
+        let path = format!("solution/{}/{}/{}", address, challenge_id, nonce);
+        let value = self.fetch(py, true, &path)?;
+        let response: SolutionResponse =
+            serde_json::from_value(value).map_err(runtime_error)?;
+        to_py(py, &response)
+    }
+
+    /// GET /work_to_star_rate
+    fn star_rate(&self, py: Python<'_>) -> PyResult<Vec<u64>> {
+        let value = self.fetch(py, false, "work_to_star_rate")?;
+        serde_json::from_value(value).map_err(runtime_error)
+    }
+
+    /// GET /TandC
+    fn terms(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let value = self.fetch(py, false, "TandC")?;
+        to_py(py, &value)
+    }
+}
+
+/// AshMaize ROM for one no_pre_mine (about 1 GB)
+#[pyclass]
+struct Rom(ashmaize::Rom);
+
+#[pymethods]
+impl Rom {
+    #[new]
+    fn new(py: Python<'_>, no_pre_mine: &str) -> Self {
+        Rom(py.allow_threads(|| mining::build_rom(no_pre_mine.as_bytes())))
+    }
+
+    fn hash<'py>(&self, py: Python<'py>, preimage: &str) -> Bound<'py, PyBytes> {
+        let digest = py.allow_threads(|| mining::hash_preimage(&self.0, preimage));
+        PyBytes::new(py, &digest)
+    }
+
+    /// Scan nonces start..start+count; (nonce, digest) of the first solution or None
+    fn mine<'py>(
+        &self,
+        py: Python<'py>,
+        address: &str,
+        challenge: &Bound<'py, PyAny>,
+        start: u64,
+        count: u64,
+    ) -> PyResult<Option<(String, Bound<'py, PyBytes>)>> {
+        let challenge = challenge_from_py(py, challenge)?;
+        let found = py.allow_threads(|| {
+            (0..count).find_map(|i| {
+                let nonce = format!("{:016x}", start.wrapping_add(i));
+                let preimage = mining::build_preimage(&nonce, address, &challenge);
+                let digest = mining::hash_preimage(&self.0, &preimage);
+                mining::meets_difficulty(&digest, &challenge.difficulty).then_some((nonce, digest))
+            })
+        });
+        Ok(found.map(|(nonce, digest)| (nonce, PyBytes::new(py, &digest))))
+    }
+}
+
+#[pyfunction]
+fn build_preimage(py: Python<'_>, nonce: &str, address: &str, challenge: &Bound<'_, PyAny>) -> PyResult<String> {
+    Ok(mining::build_preimage(nonce, address, &challenge_from_py(py, challenge)?))
+}
+
+#[pyfunction]
+fn meets_difficulty(hash: &[u8], difficulty: &str) -> bool {
+    mining::meets_difficulty(hash, difficulty)
+}
+
+/// Chance a single hash passes; None for unparsable difficulty
+#[pyfunction]
+fn success_probability(difficulty: &str) -> Option<f64> {
+    mining::success_probability(difficulty)
+}
+
+#[pyfunction]
+fn difficulty_trace(hash: &[u8], difficulty: &str) -> Vec<String> {
+    mining::difficulty_trace(hash, difficulty)
+}
+
+#[pymodule]
+fn scavenger_miner(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("BASE_URL", BASE_URL)?;
+    m.add_class::<PyClient>()?;
+    m.add_class::<Rom>()?;
+    m.add_function(wrap_pyfunction!(build_preimage, m)?)?;
+    m.add_function(wrap_pyfunction!(meets_difficulty, m)?)?;
+    m.add_function(wrap_pyfunction!(success_probability, m)?)?;
+    m.add_function(wrap_pyfunction!(difficulty_trace, m)?)?;
+    Ok(())
+}
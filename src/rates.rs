@@ -0,0 +1,153 @@
+// ==================== REWARD SCHEDULE ====================
+//
+// work_to_star_rate says what a solution is worth on each day of the
+// event, and whether mining on is worth the power comes down to it. The
+// server can change it mid-event without a word. Daemons (and --loop)
+// fetch it once a day between rounds and keep every distinct version in
+// rates.jsonl; when it differs from the last one saved, the log and
+// --notify get the days that changed and what the days still ahead are
+// worth now, since a cut there is the reason to stop. `rates` checks on
+// demand and lists the versions seen.
+
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::notify::{Notifier, Priority};
+use crate::store::Store;
+use crate::{timefmt, tls, Cli, MinerApi, ScavengerAPI};
+
+/// Between checks in daemon mode
+pub const CHECK_EVERY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Changed days in a notification before the rest are summed up
+const NOTIFY_LINES: usize = 8;
+
+fn percent(before: u64, after: u64) -> String {
+    if before == 0 {
+        return "new".to_string();
+    }
+    format!("{:+.0}%", (after as f64 - before as f64) / before as f64 * 100.0)
+}
+
+/// One line per day whose reward differs, e.g. "Day 3: 90 STAR, was 120 (-25%)"
+fn changes(old: &[u64], new: &[u64]) -> Vec<String> {
+    (0..old.len().max(new.len()))
+        .filter_map(|i| match (old.get(i), new.get(i)) {
+            (Some(before), Some(after)) if before == after => None,
+            (Some(before), Some(after)) => Some(format!(
+                "Day {}: {} STAR, was {} ({})",
+                i + 1,
+                after,
+                before,
+                percent(*before, *after)
+            )),
+            (None, Some(after)) => Some(format!("Day {}: new, {} STAR", i + 1, after)),
+            (Some(before), None) => Some(format!("Day {}: gone (was {} STAR)", i + 1, before)),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+/// What one solution a day from `day` to the end is worth, old and new
+fn outlook(old: &[u64], new: &[u64], day: usize) -> Option<String> {
+    let ahead = |rates: &[u64]| rates.iter().skip(day.saturating_sub(1)).sum::<u64>();
+    let (before, after) = (ahead(old), ahead(new));
+    if day == 0 || day > new.len() || before == after {
+        return None;
+    }
+    Some(format!(
+        "Days {}-{}: {} STAR for a solution a day, was {} ({})",
+        day,
+        new.len(),
+        after,
+        before,
+        percent(before, after)
+    ))
+}
+
+/// The event day of the latest challenge seen
+fn current_day(store: &Store) -> Option<usize> {
+    let challenges = store.challenges().ok()?;
+    challenges.last().map(|c| c.challenge.day as usize)
+}
+
+/// Fetch the schedule and save it when it is new; report a change from the
+/// last saved version, to `notifier` too. Failures are logged, never fatal.
+pub async fn check<A: MinerApi>(api: &A, store: &Store, notifier: Option<&Notifier>) {
+    let rates = match api.get_star_rate().await {
+        Ok(rates) => rates,
+        Err(e) => {
+            say!("⚠️  Cannot check the reward schedule: {}", e);
+            return;
+        }
+    };
+    let previous = match store.rate_versions() {
+        Ok(mut versions) => versions.pop(),
+        Err(e) => {
+            say!("⚠️  Cannot read the saved reward schedules: {}", e);
+            return;
+        }
+    };
+    match store.record_rates(&rates) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            say!("⚠️  Failed to save the reward schedule: {}", e);
+            return;
+        }
+    }
+    let Some(previous) = previous else {
+        say!("⭐ Reward schedule saved ({} days)", rates.len());
+        return;
+    };
+
+    let mut lines = changes(&previous.rates, &rates);
+    let outlook = current_day(store).and_then(|day| outlook(&previous.rates, &rates, day));
+    say!("⭐ The reward schedule changed since {}:", timefmt::timestamp(&previous.first_seen));
+    for line in lines.iter().chain(&outlook) {
+        say!("   {}", line);
+    }
+    if let Some(notifier) = notifier {
+        let more = lines.len().saturating_sub(NOTIFY_LINES);
+        lines.truncate(NOTIFY_LINES);
+        if more > 0 {
+            lines.push(format!("and {} more day(s)", more));
+        }
+        lines.extend(outlook);
+        notifier.try_send("Reward schedule changed", &lines.join("\n"), Priority::High).await;
+    }
+}
+
+/// `rates`: check the server for a new version, then list them all
+pub async fn run(cli: &Cli) -> Result<()> {
+    tls::warn_if_insecure();
+    let store = Store::open(&cli.data_dir)?;
+    check(&ScavengerAPI::new(&cli.base_url)?, &store, None).await;
+    print(&store)
+}
+
+/// Every version seen, with what changed in each
+pub fn print(store: &Store) -> Result<()> {
+    let versions = store.rate_versions()?;
+    if versions.is_empty() {
+        say!("⭐ No reward schedule saved yet");
+        return Ok(());
+    }
+    say!("⭐ Reward schedules seen ({}):", versions.len());
+    let mut previous: Option<&[u64]> = None;
+    for (i, version) in versions.iter().enumerate() {
+        let total: u64 = version.rates.iter().sum();
+        say!(
+            "   v{}  {}  {} days, {} STAR in all",
+            i + 1,
+            timefmt::timestamp(&version.first_seen),
+            version.rates.len(),
+            total
+        );
+        for line in previous.map(|old| changes(old, &version.rates)).unwrap_or_default() {
+            say!("        {}", line);
+        }
+        previous = Some(&version.rates);
+    }
+    Ok(())
+}
@@ -0,0 +1,213 @@
+// ==================== REGISTRATION ====================
+//
+// `register`, and the offer at the start of an interactive run: the terms
+// are signed for the mining address by whatever the user has (a pasted
+// signature, a signing key file, a recovery phrase, a Ledger, or a browser
+// wallet through the signing page), the signature is checked here before
+// anything is sent, and only then is the address registered.
+
+use anyhow::{Context, Result};
+
+use crate::store::Store;
+use crate::{address, console, hdwallet, machine, signature, signpage, timefmt, tls, wallets};
+use crate::{api_client, given_address, resolve_address, Cli, Command, Mode, ScavengerAPI};
+#[cfg(feature = "chaos")]
+use crate::chaos;
+#[cfg(feature = "ledger")]
+use crate::ledger;
+
+#[derive(Default)]
+pub struct Registration {
+    pub wallet: Option<wallets::Wallet>,
+    pub dry_run: bool,
+    pub verify_only: bool,
+    /// Pasted instead of using the signing page
+    pub signature: Option<String>,
+    pub pubkey: Option<String>,
+    /// Signing key file to sign with locally
+    pub skey: Option<std::path::PathBuf>,
+    /// Key derived from a recovery phrase (--mnemonic)
+    pub key: Option<signature::SigningKey>,
+    /// Device to sign on (--ledger), with the stake key for stake addresses
+    #[cfg(feature = "ledger")]
+    pub ledger: Option<(ledger::Ledger, bool)>,
+}
+
+/// --signature/--pubkey as register wants them
+fn pasted_signature(signature: &str, pubkey: Option<&str>) -> Result<signpage::Signed> {
+    let pasted = signature::parse_signature(signature)
+        .map_err(|e| anyhow::anyhow!("Invalid signature: {}", e))?;
+    let pubkey = match (pasted.pubkey, pubkey) {
+        (_, Some(pubkey)) => signature::parse_pubkey(pubkey)
+            .map_err(|e| anyhow::anyhow!("Invalid public key: {}", e))?,
+        (Some(pubkey), None) => pubkey,
+        (None, None) => anyhow::bail!("--signature has no key in it; pass --pubkey too"),
+    };
+    Ok(signpage::Signed { signature: pasted.signature, pubkey })
+}
+
+/// --skey: sign the message here, as the wallet would
+fn skey_signature(
+    path: &std::path::Path,
+    message: &str,
+    address: Option<&[u8]>,
+) -> Result<signpage::Signed> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    let key = signature::SigningKey::parse(&raw)
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    let address = address.context("The mining address does not decode, so cannot be signed for")?;
+    say!("\n🔑 Signing with {}", path.display());
+    Ok(signpage::Signed { signature: key.sign(message, address), pubkey: key.pubkey() })
+}
+
+/// Fetch the terms, sign them (pasted, --skey, phrase, Ledger or the signing
+/// page), check the signature locally and register `address`
+pub async fn run(api: &ScavengerAPI, address: &str, registration: &Registration) -> Result<()> {
+    say!();
+    console::banner(&["📝 REGISTRATION PROCESS"]);
+    
+    say!("\n📄 Fetching Terms & Conditions...");
+    let tandc = api.get_terms().await?;
+    say!("✅ Got T&C version: {}", tandc.version);
+    
+    say!("\n📋 Message to sign:");
+    say!("────────────────────────────────────────────────────────────────");
+    say!("{}", tandc.message);
+    say!("────────────────────────────────────────────────────────────────");
+    
+    let address_bytes = address::decode(address).ok().map(|(_, bytes)| bytes);
+    let signed = (&registration.signature, &registration.skey, &registration.key);
+    let signpage::Signed { signature, pubkey } = match signed {
+        (Some(pasted), _, _) => pasted_signature(pasted, registration.pubkey.as_deref())?,
+        (None, Some(path), _) => skey_signature(path, &tandc.message, address_bytes.as_deref())?,
+        (None, None, Some(key)) => {
+            let address = address_bytes.as_deref().context("The address does not decode")?;
+            say!("\n🔑 Signing with the key from the recovery phrase");
+            signpage::Signed { signature: key.sign(&tandc.message, address), pubkey: key.pubkey() }
+        }
+        #[cfg(feature = "ledger")]
+        (None, None, None) if registration.ledger.is_some() => {
+            let (device, stake) = registration.ledger.as_ref().expect("just checked");
+            device.sign(&tandc.message, *stake)?
+        }
+        (None, None, None) => {
+            signpage::sign(&tandc.message, address, address_bytes.as_deref(), registration.wallet)
+                .await?
+        }
+    };
+    
+    say!("\n🔎 Verifying the signature locally...");
+    let problems = signature::verify(&signature, &pubkey, &tandc.message, address_bytes.as_deref());
+    if !problems.is_empty() {
+        for problem in &problems {
+            say!("   ❌ {}", problem);
+        }
+        anyhow::bail!("The signature would be rejected; nothing was sent");
+    }
+    say!("✅ Signature matches the message, the public key and {}", address);
+    
+    if registration.verify_only {
+        say!("   --verify-only: not registering");
+        return Ok(());
+    }
+    if registration.dry_run {
+        say!("\n🧪 DRY RUN - registration not sent");
+        say!("   POST {}", api.register_url(address, &signature, &pubkey));
+        return Ok(());
+    }
+    
+    say!("\n📤 Registering...");
+    let result = api.register(address, &signature, &pubkey).await?;
+    
+    if let Some(receipt) = result.registration_receipt {
+        say!("✅ Registration successful!");
+        say!("   Timestamp: {}", timefmt::timestamp(&receipt.timestamp));
+    } else {
+        say!("✅ Registration completed");
+    }
+    
+    Ok(())
+}
+
+/// `register`: the address resolved as a mining run would (or the one a
+/// phrase or Ledger derives), signed for and registered, without the rest
+/// of a mining run's startup
+pub async fn command(cli: &Cli) -> Result<()> {
+    let Some(Command::Register {
+        verify_only,
+        signature,
+        pubkey,
+        skey,
+        mnemonic,
+        account,
+        address_index,
+        testnet,
+        ..
+    }) = &cli.command
+    else {
+        unreachable!("dispatched on Command::Register");
+    };
+    console::banner(&["🌙 SCAVENGER MINER v0.2.0", "Powered by AshMaize Algorithm"]);
+    #[cfg(feature = "chaos")]
+    chaos::announce();
+    tls::warn_if_insecure();
+    say!();
+
+    // --mnemonic: the phrase's own address unless one is given
+    let wallet = if *mnemonic {
+        let phrase = hdwallet::read_phrase()?;
+        let wallet = hdwallet::Wallet::from_phrase(&phrase, *account, *address_index)?;
+        let derived = wallet.address(!testnet);
+        say!("🔑 Account {}, address {} of the phrase: {}", account, address_index, derived);
+        Some((wallet, derived))
+    } else {
+        None
+    };
+    #[cfg(feature = "ledger")]
+    let ledger = match &cli.command {
+        Some(Command::Register { ledger: true, .. }) => {
+            let device = ledger::Ledger::open(*account, *address_index, !testnet)?;
+            let derived = device.address()?;
+            say!("🔑 Account {}, address {} of the Ledger: {}", account, address_index, derived);
+            Some((device, derived))
+        }
+        _ => None,
+    };
+    let derived = wallet.as_ref().map(|(_, derived)| derived);
+    #[cfg(feature = "ledger")]
+    let derived = derived.or(ledger.as_ref().map(|(_, derived)| derived));
+    let address = match given_address(cli, derived)? {
+        Some(address) => address,
+        None if cli.headless || cli.mode == Mode::Daemon => {
+            anyhow::bail!("Register needs an address here (--address, --label or SCAVENGER_ADDRESS)")
+        }
+        None => {
+            say!("Enter your Cardano address:");
+            console::read_line(None)?.unwrap_or_default().trim().to_string()
+        }
+    };
+    let address = resolve_address(cli, address)?;
+
+    let store = Store::open(&cli.data_dir)?;
+    machine::init(&cli.data_dir, cli.machine_id.as_deref())?;
+    let api = api_client(cli, &store)?;
+    let stake = cli.address_kind == address::AddressKind::Stake;
+    let key = match &wallet {
+        Some((wallet, _)) if stake => Some(wallet.stake_key()?),
+        Some((wallet, _)) => Some(wallet.payment_key()?),
+        None => None,
+    };
+    let registration = Registration {
+        wallet: cli.wallet,
+        dry_run: cli.dry_run,
+        verify_only: *verify_only,
+        signature: signature.clone(),
+        pubkey: pubkey.clone(),
+        skey: skey.clone(),
+        key,
+        #[cfg(feature = "ledger")]
+        ledger: ledger.map(|(device, _)| (device, stake)),
+    };
+    run(&api, &address, &registration).await
+}
@@ -0,0 +1,830 @@
+// ==================== REPLAY ====================
+//
+// Re-run the round orchestration against a recorded day of API traffic
+// (from audit.jsonl) with hashing stubbed to instant, so production
+// incidents become repeatable regression tests.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::audit::AuditEntry;
+use crate::round::{self, EngineChoice, RoundOutcome, RoundSettings};
+use crate::store::{self, AcceptanceRule, Store};
+use crate::{
+    console, parse_deadline, ChallengeResponse, DifficultyRule, MinerApi, NoChallenge,
+    SolutionResponse,
+};
+
+/// Serves recorded responses in the order they were captured
+pub struct ReplayApi {
+    challenges: Mutex<VecDeque<AuditEntry>>,
+    submissions: Mutex<VecDeque<AuditEntry>>,
+    star_rates: Mutex<VecDeque<AuditEntry>>,
+    /// When the challenge fetch last served was recorded
+    recorded_at: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl ReplayApi {
+    pub fn from_entries(entries: Vec<AuditEntry>) -> Self {
+        let mut challenges = VecDeque::new();
+        let mut submissions = VecDeque::new();
+        let mut star_rates = VecDeque::new();
+        for entry in entries {
+            match entry.action.as_str() {
+                "challenge" => challenges.push_back(entry),
+                "submit" => submissions.push_back(entry),
+                "star_rate" => star_rates.push_back(entry),
+                _ => {}
+            }
+        }
+        ReplayApi {
+            challenges: Mutex::new(challenges),
+            submissions: Mutex::new(submissions),
+            star_rates: Mutex::new(star_rates),
+            recorded_at: Mutex::new(None),
+        }
+    }
+
+    /// Load an audit log, optionally keeping only one UTC day (YYYY-MM-DD)
+    pub fn load(path: &Path, day: Option<&str>) -> Result<(Self, Vec<AuditEntry>)> {
+        let entries: Vec<AuditEntry> = store::read_jsonl(path)
+            .with_context(|| format!("Failed to read recording {}", path.display()))?;
+        let entries: Vec<AuditEntry> = entries
+            .into_iter()
+            .filter(|e| day.is_none_or(|d| e.timestamp.starts_with(d)))
+            .collect();
+        if entries.is_empty() {
+            bail!("No recorded API traffic in {}", path.display());
+        }
+        Ok((Self::from_entries(entries.clone()), entries))
+    }
+
+    fn remaining_challenges(&self) -> usize {
+        self.challenges.lock().unwrap().len()
+    }
+}
+
+fn replay_entry<T: DeserializeOwned>(entry: AuditEntry) -> Result<T> {
+    match entry.response {
+        Some(body) if entry.outcome != "error" => Ok(serde_json::from_value(body)?),
+        _ => bail!(
+            "recorded {} failed: {}",
+            entry.action,
+            entry.detail.unwrap_or_default()
+        ),
+    }
+}
+
+impl MinerApi for ReplayApi {
+    async fn get_challenge(&self) -> Result<ChallengeResponse> {
+        let entry = self.challenges.lock().unwrap().pop_front();
+        if let Some(at) = entry.as_ref().and_then(|e| parse_deadline(&e.timestamp)) {
+            *self.recorded_at.lock().unwrap() = Some(at);
+        }
+        match entry {
+            Some(entry) if entry.outcome == "idle" => {
+                let idle = entry.response.as_ref().and_then(NoChallenge::from_response);
+                Err(idle.context("recorded idle answer without a body")?.into())
+            }
+            Some(entry) => replay_entry(entry),
+            None => bail!("end of recording"),
+        }
+    }
+
+    async fn submit_solution(
+        &self,
+        _address: &str,
+        _challenge_id: &str,
+        _nonce: &str,
+    ) -> Result<SolutionResponse> {
+        let entry = self.submissions.lock().unwrap().pop_front();
+        match entry {
+            Some(entry) => replay_entry(entry),
+            None => bail!("no recorded submission left"),
+        }
+    }
+
+    async fn get_star_rate(&self) -> Result<Vec<u64>> {
+        let entry = self.star_rates.lock().unwrap().pop_front();
+        match entry {
+            Some(entry) => replay_entry(entry),
+            None => bail!("no recorded rate table left"),
+        }
+    }
+
+    async fn pause(&self, _duration: Duration) {}
+
+    fn now(&self) -> DateTime<Utc> {
+        self.recorded_at.lock().unwrap().unwrap_or_else(Utc::now)
+    }
+}
+
+fn replay_settings(address: &str) -> RoundSettings {
+    RoundSettings {
+        address: address.to_string(),
+        max_iterations: Some(1),
+        max_hashes: None,
+        stop_at: None,
+        threads: 1,
+        sprint_minutes: None,
+        sprint_threads: None,
+        work_hours: None,
+        dry_run: false,
+        confirm: false,
+        confirm_timeout: 0,
+        engine: EngineChoice::Instant,
+        resume_scan: false,
+        power_guard: None,
+        worker: None,
+        split: None,
+        rom_size: None,
+        min_success_chance: None,
+        acceptance: AcceptanceRule::PerChallenge,
+        difficulty_rule: DifficultyRule::default(),
+        share: None,
+        idle: round::IdlePolicy { rom: round::RomRetention::Keep, max_poll: None },
+        notifier: None,
+        cancel: CancellationToken::new(),
+    }
+}
+
+/// Run rounds until the recorded challenge fetches are used up
+pub async fn replay_rounds(
+    api: &ReplayApi,
+    store: &Store,
+    address: &str,
+) -> Vec<std::result::Result<RoundOutcome, String>> {
+    rounds_until_exhausted(api, store, &replay_settings(address)).await
+}
+
+async fn rounds_until_exhausted(
+    api: &ReplayApi,
+    store: &Store,
+    settings: &RoundSettings,
+) -> Vec<std::result::Result<RoundOutcome, String>> {
+    let mut outcomes = Vec::new();
+    while api.remaining_challenges() > 0 {
+        let outcome = round::run_round(api, store, settings).await;
+        outcomes.push(outcome.map_err(|e| e.to_string()));
+    }
+    outcomes
+}
+
+pub async fn run(path: &Path, day: Option<&str>, data_dir: &Path) -> Result<()> {
+    let (api, entries) = ReplayApi::load(path, day)?;
+    let address = entries
+        .iter()
+        .find_map(|e| e.address.clone())
+        .unwrap_or_else(|| "addr_replay".to_string());
+
+    // Fresh store so earlier replays can't change decisions
+    let replay_dir = data_dir
+        .join("replay")
+        .join(chrono::Utc::now().format("%Y%m%dT%H%M%S").to_string());
+    let store = Store::open(&replay_dir)?;
+
+    say!();
+    console::banner(&["🔁 REPLAY"]);
+    say!("   Recording: {}", path.display());
+    say!("   Entries: {}", entries.len());
+    say!("   Address: {}", address);
+    say!("   Output: {}", replay_dir.display());
+
+    let outcomes = replay_rounds(&api, &store, &address).await;
+
+    say!();
+    console::banner(&["🔁 REPLAY SUMMARY"]);
+    let recorded: Vec<&AuditEntry> = entries.iter().filter(|e| e.action == "submit").collect();
+    say!("   Recorded submissions: {}", recorded.len());
+    for entry in &recorded {
+        say!(
+            "     {} {} -> {}",
+            entry.timestamp,
+            entry.challenge_id.as_deref().unwrap_or("?"),
+            entry.outcome
+        );
+    }
+    say!("   Replayed rounds: {}", outcomes.len());
+    for (i, outcome) in outcomes.iter().enumerate() {
+        match outcome {
+            Ok(outcome) => say!("     #{} {:?}", i + 1, outcome),
+            Err(e) => say!("     #{} error: {}", i + 1, e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::SolutionStatus;
+    use crate::submitqueue;
+
+    const RECORDED_DAY: &str = include_str!("../tests/fixtures/replay_day.jsonl");
+
+    #[tokio::test]
+    async fn replays_recorded_day() {
+        let entries: Vec<AuditEntry> = RECORDED_DAY
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let api = ReplayApi::from_entries(entries);
+        let dir = std::env::temp_dir().join(format!("scavenger-replay-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Store::open(&dir).unwrap();
+
+        let outcomes = replay_rounds(&api, &store, "addr1qreplayfixture").await;
+
+        // Accepted; then the solved challenge is skipped and C02 gets rejected; then an outage
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0], Ok(RoundOutcome::Solution(SolutionStatus::Accepted)));
+        assert_eq!(outcomes[1], Ok(RoundOutcome::Solution(SolutionStatus::Rejected)));
+        assert!(outcomes[2].as_ref().unwrap_err().contains("timed out"));
+        assert!(dir.join("forensics").read_dir().unwrap().count() == 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // ==================== SCHEDULER SCENARIOS ====================
+
+    const ADDRESS: &str = "addr1qscenario";
+
+    fn challenge(id: &str, code: &str) -> AuditEntry {
+        serde_json::from_value(serde_json::json!({
+            "timestamp": "2025-11-03T00:00:00+00:00",
+            "action": "challenge",
+            "challenge_id": id,
+            "http_status": 200,
+            "outcome": "ok",
+            "response": {
+                "code": code,
+                "challenge": {
+                    "challenge_id": id,
+                    "day": 3,
+                    "challenge_number": 1,
+                    "difficulty": "0007ffff",
+                    "no_pre_mine": "e2a1c0d4b7f9e6a3c5d8b1f0a9e7c6d5",
+                    "latest_submission": "2025-11-03T23:59:59Z",
+                    "no_pre_mine_hour": "123456789"
+                },
+                "mining_period_ends": "2025-11-03T23:59:59Z"
+            },
+            "duration_ms": 100
+        }))
+        .unwrap()
+    }
+
+    /// /challenge while the event is not running
+    fn idle(code: &str) -> AuditEntry {
+        serde_json::from_value(serde_json::json!({
+            "timestamp": "2025-11-03T00:00:00+00:00",
+            "action": "challenge",
+            "http_status": 200,
+            "outcome": "idle",
+            "response": {"code": code},
+            "duration_ms": 100
+        }))
+        .unwrap()
+    }
+
+    fn submit(id: &str, outcome: &str) -> AuditEntry {
+        let (status, response, detail) = match outcome {
+            "accepted" => (
+                Some(200),
+                Some(serde_json::json!({"crypto_receipt": {
+                    "preimage": "p", "timestamp": "2025-11-03T01:00:00Z", "signature": "5f"
+                }})),
+                None,
+            ),
+            "rejected" => (Some(400), Some(serde_json::json!({"message": "no"})), None),
+            "duplicate" => (
+                Some(400),
+                Some(serde_json::json!({"message": "Solution already submitted for this address"})),
+                None,
+            ),
+            _ => (None, None, Some("error sending request: connection reset")),
+        };
+        serde_json::from_value(serde_json::json!({
+            "timestamp": "2025-11-03T01:00:00+00:00",
+            "action": "submit",
+            "address": ADDRESS,
+            "challenge_id": id,
+            "http_status": status,
+            "outcome": outcome,
+            "detail": detail,
+            "response": response,
+            "duration_ms": 100
+        }))
+        .unwrap()
+    }
+
+    fn scenario_store(name: &str) -> (std::path::PathBuf, Store) {
+        let dir = std::env::temp_dir()
+            .join(format!("scavenger-scenario-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Store::open(&dir).unwrap();
+        (dir, store)
+    }
+
+    fn solved_ids(store: &Store) -> Vec<(String, SolutionStatus)> {
+        store
+            .solutions()
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.challenge.challenge_id, s.status))
+            .collect()
+    }
+
+    struct Scenario {
+        name: &'static str,
+        entries: Vec<AuditEntry>,
+        expected: Vec<std::result::Result<RoundOutcome, &'static str>>,
+        /// (challenge, status) of each solution recorded, in order
+        solved: Vec<(&'static str, SolutionStatus)>,
+    }
+
+    #[tokio::test]
+    async fn scheduler_scenarios() {
+        use RoundOutcome::*;
+        let cases = [
+            Scenario {
+                name: "solve and submit",
+                entries: vec![challenge("**D03C01", "active"), submit("**D03C01", "accepted")],
+                expected: vec![Ok(Solution(SolutionStatus::Accepted))],
+                solved: vec![("**D03C01", SolutionStatus::Accepted)],
+            },
+            Scenario {
+                // The challenge rotates while we wait out a closed one
+                name: "rotation while waiting",
+                entries: vec![
+                    challenge("**D03C01", "closed"),
+                    challenge("**D03C02", "active"),
+                    submit("**D03C02", "accepted"),
+                ],
+                expected: vec![Ok(Solution(SolutionStatus::Accepted))],
+                solved: vec![("**D03C02", SolutionStatus::Accepted)],
+            },
+            Scenario {
+                // Solved before the rotation landed: don't mine it twice
+                name: "same challenge served again after solving",
+                entries: vec![
+                    challenge("**D03C01", "active"),
+                    submit("**D03C01", "accepted"),
+                    challenge("**D03C01", "active"),
+                    challenge("**D03C02", "active"),
+                    submit("**D03C02", "accepted"),
+                ],
+                expected: vec![
+                    Ok(Solution(SolutionStatus::Accepted)),
+                    Ok(Solution(SolutionStatus::Accepted)),
+                ],
+                solved: vec![
+                    ("**D03C01", SolutionStatus::Accepted),
+                    ("**D03C02", SolutionStatus::Accepted),
+                ],
+            },
+            Scenario {
+                name: "expired challenge is never mined",
+                entries: vec![challenge("**D03C01", "closed"), challenge("**D03C01", "closed")],
+                expected: vec![Err("end of recording")],
+                solved: vec![],
+            },
+            Scenario {
+                name: "rejected submission",
+                entries: vec![challenge("**D03C01", "active"), submit("**D03C01", "rejected")],
+                expected: vec![Ok(Solution(SolutionStatus::Rejected))],
+                solved: vec![("**D03C01", SolutionStatus::Rejected)],
+            },
+            Scenario {
+                // Solved from another machine: the server's word counts as a solve
+                name: "server already has a solution from this address",
+                entries: vec![
+                    challenge("**D03C01", "active"),
+                    submit("**D03C01", "duplicate"),
+                    challenge("**D03C01", "active"),
+                    challenge("**D03C02", "active"),
+                    submit("**D03C02", "accepted"),
+                ],
+                expected: vec![
+                    Ok(Solution(SolutionStatus::Rejected)),
+                    Ok(Solution(SolutionStatus::Accepted)),
+                ],
+                solved: vec![
+                    ("**D03C01", SolutionStatus::Rejected),
+                    ("**D03C02", SolutionStatus::Accepted),
+                ],
+            },
+            Scenario {
+                name: "submission fails in transit",
+                entries: vec![challenge("**D03C01", "active"), submit("**D03C01", "error")],
+                expected: vec![Err("connection reset")],
+                solved: vec![("**D03C01", SolutionStatus::SubmitFailed)],
+            },
+        ];
+
+        for Scenario { name, entries, expected, solved } in cases {
+            let api = ReplayApi::from_entries(entries);
+            let (dir, store) = scenario_store("table");
+            let outcomes = rounds_until_exhausted(&api, &store, &replay_settings(ADDRESS)).await;
+
+            assert_eq!(outcomes.len(), expected.len(), "{}: {:?}", name, outcomes);
+            for (outcome, expected) in outcomes.iter().zip(&expected) {
+                match (outcome, expected) {
+                    (Ok(got), Ok(want)) => assert_eq!(got, want, "{}", name),
+                    (Err(got), Err(want)) => assert!(got.contains(want), "{}: {}", name, got),
+                    _ => panic!("{}: got {:?}, want {:?}", name, outcome, expected),
+                }
+            }
+            let solved: Vec<(String, SolutionStatus)> =
+                solved.into_iter().map(|(id, status)| (id.to_string(), status)).collect();
+            assert_eq!(solved_ids(&store), solved, "{}", name);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    /// A submission that never arrived waits in the queue, which delivers it
+    /// once it is due; meanwhile the challenge is not mined again
+    #[tokio::test]
+    async fn failed_submission_is_queued_and_delivered() {
+        let (dir, store) = scenario_store("queue");
+        let open = |mut entry: AuditEntry| {
+            let body = &mut entry.response.as_mut().unwrap()["challenge"];
+            body["latest_submission"] = serde_json::json!("2999-01-01T00:00:00Z");
+            entry
+        };
+        let api = ReplayApi::from_entries(vec![
+            open(challenge("**D03C01", "active")),
+            submit("**D03C01", "error"),
+            open(challenge("**D03C01", "active")),
+        ]);
+        let outcomes = rounds_until_exhausted(&api, &store, &replay_settings(ADDRESS)).await;
+        assert!(outcomes[0].is_err());
+        assert!(outcomes[1].as_ref().unwrap_err().contains("end of recording"));
+        assert!(submitqueue::holds(&store, ADDRESS, "**D03C01"));
+
+        // Not due yet
+        let api = ReplayApi::from_entries(vec![submit("**D03C01", "accepted")]);
+        submitqueue::retry_due(&api, &store).await;
+        assert!(submitqueue::holds(&store, ADDRESS, "**D03C01"));
+
+        let raw = std::fs::read_to_string(store.queue_path()).unwrap();
+        let mut queue: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        queue[0]["next_try"] = serde_json::json!("2000-01-01T00:00:00Z");
+        std::fs::write(store.queue_path(), queue.to_string()).unwrap();
+        submitqueue::retry_due(&api, &store).await;
+        assert!(!submitqueue::holds(&store, ADDRESS, "**D03C01"));
+        let solved = [
+            ("**D03C01".to_string(), SolutionStatus::SubmitFailed),
+            ("**D03C01".to_string(), SolutionStatus::Accepted),
+        ];
+        assert_eq!(solved_ids(&store), solved);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// "Already submitted" from the queue is ours only once a try may have
+    /// reached the server; a reset connection never did
+    #[tokio::test]
+    async fn queued_duplicate_counts_only_after_a_possible_delivery() {
+        for (may_have_landed, want) in
+            [(false, SolutionStatus::Rejected), (true, SolutionStatus::AcceptedNoReceipt)]
+        {
+            let (dir, store) = scenario_store("queue-duplicate");
+            let mut open = challenge("**D03C01", "active");
+            let body = &mut open.response.as_mut().unwrap()["challenge"];
+            body["latest_submission"] = serde_json::json!("2999-01-01T00:00:00Z");
+            let api = ReplayApi::from_entries(vec![open, submit("**D03C01", "error")]);
+            rounds_until_exhausted(&api, &store, &replay_settings(ADDRESS)).await;
+
+            let raw = std::fs::read_to_string(store.queue_path()).unwrap();
+            let mut queue: serde_json::Value = serde_json::from_str(&raw).unwrap();
+            assert_eq!(queue[0]["may_have_landed"], false);
+            queue[0]["next_try"] = serde_json::json!("2000-01-01T00:00:00Z");
+            queue[0]["may_have_landed"] = serde_json::json!(may_have_landed);
+            std::fs::write(store.queue_path(), queue.to_string()).unwrap();
+            let api = ReplayApi::from_entries(vec![submit("**D03C01", "duplicate")]);
+            submitqueue::retry_due(&api, &store).await;
+            assert!(!submitqueue::holds(&store, ADDRESS, "**D03C01"));
+            let solved = [
+                ("**D03C01".to_string(), SolutionStatus::SubmitFailed),
+                ("**D03C01".to_string(), want),
+            ];
+            assert_eq!(solved_ids(&store), solved, "may_have_landed {}", may_have_landed);
+
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn restart_resumes_from_checkpoint() {
+        let (dir, store) = scenario_store("restart");
+        let mut settings = replay_settings(ADDRESS);
+        settings.resume_scan = true;
+
+        // First run: found a nonce but the submission never arrived
+        let api = ReplayApi::from_entries(vec![
+            challenge("**D03C01", "active"),
+            submit("**D03C01", "error"),
+        ]);
+        let outcomes = rounds_until_exhausted(&api, &store, &settings).await;
+        assert!(outcomes[0].is_err());
+        // Queued; once the queue gives up on it the challenge is open again
+        assert!(submitqueue::holds(&store, ADDRESS, "**D03C01"));
+        std::fs::remove_file(store.queue_path()).unwrap();
+
+        // Restart on the same data dir: the scan picks up where it stopped
+        let store = Store::open(&dir).unwrap();
+        let api = ReplayApi::from_entries(vec![
+            challenge("**D03C01", "active"),
+            submit("**D03C01", "accepted"),
+            challenge("**D03C01", "active"),
+        ]);
+        let outcomes = rounds_until_exhausted(&api, &store, &settings).await;
+        assert_eq!(outcomes[0], Ok(RoundOutcome::Solution(SolutionStatus::Accepted)));
+        // Solved now, so the next fetch of the same challenge is waited out
+        assert!(outcomes[1].as_ref().unwrap_err().contains("end of recording"));
+
+        let scans = store.scans_for("**D03C01", ADDRESS).unwrap();
+        assert_eq!(scans.len(), 2);
+        assert_eq!(scans[1].start_nonce, scans[0].start_nonce + scans[0].count);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn per_day_rule_idles_until_the_day_changes() {
+        let (dir, store) = scenario_store("per-day");
+        let mut next_day = challenge("**D04C01", "active");
+        next_day.response.as_mut().unwrap()["challenge"]["day"] = serde_json::json!(4);
+        let api = ReplayApi::from_entries(vec![
+            challenge("**D03C01", "active"),
+            submit("**D03C01", "accepted"),
+            challenge("**D03C02", "active"),
+            next_day,
+            submit("**D04C01", "accepted"),
+        ]);
+        let mut settings = replay_settings(ADDRESS);
+        settings.acceptance = AcceptanceRule::PerDay;
+
+        let outcomes = rounds_until_exhausted(&api, &store, &settings).await;
+        assert_eq!(outcomes.len(), 2, "{:?}", outcomes);
+        let solved: Vec<String> = solved_ids(&store).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(solved, vec!["**D03C01", "**D04C01"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// A long outage: the daemon backs off up to the cap without giving up,
+    /// and the first answer sends it straight back to mining
+    #[tokio::test]
+    async fn outage_backs_off_and_resumes() {
+        let (dir, store) = scenario_store("outage");
+        let mut down = challenge("**OUT", "active");
+        down.outcome = "error".to_string();
+        down.detail = Some("error sending request: connection refused".to_string());
+        let mut entries = vec![down; 9];
+        entries.extend([challenge("**OUT", "active"), challenge("**OUT", "active")]);
+        entries.push(submit("**OUT", "accepted"));
+        let replay = ReplayApi::from_entries(entries);
+        let settings = replay_settings(ADDRESS);
+        let api = UntilExhausted::new(&replay, settings.cancel.clone());
+
+        crate::daemon::run(&api, &store, &settings, &dir, None).await.unwrap();
+
+        assert_eq!(solved_ids(&store), vec![("**OUT".to_string(), SolutionStatus::Accepted)]);
+        let pauses = api.pauses.lock().unwrap();
+        let retries: Vec<Duration> = (1..=9).map(crate::daemon::retry_delay).collect();
+        assert_eq!(pauses[..9], retries[..]);
+        assert!(retries.windows(2).all(|w| w[1] >= w[0]));
+        assert_eq!(retries[8], crate::daemon::MAX_RETRY_DELAY);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// No challenge before the event starts: the daemon waits, slower and
+    /// slower, without treating it as an outage, and mines once one appears
+    #[tokio::test]
+    async fn event_idle_polls_slowly_and_resumes() {
+        let (dir, store) = scenario_store("event-idle");
+        let mut entries = vec![idle("before"); 4];
+        entries.extend([challenge("**IDLE", "active"), challenge("**IDLE", "active")]);
+        entries.push(submit("**IDLE", "accepted"));
+        let replay = ReplayApi::from_entries(entries);
+        let settings = replay_settings(ADDRESS);
+        let api = UntilExhausted::new(&replay, settings.cancel.clone());
+
+        crate::daemon::run(&api, &store, &settings, &dir, None).await.unwrap();
+
+        assert_eq!(solved_ids(&store), vec![("**IDLE".to_string(), SolutionStatus::Accepted)]);
+        let pauses = api.pauses.lock().unwrap();
+        let polls: Vec<Duration> = [60, 120, 240, 480].map(Duration::from_secs).to_vec();
+        assert_eq!(pauses[..4], polls[..]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // ==================== SOAK ====================
+
+    /// Cancels the daemon once the recording runs out
+    struct UntilExhausted<'a> {
+        api: &'a ReplayApi,
+        cancel: CancellationToken,
+        /// Every wait the daemon asked for
+        pauses: Mutex<Vec<Duration>>,
+    }
+
+    impl<'a> UntilExhausted<'a> {
+        fn new(api: &'a ReplayApi, cancel: CancellationToken) -> Self {
+            UntilExhausted { api, cancel, pauses: Mutex::new(Vec::new()) }
+        }
+    }
+
+    impl MinerApi for UntilExhausted<'_> {
+        async fn get_challenge(&self) -> Result<ChallengeResponse> {
+            if self.api.remaining_challenges() == 0 {
+                self.cancel.cancel();
+            }
+            self.api.get_challenge().await
+        }
+
+        async fn submit_solution(&self, a: &str, c: &str, n: &str) -> Result<SolutionResponse> {
+            self.api.submit_solution(a, c, n).await
+        }
+
+        async fn get_star_rate(&self) -> Result<Vec<u64>> {
+            self.api.get_star_rate().await
+        }
+
+        async fn pause(&self, duration: Duration) {
+            self.pauses.lock().unwrap().push(duration);
+            self.api.pause(duration).await
+        }
+
+        fn now(&self) -> DateTime<Utc> {
+            self.api.now()
+        }
+    }
+
+    const SOAK_DAYS: u32 = 7;
+    const SOAK_CHALLENGES_PER_DAY: u32 = 24;
+
+    /// A solvable challenge with a fresh seed each day, so every day rebuilds the ROM
+    fn soak_challenge(day: u32, number: u32) -> AuditEntry {
+        let id = format!("**D{:02}C{:02}", day, number);
+        let mut entry = challenge(&id, "active");
+        let body = &mut entry.response.as_mut().unwrap()["challenge"];
+        body["day"] = serde_json::json!(day);
+        body["challenge_number"] = serde_json::json!(number);
+        body["difficulty"] = serde_json::json!("ffffffff");
+        body["no_pre_mine"] = serde_json::json!(format!("{:032x}", day));
+        entry
+    }
+
+    /// A simulated week at replay speed: each challenge is solved once even
+    /// when served again, every fifth submission is lost in transit and
+    /// retried from the checkpointed scan, and memory stays flat across the
+    /// daily ROM rebuilds. Run with `cargo test -- --ignored soak`.
+    #[tokio::test]
+    #[ignore = "long-running soak test"]
+    async fn soak_daemon_through_day_rotations() {
+        let (dir, store) = scenario_store("soak");
+        let remote_dir = dir.with_extension("remote");
+        let _ = std::fs::remove_dir_all(&remote_dir);
+        let remote = crate::sync::Remote::Dir(remote_dir.clone());
+
+        let mut entries = Vec::new();
+        let mut lost = Vec::new();
+        for day in 1..=SOAK_DAYS {
+            for number in 1..=SOAK_CHALLENGES_PER_DAY {
+                let id = format!("**D{:02}C{:02}", day, number);
+                entries.push(soak_challenge(day, number));
+                if number % 5 == 0 {
+                    entries.push(submit(&id, "error"));
+                    entries.push(soak_challenge(day, number));
+                    lost.push(id.clone());
+                }
+                entries.push(submit(&id, "accepted"));
+                // Served again before the rotation: must be waited out
+                entries.push(soak_challenge(day, number));
+            }
+        }
+        let replay = ReplayApi::from_entries(entries);
+        let mut settings = replay_settings(ADDRESS);
+        settings.engine = EngineChoice::TinyRom;
+        settings.resume_scan = true;
+        let api = UntilExhausted::new(&replay, settings.cancel.clone());
+
+        // Pauses are instant, so a week of polling and retries takes seconds
+        let baseline = crate::memory::sample().rss;
+        crate::daemon::run(&api, &store, &settings, &dir, Some(&remote)).await.unwrap();
+        let after = crate::memory::sample().rss;
+
+        let solutions = store.solutions().unwrap();
+        let total = (SOAK_DAYS * SOAK_CHALLENGES_PER_DAY) as usize;
+        let mut accepted: Vec<&str> = solutions
+            .iter()
+            .filter(|s| s.status == SolutionStatus::Accepted)
+            .map(|s| s.challenge.challenge_id.as_str())
+            .collect();
+        assert_eq!(accepted.len(), total);
+        accepted.sort();
+        accepted.dedup();
+        assert_eq!(accepted.len(), total, "a challenge was submitted twice");
+        assert!(replay.submissions.lock().unwrap().is_empty(), "a submission was skipped");
+
+        for id in &lost {
+            let scans = store.scans_for(id, ADDRESS).unwrap();
+            assert_eq!(scans.len(), 2, "{}", id);
+            assert_eq!(scans[1].start_nonce, scans[0].start_nonce + scans[0].count, "{}", id);
+        }
+
+        // The remote checkpoint holds the whole history
+        let pushed = Store::open(&remote_dir).unwrap().solutions().unwrap();
+        assert_eq!(pushed.len(), solutions.len());
+
+        if let (Some(baseline), Some(after)) = (baseline, after) {
+            let growth = after.saturating_sub(baseline);
+            assert!(growth < 64 * 1024 * 1024, "RSS grew by {} MB", growth / 1024 / 1024);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&remote_dir);
+    }
+
+    /// Seed rotations through the real ROM cache: each new seed frees the
+    /// last ROM before building the next, so memory tracks one ROM however
+    /// many rotations there are. A leak of one ROM per rotation breaks the
+    /// bound by the third. Run with `cargo test -- --ignored soak`.
+    #[tokio::test]
+    #[ignore = "long-running soak test"]
+    async fn soak_rom_rebuilds_keep_memory_bounded() {
+        const ROM: usize = 64 * 1024 * 1024;
+        const ROTATIONS: u32 = 8;
+        let options = crate::MiningOptions {
+            start_nonce: 0,
+            max_iterations: 0,
+            threads: 1,
+            sprint: None,
+            work_hours: None,
+            stop_at: None,
+            power_guard: None,
+            deadline: None,
+            expires_at: None,
+            rom_size: ROM,
+            difficulty_rule: crate::DifficultyRule::default(),
+            cancel: CancellationToken::new(),
+        };
+
+        let mut first: Option<crate::memory::MemoryStats> = None;
+        for rotation in 1..=ROTATIONS {
+            let seed = format!("{:032x}", rotation);
+            let ctx = crate::romcache::build(&seed, &options).await.unwrap();
+            assert_eq!(ctx.seed(), seed);
+            drop(ctx);
+
+            let sample = crate::memory::sample();
+            let Some(first) = first else {
+                first = Some(sample);
+                continue;
+            };
+            let bound = 2 * ROM as u64;
+            if let (Some(before), Some(now)) = (first.rss, sample.rss) {
+                let growth = now.saturating_sub(before);
+                assert!(growth < bound, "RSS grew by {} after {} rotations", growth, rotation);
+            }
+            if let (Some(before), Some(now)) = (first.allocated, sample.allocated) {
+                let growth = now.saturating_sub(before);
+                assert!(growth < bound, "allocated grew by {} after {} rotations", growth, rotation);
+            }
+        }
+        crate::romcache::release();
+    }
+
+    #[tokio::test]
+    async fn tiny_rom_end_to_end() {
+        let (dir, store) = scenario_store("tiny");
+        let mut solvable = challenge("**D03C01", "active");
+        let body = solvable.response.as_mut().unwrap();
+        body["challenge"]["difficulty"] = serde_json::json!("ffffffff");
+        let api = ReplayApi::from_entries(vec![solvable, submit("**D03C01", "accepted")]);
+        let mut settings = replay_settings(ADDRESS);
+        settings.engine = EngineChoice::TinyRom;
+
+        let outcomes = rounds_until_exhausted(&api, &store, &settings).await;
+        assert_eq!(outcomes, vec![Ok(RoundOutcome::Solution(SolutionStatus::Accepted))]);
+
+        // The recorded digest is what the tiny ROM gives for the recorded preimage
+        let record = &store.solutions().unwrap()[0];
+        let rom = crate::build_rom_sized(record.challenge.no_pre_mine.as_bytes(), 1024 * 1024);
+        let digest =
+            ashmaize::hash(record.preimage.as_bytes(), &rom, crate::NB_LOOPS, crate::NB_INSTRS);
+        assert_eq!(record.hash, hex::encode(digest));
+        assert!(crate::DifficultyRule::Prefix.meets(&digest, &record.challenge.difficulty));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,290 @@
+// ==================== API RETRIES ====================
+//
+// A dropped connection or a 502 from the load balancer used to end the
+// run: every API error went straight up to main. Calls to the server now
+// go through `with_backoff`, which tries again when the failure says
+// nothing about the request itself (no connection, timeout, 5xx, 429, a
+// body cut short), waiting twice as long each time with jitter so rigs
+// that lost the server together don't hammer it in step when it returns.
+// Any other answer, a 4xx included, is final. Every try is audited on its
+// own. --api-retries sets the tries per call; the daemon's outage handling
+// takes over once a call has used them all. A submit whose earlier try
+// timed out or lost its answer may have got in anyway; the server then
+// calls the retry a duplicate. That is most likely our own solution, but
+// another rig on the same address could have sent one too, so it is kept
+// apart from an acceptance with a receipt. A try that never connected
+// cannot have landed, and a duplicate after it is a plain rejection.
+
+use anyhow::Result;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::SolutionResponse;
+
+pub const DEFAULT_ATTEMPTS: u32 = 5;
+
+/// Wait after the first failed try; doubles with each one after it
+const BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between tries
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+static ATTEMPTS: AtomicU32 = AtomicU32::new(DEFAULT_ATTEMPTS);
+
+/// --api-retries: tries per call, 1 for none
+pub fn init(attempts: u32) {
+    ATTEMPTS.store(attempts.max(1), Ordering::Relaxed);
+}
+
+/// The server answered, but with an error worth asking again about
+#[derive(Debug)]
+pub struct HttpStatus {
+    pub status: u16,
+    pub body: String,
+}
+
+impl std::fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let body: String = self.body.trim().chars().take(200).collect();
+        write!(f, "HTTP {}: {}", self.status, body)
+    }
+}
+
+impl std::error::Error for HttpStatus {}
+
+/// A failure of the connection rather than of the request
+#[derive(Debug)]
+pub struct Transient(pub String);
+
+impl std::fmt::Display for Transient {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Transient {}
+
+/// The body, unless the status says to try again later
+pub fn check_status(status: u16, body: String) -> Result<String> {
+    if status >= 500 || status == 429 {
+        return Err(HttpStatus { status, body }.into());
+    }
+    Ok(body)
+}
+
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout() || e.is_connect() || e.is_request() || e.is_body();
+        }
+        if let Some(e) = cause.downcast_ref::<serde_json::Error>() {
+            // Cut off mid-body, not a different answer
+            return e.is_eof();
+        }
+        cause.is::<HttpStatus>() || cause.is::<Transient>()
+    })
+}
+
+/// Whether a failed try may have reached the server all the same: it timed
+/// out or its answer was cut short, rather than never connecting
+pub fn may_have_landed(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return !e.is_connect() && (e.is_timeout() || e.is_body() || e.is_decode());
+        }
+        if let Some(e) = cause.downcast_ref::<serde_json::Error>() {
+            return e.is_eof();
+        }
+        false
+    })
+}
+
+/// Exponential, capped, then jittered into its upper half
+fn delay(failures: u32) -> Duration {
+    let full = BASE_DELAY.saturating_mul(1 << failures.saturating_sub(1).min(16)).min(MAX_DELAY);
+    let jitter = RandomState::new().build_hasher().finish() % (full.as_millis() as u64 / 2 + 1);
+    full / 2 + Duration::from_millis(jitter)
+}
+
+/// `call` until it succeeds, fails for good or is out of tries
+pub async fn with_backoff<T, F, Fut>(action: &str, mut call: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let attempts = ATTEMPTS.load(Ordering::Relaxed);
+    let mut tries = 1;
+    loop {
+        match call().await {
+            Err(e) if tries < attempts && is_transient(&e) => {
+                let wait = delay(tries);
+                say!(
+                    "⚠️  {} failed (try {} of {}): {}; retrying in {:.1}s",
+                    action,
+                    tries,
+                    attempts,
+                    e,
+                    wait.as_secs_f64()
+                );
+                tokio::time::sleep(wait).await;
+                tries += 1;
+            }
+            Err(e) if tries > 1 && is_transient(&e) => {
+                say!("⚠️  {} failed {} times in a row, giving up on it", action, tries);
+                return Err(e);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `with_backoff` for a solution submit
+pub async fn submit_with_backoff<F, Fut>(mut call: F) -> Result<SolutionResponse>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<SolutionResponse>>,
+{
+    // Set once a failed try may have reached the server
+    let landed = AtomicBool::new(false);
+    let landed = &landed;
+    with_backoff("submit", || {
+        let answer = call();
+        async move {
+            let mut response = match answer.await {
+                Ok(response) => response,
+                Err(e) => {
+                    if may_have_landed(&e) {
+                        landed.store(true, Ordering::Relaxed);
+                    }
+                    return Err(e);
+                }
+            };
+            if landed.load(Ordering::Relaxed) && response.already_submitted() {
+                say!(
+                    "✅ An earlier try of this submit may have got through; the server \
+                     already has a solution from this address"
+                );
+                response.landed_earlier = true;
+            }
+            Ok(response)
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    fn answer(body: serde_json::Value) -> Result<SolutionResponse> {
+        Ok(serde_json::from_value(body)?)
+    }
+
+    fn duplicate() -> Result<SolutionResponse> {
+        answer(serde_json::json!({"message": "Solution already submitted for this address"}))
+    }
+
+    /// A body cut short: the server had the request
+    fn cut_short() -> anyhow::Error {
+        serde_json::from_str::<serde_json::Value>("{\"code\": ").unwrap_err().into()
+    }
+
+    /// `submit_with_backoff` over a first try failing with `first`, then a duplicate
+    async fn duplicate_after(first: anyhow::Error) -> SolutionResponse {
+        let mut first = Some(first);
+        let mut tries = 0;
+        let response = submit_with_backoff(|| {
+            tries += 1;
+            let failure = first.take();
+            async move {
+                match failure {
+                    Some(e) => Err(e),
+                    None => duplicate(),
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(tries, 2);
+        response
+    }
+
+    #[test]
+    fn connection_failures_and_server_errors_are_transient() {
+        let status = |status| check_status(status, "busy".into()).unwrap_err();
+        assert!(is_transient(&status(502)));
+        assert!(is_transient(&status(429)));
+        assert!(is_transient(&Transient("reset".into()).into()));
+        let wrapped = Err::<(), _>(status(503)).context("submit").unwrap_err();
+        assert!(is_transient(&wrapped));
+        let cut_short = serde_json::from_str::<serde_json::Value>("{\"code\": ").unwrap_err();
+        assert!(is_transient(&cut_short.into()));
+    }
+
+    #[test]
+    fn answers_about_the_request_are_final() {
+        assert_eq!(check_status(400, "bad".into()).unwrap(), "bad");
+        assert_eq!(check_status(404, "gone".into()).unwrap(), "gone");
+        let garbage = serde_json::from_str::<serde_json::Value>("<html>").unwrap_err();
+        assert!(!is_transient(&garbage.into()));
+        assert!(!is_transient(&anyhow!("Invalid address")));
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_cap_with_jitter_in_the_upper_half() {
+        let ms = Duration::from_millis;
+        let bounds = [
+            (0, ms(500), ms(1_000)),
+            (1, ms(500), ms(1_000)),
+            (2, ms(1_000), ms(2_000)),
+            (3, ms(2_000), ms(4_000)),
+            (5, ms(8_000), ms(16_000)),
+            (6, ms(15_000), ms(30_000)),
+            (20, ms(15_000), ms(30_000)),
+            (u32::MAX, ms(15_000), ms(30_000)),
+        ];
+        for (failures, low, high) in bounds {
+            for _ in 0..50 {
+                let wait = delay(failures);
+                assert!(wait >= low && wait <= high, "{:?} for {} failures", wait, failures);
+            }
+        }
+    }
+
+    #[test]
+    fn only_failures_past_the_connection_may_have_landed() {
+        assert!(may_have_landed(&cut_short()));
+        assert!(!may_have_landed(&Transient("connection refused".into()).into()));
+        assert!(!may_have_landed(&check_status(503, "busy".into()).unwrap_err()));
+    }
+
+    #[tokio::test]
+    async fn duplicate_after_a_possibly_delivered_try_landed_earlier() {
+        let response = duplicate_after(cut_short()).await;
+        assert!(response.landed_earlier);
+        assert!(response.crypto_receipt.is_none());
+    }
+
+    #[tokio::test]
+    async fn duplicate_after_a_try_that_never_got_through_is_a_rejection() {
+        let response = duplicate_after(Transient("connection refused".into()).into()).await;
+        assert!(!response.landed_earlier);
+        assert!(!response.accepted());
+        let response = duplicate_after(check_status(502, "bad gateway".into()).unwrap_err()).await;
+        assert!(!response.landed_earlier);
+    }
+
+    #[tokio::test]
+    async fn duplicate_answer_to_a_first_try_is_a_rejection() {
+        let response = submit_with_backoff(|| async { duplicate() }).await.unwrap();
+        assert!(!response.landed_earlier);
+        assert!(!response.accepted());
+        let other = serde_json::json!({"message": "Solution does not meet difficulty"});
+        let response = submit_with_backoff(|| async { answer(other.clone()) }).await;
+        assert!(!response.unwrap().accepted());
+    }
+}
@@ -0,0 +1,125 @@
+// ==================== ROM CACHE ====================
+//
+// Building a challenge's MiningContext the way a mining run does: on a
+// blocking thread, with the build time in the metrics, given up on as soon
+// as the run should stop. The last ROM stays in memory for the next
+// challenge with the same seed, and an abandoned build is waited for
+// before the next one starts.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use scavenger_miner::MiningContext;
+
+use crate::{limits, metrics, romfile, MiningOptions};
+
+/// A ROM build that was given up on but whose thread is still running
+static ABANDONED_ROM: Mutex<Option<tokio::task::JoinHandle<MiningContext>>> = Mutex::new(None);
+
+/// The last ROM built, kept for the next challenge with the same seed
+static CACHED_ROM: Mutex<Option<Arc<MiningContext>>> = Mutex::new(None);
+
+/// `MiningContext::new`, reporting progress and the build time
+fn build_now(no_pre_mine: &str, rom_size: usize) -> MiningContext {
+    say!("🔧 Initializing AshMaize ROM...");
+    // Char-based so a non-ASCII seed can't split a code point
+    let seed: String = no_pre_mine.chars().take(16).collect();
+    say!("   Seed: {}...", seed);
+    say!("   Loops: {}", crate::NB_LOOPS);
+    say!("   Instructions: {}", crate::NB_INSTRS);
+
+    let started = Instant::now();
+    let ctx = MiningContext::new(no_pre_mine, rom_size);
+    metrics::rom_built(started.elapsed());
+
+    say!(
+        "✅ ROM initialized ({} MB in {:.1}s)",
+        rom_size / 1_024 / 1_024,
+        started.elapsed().as_secs_f64()
+    );
+    if romfile::mapped() > 0 {
+        say!("   Paged from disk: {} mapped", limits::size(romfile::mapped() as u64));
+    }
+    ctx
+}
+
+/// `build_now` on a blocking thread, given up as soon as mining should stop
+/// (Ctrl+C, time budget, spot reclaim, challenge expiry). AshMaize can't
+/// be stopped mid-generation, so the thread runs on and drops its ROM;
+/// the next build waits for it so two 1 GB ROMs never coexist.
+///
+/// The seed (no_pre_mine) changes less often than the challenge, so the
+/// last ROM stays in memory and is handed out again while it matches.
+pub async fn build(no_pre_mine: &str, options: &MiningOptions) -> Option<Arc<MiningContext>> {
+    let cached = CACHED_ROM.lock().unwrap().take();
+    match cached {
+        Some(ctx) if ctx.seed() == no_pre_mine && ctx.rom_size() == options.rom_size => {
+            say!("♻️  Reusing the ROM in memory: seed unchanged");
+            *CACHED_ROM.lock().unwrap() = Some(Arc::clone(&ctx));
+            return Some(ctx);
+        }
+        // Dropped here, before the new ROM is allocated
+        Some(ctx) => say!(
+            "🔧 Seed changed ({}... -> {}...), building a new ROM",
+            ctx.seed().chars().take(8).collect::<String>(),
+            no_pre_mine.chars().take(8).collect::<String>()
+        ),
+        None => {}
+    }
+
+    let previous = ABANDONED_ROM.lock().unwrap().take();
+    if let Some(previous) = previous {
+        say!("⏳ Waiting for the abandoned ROM build to finish...");
+        if let Ok(ctx) = previous.await {
+            if ctx.seed() == no_pre_mine && ctx.rom_size() == options.rom_size {
+                say!("♻️  Reusing the abandoned ROM build: same seed");
+                return Some(cache(ctx));
+            }
+        }
+    }
+
+    let (seed, rom_size) = (no_pre_mine.to_string(), options.rom_size);
+    let mut building = tokio::task::spawn_blocking(move || build_now(&seed, rom_size));
+    let stop = async {
+        while options.stop_reason().is_none() {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    };
+    tokio::select! {
+        ctx = &mut building => Some(cache(ctx.expect("ROM generation panicked"))),
+        _ = stop => {
+            say!("\n🛑 ROM generation abandoned: {}", options.stop_reason().unwrap_or_default());
+            *ABANDONED_ROM.lock().unwrap() = Some(building);
+            None
+        }
+    }
+}
+
+fn cache(ctx: MiningContext) -> Arc<MiningContext> {
+    let ctx = Arc::new(ctx);
+    *CACHED_ROM.lock().unwrap() = Some(Arc::clone(&ctx));
+    ctx
+}
+
+/// A 1 MB ROM: the real hash pipeline in seconds, for tests
+#[cfg(test)]
+pub fn tiny(no_pre_mine: &str) -> MiningContext {
+    build_now(no_pre_mine, 1024 * 1024)
+}
+
+/// Whether a given-up build is still generating its ROM
+pub fn abandoned_running() -> bool {
+    ABANDONED_ROM.lock().unwrap().as_ref().is_some_and(|build| !build.is_finished())
+}
+
+pub fn cached_size() -> Option<usize> {
+    CACHED_ROM.lock().unwrap().as_ref().map(|ctx| ctx.rom_size())
+}
+
+/// Free the cached ROM; returns its size when there was one
+pub fn release() -> Option<usize> {
+    let ctx = CACHED_ROM.lock().unwrap().take()?;
+    let size = ctx.rom_size();
+    drop(ctx);
+    Some(size)
+}
@@ -0,0 +1,209 @@
+// ==================== ROM ON DISK ====================
+//
+// --rom-storage mmap, for machines with less free RAM than the ROM (2 GB
+// VPSes). ashmaize allocates the ROM itself, so this works at the
+// allocator: once enabled, an allocation of at least the ROM's size becomes
+// a shared mapping of an unlinked file in --rom-dir instead of anonymous
+// memory. Those pages are file-backed, so under pressure the kernel writes
+// them back and drops them rather than swapping or OOM-killing the miner,
+// and reads them in again when a hash touches them: slower, but it runs.
+// The file goes away with the mapping. Unix only.
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RomStorage {
+    /// In memory (fastest)
+    Ram,
+    /// In a memory-mapped file the OS pages in and out
+    Mmap,
+}
+
+#[cfg(unix)]
+pub use unix::{enable, mapped, FileBacked};
+
+#[cfg(not(unix))]
+pub fn enable(
+    storage: RomStorage,
+    _dir: &std::path::Path,
+    _rom_size: usize,
+) -> anyhow::Result<bool> {
+    if storage == RomStorage::Mmap {
+        anyhow::bail!("--rom-storage mmap is only available on Linux, macOS and other Unix systems");
+    }
+    Ok(false)
+}
+
+#[cfg(not(unix))]
+pub fn mapped() -> usize {
+    0
+}
+
+#[cfg(unix)]
+mod unix {
+    use anyhow::{bail, Context, Result};
+    use std::alloc::{GlobalAlloc, Layout};
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Mutex, OnceLock};
+
+    use super::RomStorage;
+    use crate::limits;
+
+    /// Allocations this large go to a file; usize::MAX while disabled
+    static THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+    /// mkstemp template in --rom-dir
+    static TEMPLATE: OnceLock<CString> = OnceLock::new();
+
+    /// Live file mappings (address, length); the old and new ROM overlap
+    /// briefly at most, so a few slots are plenty
+    static MAPPINGS: Mutex<[(usize, usize); 4]> = Mutex::new([(0, 0); 4]);
+
+    /// Route ROM-sized allocations to `dir` from now on; true when enabled
+    pub fn enable(storage: RomStorage, dir: &Path, rom_size: usize) -> Result<bool> {
+        if storage == RomStorage::Ram {
+            return Ok(false);
+        }
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Cannot create --rom-dir {}", dir.display()))?;
+        let template = CString::new(dir.join("scavenger-rom-XXXXXX").as_os_str().as_bytes())
+            .context("--rom-dir contains a NUL byte")?;
+        if let Some(free) = free_space(dir) {
+            if free < rom_size as u64 {
+                bail!(
+                    "--rom-dir {} has {} free, the ROM needs {}",
+                    dir.display(),
+                    limits::size(free),
+                    limits::size(rom_size as u64)
+                );
+            }
+        }
+        let _ = TEMPLATE.set(template);
+        THRESHOLD.store(rom_size, Ordering::Relaxed);
+        say!("💽 ROM in a memory-mapped file under {} (--rom-storage mmap)", dir.display());
+        Ok(true)
+    }
+
+    /// Bytes currently in file mappings
+    pub fn mapped() -> usize {
+        MAPPINGS.lock().unwrap().iter().map(|&(_, len)| len).sum()
+    }
+
+    fn free_space(dir: &Path) -> Option<u64> {
+        let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+        // SAFETY: statvfs only writes the struct it is given
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+
+    /// A fresh unlinked file of `len` bytes, mapped shared; None to fall
+    /// back to memory. Allocates nothing, since it runs inside the allocator.
+    unsafe fn map(len: usize) -> Option<*mut u8> {
+        let template = TEMPLATE.get()?.as_bytes_with_nul();
+        let mut path = [0u8; 4096];
+        if template.len() > path.len() {
+            return None;
+        }
+        path[..template.len()].copy_from_slice(template);
+        let fd = libc::mkstemp(path.as_mut_ptr().cast());
+        if fd < 0 {
+            return None;
+        }
+        libc::unlink(path.as_ptr().cast());
+        // Reserve the blocks now: a full disk later would be a SIGBUS mid-hash
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let sized = libc::posix_fallocate(fd, 0, len as libc::off_t) == 0;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let sized = libc::ftruncate(fd, len as libc::off_t) == 0;
+        let ptr = if sized {
+            let flags = libc::PROT_READ | libc::PROT_WRITE;
+            libc::mmap(std::ptr::null_mut(), len, flags, libc::MAP_SHARED, fd, 0)
+        } else {
+            libc::MAP_FAILED
+        };
+        libc::close(fd);
+        if ptr == libc::MAP_FAILED {
+            return None;
+        }
+        let mut mappings = MAPPINGS.lock().unwrap();
+        match mappings.iter_mut().find(|(addr, _)| *addr == 0) {
+            Some(slot) => {
+                *slot = (ptr as usize, len);
+                Some(ptr.cast())
+            }
+            None => {
+                libc::munmap(ptr, len);
+                None
+            }
+        }
+    }
+
+    /// Unmap `ptr` if it is one of ours
+    unsafe fn unmap(ptr: *mut u8) -> bool {
+        let mut mappings = MAPPINGS.lock().unwrap();
+        let Some(slot) = mappings.iter_mut().find(|(addr, _)| *addr == ptr as usize) else {
+            return false;
+        };
+        libc::munmap(ptr.cast(), slot.1);
+        *slot = (0, 0);
+        true
+    }
+
+    /// The global allocator, passing everything but the ROM to `A`
+    pub struct FileBacked<A>(pub A);
+
+    impl<A> FileBacked<A> {
+        fn wants(layout: &Layout) -> bool {
+            // mmap hands out page-aligned memory; anything stricter stays in RAM
+            layout.size() >= THRESHOLD.load(Ordering::Relaxed) && layout.align() <= 4096
+        }
+    }
+
+    // SAFETY: file mappings are page-aligned, at least `size` long and
+    // zero-filled; everything else is `A`'s
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for FileBacked<A> {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            if Self::wants(&layout) {
+                if let Some(ptr) = map(layout.size()) {
+                    return ptr;
+                }
+            }
+            self.0.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            if Self::wants(&layout) {
+                if let Some(ptr) = map(layout.size()) {
+                    return ptr;
+                }
+            }
+            self.0.alloc_zeroed(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            if layout.size() < THRESHOLD.load(Ordering::Relaxed) || !unmap(ptr) {
+                self.0.dealloc(ptr, layout)
+            }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let threshold = THRESHOLD.load(Ordering::Relaxed);
+            if layout.size() < threshold && new_size < threshold {
+                return self.0.realloc(ptr, layout, new_size);
+            }
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new = self.alloc(new_layout);
+            if !new.is_null() {
+                std::ptr::copy_nonoverlapping(ptr, new, layout.size().min(new_size));
+                self.dealloc(ptr, layout);
+            }
+            new
+        }
+    }
+}
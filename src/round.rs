@@ -0,0 +1,730 @@
+// ==================== MINING ROUND ====================
+//
+// One fetch → mine → submit cycle, written against `MinerApi` so the same
+// orchestration runs live and against recorded traffic.
+
+use anyhow::Result;
+use chrono::Utc;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::advisor;
+use crate::contrib::Split;
+use crate::forensics;
+use crate::machine;
+use crate::metrics;
+use crate::notify::{self, Notifier};
+use crate::power::PowerGuard;
+use crate::retry;
+use crate::session::{Session, SessionState};
+use crate::share::Share;
+use crate::spot;
+use crate::status;
+use crate::submitqueue;
+use crate::tls;
+use crate::window;
+use crate::workhours;
+use crate::store::{self, AcceptanceRule, ScanRecord, SolutionRecord, SolutionStatus, Store};
+use crate::{
+    console, initial_nonce, parse_deadline, preimage_mismatches, romcache, timefmt, ChallengeCode, ChallengeResponse, Challenge, Cli, CryptoReceipt,
+    DifficultyRule, MinerApi, MiningOptions, ScavengerAPI, Solution, SolutionResponse,
+    SprintPlan, ROM_SIZE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineChoice {
+    AshMaize,
+    Instant,
+    /// Real AshMaize on a 1 MB ROM, for end-to-end tests
+    #[cfg(test)]
+    TinyRom,
+}
+
+/// Everything a round needs that would otherwise come from flags or prompts
+pub struct RoundSettings {
+    pub address: String,
+    /// Prompted for interactively when not given (and no hash budget is set)
+    pub max_iterations: Option<u64>,
+    /// Hash budget from --max-hashes, caps the iteration count
+    pub max_hashes: Option<u64>,
+    /// Stop mining at this instant (--max-duration)
+    pub stop_at: Option<Instant>,
+    pub threads: usize,
+    pub sprint_minutes: Option<u64>,
+    pub sprint_threads: Option<usize>,
+    /// --work-hours and the share of threads they take
+    pub work_hours: Option<workhours::Yield>,
+    pub dry_run: bool,
+    pub confirm: bool,
+    pub confirm_timeout: u64,
+    pub engine: EngineChoice,
+    /// Pick up after the last scanned nonce instead of a fresh time-based start
+    pub resume_scan: bool,
+    pub power_guard: Option<PowerGuard>,
+    /// Recorded with scanned ranges for contribution reports
+    pub worker: Option<String>,
+    /// Write a payout suggestion for each accepted solution
+    pub split: Option<Split>,
+    /// Non-protocol ROM size (--rom-size); solutions are not submitted
+    pub rom_size: Option<usize>,
+    /// Skip challenges less likely than this (0.0..=1.0) to be solved in time
+    pub min_success_chance: Option<f64>,
+    /// What an accepted solution rules out for this address
+    pub acceptance: AcceptanceRule,
+    /// How a digest is held against the difficulty (--difficulty-rule)
+    pub difficulty_rule: DifficultyRule,
+    /// Other addresses taking turns with `address` (--share)
+    pub share: Option<Share>,
+    pub idle: IdlePolicy,
+    /// Push solution alerts to the phone
+    pub notifier: Option<Notifier>,
+    /// Ctrl+C / shutdown; each round mines under a child token
+    pub cancel: CancellationToken,
+}
+
+/// Waiting for the next challenge should cost next to nothing: the worker
+/// threads are already gone (they live for one mining run), so what is left
+/// is the cached ROM and the polling.
+#[derive(Debug, Clone, Copy)]
+pub struct IdlePolicy {
+    pub rom: RomRetention,
+    /// Double the poll interval while nothing changes, up to this
+    pub max_poll: Option<Duration>,
+}
+
+/// What happens to the cached ROM between challenges (--idle-rom). There
+/// is no spill-to-disk middle ground: ashmaize::Rom exposes neither its
+/// bytes nor a constructor from them, so a ROM can only be kept or rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RomRetention {
+    /// Keep it in RAM for an instant start when the next challenge reuses the seed
+    Keep,
+    /// Free it; the next challenge builds from scratch
+    Drop,
+}
+
+impl IdlePolicy {
+    fn next_poll(&self, poll: Duration) -> Duration {
+        match self.max_poll {
+            Some(max) => (poll * 2).min(max.max(poll)),
+            None => poll,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundOutcome {
+    Solution(SolutionStatus),
+    NotFound,
+}
+
+pub async fn run_round<A: MinerApi>(
+    api: &A,
+    store: &Store,
+    settings: &RoundSettings,
+) -> Result<RoundOutcome> {
+    // Get challenge
+    say!();
+    console::banner(&["📡 FETCHING CHALLENGE"]);
+    tls::warn_if_insecure();
+    status::set_phase("fetching", None);
+    
+    let mut challenge_response = api.get_challenge().await?;
+    archive_challenge(store, &challenge_response);
+    
+    // Don't build a ROM for a challenge we can no longer win
+    let hold = |response: &ChallengeResponse| {
+        if parse_deadline(&response.mining_period_ends).is_some_and(|t| api.now() >= t) {
+            return Err(format!(
+                "Challenge {}: its mining period ended {}",
+                response.challenge.challenge_id,
+                timefmt::timestamp(&response.mining_period_ends)
+            ));
+        }
+        let address = payee(store, settings, response)?;
+        match api.held_back(&response.challenge.challenge_id) {
+            Some(reason) => Err(reason),
+            None => Ok(address),
+        }
+    };
+    let address = loop {
+        let reason = match hold(&challenge_response) {
+            Ok(address) => break address,
+            Err(reason) => reason,
+        };
+        let mut poll = challenge_response.code.poll_interval();
+        say!("\n⏭️  {}", reason);
+        match settings.idle.max_poll {
+            Some(max) if max > poll => say!(
+                "   Waiting for the next challenge (checking every {}s, backing off to {}s)...",
+                poll.as_secs(),
+                max.as_secs()
+            ),
+            _ => say!("   Waiting for the next challenge (checking every {}s)...", poll.as_secs()),
+        }
+        match settings.idle.rom {
+            RomRetention::Drop => {
+                if let Some(size) = romcache::release() {
+                    say!("   Freed the {} MB ROM while idle", size / 1_024 / 1_024);
+                }
+            }
+            RomRetention::Keep => {
+                if let Some(size) = romcache::cached_size() {
+                    say!("   Keeping the {} MB ROM in case the seed repeats", size / 1_024 / 1_024);
+                }
+            }
+        }
+        let current_id = challenge_response.challenge.challenge_id.clone();
+        loop {
+            if out_of_time(settings) || spot::termination_pending() || settings.cancel.is_cancelled() {
+                say!("⏱️  Stopping while waiting, not mining");
+                return Ok(RoundOutcome::NotFound);
+            }
+            status::set_phase("waiting", Some(&current_id));
+            tokio::select! {
+                _ = api.pause(poll) => {}
+                _ = settings.cancel.cancelled() => continue,
+            }
+            poll = settings.idle.next_poll(poll);
+            challenge_response = api.get_challenge().await?;
+            archive_challenge(store, &challenge_response);
+            if challenge_response.challenge.challenge_id != current_id
+                || hold(&challenge_response).is_ok()
+            {
+                break;
+            }
+        }
+    };
+    let address = address.as_str();
+    
+    // From here on the round also goes to the challenge's own log
+    let log_path = store.challenge_log_path(&challenge_response.challenge.challenge_id);
+    let _segment = match console::Segment::open(&log_path) {
+        Ok(segment) => Some(segment),
+        Err(e) => {
+            say!("⚠️  Cannot write the challenge log {}: {}", log_path.display(), e);
+            None
+        }
+    };
+    say!("\n✅ Challenge received:");
+    print_challenge(&challenge_response, settings.difficulty_rule);
+    if let Some(share) = &settings.share {
+        say!("   Mining for: {} ({})", share.name_of(address), address);
+    }
+    if let ChallengeCode::Unknown(code) = &challenge_response.code {
+        say!("   ⚠️  Unrecognized status code '{}', mining anyway", code);
+    }
+    let stop_at = stop_at_utc(settings);
+    let rule = settings.difficulty_rule;
+    if let Some(advice) =
+        advisor::advise(store, &challenge_response, settings.threads, stop_at, rule)
+    {
+        advisor::print(&advice);
+    }
+    
+    // Mining
+    say!();
+    console::banner(&["⛏️  MINING"]);
+    
+    let max_iterations = match (settings.max_iterations, settings.max_hashes) {
+        (Some(n), Some(budget)) => n.min(budget),
+        (Some(n), None) => n,
+        (None, Some(budget)) => budget,
+        (None, None) => prompt_iterations()?,
+    };
+    
+    let deadline = parse_deadline(&challenge_response.mining_period_ends);
+    let sprint = match (settings.sprint_minutes, deadline) {
+        (Some(minutes), Some(deadline)) => Some(SprintPlan::new(
+            deadline,
+            minutes,
+            settings.sprint_threads.unwrap_or_else(num_cpus::get),
+        )),
+        (Some(_), None) => {
+            say!("⚠️  Cannot parse deadline, final sprint disabled");
+            None
+        }
+        _ => None,
+    };
+    let challenge_id = challenge_response.challenge.challenge_id.clone();
+    let (start_nonce, max_iterations) =
+        match api.claim_nonces(address, &challenge_id, max_iterations).await? {
+            Some(range) if range.is_empty() => {
+                say!("⏭️  The coordinator has no nonces left for {}", challenge_id);
+                return Ok(RoundOutcome::NotFound);
+            }
+            Some(range) => {
+                say!("📦 Nonces {}..{} from the coordinator", range.start, range.end);
+                (range.start, range.end - range.start)
+            }
+            None if settings.resume_scan => {
+                let start = resume_nonce(store, address, &challenge_id);
+                (start.unwrap_or_else(initial_nonce), max_iterations)
+            }
+            None => (initial_nonce(), max_iterations),
+        };
+    warn_about_rescan(
+        store,
+        address,
+        &challenge_response.challenge.challenge_id,
+        start_nonce,
+        max_iterations,
+    );
+    
+    let options = MiningOptions {
+        start_nonce,
+        max_iterations,
+        threads: settings.threads,
+        sprint,
+        work_hours: settings.work_hours,
+        stop_at: settings.stop_at,
+        power_guard: settings.power_guard,
+        deadline,
+        expires_at: parse_deadline(&challenge_response.challenge.latest_submission),
+        rom_size: settings.rom_size.unwrap_or(ROM_SIZE),
+        difficulty_rule: settings.difficulty_rule,
+        cancel: settings.cancel.child_token(),
+    };
+    
+    let mut session = Session::new(challenge_response, options);
+    session.build_rom(settings.engine).await?;
+    if session.state() == SessionState::Expired {
+        return Ok(RoundOutcome::NotFound);
+    }
+    session.mine(address)?;
+    if let Some(rate) = session.stats().hash_rate {
+        if let Err(e) = store.record_hash_rate(rate, settings.threads) {
+            say!("⚠️  Failed to save the measured hash rate: {}", e);
+        }
+    }
+    say!("   Session {:?}: {}", session.state(), session.timing());
+    let challenge_response = session.response();
+    
+    let scan = ScanRecord {
+        recorded_at: Utc::now().to_rfc3339(),
+        address: address.to_string(),
+        challenge_id: challenge_response.challenge.challenge_id.clone(),
+        start_nonce,
+        count: session.stats().scanned,
+        worker: settings.worker.clone(),
+        machine_id: machine::id().map(str::to_string),
+    };
+    if let Err(e) = store.record_scan(&scan) {
+        say!("⚠️  Failed to record scanned range: {}", e);
+    }
+    
+    if let Some(solution) = session.solution().cloned() {
+        let mut response = None;
+        let mut submit_error = None;
+        let mut submitted_at = None;
+        let mut receipt_problems = Vec::new();
+        let status = if let Some(rom_size) = settings.rom_size {
+            say!(
+                "\n🧪 Mined with a {} MB ROM instead of the protocol's - not submitted",
+                rom_size / 1_024 / 1_024
+            );
+            say!("   Nonce: {}", solution.nonce);
+            SolutionStatus::NotSubmittable
+        } else if settings.dry_run {
+            say!("\n🧪 DRY RUN - solution not submitted");
+            say!("   POST {}", api.solution_url(
+                address,
+                &challenge_response.challenge.challenge_id,
+                &solution.nonce,
+            ));
+            say!("   Body: (empty)");
+            say!("   Preimage: {}", solution.preimage);
+            say!("   Hash: {}", hex::encode(solution.hash));
+            SolutionStatus::DryRun
+        } else if settings.confirm
+            // A reclaimed VM can't wait for an answer
+            && !spot::termination_pending()
+            && !confirm_submission(challenge_response, &solution, settings.confirm_timeout).await?
+        {
+            say!("⏭️  Submission cancelled");
+            SolutionStatus::Cancelled
+        } else {
+            submitted_at = Some(Utc::now());
+            status::set_phase("submitting", Some(&challenge_id));
+            match submit_and_report(api, address, &challenge_response.challenge, &solution).await {
+                Ok(result) => {
+                    let status = if let Some(receipt) = &result.crypto_receipt {
+                        // A replayed receipt is for the nonce mined when it was recorded
+                        if !matches!(settings.engine, EngineChoice::Instant) {
+                            receipt_problems = check_receipt(
+                                receipt,
+                                address,
+                                &challenge_response.challenge,
+                                &solution.nonce,
+                            );
+                        }
+                        SolutionStatus::Accepted
+                    } else if result.landed_earlier {
+                        SolutionStatus::AcceptedNoReceipt
+                    } else {
+                        SolutionStatus::Rejected
+                    };
+                    response = serde_json::to_value(&result).ok();
+                    status
+                }
+                Err(e) => {
+                    submit_error = Some(e);
+                    SolutionStatus::SubmitFailed
+                }
+            }
+        };
+        
+        let record = SolutionRecord {
+            recorded_at: Utc::now().to_rfc3339(),
+            address: address.to_string(),
+            challenge: challenge_response.challenge.clone(),
+            mining_period_ends: challenge_response.mining_period_ends.clone(),
+            nonce: solution.nonce.clone(),
+            preimage: solution.preimage.clone(),
+            hash: hex::encode(solution.hash),
+            status,
+            response,
+            window: window::position(
+                store,
+                &challenge_response.challenge,
+                solution.found_at,
+                submitted_at,
+            ),
+            receipt_problems,
+            machine_id: machine::id().map(str::to_string),
+        };
+        match store.record_solution(&record) {
+            Ok(path) => say!("💾 Saved solution details to {}", path.display()),
+            Err(e) => say!("⚠️  Failed to save solution details: {}", e),
+        }
+        if status == SolutionStatus::SubmitFailed {
+            let landed = submit_error.as_ref().is_some_and(retry::may_have_landed);
+            match submitqueue::add(store, &record, landed) {
+                Ok(()) => say!(
+                    "📮 Queued to send again in the background until {}",
+                    timefmt::deadline(&record.challenge.latest_submission)
+                ),
+                Err(e) => say!("⚠️  Cannot queue the solution for another try: {}", e),
+            }
+        }
+        session.record_submission(status)?;
+        if status == SolutionStatus::Accepted {
+            status::solution_accepted();
+            metrics::solution_accepted();
+        }
+        if let Some(notifier) = &settings.notifier {
+            notify::solution(notifier, &record).await;
+        }
+        if let (SolutionStatus::Accepted, Some(split)) = (status, &settings.split) {
+            write_payout(store, &record, split);
+        }
+        
+        // A failed submit never reached the server; there is no verdict to explain
+        if status == SolutionStatus::Rejected || !record.receipt_problems.is_empty() {
+            let report = forensics::build_report(
+                &record,
+                solution.found_at,
+                submitted_at,
+                settings.difficulty_rule,
+            );
+            match store.write_forensic_report(&record, &report) {
+                Ok(path) => say!("🔬 Forensic report written to {}", path.display()),
+                Err(e) => say!("⚠️  Failed to write forensic report: {}", e),
+            }
+        }
+        
+        if let Some(e) = submit_error {
+            return Err(e);
+        }
+        return Ok(session.outcome());
+    }
+    
+    Ok(session.outcome())
+}
+
+fn archive_challenge(store: &Store, response: &ChallengeResponse) {
+    if let Err(e) = store.archive_challenge(response) {
+        say!("⚠️  Failed to archive challenge: {}", e);
+    }
+}
+
+fn write_payout(store: &Store, record: &SolutionRecord, split: &Split) {
+    let written = store
+        .scans_for(&record.challenge.challenge_id, &record.address)
+        .and_then(|scans| store.write_payout(record, &split.suggest(record, &scans)));
+    match written {
+        Ok(path) => say!("💸 Payout suggestion ({:?}) written to {}", split.policy, path.display()),
+        Err(e) => say!("⚠️  Failed to write payout suggestion: {}", e),
+    }
+}
+
+fn out_of_time(settings: &RoundSettings) -> bool {
+    settings.stop_at.is_some_and(|t| Instant::now() >= t)
+}
+
+/// `challenge [--json]`: the current challenge and this machine's chance at it
+pub async fn show_challenge(cli: &Cli, json: bool) -> Result<()> {
+    tls::warn_if_insecure();
+    let store = Store::open(&cli.data_dir)?;
+    let response = ScavengerAPI::new(&cli.base_url)?.get_challenge().await?;
+    let threads = cli.profile.tuning().threads(cli.threads);
+    let rule = cli.difficulty_rule;
+    if json {
+        let advice = advisor::advise(&store, &response, threads, None, rule);
+        println!("{}", challenge_json(&response, advice.as_ref()));
+        return Ok(());
+    }
+    say!("🎯 Current challenge:");
+    print_challenge(&response, rule);
+    match advisor::advise(&store, &response, threads, None, rule) {
+        Some(advice) => advisor::print(&advice),
+        None => say!("   Run `bench` once for the chance of solving it on this machine"),
+    }
+    Ok(())
+}
+
+/// The fields worth knowing about a challenge, one per line
+pub fn print_challenge(response: &ChallengeResponse, rule: DifficultyRule) {
+    say!("   Status: {}", response.code);
+    say!("   ID: {}", response.challenge.challenge_id);
+    say!("   Day: {}", response.challenge.day);
+    say!("   Challenge #: {}", response.challenge.challenge_number);
+    match rule {
+        DifficultyRule::Prefix => say!("   Difficulty: {}", response.challenge.difficulty),
+        rule => say!("   Difficulty: {} ({} rule)", response.challenge.difficulty, rule.name()),
+    }
+    say!("   Deadline: {}", timefmt::deadline(&response.mining_period_ends));
+}
+
+/// `challenge --json`: the challenge as served, with the chance of solving
+/// it once this machine is calibrated
+pub fn challenge_json(
+    response: &ChallengeResponse,
+    advice: Option<&advisor::Advice>,
+) -> serde_json::Value {
+    let advice = advice.map(|advice| {
+        serde_json::json!({
+            "chance": advice.chance,
+            "rate": advice.rate,
+            "calibration": advice.calibration,
+        })
+    });
+    serde_json::json!({
+        "code": response.code.as_str(),
+        "challenge": response.challenge,
+        "mining_period_ends": response.mining_period_ends,
+        "advice": advice,
+    })
+}
+
+fn prompt_iterations() -> Result<u64> {
+    say!("\nHow many hashes to try?");
+    say!("  100000     = Quick test (~few minutes)");
+    say!("  1000000    = Medium test");
+    say!("  100000000  = Serious mining (hours)");
+    say!("\nEnter number:");
+    
+    let iterations_input = console::read_line(None)?.unwrap_or_default();
+    Ok(iterations_input
+        .trim()
+        .parse()
+        .unwrap_or(100_000))
+}
+
+/// The address to mine this challenge for: `address`, or with --share the
+/// first whose turn it is that can still use it; else why none can
+pub(crate) fn payee(
+    store: &Store,
+    settings: &RoundSettings,
+    response: &ChallengeResponse,
+) -> Result<String, String> {
+    let addresses = match &settings.share {
+        Some(share) => share.order(store, &response.challenge.challenge_id),
+        None => vec![settings.address.as_str()],
+    };
+    let mut first_reason = None;
+    for address in addresses {
+        match skip_reason(store, settings, address, response) {
+            None => return Ok(address.to_string()),
+            Some(reason) => {
+                first_reason.get_or_insert(reason);
+            }
+        }
+    }
+    Err(first_reason.unwrap_or_default())
+}
+
+/// Why mining this challenge for `address` would be wasted work, if it would be
+fn skip_reason(
+    store: &Store,
+    settings: &RoundSettings,
+    address: &str,
+    response: &ChallengeResponse,
+) -> Option<String> {
+    let challenge_id = &response.challenge.challenge_id;
+    if !response.code.is_mineable() {
+        return Some(format!("Challenge {} is not open ({})", challenge_id, response.code));
+    }
+    match store.allowance_used(address, &response.challenge, settings.acceptance) {
+        Ok(Some(solved)) if solved.challenge.challenge_id == *challenge_id => {
+            return Some(format!("Challenge {} already solved by this address", challenge_id))
+        }
+        Ok(Some(solved)) => {
+            return Some(format!(
+                "Challenge {}: this address already solved {} on day {} (one per day)",
+                challenge_id, solved.challenge.challenge_id, solved.challenge.day
+            ))
+        }
+        Ok(None) => {}
+        Err(e) => say!("⚠️  Cannot read history: {}", e),
+    }
+    if submitqueue::holds(store, address, challenge_id) {
+        return Some(format!(
+            "Challenge {}: this address's solution is waiting in the submission queue",
+            challenge_id
+        ));
+    }
+    let min = settings.min_success_chance?;
+    let stop_at = stop_at_utc(settings);
+    let advice =
+        advisor::advise(store, response, settings.threads, stop_at, settings.difficulty_rule)?;
+    (advice.chance < min).then(|| {
+        format!(
+            "Challenge {}: {:.1}% chance of a solution before it closes, below {:.1}% (--min-success-chance)",
+            challenge_id,
+            advice.chance * 100.0,
+            min * 100.0
+        )
+    })
+}
+
+/// --max-duration as a wall-clock instant
+fn stop_at_utc(settings: &RoundSettings) -> Option<chrono::DateTime<Utc>> {
+    let left = settings.stop_at?.saturating_duration_since(Instant::now());
+    Some(Utc::now() + chrono::Duration::from_std(left).ok()?)
+}
+
+/// First nonce after everything already scanned for this challenge
+fn resume_nonce(store: &Store, address: &str, challenge_id: &str) -> Option<u64> {
+    let scans = store.scans_for(challenge_id, address).ok()?;
+    scans.iter().map(|s| s.start_nonce.saturating_add(s.count)).max()
+}
+
+/// Warn when this run would hash nonces an earlier run already covered
+fn warn_about_rescan(store: &Store, address: &str, challenge_id: &str, start: u64, count: u64) {
+    let scans = match store.scans_for(challenge_id, address) {
+        Ok(scans) => scans,
+        Err(e) => {
+            say!("⚠️  Cannot read scan history: {}", e);
+            return;
+        }
+    };
+    let overlap = store::scanned_overlap(&scans, start, count);
+    if overlap > 0 {
+        say!("\n⚠️  {} of {} planned nonces were already scanned for {} in {} earlier run(s)",
+            overlap, count, challenge_id, scans.len());
+    }
+}
+
+/// Returns true when the solution should be submitted
+async fn confirm_submission(
+    response: &ChallengeResponse,
+    solution: &Solution,
+    timeout_secs: u64,
+) -> Result<bool> {
+    let challenge = &response.challenge;
+    say!();
+    console::banner(&["🔍 REVIEW SOLUTION"]);
+    say!("   Challenge ID: {}", challenge.challenge_id);
+    say!("   Day: {}", challenge.day);
+    say!("   Challenge #: {}", challenge.challenge_number);
+    say!("   Difficulty: {}", challenge.difficulty);
+    say!("   No pre-mine: {}", challenge.no_pre_mine);
+    say!("   Latest submission: {}", challenge.latest_submission);
+    say!("   No pre-mine hour: {}", challenge.no_pre_mine_hour);
+    say!("   Deadline: {}", timefmt::deadline(&response.mining_period_ends));
+    say!("   Nonce: 0x{}", solution.nonce);
+    say!("   Preimage: {}", solution.preimage);
+    say!("   Hash: {}", hex::encode(solution.hash));
+    
+    say!("\nSubmit this solution? (y/n) [auto-submit in {}s]", timeout_secs);
+    
+    // One deadline for the whole question, however often it is asked again
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    loop {
+        io::stdout().flush()?;
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let answer =
+            tokio::task::spawn_blocking(move || console::read_line(Some(timeout))).await??;
+        // Timed out, or stdin is closed and nobody can answer
+        let Some(input) = answer.filter(|line| !line.is_empty()) else {
+            say!("⏱️  No answer, submitting automatically");
+            return Ok(true);
+        };
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => say!(
+                "Please answer y or n [auto-submit in {}s]",
+                deadline.saturating_duration_since(Instant::now()).as_secs()
+            ),
+        }
+    }
+}
+
+/// The receipt's preimage against what was submitted. A mismatch means the
+/// server signed something other than this solution, whatever it answered.
+fn check_receipt(
+    receipt: &CryptoReceipt,
+    address: &str,
+    challenge: &Challenge,
+    nonce: &str,
+) -> Vec<String> {
+    let problems = preimage_mismatches(&receipt.preimage, nonce, address, challenge);
+    if problems.is_empty() {
+        say!("   Receipt matches the submission: ✅");
+    } else {
+        say!("\n⚠️  The receipt is not for what was submitted:");
+        for problem in &problems {
+            say!("   {}", problem);
+        }
+    }
+    problems
+}
+
+async fn submit_and_report<A: MinerApi>(
+    api: &A,
+    address: &str,
+    challenge: &Challenge,
+    solution: &Solution,
+) -> Result<SolutionResponse> {
+    say!();
+    console::banner(&["📤 SUBMITTING SOLUTION"]);
+    
+    let result = api.submit_solution(
+        address,
+        &challenge.challenge_id,
+        &solution.nonce,
+    ).await?;
+    
+    if let Some(receipt) = &result.crypto_receipt {
+        say!("\n🎉🎉🎉 SOLUTION ACCEPTED! 🎉🎉🎉");
+        say!("   Timestamp: {}", timefmt::timestamp(&receipt.timestamp));
+        
+        // Check reward
+        if let Ok(rates) = api.get_star_rate().await {
+            let day = challenge.day as usize;
+            if day > 0 && day <= rates.len() {
+                say!("\n⭐ REWARD: {} STAR tokens!", rates[day - 1]);
+            }
+        }
+    } else if result.landed_earlier {
+        say!("\n✅ Most likely accepted: an earlier try may have got in, but there is no receipt");
+    } else {
+        say!("\n📋 Solution submitted");
+        say!("   Response: {:?}", result.extra);
+    }
+    
+    Ok(result)
+}
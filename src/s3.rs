@@ -0,0 +1,281 @@
+// ==================== S3 CLIENT ====================
+//
+// Just enough of the S3 API (PUT, conditional PUT, GET, ListObjectsV2) with SigV4 signing
+// to keep miner state in any S3-compatible bucket: AWS, MinIO, R2, B2...
+// Path-style URLs, since not every compatible store does virtual hosts.
+//
+// Configuration comes from the usual environment variables:
+//   AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN (optional)
+//   AWS_REGION (default us-east-1)
+//   S3_ENDPOINT (default https://s3.<region>.amazonaws.com)
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::header::ETAG;
+use reqwest::{Client, Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use crate::tls;
+
+pub struct S3Client {
+    client: Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl S3Client {
+    pub fn from_env(bucket: &str) -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let region = var("AWS_REGION")
+            .or_else(|| var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = var("S3_ENDPOINT")
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        Ok(S3Client {
+            client: tls::builder().timeout(Duration::from_secs(60)).build()?,
+            endpoint: Url::parse(&endpoint)
+                .with_context(|| format!("Invalid S3_ENDPOINT {}", endpoint))?,
+            bucket: bucket.to_string(),
+            region,
+            access_key: var("AWS_ACCESS_KEY_ID").context("AWS_ACCESS_KEY_ID is not set")?,
+            secret_key: var("AWS_SECRET_ACCESS_KEY").context("AWS_SECRET_ACCESS_KEY is not set")?,
+            session_token: var("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    pub async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        let response = self.send(Method::PUT, key, &[], &[], body).await?;
+        if !response.status().is_success() {
+            bail!("PUT {} failed: {}", key, error_text(response).await);
+        }
+        Ok(())
+    }
+
+    /// PUT only if the object still has the ETag `expected`, or is absent
+    /// when None; false when it has changed. `Some(None)` puts unconditionally.
+    pub async fn put_if(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        expected: Option<Option<&str>>,
+    ) -> Result<bool> {
+        let condition = match expected {
+            None => vec![("if-none-match", "*".to_string())],
+            Some(Some(etag)) => vec![("if-match", etag.to_string())],
+            Some(None) => Vec::new(),
+        };
+        let response = self.send(Method::PUT, key, &[], &condition, body).await?;
+        // 409 is a concurrent conditional write that lost
+        if matches!(response.status(), StatusCode::PRECONDITION_FAILED | StatusCode::CONFLICT) {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            bail!("PUT {} failed: {}", key, error_text(response).await);
+        }
+        Ok(true)
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self.send(Method::GET, key, &[], &[], Vec::new()).await?;
+        if !response.status().is_success() {
+            bail!("GET {} failed: {}", key, error_text(response).await);
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// The object and its ETag, or None when there is no such key
+    pub async fn get_versioned(&self, key: &str) -> Result<Option<(Vec<u8>, Option<String>)>> {
+        let response = self.send(Method::GET, key, &[], &[], Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            bail!("GET {} failed: {}", key, error_text(response).await);
+        }
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok(Some((response.bytes().await?.to_vec(), etag)))
+    }
+
+    /// Every key under `prefix`, following continuation tokens
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("prefix".to_string(), prefix.to_string()),
+            ];
+            if let Some(token) = &token {
+                query.push(("continuation-token".to_string(), token.clone()));
+            }
+            let response = self.send(Method::GET, "", &query, &[], Vec::new()).await?;
+            if !response.status().is_success() {
+                bail!("Listing {} failed: {}", prefix, error_text(response).await);
+            }
+            let body = response.text().await?;
+            keys.extend(xml_values(&body, "Key"));
+            token = xml_values(&body, "NextContinuationToken").into_iter().next();
+            if token.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(String, String)],
+        conditions: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response> {
+        let mut path = format!("/{}", uri_encode(&self.bucket, false));
+        if !key.is_empty() {
+            path.push('/');
+            path.push_str(&uri_encode(key, true));
+        }
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, false), uri_encode(v, false)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", self.endpoint.host_str().unwrap_or_default(), port),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signature = self.signature(method.as_str(), &path, &query, &headers, &payload_hash, &amz_date);
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        url.set_query((!query.is_empty()).then_some(query.as_str()));
+        let mut request = self.client.request(method, url).body(body);
+        // Conditions go unsigned; SigV4 only requires host and x-amz-*
+        let unsigned = conditions.iter();
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host").chain(unsigned) {
+            request = request.header(*name, value);
+        }
+        Ok(request.header("authorization", signature).send().await?)
+    }
+
+    /// The Authorization header value for a request (AWS Signature Version 4)
+    fn signature(
+        &self,
+        method: &str,
+        path: &str,
+        query: &str,
+        headers: &[(&str, String)],
+        payload_hash: &str,
+        amz_date: &str,
+    ) -> String {
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, query, canonical_headers, signed_headers, payload_hash
+        );
+
+        let date = &amz_date[..8];
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut key = hmac(format!("AWS4{}", self.secret_key).as_bytes(), date.as_bytes());
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        )
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 percent-encoding: everything but unreserved characters (and '/' in paths)
+fn uri_encode(s: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Text content of every `<tag>` element; the listing XML is simple enough
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else { break };
+        values.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+async fn error_text(response: reqwest::Response) -> String {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let code = xml_values(&body, "Code").into_iter().next();
+    match code {
+        Some(code) => format!("{} ({})", status, code),
+        None => status.to_string(),
+    }
+}
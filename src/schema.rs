@@ -0,0 +1,233 @@
+// ==================== RESPONSE SCHEMA ====================
+//
+// The server's answers carry more than the miner reads, and serde drops
+// (or `extra` swallows) whatever it does not know, so a protocol change
+// only shows once a field the miner needs moves and parsing breaks. Every
+// JSON answer is compared with the fields known for its call; a new one
+// is logged once per run, with a sample of its value, as early warning.
+// The snapshot tests pin the shape of each answer the same way, so a
+// change to the types shows up in review as a changed snapshot.
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Field paths each call's answer is known to carry ("a.b" for nested),
+/// by audit action name
+const KNOWN: &[(&str, &[&str])] = &[
+    (
+        "challenge",
+        &[
+            "code",
+            "challenge",
+            "challenge.challenge_id",
+            "challenge.day",
+            "challenge.challenge_number",
+            "challenge.difficulty",
+            "challenge.no_pre_mine",
+            "challenge.latest_submission",
+            "challenge.no_pre_mine_hour",
+            "mining_period_ends",
+            // With no challenge (types::NoChallenge)
+            "starts_at",
+            "next_challenge_starts_at",
+            "mining_period_starts",
+            // Added by a coordinator for its workers
+            "fleet_solved",
+        ],
+    ),
+    (
+        "submit",
+        &[
+            "crypto_receipt",
+            "crypto_receipt.preimage",
+            "crypto_receipt.timestamp",
+            "crypto_receipt.signature",
+            "message",
+            "error",
+            "statusCode",
+        ],
+    ),
+    (
+        "terms",
+        &["version", "content", "message"],
+    ),
+    (
+        "register",
+        &[
+            "registrationReceipt",
+            "registrationReceipt.preimage",
+            "registrationReceipt.signature",
+            "registrationReceipt.timestamp",
+            "message",
+            "error",
+            "statusCode",
+        ],
+    ),
+];
+
+/// Drift already reported this run, as "action field"
+static REPORTED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Fields of `value` not known for `action`, with their values
+pub fn unknown_fields<'a>(action: &str, value: &'a Value) -> Vec<(String, &'a Value)> {
+    let Some((_, known)) = KNOWN.iter().find(|(name, _)| *name == action) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    let mut stack = vec![(String::new(), value)];
+    while let Some((path, value)) = stack.pop() {
+        let Value::Object(fields) = value else {
+            continue;
+        };
+        for (key, field) in fields {
+            let nested = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            if known.contains(&nested.as_str()) {
+                stack.push((nested, field));
+            } else {
+                found.push((nested, field));
+            }
+        }
+    }
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+/// Warn about fields the server has started sending, once each per run
+pub fn check(action: &str, value: &Value) {
+    let unknown = unknown_fields(action, value);
+    if unknown.is_empty() {
+        return;
+    }
+    let mut reported = REPORTED.lock().unwrap_or_else(|e| e.into_inner());
+    let reported = reported.get_or_insert_with(HashSet::new);
+    for (path, field) in unknown {
+        if reported.insert(format!("{} {}", action, path)) {
+            let sample: String = field.to_string().chars().take(80).collect();
+            say!("⚠️  New field in the server's {} answer: {} = {}", action, path, sample);
+            say!("   The miner ignores it; the API may be changing");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChallengeResponse, NoChallenge, RegistrationResponse, SolutionResponse};
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    /// One line per field, "path: type", sorted
+    fn shape(value: &Value) -> Vec<String> {
+        let mut lines = Vec::new();
+        walk(value, "", &mut lines);
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+
+    fn walk(value: &Value, path: &str, lines: &mut Vec<String>) {
+        let kind = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(items) => {
+                for item in items {
+                    walk(item, &format!("{}[]", path), lines);
+                }
+                "array"
+            }
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    let nested =
+                        if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                    walk(field, &nested, lines);
+                }
+                "object"
+            }
+        };
+        if !path.is_empty() {
+            lines.push(format!("{}: {}", path, kind));
+        }
+    }
+
+    /// Compare with tests/snapshots/<name>.shape; SCAVENGER_UPDATE_SNAPSHOTS=1
+    /// writes it instead
+    fn assert_snapshot(name: &str, value: &Value) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(format!("{}.shape", name));
+        let actual = shape(value).join("\n") + "\n";
+        if std::env::var_os("SCAVENGER_UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("no snapshot {}; set SCAVENGER_UPDATE_SNAPSHOTS=1", name));
+        assert_eq!(actual, expected, "{} changed shape", name);
+    }
+
+    fn challenge() -> Value {
+        json!({
+            "code": "active",
+            "challenge": {
+                "challenge_id": "**D05C08",
+                "day": 5,
+                "challenge_number": 8,
+                "difficulty": "000fffff",
+                "no_pre_mine": "e2a1c0d4b7f9e6a3c5d8b1f0a9e7c6d5",
+                "latest_submission": "2025-11-05T23:59:59Z",
+                "no_pre_mine_hour": "123456789"
+            },
+            "mining_period_ends": "2025-11-05T23:59:59Z"
+        })
+    }
+
+    /// Each answer parses into its type and keeps its recorded shape, and
+    /// the known fields cover all of it
+    #[test]
+    fn responses_match_snapshots() {
+        let idle = json!({"code": "before", "starts_at": "2025-10-30T00:00:00Z"});
+        let accepted = json!({"crypto_receipt": {
+            "preimage": "00000000a1b2c3d4addr1q**D05C08", "timestamp": "2025-11-05T01:00:00Z",
+            "signature": "5f1c"
+        }});
+        let rejected = json!({"message": "Solution does not meet difficulty", "statusCode": 400});
+        let registered = json!({"registrationReceipt": {
+            "preimage": "addr1q...", "signature": "84a4", "timestamp": "2025-10-30T12:00:00Z"
+        }});
+        let samples = [
+            ("challenge", "challenge", challenge()),
+            ("challenge", "challenge_idle", idle.clone()),
+            ("submit", "submit_accepted", accepted.clone()),
+            ("submit", "submit_rejected", rejected.clone()),
+            ("register", "register", registered.clone()),
+        ];
+
+        serde_json::from_value::<ChallengeResponse>(challenge()).unwrap();
+        assert!(NoChallenge::from_response(&idle).is_some());
+        let solution = serde_json::from_value::<SolutionResponse>(accepted).unwrap();
+        assert!(solution.crypto_receipt.is_some());
+        serde_json::from_value::<SolutionResponse>(rejected).unwrap();
+        let receipt = serde_json::from_value::<RegistrationResponse>(registered).unwrap();
+        assert!(receipt.registration_receipt.is_some());
+
+        for (action, name, sample) in &samples {
+            assert_snapshot(name, sample);
+            assert_eq!(unknown_fields(action, sample), Vec::new(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn new_fields_are_drift() {
+        let mut value = challenge();
+        value["challenge"]["rom_key"] = json!("abc");
+        value["epoch"] = json!(3);
+        let unknown = unknown_fields("challenge", &value);
+        let paths: Vec<&str> = unknown.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["challenge.rom_key", "epoch"]);
+        assert!(unknown_fields("star rate", &value).is_empty());
+    }
+}
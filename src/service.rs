@@ -0,0 +1,301 @@
+// ==================== SERVICE INSTALLERS ====================
+//
+// Running the daemon persistently shouldn't need a wrapper script per OS.
+// The installers turn the current flags into the OS's own job definition:
+// only what identifies the run (address, profile, threads, work hours,
+// worker, machine ID, coordinator, data dir, ROM storage, metrics address,
+// config file, extra CA) is carried over, as explicit flags,
+// because the job will not see this shell's SCAVENGER_* environment. Paths
+// are made absolute since jobs start somewhere else.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::path::PathBuf;
+
+use crate::romfile::RomStorage;
+use crate::{address::AddressKind, Cli, DifficultyRule};
+
+/// Flags for the installed daemon, from the ones this run was given
+pub fn daemon_args(cli: &Cli) -> Result<Vec<String>> {
+    let mut args = vec!["--mode".to_string(), "daemon".to_string()];
+    match (&cli.address, &cli.label) {
+        (Some(address), _) => args.extend(["--address".to_string(), address.trim().to_string()]),
+        (None, Some(label)) => args.extend(["--label".to_string(), label.clone()]),
+        (None, None) => bail!("Pass --address or --label for the installed daemon to mine for"),
+    }
+    if cli.address_kind != AddressKind::Payment {
+        args.extend(["--address-kind".to_string(), value_name(&cli.address_kind)]);
+    }
+    if cli.difficulty_rule != DifficultyRule::Prefix {
+        args.extend(["--difficulty-rule".to_string(), value_name(&cli.difficulty_rule)]);
+    }
+    args.extend(["--profile".to_string(), value_name(&cli.profile)]);
+    args.extend(["--threads".to_string(), cli.threads.to_string()]);
+    if let Some(hours) = &cli.work_hours {
+        args.extend(["--work-hours".to_string(), hours.to_string()]);
+        args.extend(["--work-yield".to_string(), cli.work_yield.to_string()]);
+    }
+    for (name, weight) in &cli.share {
+        args.extend(["--share".to_string(), format!("{}={}", name, weight)]);
+    }
+    if !cli.share.is_empty() {
+        args.extend(["--share-by".to_string(), value_name(&cli.share_by)]);
+        args.extend(["--share-slice".to_string(), format!("{}s", cli.share_slice.as_secs())]);
+    }
+    if let Some(worker) = &cli.worker {
+        args.extend(["--worker".to_string(), worker.clone()]);
+    }
+    if let Some(id) = &cli.machine_id {
+        args.extend(["--machine-id".to_string(), id.clone()]);
+    }
+    if let Some(url) = &cli.coordinator {
+        args.extend(["--coordinator".to_string(), url.clone()]);
+    }
+    if let Some(addr) = cli.coordinate {
+        args.extend(["--coordinate".to_string(), addr.to_string()]);
+    }
+    args.extend(["--data-dir".to_string(), data_dir(cli)?.display().to_string()]);
+    if cli.rom_storage == RomStorage::Mmap {
+        args.extend(["--rom-storage".to_string(), value_name(&cli.rom_storage)]);
+        if let Some(dir) = &cli.rom_dir {
+            std::fs::create_dir_all(dir)?;
+            let dir = dir.canonicalize().with_context(|| format!("Cannot resolve {}", dir.display()))?;
+            args.extend(["--rom-dir".to_string(), dir.display().to_string()]);
+        }
+    }
+    if let Some(addr) = cli.metrics_addr {
+        args.extend(["--metrics-addr".to_string(), addr.to_string()]);
+    }
+    // Without the proxy's root the job could not reach the API
+    for pem in &cli.extra_ca_cert {
+        let pem = pem.canonicalize().with_context(|| format!("Cannot resolve {}", pem.display()))?;
+        args.extend(["--extra-ca-cert".to_string(), pem.display().to_string()]);
+    }
+    // Jobs start in another directory; point them at this run's config file
+    if let Ok(config) = cli.config.canonicalize() {
+        args.extend(["--config".to_string(), config.display().to_string()]);
+    }
+    Ok(args)
+}
+
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default()
+}
+
+fn data_dir(cli: &Cli) -> Result<PathBuf> {
+    std::fs::create_dir_all(&cli.data_dir)?;
+    cli.data_dir
+        .canonicalize()
+        .with_context(|| format!("Cannot resolve {}", cli.data_dir.display()))
+}
+
+pub fn executable() -> Result<PathBuf> {
+    std::env::current_exe().context("Cannot find the miner's own executable")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// ==================== WINDOWS TASK SCHEDULER ====================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TaskTrigger {
+    /// When the current user logs on
+    Logon,
+    /// At system start, before anyone logs on (needs an elevated prompt)
+    Boot,
+}
+
+/// Windows command line quoting, enough for paths and addresses
+fn quote_windows(arg: &str) -> String {
+    if arg.is_empty() || arg.contains([' ', '\t', '"']) {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Task Scheduler XML: restart on failure, no time limit, runs on battery,
+/// below-normal priority so the desktop stays responsive
+fn task_xml(trigger: TaskTrigger, command: &str, args: &[String], workdir: &str) -> String {
+    // Nobody is logged on at boot, so that task runs as SYSTEM
+    let (trigger, principal) = match trigger {
+        TaskTrigger::Logon => ("<LogonTrigger><Enabled>true</Enabled></LogonTrigger>", ""),
+        TaskTrigger::Boot => (
+            "<BootTrigger><Enabled>true</Enabled><Delay>PT1M</Delay></BootTrigger>",
+            "<Principals><Principal><UserId>S-1-5-18</UserId></Principal></Principals>",
+        ),
+    };
+    let args: Vec<String> = args.iter().map(|arg| quote_windows(arg)).collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Scavenger Mine daemon</Description>
+  </RegistrationInfo>
+  <Triggers>
+    {trigger}
+  </Triggers>
+  {principal}
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <RunOnlyIfNetworkAvailable>false</RunOnlyIfNetworkAvailable>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <Priority>7</Priority>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+  </Settings>
+  <Actions>
+    <Exec>
+      <Command>{command}</Command>
+      <Arguments>{args}</Arguments>
+      <WorkingDirectory>{workdir}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        trigger = trigger,
+        principal = principal,
+        command = xml_escape(&quote_windows(command)),
+        args = xml_escape(&args.join(" ")),
+        workdir = xml_escape(workdir),
+    )
+}
+
+/// `install-task`: register (or with `print`, show) a scheduled task that
+/// starts the daemon at logon or boot
+pub fn install_task(cli: &Cli, name: &str, trigger: TaskTrigger, print: bool) -> Result<()> {
+    let args = daemon_args(cli)?;
+    let workdir = data_dir(cli)?.display().to_string();
+    let xml = task_xml(trigger, &executable()?.display().to_string(), &args, &workdir);
+    if print {
+        print!("{}", xml);
+        return Ok(());
+    }
+    if !cfg!(windows) {
+        bail!("install-task registers a Windows scheduled task; use --print to see its XML here");
+    }
+
+    // schtasks wants the XML as UTF-16 with a byte order mark
+    let path = std::env::temp_dir().join(format!("{}.xml", name));
+    let utf16: Vec<u8> = std::iter::once(0xfeff)
+        .chain(xml.encode_utf16())
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    std::fs::write(&path, utf16).with_context(|| format!("Failed to write {}", path.display()))?;
+    let status = std::process::Command::new("schtasks")
+        .args(["/Create", "/F", "/TN", name, "/XML"])
+        .arg(&path)
+        .status()
+        .context("Cannot run schtasks");
+    let _ = std::fs::remove_file(&path);
+    if !status?.success() {
+        match trigger {
+            TaskTrigger::Boot => bail!("schtasks failed; a boot task needs an elevated prompt"),
+            TaskTrigger::Logon => bail!("schtasks failed"),
+        }
+    }
+    say!("✅ Scheduled task '{}' starts the daemon at {}", name, value_name(&trigger));
+    say!("   {} {}", executable()?.display(), args.join(" "));
+    say!("   Start it now: schtasks /Run /TN {}", name);
+    say!("   Remove it:    schtasks /Delete /TN {} /F", name);
+    Ok(())
+}
+
+// ==================== MACOS LAUNCHD ====================
+
+/// LaunchAgent plist: started at login, restarted when it exits, output to
+/// the data dir, niced so the Mac stays usable
+fn launchd_plist(label: &str, command: &str, args: &[String], workdir: &str, nice: i32) -> String {
+    let arguments: String = std::iter::once(command)
+        .chain(args.iter().map(String::as_str))
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    let log = |name: &str| xml_escape(&format!("{}/{}", workdir, name));
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>WorkingDirectory</key>
+    <string>{workdir}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>ThrottleInterval</key>
+    <integer>60</integer>
+    <key>Nice</key>
+    <integer>{nice}</integer>
+    <key>ProcessType</key>
+    <string>Background</string>
+    <key>LowPriorityIO</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{stdout}</string>
+    <key>StandardErrorPath</key>
+    <string>{stderr}</string>
+</dict>
+</plist>
+"#,
+        label = xml_escape(label),
+        arguments = arguments,
+        workdir = xml_escape(workdir),
+        nice = nice,
+        stdout = log("launchd.out.log"),
+        stderr = log("launchd.err.log"),
+    )
+}
+
+/// `install-launchd`: write (or with `print`, show) a LaunchAgent for the
+/// daemon and load it
+pub fn install_launchd(cli: &Cli, label: &str, nice: i32, print: bool) -> Result<()> {
+    if !(-20..=20).contains(&nice) {
+        bail!("--nice must be between -20 and 20");
+    }
+    let args = daemon_args(cli)?;
+    let workdir = data_dir(cli)?.display().to_string();
+    let plist = launchd_plist(label, &executable()?.display().to_string(), &args, &workdir, nice);
+    if print {
+        print!("{}", plist);
+        return Ok(());
+    }
+    if !cfg!(target_os = "macos") {
+        bail!("install-launchd writes a macOS LaunchAgent; use --print to see the plist here");
+    }
+
+    let home = std::env::var_os("HOME").context("HOME is not set")?;
+    let agents = PathBuf::from(home).join("Library").join("LaunchAgents");
+    std::fs::create_dir_all(&agents)?;
+    let path = agents.join(format!("{}.plist", label));
+    // Reloading an already loaded agent fails; unload first, ignoring "not loaded"
+    let _ = std::process::Command::new("launchctl").arg("unload").arg(&path).output();
+    std::fs::write(&path, plist).with_context(|| format!("Failed to write {}", path.display()))?;
+    let status = std::process::Command::new("launchctl")
+        .args(["load", "-w"])
+        .arg(&path)
+        .status()
+        .context("Cannot run launchctl")?;
+    if !status.success() {
+        bail!("launchctl load failed for {}", path.display());
+    }
+    say!("✅ LaunchAgent {} loaded; the daemon starts at login and restarts if it exits", label);
+    say!("   Plist: {}", path.display());
+    say!("   Logs:  {}/launchd.out.log, launchd.err.log", workdir);
+    say!("   Remove it: launchctl unload -w {} && rm {}", path.display(), path.display());
+    Ok(())
+}
@@ -0,0 +1,223 @@
+// ==================== MINING SESSION ====================
+//
+// One challenge from fetch to verdict: the challenge, its ROM, the mining
+// options and what came of them, moving through an explicit lifecycle
+//
+//   Created → RomReady → Mining → Solved
+//       ↘         ↘         ↘
+//                 Expired
+//
+// so a round (and the daemon looping over rounds) is a sequence of checked
+// transitions instead of locals that may or may not be set yet.
+
+use anyhow::{bail, Result};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::round::{EngineChoice, RoundOutcome};
+use crate::store::SolutionStatus;
+use crate::{mine_challenge, romcache, status, timefmt, ChallengeResponse, MiningContext, MiningOptions};
+use crate::Solution;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// Challenge accepted for mining, no ROM yet
+    Created,
+    RomReady,
+    Mining,
+    /// A nonce meets the difficulty (submitted or not)
+    Solved,
+    /// Ended without a solution: deadline, budget or cancellation
+    Expired,
+}
+
+impl SessionState {
+    /// The lifecycle edges; everything else is a bug in the caller
+    pub fn can_move_to(self, next: SessionState) -> bool {
+        use SessionState::*;
+        matches!(
+            (self, next),
+            (Created, RomReady)
+                | (Created, Expired)
+                | (RomReady, Mining)
+                | (RomReady, Expired)
+                | (Mining, Solved)
+                | (Mining, Expired)
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SessionStats {
+    pub created_at: Option<Instant>,
+    pub rom_ready_at: Option<Instant>,
+    pub mining_started_at: Option<Instant>,
+    pub finished_at: Option<Instant>,
+    /// Nonces hashed in this session
+    pub scanned: u64,
+    /// Measured H/s, when the run was long enough to calibrate from
+    pub hash_rate: Option<f64>,
+}
+
+pub struct Session {
+    state: SessionState,
+    response: ChallengeResponse,
+    options: MiningOptions,
+    ctx: Option<Arc<MiningContext>>,
+    solution: Option<Solution>,
+    submission: Option<SolutionStatus>,
+    stats: SessionStats,
+}
+
+impl Session {
+    pub fn new(response: ChallengeResponse, options: MiningOptions) -> Self {
+        Session {
+            state: SessionState::Created,
+            response,
+            options,
+            ctx: None,
+            solution: None,
+            submission: None,
+            stats: SessionStats { created_at: Some(Instant::now()), ..Default::default() },
+        }
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn response(&self) -> &ChallengeResponse {
+        &self.response
+    }
+
+    pub fn solution(&self) -> Option<&Solution> {
+        self.solution.as_ref()
+    }
+
+    /// The round's result once the session is over
+    pub fn outcome(&self) -> RoundOutcome {
+        match self.submission {
+            Some(status) => RoundOutcome::Solution(status),
+            None => RoundOutcome::NotFound,
+        }
+    }
+
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    fn transition(&mut self, next: SessionState) -> Result<()> {
+        if !self.state.can_move_to(next) {
+            bail!("mining session cannot go from {:?} to {:?}", self.state, next);
+        }
+        let now = Some(Instant::now());
+        match next {
+            SessionState::RomReady => self.stats.rom_ready_at = now,
+            SessionState::Mining => self.stats.mining_started_at = now,
+            SessionState::Solved | SessionState::Expired => self.stats.finished_at = now,
+            SessionState::Created => {}
+        }
+        self.state = next;
+        Ok(())
+    }
+
+    /// Created → RomReady, or Expired when the build was abandoned
+    pub async fn build_rom(&mut self, engine: EngineChoice) -> Result<()> {
+        let challenge = &self.response.challenge;
+        status::set_phase("building_rom", Some(&challenge.challenge_id));
+        let ctx = match engine {
+            EngineChoice::AshMaize => {
+                romcache::build(&challenge.no_pre_mine, &self.options).await
+            }
+            EngineChoice::Instant => Some(Arc::new(MiningContext::instant())),
+            #[cfg(test)]
+            EngineChoice::TinyRom => {
+                Some(Arc::new(romcache::tiny(&challenge.no_pre_mine)))
+            }
+        };
+        match ctx {
+            Some(ctx) => {
+                self.ctx = Some(ctx);
+                self.transition(SessionState::RomReady)
+            }
+            None => self.transition(SessionState::Expired),
+        }
+    }
+
+    /// "ROM 41 s, mining 12 m 3 s"
+    pub fn timing(&self) -> String {
+        let span = |from: Option<Instant>, to: Option<Instant>| match (from, to) {
+            (Some(from), Some(to)) => {
+                timefmt::humanize(chrono::Duration::from_std(to - from).unwrap_or_default())
+            }
+            _ => "-".to_string(),
+        };
+        format!(
+            "ROM {}, mining {}",
+            span(self.stats.created_at, self.stats.rom_ready_at),
+            span(self.stats.mining_started_at, self.stats.finished_at)
+        )
+    }
+
+    /// RomReady → Mining → Solved | Expired; blocks until the workers stop
+    pub fn mine(&mut self, address: &str) -> Result<()> {
+        self.transition(SessionState::Mining)?;
+        let Some(ctx) = self.ctx.as_ref() else {
+            bail!("mining session has no ROM");
+        };
+        let report = mine_challenge(ctx, address, &self.response.challenge, &self.options);
+        self.stats.scanned = report.scanned;
+        self.stats.hash_rate = report.hash_rate;
+        self.solution = report.solution;
+        // Only the ROM cache keeps it now, for a next challenge with the same seed
+        self.ctx = None;
+        let next = if self.solution.is_some() { SessionState::Solved } else { SessionState::Expired };
+        self.transition(next)
+    }
+
+    /// What became of the solution: accepted, rejected, dry run...
+    pub fn record_submission(&mut self, outcome: SolutionStatus) -> Result<()> {
+        if self.state != SessionState::Solved {
+            bail!("no solution to submit in a {:?} session", self.state);
+        }
+        self.submission = Some(outcome);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionState::{self, *};
+
+    #[test]
+    fn lifecycle_edges() {
+        let all = [Created, RomReady, Mining, Solved, Expired];
+        let allowed = [
+            (Created, RomReady),
+            (Created, Expired),
+            (RomReady, Mining),
+            (RomReady, Expired),
+            (Mining, Solved),
+            (Mining, Expired),
+        ];
+        for from in all {
+            for to in all {
+                assert_eq!(
+                    from.can_move_to(to),
+                    allowed.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn final_states_stay_final() {
+        let all = [Created, RomReady, Mining, Solved, Expired];
+        for end in [SessionState::Solved, SessionState::Expired] {
+            assert!(!all.iter().any(|&s| end.can_move_to(s)), "{:?}", end);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+// ==================== SHARED MACHINE ====================
+//
+// Mining for friends on one machine: --share adds payout addresses (labels
+// from the address book, or addresses) with weights, and the machine takes
+// turns between them and the address it was started with. Per challenge
+// (the default), each new challenge goes to the address furthest behind its
+// share of the challenges mined since the first one for a friend, counted
+// from the scan history so the split holds across restarts; a challenge
+// already started stays with its address. Per time slice, the whole
+// machine belongs to one address for --share-slice at a time, weight turns
+// in a row per cycle. Either way an
+// address that cannot use a challenge (its allowance for it is used up)
+// passes it on to the next. Scans and solutions are recorded under the
+// address they were mined for, so history and the address book's earnings
+// come out per friend.
+
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::store::Store;
+use crate::{address, addressbook, timefmt, Cli};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ShareBy {
+    /// Each new challenge to the address furthest behind its weight
+    Challenge,
+    /// The whole machine to one address per --share-slice
+    Slice,
+}
+
+/// --share NAME[=WEIGHT]
+pub fn parse(raw: &str) -> Result<(String, u32), String> {
+    let (name, weight) = match raw.rsplit_once('=') {
+        Some((name, weight)) => {
+            let weight = weight.trim().parse::<u32>().ok().filter(|w| (1..=1000).contains(w));
+            match weight {
+                Some(weight) => (name.trim(), weight),
+                None => return Err(format!("'{}': the weight must be 1-1000", raw)),
+            }
+        }
+        None => (raw.trim(), 1),
+    };
+    if name.is_empty() {
+        return Err(format!("'{}' names no address or label", raw));
+    }
+    Ok((name.to_string(), weight))
+}
+
+/// --share-slice: whole minutes at least, so a slice outlasts a ROM build
+pub fn parse_slice(raw: &str) -> Result<Duration, String> {
+    let slice = timefmt::parse_duration(raw)?;
+    if slice < Duration::from_secs(60) {
+        return Err(format!("'{}' is shorter than a minute", raw));
+    }
+    Ok(slice)
+}
+
+#[derive(Debug, Clone)]
+struct Payee {
+    address: String,
+    name: String,
+    weight: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Share {
+    payees: Vec<Payee>,
+    by: ShareBy,
+    slice: Duration,
+}
+
+impl Share {
+    /// The addresses behind --share, plus `main` with weight 1 unless it is
+    /// listed; None without --share
+    pub fn from_cli(cli: &Cli, main: &str) -> Result<Option<Share>> {
+        if cli.share.is_empty() {
+            return Ok(None);
+        }
+        let main_name = cli.label.clone().unwrap_or_else(|| short(main));
+        let mut payees = vec![Payee { address: main.to_string(), name: main_name, weight: 1 }];
+        let mut main_listed = false;
+        for (name, weight) in &cli.share {
+            let (raw, name) = if address::decode(name).is_ok() {
+                (name.clone(), short(name))
+            } else {
+                (addressbook::lookup(&cli.data_dir, name)?, name.clone())
+            };
+            let resolved = match address::resolve(raw.trim(), cli.address_kind) {
+                Ok((resolved, _)) => resolved,
+                Err(e) => bail!("--share {}: {}", name, e),
+            };
+            match payees.iter().position(|p| p.address == resolved) {
+                // The main address, listed to give it a weight of its own
+                Some(0) if !main_listed => {
+                    payees[0].weight = *weight;
+                    main_listed = true;
+                }
+                Some(_) => bail!("--share {}: that address is already sharing", name),
+                None => payees.push(Payee { address: resolved, name, weight: *weight }),
+            }
+        }
+        Ok(Some(Share { payees, by: cli.share_by, slice: cli.share_slice }))
+    }
+
+    pub fn print(&self) {
+        let total: u32 = self.payees.iter().map(|p| p.weight).sum();
+        match self.by {
+            ShareBy::Challenge => say!("🤝 Sharing this machine by challenge:"),
+            ShareBy::Slice => say!(
+                "🤝 Sharing this machine in {} slices:",
+                timefmt::humanize(chrono::Duration::seconds(self.slice.as_secs() as i64))
+            ),
+        }
+        for payee in &self.payees {
+            say!("   {:<16} {}/{}  {}", payee.name, payee.weight, total, payee.address);
+        }
+    }
+
+    /// How the log names `address`
+    pub fn name_of<'a>(&'a self, address: &'a str) -> &'a str {
+        self.payees.iter().find(|p| p.address == address).map_or(address, |p| &p.name)
+    }
+
+    /// The addresses to mine `challenge_id` for, whose turn it is first
+    pub fn order(&self, store: &Store, challenge_id: &str) -> Vec<&str> {
+        let mut order: Vec<usize> = (0..self.payees.len()).collect();
+        match self.by {
+            ShareBy::Challenge => {
+                let scans = store.scans().unwrap_or_else(|e| {
+                    say!("⚠️  Cannot read the scan history: {}", e);
+                    Vec::new()
+                });
+                // What the main address mined alone before sharing doesn't count
+                let since = scans
+                    .iter()
+                    .filter(|s| self.payees[1..].iter().any(|p| p.address == s.address))
+                    .map(|s| s.recorded_at.as_str())
+                    .min()
+                    .unwrap_or("~");
+                let served: Vec<usize> = self
+                    .payees
+                    .iter()
+                    .map(|p| {
+                        let mined = scans.iter().filter(|s| {
+                            s.address == p.address && s.recorded_at.as_str() >= since
+                        });
+                        mined.map(|s| s.challenge_id.as_str()).collect::<HashSet<_>>().len()
+                    })
+                    .collect();
+                let weight = |i: usize| self.payees[i].weight as usize;
+                // Fewest challenges per unit of weight first, the heavier on a tie
+                order.sort_by(|&a, &b| {
+                    let behind = (served[a] * weight(b)).cmp(&(served[b] * weight(a)));
+                    behind.then(weight(b).cmp(&weight(a)))
+                });
+                let started = scans
+                    .iter()
+                    .rev()
+                    .filter(|s| s.challenge_id == challenge_id)
+                    .find_map(|s| self.payees.iter().position(|p| p.address == s.address));
+                if let Some(owner) = started {
+                    order.retain(|&i| i != owner);
+                    order.insert(0, owner);
+                }
+            }
+            ShareBy::Slice => {
+                let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+                let slice = now.unwrap_or_default().as_secs() / self.slice.as_secs().max(1);
+                let total: u64 = self.payees.iter().map(|p| p.weight as u64).sum();
+                let mut turn = slice % total;
+                let current = self
+                    .payees
+                    .iter()
+                    .position(|p| {
+                        let mine = turn < p.weight as u64;
+                        turn = turn.saturating_sub(p.weight as u64);
+                        mine
+                    })
+                    .unwrap_or(0);
+                order.rotate_left(current);
+            }
+        }
+        order.into_iter().map(|i| self.payees[i].address.as_str()).collect()
+    }
+}
+
+/// An address short enough for a log line
+fn short(address: &str) -> String {
+    match address.char_indices().nth(16) {
+        Some((end, _)) => format!("{}…", &address[..end]),
+        None => address.to_string(),
+    }
+}
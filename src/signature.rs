@@ -0,0 +1,645 @@
+// ==================== SIGNATURE INPUT ====================
+//
+// Wallets hand back signData results in several shapes: the COSE_Sign1 /
+// COSE_Key hex pair, the whole object (JSON or as the console prints it),
+// a raw 32-byte key, an ed25519_pk1 bech32 key, with or without 0x and
+// quotes. /register wants the COSE_Sign1 hex and the raw key hex; turn
+// whatever was pasted into that, or say exactly what is wrong with it
+// before the server answers with a bare 400.
+
+use crate::address;
+
+/// A tiny CBOR reader, enough for COSE structures
+#[derive(Debug, Clone, PartialEq)]
+enum Cbor {
+    Uint(u64),
+    Nint(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+    Tag(u64, Box<Cbor>),
+    Simple(u8),
+}
+
+const MAX_DEPTH: usize = 16;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], String> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or("CBOR ends early (truncated paste?)")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn argument(&mut self, info: u8) -> Result<u64, String> {
+        Ok(match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            _ => return Err("indefinite-length CBOR is not supported".to_string()),
+        })
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Cbor, String> {
+        if depth > MAX_DEPTH {
+            return Err("CBOR nested too deeply".to_string());
+        }
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            return Ok(Cbor::Simple(info));
+        }
+        let arg = self.argument(info)?;
+        // Every element takes at least a byte, so longer counts are garbage
+        let count = usize::try_from(arg)
+            .ok()
+            .filter(|&n| n <= self.bytes.len())
+            .ok_or("CBOR length is larger than the input");
+        Ok(match major {
+            0 => Cbor::Uint(arg),
+            1 => Cbor::Nint(arg),
+            2 => Cbor::Bytes(self.take(count?)?.to_vec()),
+            3 => Cbor::Text(
+                String::from_utf8(self.take(count?)?.to_vec()).map_err(|_| "CBOR text is not UTF-8")?,
+            ),
+            4 => Cbor::Array((0..count?).map(|_| self.item(depth + 1)).collect::<Result<_, _>>()?),
+            5 => Cbor::Map(
+                (0..count?)
+                    .map(|_| Ok((self.item(depth + 1)?, self.item(depth + 1)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            _ => Cbor::Tag(arg, Box::new(self.item(depth + 1)?)),
+        })
+    }
+}
+
+fn parse_cbor(bytes: &[u8]) -> Result<Cbor, String> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let item = reader.item(0)?;
+    if reader.pos != bytes.len() {
+        return Err(format!("{} stray byte(s) after the CBOR value", bytes.len() - reader.pos));
+    }
+    Ok(item)
+}
+
+fn map_get<'a>(map: &'a [(Cbor, Cbor)], key: &Cbor) -> Option<&'a Cbor> {
+    map.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+/// Strip quotes, 0x, whitespace; decode hex with a precise complaint
+fn clean_hex(raw: &str, what: &str) -> Result<Vec<u8>, String> {
+    let text: String = raw
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let text = text.strip_prefix("0x").unwrap_or(&text);
+    if text.is_empty() {
+        return Err(format!("{} is empty", what));
+    }
+    if let Some(bad) = text.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("{} contains '{}', which is not hex", what, bad));
+    }
+    if text.len() % 2 == 1 {
+        return Err(format!("{} has an odd number of hex digits (cut off while copying?)", what));
+    }
+    Ok(hex::decode(text).expect("checked hex"))
+}
+
+/// `name: "..."` out of a pasted object, JSON or console notation
+fn field(raw: &str, name: &str) -> Option<String> {
+    if let Ok(serde_json::Value::Object(object)) = serde_json::from_str(raw.trim()) {
+        return object.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    }
+    let after = &raw[raw.find(name)? + name.len()..];
+    let open = after.find(['"', '\''])?;
+    let quote = after[open..].chars().next()?;
+    let value = &after[open + 1..];
+    Some(value[..value.find(quote)?].to_string())
+}
+
+/// What the user pasted at the signature prompt
+#[derive(Debug)]
+pub struct PastedSignature {
+    /// COSE_Sign1, hex
+    pub signature: String,
+    /// Raw public key, hex, when the whole signData object was pasted
+    pub pubkey: Option<String>,
+}
+
+pub fn parse_signature(raw: &str) -> Result<PastedSignature, String> {
+    let (signature, pubkey) = match field(raw, "signature") {
+        Some(signature) => {
+            let key = field(raw, "key").map(|key| parse_pubkey(&key)).transpose()?;
+            (signature, key)
+        }
+        None => (raw.to_string(), None),
+    };
+    let bytes = clean_hex(&signature, "signature")?;
+    if bytes.len() == 64 {
+        return Err("that is a bare 64-byte signature; paste the full COSE_Sign1 \
+                    (signed.signature from signData)"
+            .to_string());
+    }
+    let cbor = parse_cbor(&bytes).map_err(|e| format!("signature is not valid CBOR: {}", e))?;
+    // COSE_Sign1 is a 4-element array, optionally under tag 18
+    let parts = match cbor {
+        Cbor::Tag(18, inner) => *inner,
+        other => other,
+    };
+    let Cbor::Array(parts) = parts else {
+        return Err("signature is CBOR but not a COSE_Sign1 array; did you paste the key instead?".to_string());
+    };
+    let [Cbor::Bytes(_), _, _, Cbor::Bytes(sig)] = parts.as_slice() else {
+        return Err(format!("signature has {} parts, a COSE_Sign1 has 4", parts.len()));
+    };
+    if sig.len() != 64 {
+        return Err(format!("COSE_Sign1 carries a {}-byte signature, expected 64", sig.len()));
+    }
+    Ok(PastedSignature { signature: hex::encode(&bytes), pubkey })
+}
+
+/// Raw ed25519 key hex from raw hex, a COSE_Key or an ed25519_pk1 bech32 key
+pub fn parse_pubkey(raw: &str) -> Result<String, String> {
+    let trimmed = raw.trim().trim_matches(|c| c == '"' || c == '\'');
+    if trimmed.starts_with("ed25519_pk1") {
+        let (_, bytes) = address::decode(trimmed).map_err(|e| format!("public key: {}", e))?;
+        if bytes.len() != 32 {
+            return Err(format!("public key is {} bytes, expected 32", bytes.len()));
+        }
+        return Ok(hex::encode(bytes));
+    }
+    let bytes = clean_hex(trimmed, "public key")?;
+    if bytes.len() == 32 {
+        return Ok(hex::encode(bytes));
+    }
+    match parse_cbor(&bytes) {
+        // COSE_Key: the key itself is parameter -2 (x)
+        Ok(Cbor::Map(key)) => match map_get(&key, &Cbor::Nint(1)) {
+            Some(Cbor::Bytes(x)) if x.len() == 32 => Ok(hex::encode(x)),
+            _ => Err("public key is a COSE_Key without a 32-byte ed25519 key".to_string()),
+        },
+        Ok(Cbor::Bytes(x)) if x.len() == 32 => Ok(hex::encode(x)),
+        _ if bytes.len() == 64 => Err("that looks like a 64-byte extended key or a signature; \
+                                       paste signed.key from signData"
+            .to_string()),
+        _ => Err(format!("public key is {} bytes, expected 32 (or a COSE_Key)", bytes.len())),
+    }
+}
+
+// ==================== LOCAL VERIFICATION ====================
+//
+// The server only answers "invalid signature", and every failed attempt
+// is a request against the registration endpoint. Redo its checks here
+// and say which one fails: the address the wallet signed as, whether the
+// key belongs to that address, what exactly was signed, and finally the
+// ed25519 signature itself over the COSE Sig_structure.
+
+pub fn blake2b_224(bytes: &[u8]) -> [u8; 28] {
+    use blake2::digest::consts::U28;
+    use blake2::{Blake2b, Digest};
+    Blake2b::<U28>::digest(bytes).into()
+}
+
+/// Payment (or stake, for reward addresses) key hash of an address
+fn key_hash(address: &[u8]) -> Option<&[u8]> {
+    let kind = address.first()? >> 4;
+    let keyed = (kind <= 7 && kind % 2 == 0) || kind == 14;
+    address.get(1..29).filter(|_| keyed)
+}
+
+fn cbor_head(major: u8, len: usize) -> Vec<u8> {
+    let major = major << 5;
+    match len {
+        0..=23 => vec![major | len as u8],
+        24..=0xff => vec![major | 24, len as u8],
+        0x100..=0xffff => [&[major | 25][..], &(len as u16).to_be_bytes()].concat(),
+        _ => [&[major | 26][..], &(len as u32).to_be_bytes()].concat(),
+    }
+}
+
+/// ["Signature1", protected, external_aad = h'', payload], CBOR encoded
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut out = cbor_head(4, 4);
+    out.extend(cbor_head(3, 10));
+    out.extend(b"Signature1");
+    out.extend(cbor_head(2, protected.len()));
+    out.extend(protected);
+    out.extend(cbor_head(2, 0));
+    out.extend(cbor_head(2, payload.len()));
+    out.extend(payload);
+    out
+}
+
+/// What was signed instead of the message, if anything
+fn payload_problem(payload: &[u8], hashed: bool, message: &str) -> Option<String> {
+    if payload == message.as_bytes() {
+        return None;
+    }
+    if hashed || payload == blake2b_224(message.as_bytes()) {
+        return Some("the wallet signed a hash of the message (hashed: true); \
+                     the server expects the message itself"
+            .to_string());
+    }
+    if payload == hex::encode(message).as_bytes() {
+        return Some("the message was hex encoded twice before signing".to_string());
+    }
+    Some(match std::str::from_utf8(payload) {
+        Ok(text) => {
+            let shown: String = text.chars().take(60).collect();
+            format!("the wallet signed a different message: \"{}\"", shown)
+        }
+        Err(_) => format!("the wallet signed {} bytes that are not the message", payload.len()),
+    })
+}
+
+/// Everything the server would reject about this signature; empty when it
+/// should register. `signature` and `pubkey` are hex as parse_* return them.
+pub fn verify(signature: &str, pubkey: &str, message: &str, address: Option<&[u8]>) -> Vec<String> {
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    let parts = match hex::decode(signature).map_err(|e| e.to_string()).and_then(|b| parse_cbor(&b)) {
+        Ok(Cbor::Tag(18, inner)) => *inner,
+        Ok(other) => other,
+        Err(e) => return vec![format!("signature is unreadable: {}", e)],
+    };
+    let Cbor::Array(parts) = parts else {
+        return vec!["signature is not a COSE_Sign1".to_string()];
+    };
+    let [Cbor::Bytes(protected), unprotected, payload, Cbor::Bytes(sig)] = parts.as_slice() else {
+        return vec!["signature is not a COSE_Sign1".to_string()];
+    };
+    let key: Option<[u8; 32]> = hex::decode(pubkey).ok().and_then(|k| k.try_into().ok());
+    let Some(key) = key else {
+        return vec!["public key is not 32 bytes".to_string()];
+    };
+
+    let mut problems = Vec::new();
+    let header = match parse_cbor(protected) {
+        Ok(Cbor::Map(header)) => header,
+        _ => Vec::new(),
+    };
+    let signed_address = match map_get(&header, &Cbor::Text("address".to_string())) {
+        Some(Cbor::Bytes(signed)) => Some(signed.as_slice()),
+        _ => None,
+    };
+    if let (Some(signed), Some(address)) = (signed_address, address) {
+        if signed != address {
+            problems.push(format!(
+                "the wallet signed as {}, not the mining address \
+                 (another account or address index is selected)",
+                address::from_bytes(signed)
+            ));
+        }
+    }
+    if let Some(expected) = address.and_then(key_hash) {
+        let actual = blake2b_224(&key);
+        if actual != expected {
+            problems.push(format!(
+                "the public key hashes to {} but the mining address expects {} \
+                 (the key is for a different address index)",
+                hex::encode(actual),
+                hex::encode(expected)
+            ));
+        }
+    }
+
+    let hashed = matches!(
+        unprotected,
+        Cbor::Map(header) if map_get(header, &Cbor::Text("hashed".to_string())) == Some(&Cbor::Simple(21))
+    );
+    // A detached (null) payload means the message travels separately
+    let payload = match payload {
+        Cbor::Bytes(payload) => payload.as_slice(),
+        _ => message.as_bytes(),
+    };
+    problems.extend(payload_problem(payload, hashed, message));
+
+    let signature = <[u8; 64]>::try_from(sig.as_slice()).map(|sig| Signature::from_bytes(&sig));
+    let valid = match (VerifyingKey::from_bytes(&key), signature) {
+        (Ok(key), Ok(signature)) => key.verify(&sig_structure(protected, payload), &signature).is_ok(),
+        _ => false,
+    };
+    if !valid {
+        problems.push(
+            "the ed25519 signature does not verify with this public key \
+             (key from another wallet, or the signature was altered)"
+                .to_string(),
+        );
+    }
+    problems
+}
+
+// ==================== LOCAL SIGNING ====================
+//
+// With the signing key on disk there is no need for a browser at all.
+// cardano-cli writes keys as a text envelope whose cborHex is a CBOR byte
+// string: the 32-byte seed for a plain key, or for an extended (BIP32)
+// key the 64-byte expanded secret, the public key and the chain code.
+// Signing builds the COSE_Sign1 a CIP-30 wallet's signData returns:
+// protected header {alg: EdDSA, "address": address bytes}, unprotected
+// {"hashed": false} and the message itself as payload, with the ed25519
+// signature over the same Sig_structure `verify` checks.
+
+/// A signing key from a cardano-cli .skey file
+pub struct SigningKey {
+    secret: ed25519_dalek::hazmat::ExpandedSecretKey,
+    public: ed25519_dalek::VerifyingKey,
+}
+
+impl SigningKey {
+    /// The key in a text envelope as cardano-cli writes it
+    pub fn parse(raw: &str) -> Result<SigningKey, String> {
+        use ed25519_dalek::hazmat::ExpandedSecretKey;
+
+        let envelope: serde_json::Value = serde_json::from_str(raw)
+            .map_err(|_| "not a cardano-cli key file (JSON)".to_string())?;
+        let kind = envelope["type"].as_str().unwrap_or_default();
+        if kind.contains("VerificationKey") {
+            return Err(format!("that is a verification key ({}); pass the .skey", kind));
+        }
+        if !kind.contains("SigningKey") {
+            return Err(format!("'{}' is not a signing key type", kind));
+        }
+        let cbor_hex = envelope["cborHex"].as_str().ok_or("the key file has no cborHex")?;
+        let Ok(Cbor::Bytes(bytes)) = parse_cbor(&clean_hex(cbor_hex, "cborHex")?) else {
+            return Err("cborHex is not a CBOR byte string".to_string());
+        };
+        match bytes.len() {
+            32 => {
+                let seed: [u8; 32] = bytes.try_into().expect("checked length");
+                let signing = ed25519_dalek::SigningKey::from_bytes(&seed);
+                Ok(SigningKey {
+                    secret: ExpandedSecretKey::from(&seed),
+                    public: signing.verifying_key(),
+                })
+            }
+            // kL, kR, public key, chain code
+            128 => {
+                let extended: [u8; 64] = bytes[..64].try_into().expect("checked length");
+                let public = <[u8; 32]>::try_from(&bytes[64..96]).expect("checked length");
+                SigningKey::extended(&extended, &public)
+            }
+            n => Err(format!("the key is {} bytes, expected 32 or 128 (extended)", n)),
+        }
+    }
+
+    /// An extended (BIP32-Ed25519) key, kL then kR, and its public key. kL is
+    /// used as is: derived keys are not clamped the way a hashed seed is
+    pub fn extended(secret: &[u8; 64], public: &[u8; 32]) -> Result<SigningKey, String> {
+        let scalar: [u8; 32] = secret[..32].try_into().expect("64 bytes");
+        let secret = ed25519_dalek::hazmat::ExpandedSecretKey {
+            scalar: curve25519_dalek::Scalar::from_bytes_mod_order(scalar),
+            hash_prefix: secret[32..].try_into().expect("64 bytes"),
+        };
+        let public = ed25519_dalek::VerifyingKey::from_bytes(public)
+            .map_err(|_| "the extended key's public key is invalid".to_string())?;
+        Ok(SigningKey { secret, public })
+    }
+
+    /// Raw public key, hex, as /register wants it
+    pub fn pubkey(&self) -> String {
+        hex::encode(self.public.as_bytes())
+    }
+
+    /// COSE_Sign1 over `message` for `address` (raw address bytes), hex
+    pub fn sign(&self, message: &str, address: &[u8]) -> String {
+        let protected = protected_header(address);
+        let signature = ed25519_dalek::hazmat::raw_sign::<sha2::Sha512>(
+            &self.secret,
+            &sig_structure(&protected, message.as_bytes()),
+            &self.public,
+        );
+        cose_sign1(message, address, &signature.to_bytes())
+    }
+}
+
+/// {1 (alg): -8 (EdDSA), "address": address bytes}
+fn protected_header(address: &[u8]) -> Vec<u8> {
+    let mut protected = cbor_head(5, 2);
+    protected.extend([0x01, 0x27]);
+    protected.extend(cbor_head(3, 7));
+    protected.extend(b"address");
+    protected.extend(cbor_head(2, address.len()));
+    protected.extend(address);
+    protected
+}
+
+/// The COSE_Sign1 for a signature made over the Sig_structure of the
+/// header `sign` writes (by a key here or on a device), hex
+pub fn cose_sign1(message: &str, address: &[u8], signature: &[u8]) -> String {
+    let protected = protected_header(address);
+    let payload = message.as_bytes();
+    let mut out = cbor_head(4, 4);
+    out.extend(cbor_head(2, protected.len()));
+    out.extend(&protected);
+    out.extend(cbor_head(5, 1));
+    out.extend(cbor_head(3, 6));
+    out.extend(b"hashed");
+    out.push(0xf4); // false
+    out.extend(cbor_head(2, payload.len()));
+    out.extend(payload);
+    out.extend(cbor_head(2, signature.len()));
+    out.extend(signature);
+    hex::encode(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "I agree to the terms";
+
+    /// A cardano-cli .skey with a fixed seed
+    fn key() -> SigningKey {
+        let envelope = serde_json::json!({
+            "type": "PaymentSigningKeyShelley_ed25519",
+            "description": "Payment Signing Key",
+            "cborHex": format!("5820{}", "42".repeat(32)),
+        });
+        SigningKey::parse(&envelope.to_string()).unwrap()
+    }
+
+    /// Mainnet enterprise address of `key`
+    fn address_of(key: &SigningKey) -> Vec<u8> {
+        let pubkey = hex::decode(key.pubkey()).unwrap();
+        [&[0x61][..], &blake2b_224(&pubkey)].concat()
+    }
+
+    /// A COSE_Sign1 with `unprotected` and `payload` as given, signed over them
+    fn cose_with(key: &SigningKey, address: &[u8], unprotected: &[u8], payload: &[u8]) -> String {
+        let protected = protected_header(address);
+        let signature = ed25519_dalek::hazmat::raw_sign::<sha2::Sha512>(
+            &key.secret,
+            &sig_structure(&protected, payload),
+            &key.public,
+        );
+        let mut out = cbor_head(4, 4);
+        out.extend(cbor_head(2, protected.len()));
+        out.extend(&protected);
+        out.extend(unprotected);
+        out.extend(cbor_head(2, payload.len()));
+        out.extend(payload);
+        out.extend(cbor_head(2, 64));
+        out.extend(signature.to_bytes());
+        hex::encode(out)
+    }
+
+    #[test]
+    fn signed_here_verifies() {
+        let key = key();
+        let address = address_of(&key);
+        let signature = key.sign(MESSAGE, &address);
+        assert_eq!(verify(&signature, &key.pubkey(), MESSAGE, Some(&address)), Vec::<String>::new());
+        // What the signing page and --signature read back is the same thing
+        let pasted = parse_signature(&signature).unwrap();
+        assert_eq!(pasted.signature, signature);
+        assert!(pasted.pubkey.is_none());
+    }
+
+    #[test]
+    fn tampered_payload_is_caught() {
+        let key = key();
+        let address = address_of(&key);
+        let signature = key.sign(MESSAGE, &address);
+        let tampered = signature.replace(&hex::encode("terms"), &hex::encode("tErms"));
+        assert_ne!(tampered, signature);
+        let problems = verify(&tampered, &key.pubkey(), MESSAGE, Some(&address));
+        assert!(problems.iter().any(|p| p.contains("different message")), "{:?}", problems);
+        assert!(problems.iter().any(|p| p.contains("does not verify")), "{:?}", problems);
+        // Signed properly, but not the message asked for
+        let problems = verify(&signature, &key.pubkey(), "I agree", Some(&address));
+        assert!(problems.iter().any(|p| p.contains("different message")), "{:?}", problems);
+    }
+
+    #[test]
+    fn other_address_in_the_header_is_caught() {
+        let key = key();
+        let address = address_of(&key);
+        let mut other = address.clone();
+        other[0] = 0x60; // the same key, on testnet
+        let signature = key.sign(MESSAGE, &other);
+        let problems = verify(&signature, &key.pubkey(), MESSAGE, Some(&address));
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("the wallet signed as addr_test1"), "{:?}", problems);
+        // A key that is not the address's payment key
+        let mut stranger = address.clone();
+        stranger[5] ^= 1;
+        let problems = verify(&key.sign(MESSAGE, &stranger), &key.pubkey(), MESSAGE, Some(&stranger));
+        assert!(problems.iter().any(|p| p.contains("public key hashes to")), "{:?}", problems);
+    }
+
+    #[test]
+    fn hashed_payload_is_caught() {
+        let key = key();
+        let address = address_of(&key);
+        // {"hashed": true}, payload blake2b-224 of the message
+        let hashed = [cbor_head(5, 1), cbor_head(3, 6), b"hashed".to_vec(), vec![0xf5]].concat();
+        let digest = blake2b_224(MESSAGE.as_bytes());
+        let signature = cose_with(&key, &address, &hashed, &digest);
+        let problems = verify(&signature, &key.pubkey(), MESSAGE, Some(&address));
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("hashed: true"), "{:?}", problems);
+        // The same layout with the message itself is fine
+        let plain = [cbor_head(5, 1), cbor_head(3, 6), b"hashed".to_vec(), vec![0xf4]].concat();
+        let signature = cose_with(&key, &address, &plain, MESSAGE.as_bytes());
+        assert!(verify(&signature, &key.pubkey(), MESSAGE, Some(&address)).is_empty());
+    }
+
+    #[test]
+    fn pubkey_in_every_shape() {
+        let raw = key().pubkey();
+        let cose_key = format!("a4010103272006215820{}", raw);
+        assert_eq!(parse_pubkey(&cose_key).unwrap(), raw);
+        assert_eq!(parse_pubkey(&raw).unwrap(), raw);
+        assert_eq!(parse_pubkey(&format!("\"0x{}\"", raw.to_uppercase())).unwrap(), raw);
+        let bech32 = address::encode("ed25519_pk", &hex::decode(&raw).unwrap());
+        assert!(bech32.starts_with("ed25519_pk1"));
+        assert_eq!(parse_pubkey(&bech32).unwrap(), raw);
+        // The signature where the key should be, and a short key
+        assert!(parse_pubkey(&"ab".repeat(64)).unwrap_err().contains("64-byte"));
+        assert!(parse_pubkey(&raw[..62]).is_err());
+        // A COSE_Key whose x is missing
+        assert!(parse_pubkey("a201010327").unwrap_err().contains("COSE_Key"));
+    }
+
+    #[test]
+    fn whole_sign_data_object_is_read() {
+        let key = key();
+        let signature = key.sign(MESSAGE, &address_of(&key));
+        let object = serde_json::json!({
+            "signature": signature,
+            "key": format!("a4010103272006215820{}", key.pubkey()),
+        });
+        let pasted = parse_signature(&object.to_string()).unwrap();
+        assert_eq!(pasted.signature, signature);
+        assert_eq!(pasted.pubkey.unwrap(), key.pubkey());
+        // As the browser console prints it
+        let console = format!("{{signature: '{}', key: '{}'}}", signature, key.pubkey());
+        assert_eq!(parse_signature(&console).unwrap().pubkey.unwrap(), key.pubkey());
+    }
+
+    /// signData's answer for a base address, laid out as CIP-30 wallets
+    /// return it (COSE_Sign1 with {"hashed": false}, COSE_Key), encoded and
+    /// signed outside this crate (Python's `cryptography`), so the reader
+    /// and checks here are not only tested against their own writer
+    const SIGN_DATA: &str = include_str!("../tests/fixtures/sign_data.json");
+
+    #[test]
+    fn wallet_sign_data_parses_and_verifies() {
+        let fixture: serde_json::Value = serde_json::from_str(SIGN_DATA).unwrap();
+        let message = fixture["message"].as_str().unwrap();
+        let address = hex::decode(fixture["address"].as_str().unwrap()).unwrap();
+        // Pasted whole, as --signature takes it
+        let pasted = parse_signature(SIGN_DATA).unwrap();
+        let pubkey = pasted.pubkey.unwrap();
+        assert_eq!(pubkey, "1ad9a97f0b1cf98ae9299e95a970f749b35f857c30677df681a0fc4c36a01a67");
+        assert!(verify(&pasted.signature, &pubkey, message, Some(&address)).is_empty());
+        // Or as the two hex strings, --signature and --pubkey
+        let signature = parse_signature(fixture["signature"].as_str().unwrap()).unwrap();
+        let key = parse_pubkey(fixture["key"].as_str().unwrap()).unwrap();
+        assert_eq!((&signature.signature, &key), (&pasted.signature, &pubkey));
+        assert!(address::check(&address::from_bytes(&address)).is_ok());
+        // The message is what the signature holds
+        assert!(!verify(&signature.signature, &key, "another message", Some(&address)).is_empty());
+    }
+
+    #[test]
+    fn broken_cbor_is_an_error_not_a_panic() {
+        let key = key();
+        let signature = key.sign(MESSAGE, &address_of(&key));
+        for cut in [2, 10, signature.len() / 2, signature.len() - 2] {
+            assert!(parse_signature(&signature[..cut]).is_err(), "cut at {}", cut);
+            assert!(!verify(&signature[..cut], &key.pubkey(), MESSAGE, None).is_empty());
+        }
+        let garbage = [
+            "ff",
+            "1f",
+            "5bffffffffffffffff00",
+            "9bffffffffffffffff",
+            "bf",
+            "6280ff",
+            "8401020304",
+            &"81".repeat(40),
+        ];
+        for garbage in garbage {
+            assert!(parse_signature(garbage).is_err(), "{}", garbage);
+            assert!(parse_pubkey(garbage).is_err(), "{}", garbage);
+            assert!(!verify(garbage, &key.pubkey(), MESSAGE, None).is_empty(), "{}", garbage);
+        }
+        assert!(parse_signature("84zz").unwrap_err().contains("not hex"));
+        assert!(parse_signature("845").unwrap_err().contains("odd number"));
+        assert!(SigningKey::parse("{\"type\":\"PaymentSigningKeyShelley_ed25519\",\"cborHex\":\"58\"}")
+            .is_err());
+    }
+}
@@ -0,0 +1,241 @@
+// ==================== SIGNING PAGE ====================
+//
+// Registration needs a CIP-30 signData signature. Pasting a snippet into
+// the devtools console and copying two hex blobs back was where most
+// registrations went wrong, so the miner serves a one-off page on
+// localhost instead: it lists the wallet extensions the browser has, signs
+// with the one picked and posts the result straight back here. The page
+// speaks English and Vietnamese.
+//
+// Details the page relies on: browsers have no Buffer, so the message is
+// hex encoded with TextEncoder; the address is the mining address itself
+// as hex bytes (CIP-30's format), so the signature is for the address that
+// gets registered; and the result is verified here (signature::verify)
+// before the page says "signed", so a wrong wallet shows up in the
+// browser, not later as a 400.
+//
+// The URL carries a random token so other local pages cannot post to it.
+
+use anyhow::{anyhow, Context, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use crate::{signature, wallets::Wallet};
+
+const PAGE: &str = include_str!("signpage.html");
+const MAX_REQUEST: usize = 64 * 1024;
+
+/// A connection that opens and sends nothing (a browser preconnect) is
+/// dropped after this
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Signed {
+    /// COSE_Sign1, hex
+    pub signature: String,
+    /// Raw public key, hex
+    pub pubkey: String,
+}
+
+/// 128 bits from the OS random source
+fn token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| anyhow!("No randomness for the session token: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+fn page(
+    message: &str,
+    address: &str,
+    address_hex: Option<&str>,
+    wallet: Option<Wallet>,
+    result: &str,
+) -> String {
+    let config = serde_json::json!({
+        "message": message,
+        "address": address,
+        "addressHex": address_hex,
+        "preferred": wallet.and_then(Wallet::key),
+        "result": result,
+    });
+    // Keep "</script>" in the message from closing the script early
+    PAGE.replace("/*CONFIG*/", &config.to_string().replace("</", "<\\/"))
+}
+
+/// Best effort; the URL is printed either way
+fn open_browser(url: &str) {
+    use std::process::{Command, Stdio};
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", "", url]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg(url);
+        command
+    } else {
+        let mut command = Command::new("xdg-open");
+        command.arg(url);
+        command
+    };
+    let _ = command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        anyhow::ensure!(n > 0, "connection closed mid-request");
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        anyhow::ensure!(buf.len() < MAX_REQUEST, "request headers too large");
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    anyhow::ensure!(length <= MAX_REQUEST, "request body too large");
+    while buf.len() < header_end + length {
+        let n = stream.read(&mut chunk).await?;
+        anyhow::ensure!(n > 0, "connection closed mid-request");
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body = String::from_utf8_lossy(&buf[header_end..header_end + length]).to_string();
+    Ok(Request { method, path, body })
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Turn what the page posted into a signature of `message` by `address`
+fn check(body: &str, message: &str, address: Option<&[u8]>) -> Result<Signed, String> {
+    let posted: serde_json::Value =
+        serde_json::from_str(body).map_err(|e| format!("unreadable result: {}", e))?;
+    if let Some(error) = posted["error"].as_str() {
+        return Err(format!("wallet error: {}", error));
+    }
+    let pasted = signature::parse_signature(body)?;
+    let pubkey = pasted.pubkey.ok_or("the wallet returned no public key")?;
+    let problems = signature::verify(&pasted.signature, &pubkey, message, address);
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    Ok(Signed { signature: pasted.signature, pubkey })
+}
+
+/// What every connection to the page is answered from
+struct Session {
+    html: String,
+    page_path: String,
+    result_path: String,
+    message: String,
+    address: Option<Vec<u8>>,
+}
+
+/// Answer one connection; the signature once it posts a usable one
+async fn answer(mut stream: TcpStream, session: &Session) -> Result<Option<Signed>> {
+    let request = read_request(&mut stream).await?;
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", path) if path == session.page_path => {
+            respond(&mut stream, "200 OK", "text/html; charset=utf-8", &session.html).await?;
+            Ok(None)
+        }
+        ("POST", path) if path == session.result_path => {
+            let (answer, signed) =
+                match check(&request.body, &session.message, session.address.as_deref()) {
+                    Ok(signed) => (serde_json::json!({ "accepted": true }), Some(signed)),
+                    Err(error) => {
+                        say!("❌ Signing page: {}", error);
+                        (serde_json::json!({ "accepted": false, "error": error }), None)
+                    }
+                };
+            // The signature is good even if the tab went away before the answer
+            let _ = respond(&mut stream, "200 OK", "application/json", &answer.to_string()).await;
+            Ok(signed)
+        }
+        _ => {
+            respond(&mut stream, "404 Not Found", "text/plain", "not found").await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Serve the page until it posts a usable signature (or Ctrl+C)
+pub async fn sign(
+    message: &str,
+    address: &str,
+    address_bytes: Option<&[u8]>,
+    wallet: Option<Wallet>,
+) -> Result<Signed> {
+    let address_hex = address_bytes.map(hex::encode);
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Cannot open a local port for the signing page")?;
+    let port = listener.local_addr()?.port();
+    let token = token()?;
+    let (page_path, result_path) = (format!("/{}", token), format!("/{}/result", token));
+    let url = format!("http://127.0.0.1:{}{}", port, page_path);
+    let session = Arc::new(Session {
+        html: page(message, address, address_hex.as_deref(), wallet, &result_path),
+        page_path,
+        result_path,
+        message: message.to_string(),
+        address: address_bytes.map(<[u8]>::to_vec),
+    });
+
+    say!("\n🔐 Open this page in the browser that has your wallet extension:");
+    say!("   {}", url);
+    say!("   (On a remote machine: ssh -L {}:127.0.0.1:{} <host>, then open it locally)", port, port);
+    say!("   Waiting for the signature... Ctrl+C to give up");
+    open_browser(&url);
+
+    // Each connection on its own task, so one that never sends holds up
+    // neither the page nor Ctrl+C
+    let (found, mut received) = mpsc::channel(1);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                let (session, found) = (session.clone(), found.clone());
+                tokio::spawn(async move {
+                    let answered = tokio::time::timeout(REQUEST_TIMEOUT, answer(stream, &session));
+                    if let Ok(Ok(Some(signed))) = answered.await {
+                        let _ = found.send(signed).await;
+                    }
+                });
+            }
+            Some(signed) = received.recv() => {
+                say!("✅ Signature received from the browser");
+                return Ok(signed);
+            }
+            _ = tokio::signal::ctrl_c() => anyhow::bail!("Signing cancelled"),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// ==================== SPOT INTERRUPTION ====================
+//
+// Spot/preemptible VMs get a short warning before they are reclaimed
+// (two minutes on AWS, about thirty seconds on GCP and Azure). Poll the
+// cloud's metadata service and, once a notice shows up, raise a flag and
+// cancel the supervisor's token so the round can record its scanned range, submit what it
+// has, and the state can be pushed for the replacement instance.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use reqwest::{Client, StatusCode};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Cloud {
+    Aws,
+    Gcp,
+    Azure,
+}
+
+static TERMINATION_PENDING: AtomicBool = AtomicBool::new(false);
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+const AWS_METADATA: &str = "http://169.254.169.254/latest";
+const GCP_METADATA: &str = "http://metadata.google.internal/computeMetadata/v1";
+const AZURE_METADATA: &str = "http://169.254.169.254/metadata";
+
+/// True once the cloud has announced that this instance is going away
+pub fn termination_pending() -> bool {
+    TERMINATION_PENDING.load(Ordering::SeqCst)
+}
+
+/// Poll the metadata service until a termination notice appears
+pub async fn watch(cloud: Cloud, cancel: CancellationToken) {
+    // Link-local endpoints: never go through a proxy, never wait long
+    let client = match Client::builder()
+        .timeout(Duration::from_secs(2))
+        .no_proxy()
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            say!("⚠️  Spot watcher disabled: {}", e);
+            return;
+        }
+    };
+
+    let mut warned = false;
+    loop {
+        match check(&client, cloud).await {
+            Ok(Some(notice)) => {
+                TERMINATION_PENDING.store(true, Ordering::SeqCst);
+                say!("\n⚠️  {:?} termination notice: {}", cloud, notice);
+                say!("   Stopping to checkpoint, submit and push state");
+                cancel.cancel();
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                if !warned {
+                    say!("⚠️  Cannot reach the {:?} metadata service: {}", cloud, e);
+                    warned = true;
+                }
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = cancel.cancelled() => return,
+        }
+    }
+}
+
+/// Some(description) when termination is scheduled
+async fn check(client: &Client, cloud: Cloud) -> Result<Option<String>> {
+    match cloud {
+        Cloud::Aws => {
+            // IMDSv2: session token first
+            let token = client
+                .put(format!("{}/api/token", AWS_METADATA))
+                .header("X-aws-ec2-metadata-token-ttl-seconds", "300")
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            let response = client
+                .get(format!("{}/meta-data/spot/instance-action", AWS_METADATA))
+                .header("X-aws-ec2-metadata-token", token)
+                .send()
+                .await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            Ok(Some(response.error_for_status()?.text().await?))
+        }
+        Cloud::Gcp => {
+            let body = client
+                .get(format!("{}/instance/preempted", GCP_METADATA))
+                .header("Metadata-Flavor", "Google")
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            Ok((body.trim() == "TRUE").then(|| "instance preempted".to_string()))
+        }
+        Cloud::Azure => {
+            let events: serde_json::Value = client
+                .get(format!("{}/scheduledevents?api-version=2020-07-01", AZURE_METADATA))
+                .header("Metadata", "true")
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let notice = events["Events"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|e| matches!(e["EventType"].as_str(), Some("Preempt" | "Terminate")));
+            Ok(notice.map(|e| format!("{} not before {}", e["EventType"], e["NotBefore"])))
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// ==================== STATUS FILE ====================
+//
+// What the miner is doing right now, written to status.json in the data
+// dir so `healthcheck` (Docker HEALTHCHECK, Kubernetes exec probes) can
+// tell a working miner from a dead or hung one without opening a port.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::machine;
+use crate::memory::{self, MemoryStats};
+use crate::{chart, console, histogram, parse_deadline, timefmt};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    /// fetching, waiting, building_rom, mining, submitting, retrying, stopped
+    pub phase: String,
+    pub challenge_id: Option<String>,
+    pub hashes: u64,
+    pub hash_rate: f64,
+    /// Last time the miner made progress (RFC 3339)
+    pub updated_at: String,
+    /// Last time the file was written; stale means the process is gone
+    pub written_at: String,
+    /// Hash rate at each write, oldest first, for the `status` sparkline
+    #[serde(default)]
+    pub rate_history: Vec<f64>,
+    /// Per-hash latency of the current (or last) mining run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<histogram::Snapshot>,
+    /// Sampled at each write, to catch leaks across ROM rotations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryStats>,
+    /// Solutions accepted since the miner started
+    #[serde(default)]
+    pub accepted: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine_id: Option<String>,
+}
+
+/// Writes kept in rate_history (30 minutes at the default interval)
+const RATE_HISTORY: usize = 120;
+
+static CURRENT: Mutex<Option<Status>> = Mutex::new(None);
+
+fn update(f: impl FnOnce(&mut Status)) {
+    let mut current = CURRENT.lock().unwrap();
+    let status = current.get_or_insert_with(|| Status {
+        pid: std::process::id(),
+        phase: "starting".to_string(),
+        challenge_id: None,
+        hashes: 0,
+        hash_rate: 0.0,
+        updated_at: String::new(),
+        written_at: String::new(),
+        rate_history: Vec::new(),
+        latency: None,
+        memory: None,
+        accepted: 0,
+        machine_id: machine::id().map(str::to_string),
+    });
+    f(status);
+    status.updated_at = Utc::now().to_rfc3339();
+}
+
+pub fn set_phase(phase: &str, challenge_id: Option<&str>) {
+    update(|s| {
+        s.phase = phase.to_string();
+        if let Some(id) = challenge_id {
+            s.challenge_id = Some(id.to_string());
+        }
+        if phase != "mining" {
+            s.hashes = 0;
+            s.hash_rate = 0.0;
+        }
+    });
+}
+
+/// Called by the mining progress reporter
+pub fn mining(hashes: u64, hash_rate: f64) {
+    update(|s| {
+        s.phase = "mining".to_string();
+        s.hashes = hashes;
+        s.hash_rate = hash_rate;
+    });
+}
+
+pub fn solution_accepted() {
+    update(|s| s.accepted += 1);
+}
+
+pub fn latency(snapshot: histogram::Snapshot) {
+    update(|s| s.latency = Some(snapshot));
+}
+
+/// What the status file would say right now
+pub fn current() -> Option<Status> {
+    CURRENT.lock().unwrap().clone()
+}
+
+/// Rewrite the status file forever; spawn on the runtime
+pub async fn write_periodically(path: PathBuf, interval: Duration, cancel: CancellationToken) {
+    // Have something to write before the first round reports in
+    update(|_| {});
+    loop {
+        let snapshot = {
+            let mut current = CURRENT.lock().unwrap();
+            if let Some(status) = current.as_mut() {
+                if status.rate_history.len() >= RATE_HISTORY {
+                    status.rate_history.remove(0);
+                }
+                status.rate_history.push(status.hash_rate);
+                status.memory = Some(memory::sample()).filter(|m| !m.is_empty());
+            }
+            current.clone()
+        };
+        if let Some(mut status) = snapshot {
+            status.written_at = Utc::now().to_rfc3339();
+            let written = serde_json::to_string_pretty(&status)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| {
+                    // Write-then-rename so a probe never reads half a file
+                    let tmp = path.with_extension("json.tmp");
+                    fs::write(&tmp, json)?;
+                    fs::rename(&tmp, &path)?;
+                    Ok(())
+                });
+            if let Err(e) = written {
+                say!("⚠️  Failed to write {}: {}", path.display(), e);
+            }
+        }
+        if cancel.is_cancelled() {
+            return;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            // One last write so the file shows the miner stopped
+            _ = cancel.cancelled() => set_phase("stopped", None),
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<Status> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("No status file at {} (miner not running in daemon mode?)", path.display()))?;
+    serde_json::from_str(&raw).with_context(|| format!("Unreadable status file {}", path.display()))
+}
+
+/// The `status` command: the daemon's status file with a hash rate chart,
+/// or as it is with `json`
+pub fn print(path: &Path, json: bool) -> Result<()> {
+    let status = read(path)?;
+    if json {
+        println!("{}", serde_json::to_string(&status)?);
+        return Ok(());
+    }
+    let since = |raw: &str| {
+        parse_deadline(raw).map_or(raw.to_string(), |t| timefmt::humanize(Utc::now() - t) + " ago")
+    };
+    say!("📊 Miner {} (pid {})", status.phase, status.pid);
+    if let Some(id) = &status.machine_id {
+        say!("   Machine: {}", id);
+    }
+    say!("   Challenge: {}", status.challenge_id.as_deref().unwrap_or("-"));
+    say!("   Hashes: {} at {:.0} H/s", status.hashes, status.hash_rate);
+    say!("   Progress: {}", since(&status.updated_at));
+    say!("   Written: {}", since(&status.written_at));
+    let width = console::width().saturating_sub(40).clamp(10, RATE_HISTORY);
+    say!("   Hash rate: {}", chart::labelled(&status.rate_history, width, " H/s"));
+    if let Some(memory) = &status.memory {
+        say!("   Memory: {}", memory);
+    }
+    if let Some(latency) = status.latency.filter(|l| l.total() > 0) {
+        say!("   Per-hash latency: {} over {} batches", latency.summary(), latency.total());
+        for row in latency.rows(30) {
+            say!("     {}", row);
+        }
+    }
+    Ok(())
+}
+
+/// `healthcheck`: "healthy: ..." on stdout, or the reason as the error
+pub fn healthcheck(data_dir: &Path, max_age: Duration, max_stall: Duration) -> Result<()> {
+    let summary = check(&data_dir.join(crate::store::STATUS_FILE), max_age, max_stall)?;
+    println!("healthy: {}", summary);
+    Ok(())
+}
+
+/// Ok(summary) when the status file shows a live, progressing miner
+pub fn check(path: &Path, max_age: Duration, max_stall: Duration) -> Result<String> {
+    let status = read(path)?;
+
+    let now = Utc::now();
+    let parse = |raw: &str| parse_deadline(raw).context("Bad timestamp in status file");
+    let age = now - parse(&status.written_at)?;
+    if age.to_std().unwrap_or_default() > max_age {
+        bail!("Status file is {} old, miner process is gone or hung", timefmt::humanize(age));
+    }
+    let stall = now - parse(&status.updated_at)?;
+    if stall.to_std().unwrap_or_default() > max_stall {
+        bail!(
+            "No progress for {} (phase: {})",
+            timefmt::humanize(stall),
+            status.phase
+        );
+    }
+    Ok(format!(
+        "{} {} - {} hashes at {:.0} H/s, updated {} ago",
+        status.phase,
+        status.challenge_id.as_deref().unwrap_or("-"),
+        status.hashes,
+        status.hash_rate,
+        timefmt::humanize(stall)
+    ))
+}
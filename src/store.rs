@@ -0,0 +1,419 @@
+// ==================== LOCAL STORE ====================
+//
+// Everything the miner persists lives under one data directory:
+//   history.jsonl      - append-only history DB, one JSON record per line
+//   receipts/<id>.json - full detail of each found solution
+//   forensics/<id>.txt - reports for solutions the server rejected
+//   scans.jsonl        - nonce ranges scanned per challenge
+//   audit.jsonl        - outcome of every API interaction
+//   status.json        - live daemon status for health probes
+//   addresses.json     - labelled addresses (see addressbook.rs)
+//   payouts/           - reward split suggestions per accepted solution
+//   challenges.jsonl   - every distinct challenge seen, for trend analysis
+//   rates.jsonl        - every distinct reward schedule (work_to_star_rate)
+//   hashrate.json      - last measured hash rate, for the success advisor
+//   logs/<id>.log      - the miner's output during each challenge's rounds
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::window::WindowPosition;
+use crate::{already_submitted, Challenge, ChallengeResponse};
+
+pub const DEFAULT_DATA_DIR: &str = "scavenger_data";
+pub const STATUS_FILE: &str = "status.json";
+pub const SCANS_FILE: &str = "scans.jsonl";
+pub const QUEUE_FILE: &str = "submit_queue.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SolutionStatus {
+    Accepted,
+    /// Told the address already has a solution after an earlier try that
+    /// may have got through: most likely ours, but with no receipt
+    AcceptedNoReceipt,
+    Rejected,
+    SubmitFailed,
+    DryRun,
+    Cancelled,
+    /// Mined with a non-protocol ROM (--rom-size)
+    NotSubmittable,
+}
+
+/// Everything needed to investigate a solution with the server later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolutionRecord {
+    pub recorded_at: String,
+    pub address: String,
+    pub challenge: Challenge,
+    pub mining_period_ends: String,
+    pub nonce: String,
+    pub preimage: String,
+    /// Full 64-byte digest, hex encoded
+    pub hash: String,
+    pub status: SolutionStatus,
+    /// Raw server response, when there was one
+    pub response: Option<serde_json::Value>,
+    /// When in the challenge window it was found and submitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowPosition>,
+    /// Where the receipt's preimage differs from the submission
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub receipt_problems: Vec<String>,
+    /// The machine that found it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine_id: Option<String>,
+}
+
+impl SolutionRecord {
+    /// Accepted (with a receipt or without), or turned away because the
+    /// address already has a solution
+    pub fn uses_allowance(&self) -> bool {
+        match self.status {
+            SolutionStatus::Accepted | SolutionStatus::AcceptedNoReceipt => true,
+            SolutionStatus::Rejected => self.response.as_ref().is_some_and(already_submitted),
+            _ => false,
+        }
+    }
+}
+
+/// How many solutions the server accepts per address (--acceptance-rule)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum AcceptanceRule {
+    /// One per challenge
+    #[default]
+    PerChallenge,
+    /// One per day, whichever challenge it solves
+    PerDay,
+}
+
+impl AcceptanceRule {
+    /// Whether a solution to `solved` uses up the allowance for `challenge`
+    pub fn same_slot(self, solved: &Challenge, challenge: &Challenge) -> bool {
+        match self {
+            AcceptanceRule::PerChallenge => solved.challenge_id == challenge.challenge_id,
+            AcceptanceRule::PerDay => solved.day == challenge.day,
+        }
+    }
+}
+
+/// A challenge as first served, archived once per challenge id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeRecord {
+    pub first_seen: String,
+    /// Server status code at first sight
+    pub code: String,
+    pub mining_period_ends: String,
+    pub challenge: Challenge,
+}
+
+/// A reward schedule as served, saved when it differs from the last one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateRecord {
+    pub first_seen: String,
+    /// STAR per solution, day 1 first
+    pub rates: Vec<u64>,
+}
+
+/// Hash rate of the last mining run long enough to be representative
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashRateRecord {
+    pub measured_at: String,
+    pub rate: f64,
+    pub threads: usize,
+}
+
+/// Nonces `start_nonce .. start_nonce + count` were hashed for this challenge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanRecord {
+    pub recorded_at: String,
+    pub address: String,
+    pub challenge_id: String,
+    pub start_nonce: u64,
+    pub count: u64,
+    /// --worker of the machine that did the hashing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker: Option<String>,
+    /// ID of the machine that did the hashing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine_id: Option<String>,
+}
+
+impl ScanRecord {
+    fn end(&self) -> u64 {
+        self.start_nonce.saturating_add(self.count)
+    }
+}
+
+pub struct Store {
+    root: PathBuf,
+}
+
+impl Store {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(root.join("receipts"))
+            .with_context(|| format!("Failed to create data dir {}", root.display()))?;
+        Ok(Store { root })
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.root.join("history.jsonl")
+    }
+
+    pub fn audit_path(&self) -> PathBuf {
+        self.root.join("audit.jsonl")
+    }
+
+    pub fn status_path(&self) -> PathBuf {
+        self.root.join(STATUS_FILE)
+    }
+
+    pub fn queue_path(&self) -> PathBuf {
+        self.root.join(QUEUE_FILE)
+    }
+
+    fn hash_rate_path(&self) -> PathBuf {
+        self.root.join("hashrate.json")
+    }
+
+    pub fn hash_rate(&self) -> Result<Option<HashRateRecord>> {
+        match fs::read_to_string(self.hash_rate_path()) {
+            Ok(raw) => Ok(Some(serde_json::from_str(&raw)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn record_hash_rate(&self, rate: f64, threads: usize) -> Result<()> {
+        let record = HashRateRecord { measured_at: Utc::now().to_rfc3339(), rate, threads };
+        fs::write(self.hash_rate_path(), serde_json::to_string_pretty(&record)?)
+            .with_context(|| format!("Failed to write {}", self.hash_rate_path().display()))
+    }
+
+    fn challenges_path(&self) -> PathBuf {
+        self.root.join("challenges.jsonl")
+    }
+
+    pub fn challenges(&self) -> Result<Vec<ChallengeRecord>> {
+        read_jsonl(&self.challenges_path())
+    }
+
+    /// Archive the challenge unless it was seen before; true when it was new
+    pub fn archive_challenge(&self, response: &ChallengeResponse) -> Result<bool> {
+        let id = &response.challenge.challenge_id;
+        if self.challenges()?.iter().any(|c| &c.challenge.challenge_id == id) {
+            return Ok(false);
+        }
+        let record = ChallengeRecord {
+            first_seen: Utc::now().to_rfc3339(),
+            code: response.code.as_str().to_string(),
+            mining_period_ends: response.mining_period_ends.clone(),
+            challenge: response.challenge.clone(),
+        };
+        append_jsonl(&self.challenges_path(), &record)?;
+        Ok(true)
+    }
+
+    fn rates_path(&self) -> PathBuf {
+        self.root.join("rates.jsonl")
+    }
+
+    /// Reward schedules seen, oldest first
+    pub fn rate_versions(&self) -> Result<Vec<RateRecord>> {
+        read_jsonl(&self.rates_path())
+    }
+
+    /// Save the schedule unless it is the latest one already; true when it
+    /// was new
+    pub fn record_rates(&self, rates: &[u64]) -> Result<bool> {
+        if self.rate_versions()?.last().is_some_and(|r| r.rates == rates) {
+            return Ok(false);
+        }
+        let record = RateRecord { first_seen: Utc::now().to_rfc3339(), rates: rates.to_vec() };
+        append_jsonl(&self.rates_path(), &record)?;
+        Ok(true)
+    }
+
+    fn scans_path(&self) -> PathBuf {
+        self.root.join(SCANS_FILE)
+    }
+
+    pub fn record_scan(&self, scan: &ScanRecord) -> Result<()> {
+        append_jsonl(&self.scans_path(), scan)
+    }
+
+    pub fn scans(&self) -> Result<Vec<ScanRecord>> {
+        read_jsonl(&self.scans_path())
+    }
+
+    pub fn scans_for(&self, challenge_id: &str, address: &str) -> Result<Vec<ScanRecord>> {
+        Ok(self
+            .scans()?
+            .into_iter()
+            .filter(|s| s.challenge_id == challenge_id && s.address == address)
+            .collect())
+    }
+
+    pub fn solutions(&self) -> Result<Vec<SolutionRecord>> {
+        read_jsonl(&self.history_path())
+    }
+
+    /// The solution that used up `address`'s allowance for `challenge`, if one did
+    pub fn allowance_used(
+        &self,
+        address: &str,
+        challenge: &Challenge,
+        rule: AcceptanceRule,
+    ) -> Result<Option<SolutionRecord>> {
+        Ok(self.solutions()?.into_iter().find(|r| {
+            r.address == address && r.uses_allowance() && rule.same_slot(&r.challenge, challenge)
+        }))
+    }
+
+    /// Append to the history DB and write the receipt file
+    pub fn record_solution(&self, record: &SolutionRecord) -> Result<PathBuf> {
+        append_jsonl(&self.history_path(), record)?;
+
+        let path = self.root.join("receipts").join(solution_file_name(record, "json"));
+        fs::write(&path, serde_json::to_string_pretty(record)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// payouts/<challenge>_<nonce>.json
+    pub fn write_payout<T: Serialize>(&self, record: &SolutionRecord, payout: &T) -> Result<PathBuf> {
+        let dir = self.root.join("payouts");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(solution_file_name(record, "json"));
+        fs::write(&path, serde_json::to_string_pretty(payout)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// logs/<challenge>.log
+    pub fn challenge_log_path(&self, challenge_id: &str) -> PathBuf {
+        self.root.join("logs").join(format!("{}.log", file_safe(challenge_id)))
+    }
+
+    pub fn write_forensic_report(&self, record: &SolutionRecord, report: &str) -> Result<PathBuf> {
+        let dir = self.root.join("forensics");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(solution_file_name(record, "txt"));
+        fs::write(&path, report)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+fn solution_file_name(record: &SolutionRecord, extension: &str) -> String {
+    format!(
+        "{}_{}.{}",
+        file_safe(&record.challenge.challenge_id),
+        file_safe(&record.nonce),
+        extension
+    )
+}
+
+/// How many nonces of `start .. start + count` earlier scans already covered
+pub fn scanned_overlap(scans: &[ScanRecord], start: u64, count: u64) -> u64 {
+    let end = start.saturating_add(count);
+    let mut ranges: Vec<(u64, u64)> = scans
+        .iter()
+        .map(|s| (s.start_nonce.max(start), s.end().min(end)))
+        .filter(|(a, b)| a < b)
+        .collect();
+    ranges.sort_unstable();
+
+    // Merge so that ranges scanned twice are not counted twice
+    let mut total = 0;
+    let mut covered_to = start;
+    for (a, b) in ranges {
+        let a = a.max(covered_to);
+        if b > a {
+            total += b - a;
+            covered_to = b;
+        }
+    }
+    total
+}
+
+/// Read a JSONL file, skipping lines that no longer parse
+pub fn read_jsonl<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+    };
+    let mut items = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(item) = serde_json::from_str(&line?) {
+            items.push(item);
+        }
+    }
+    Ok(items)
+}
+
+pub fn append_jsonl<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(value)?)?;
+    Ok(())
+}
+
+/// Challenge ids look like "**D07C10", which is not a valid Windows file name
+pub fn file_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(response: serde_json::Value) -> SolutionRecord {
+        SolutionRecord {
+            recorded_at: "2025-11-03T01:00:00Z".into(),
+            address: "addr1qtest".into(),
+            challenge: serde_json::from_value(serde_json::json!({
+                "challenge_id": "**D05C10", "day": 5, "challenge_number": 10,
+                "difficulty": "0000FFFF", "no_pre_mine": "00",
+                "latest_submission": "2025-11-03T23:59:59Z", "no_pre_mine_hour": "0"
+            }))
+            .unwrap(),
+            mining_period_ends: "2025-11-03T23:59:59Z".into(),
+            nonce: "0000000000000001".into(),
+            preimage: String::new(),
+            hash: String::new(),
+            status: SolutionStatus::Rejected,
+            response: Some(response),
+            window: None,
+            receipt_problems: Vec::new(),
+            machine_id: None,
+        }
+    }
+
+    #[test]
+    fn duplicate_submission_uses_the_allowance() {
+        let body = serde_json::json!({"message": "Solution already submitted for this address"});
+        assert!(rejected(body).uses_allowance());
+    }
+
+    #[test]
+    fn other_rejections_leave_the_allowance() {
+        // As recorded in tests/fixtures/replay_day.jsonl
+        let body = serde_json::json!({"message": "Solution does not meet difficulty"});
+        assert!(!rejected(body).uses_allowance());
+        // "already" elsewhere in the body is not the server saying so
+        let body = serde_json::json!({"message": "Invalid nonce", "detail": "already expired"});
+        assert!(!rejected(body).uses_allowance());
+    }
+}
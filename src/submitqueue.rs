@@ -0,0 +1,213 @@
+// ==================== SUBMISSION QUEUE ====================
+//
+// A nonce found but never delivered is a solution thrown away: once
+// `submit` had used its retries (network down, a run of 5xx) the round
+// moved on, and the nonce survived only as a SubmitFailed line in
+// history.jsonl. Such solutions now wait in submit_queue.json, and a
+// background service sends them again, waiting longer after each failure,
+// until the server answers either way or the challenge stops taking
+// solutions (latest_submission). The queue outlives restarts (and travels
+// with --state-remote), and rounds skip a challenge whose solution is still
+// queued rather than mine it again. A "solution already submitted" answer
+// only counts as ours (accepted, without a receipt) once some earlier try,
+// the round's or the queue's, may have reached the server; before that it
+// is someone else's solution on the address.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::store::{SolutionRecord, SolutionStatus, Store};
+use crate::{metrics, parse_deadline, retry, status, timefmt, MinerApi};
+
+/// How often the service looks for solutions due another try
+const CHECK_EVERY: Duration = Duration::from_secs(60);
+
+/// Wait before the first try from the queue, doubled after each failure
+const FIRST_RETRY: Duration = Duration::from_secs(2 * 60);
+
+/// Longest wait between tries
+const MAX_RETRY: Duration = Duration::from_secs(60 * 60);
+
+/// Rounds and the service both rewrite the file
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Queued {
+    /// As recorded when the round gave up on it
+    pub record: SolutionRecord,
+    /// Tries from the queue so far
+    pub attempts: u32,
+    pub next_try: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// A failed try so far may have reached the server
+    #[serde(default)]
+    pub may_have_landed: bool,
+}
+
+impl Queued {
+    fn is(&self, record: &SolutionRecord) -> bool {
+        self.record.address == record.address
+            && self.record.challenge.challenge_id == record.challenge.challenge_id
+            && self.record.nonce == record.nonce
+    }
+}
+
+fn load(store: &Store) -> Result<Vec<Queued>> {
+    let path = store.queue_path();
+    match fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw)
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Read, change and write the queue in one go
+fn update<T>(store: &Store, change: impl FnOnce(&mut Vec<Queued>) -> T) -> Result<T> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut queue = load(store)?;
+    let result = change(&mut queue);
+    let path = store.queue_path();
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(&queue)?)?;
+    fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(result)
+}
+
+/// Keep a solution the round could not deliver; `may_have_landed` when one
+/// of its tries may have reached the server all the same
+pub fn add(store: &Store, record: &SolutionRecord, may_have_landed: bool) -> Result<()> {
+    let next_try = Utc::now() + chrono::Duration::from_std(FIRST_RETRY)?;
+    update(store, |queue| {
+        queue.retain(|q| !q.is(record));
+        queue.push(Queued {
+            record: record.clone(),
+            attempts: 0,
+            next_try: next_try.to_rfc3339(),
+            last_error: None,
+            may_have_landed,
+        });
+    })
+}
+
+fn remove(store: &Store, record: &SolutionRecord) {
+    if let Err(e) = update(store, |queue| queue.retain(|q| !q.is(record))) {
+        say!("⚠️  Cannot update the submission queue: {}", e);
+    }
+}
+
+/// Whether `address` has a solution for `challenge_id` waiting
+pub fn holds(store: &Store, address: &str, challenge_id: &str) -> bool {
+    let queue = load(store).unwrap_or_default();
+    queue
+        .iter()
+        .any(|q| q.record.address == address && q.record.challenge.challenge_id == challenge_id)
+}
+
+/// Send what is due; drop what can no longer be taken
+pub async fn retry_due<A: MinerApi>(api: &A, store: &Store) {
+    let queue = match load(store) {
+        Ok(queue) => queue,
+        Err(e) => {
+            say!("⚠️  Cannot read the submission queue: {}", e);
+            return;
+        }
+    };
+    for entry in queue {
+        let record = &entry.record;
+        let challenge_id = &record.challenge.challenge_id;
+        let now = Utc::now();
+        if parse_deadline(&record.challenge.latest_submission).is_some_and(|t| api.now() >= t) {
+            say!(
+                "⌛ Gave up on the queued solution for {} (nonce {}): the challenge closed {}",
+                challenge_id,
+                record.nonce,
+                timefmt::timestamp(&record.challenge.latest_submission)
+            );
+            remove(store, record);
+            continue;
+        }
+        if parse_deadline(&entry.next_try).is_some_and(|t| now < t) {
+            continue;
+        }
+        say!(
+            "\n📮 Sending the queued solution for {} again (nonce {})",
+            challenge_id,
+            record.nonce
+        );
+        match api.submit_solution(&record.address, challenge_id, &record.nonce).await {
+            Ok(response) => {
+                let landed = response.landed_earlier
+                    || (entry.may_have_landed && response.already_submitted());
+                let status = if response.crypto_receipt.is_some() {
+                    say!("🎉 Queued solution for {} accepted", challenge_id);
+                    status::solution_accepted();
+                    metrics::solution_accepted();
+                    SolutionStatus::Accepted
+                } else if landed {
+                    say!(
+                        "✅ Queued solution for {} most likely accepted: an earlier try may \
+                         have got in, but there is no receipt",
+                        challenge_id
+                    );
+                    SolutionStatus::AcceptedNoReceipt
+                } else {
+                    say!("📋 The server turned down the queued solution: {}", response.extra);
+                    SolutionStatus::Rejected
+                };
+                let answered = SolutionRecord {
+                    recorded_at: Utc::now().to_rfc3339(),
+                    status,
+                    response: serde_json::to_value(&response).ok(),
+                    ..record.clone()
+                };
+                if let Err(e) = store.record_solution(&answered) {
+                    say!("⚠️  Failed to save solution details: {}", e);
+                }
+                remove(store, record);
+            }
+            Err(e) => {
+                let attempts = entry.attempts + 1;
+                let wait = FIRST_RETRY.saturating_mul(1 << attempts.min(16)).min(MAX_RETRY);
+                let wait = chrono::Duration::from_std(wait).unwrap_or_default();
+                say!("📮 Still not delivered ({}); next try in {}", e, timefmt::humanize(wait));
+                let next_try = (Utc::now() + wait).to_rfc3339();
+                let error = e.to_string();
+                let landed = retry::may_have_landed(&e);
+                let updated = update(store, |queue| {
+                    for queued in queue.iter_mut().filter(|q| q.is(record)) {
+                        queued.attempts = attempts;
+                        queued.next_try = next_try.clone();
+                        queued.last_error = Some(error.clone());
+                        queued.may_have_landed |= landed;
+                    }
+                });
+                if let Err(e) = updated {
+                    say!("⚠️  Cannot update the submission queue: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Keep trying until cancelled
+pub async fn service<A: MinerApi>(api: A, store: Store, cancel: CancellationToken) {
+    loop {
+        retry_due(&api, &store).await;
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = tokio::time::sleep(CHECK_EVERY) => {}
+        }
+    }
+}
+
+/// How many solutions are waiting
+pub fn pending(store: &Store) -> usize {
+    load(store).map(|queue| queue.len()).unwrap_or(0)
+}
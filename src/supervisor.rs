@@ -0,0 +1,87 @@
+// ==================== SUPERVISOR ====================
+//
+// The miner's background services (signal handling, spot watcher, status
+// writer) run as named tokio tasks under one cancellation token, with the
+// round loop as the foreground task. Whoever stops first - the loop, a
+// Ctrl+C, a reclaim notice, a crashed service - cancels the token; then
+// services are stopped newest first, so the status writer records the
+// final state after mining has ended and before the process exits.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// How long a service gets to wind down before it is abandoned
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+pub struct Supervisor {
+    cancel: CancellationToken,
+    services: Vec<(&'static str, JoinHandle<()>)>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Supervisor { cancel: CancellationToken::new(), services: Vec::new() }
+    }
+
+    /// Cancelled when anything decides the miner should stop
+    pub fn token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Start a service; it should return once its token is cancelled.
+    /// A panic is reported and shuts everything else down.
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, service: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let task = tokio::spawn(service(self.cancel.clone()));
+        let cancel = self.cancel.clone();
+        let watcher = tokio::spawn(async move {
+            if let Err(e) = task.await {
+                if e.is_panic() {
+                    say!("❌ {} service crashed, shutting down", name);
+                    cancel.cancel();
+                }
+            }
+        });
+        self.services.push((name, watcher));
+    }
+
+    /// Run the foreground task, then stop the services in reverse start order
+    pub async fn run<T>(self, main: impl Future<Output = T>) -> T {
+        let result = main.await;
+        self.shutdown().await;
+        result
+    }
+
+    async fn shutdown(self) {
+        self.cancel.cancel();
+        for (name, service) in self.services.into_iter().rev() {
+            if tokio::time::timeout(SHUTDOWN_GRACE, service).await.is_err() {
+                say!("⚠️  {} service did not stop within {}s", name, SHUTDOWN_GRACE.as_secs());
+            }
+        }
+    }
+}
+
+/// First Ctrl+C stops mining cleanly (scan recorded, state synced); the second exits
+pub async fn signals(cancel: CancellationToken) {
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            if result.is_err() {
+                return;
+            }
+        }
+        _ = cancel.cancelled() => return,
+    }
+    say!("\n🛑 Stopping... (Ctrl+C again to quit now)");
+    cancel.cancel();
+    // Detached: must outlive the supervisor's wait for this service
+    tokio::spawn(async {
+        let _ = tokio::signal::ctrl_c().await;
+        std::process::exit(130);
+    });
+}
@@ -0,0 +1,301 @@
+// ==================== STATE SYNC ====================
+//
+// Ephemeral miners keep their history somewhere that outlives the VM:
+// either a directory (typically a mounted network volume) or an
+// S3-compatible bucket. Push mirrors the data dir to the remote, pull
+// seeds a fresh data dir from it. `sync push/pull` moves one sealed file
+// instead (bundle), which may also live on a WebDAV share.
+
+use anyhow::{bail, Context, Result};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::s3::S3Client;
+use crate::webdav::WebDavClient;
+
+/// Files and directories of the data dir worth carrying to a new instance
+const SYNCED: &[&str] = &[
+    "history.jsonl",
+    "scans.jsonl",
+    "challenges.jsonl",
+    "rates.jsonl",
+    "addresses.json",
+    "submit_queue.json",
+    "audit.jsonl",
+    "receipts",
+    "payouts",
+    "forensics",
+    "logs",
+];
+
+#[derive(Debug, Clone)]
+pub enum Remote {
+    Dir(PathBuf),
+    /// s3://bucket/prefix
+    S3 { bucket: String, prefix: String },
+    /// http(s)://host/path of a WebDAV collection
+    WebDav(Url),
+}
+
+impl Remote {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        if raw.starts_with("http://") || raw.starts_with("https://") {
+            let url = Url::parse(raw).map_err(|e| format!("'{}': {}", raw, e))?;
+            if url.password().is_some() {
+                return Err("put the WebDAV password in WEBDAV_PASSWORD, not the URL".to_string());
+            }
+            return Ok(Remote::WebDav(url));
+        }
+        let Some(rest) = raw.strip_prefix("s3://") else {
+            return Ok(Remote::Dir(PathBuf::from(raw)));
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("missing bucket name in '{}'", raw));
+        }
+        let prefix = prefix.trim_matches('/');
+        Ok(Remote::S3 {
+            bucket: bucket.to_string(),
+            prefix: if prefix.is_empty() { String::new() } else { format!("{}/", prefix) },
+        })
+    }
+}
+
+impl std::fmt::Display for Remote {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Remote::Dir(path) => write!(f, "{}", path.display()),
+            Remote::S3 { bucket, prefix } => write!(f, "s3://{}/{}", bucket, prefix),
+            Remote::WebDav(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+impl Remote {
+    /// Store one file under `name`, replacing it
+    /// Store `body` under `name` only if the file is still as `read` found
+    /// it (absent when None); false, and nothing written, when another
+    /// writer got there in between
+    pub async fn put_file(&self, name: &str, body: Vec<u8>, read: Option<&Stored>) -> Result<bool> {
+        let expected = read.map(|stored| stored.version.as_deref());
+        match self {
+            Remote::Dir(dir) => {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?;
+                let _lock = DirLock::take(&dir.join(format!("{}.lock", name)))?;
+                let current = read_file(&dir.join(name))?.map(|body| Some(version_of(&body)));
+                if current.as_ref().map(Option::as_deref) != expected {
+                    return Ok(false);
+                }
+                let tmp = dir.join(format!("{}.tmp", name));
+                fs::write(&tmp, body)?;
+                fs::rename(&tmp, dir.join(name))
+                    .with_context(|| format!("Failed to write {}", dir.join(name).display()))?;
+                Ok(true)
+            }
+            Remote::S3 { bucket, prefix } => {
+                S3Client::from_env(bucket)?
+                    .put_if(&format!("{}{}", prefix, name), body, expected)
+                    .await
+            }
+            Remote::WebDav(url) => WebDavClient::from_env(url)?.put_if(name, body, expected).await,
+        }
+    }
+
+    /// The file stored under `name`, if there is one
+    pub async fn get_file(&self, name: &str) -> Result<Option<Stored>> {
+        match self {
+            Remote::Dir(dir) => Ok(read_file(&dir.join(name))?.map(|body| Stored {
+                version: Some(version_of(&body)),
+                body,
+            })),
+            Remote::S3 { bucket, prefix } => Ok(S3Client::from_env(bucket)?
+                .get_versioned(&format!("{}{}", prefix, name))
+                .await?
+                .map(|(body, version)| Stored { body, version })),
+            Remote::WebDav(url) => Ok(WebDavClient::from_env(url)?
+                .get(name)
+                .await?
+                .map(|(body, version)| Stored { body, version })),
+        }
+    }
+}
+
+/// A file read from a remote, with the version a conditional put checks
+pub struct Stored {
+    pub body: Vec<u8>,
+    /// ETag, or a hash of the contents in a directory; None when the
+    /// server gives no ETag, and then the put cannot be conditional
+    pub version: Option<String>,
+}
+
+fn read_file(path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(body) => Ok(Some(body)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn version_of(body: &[u8]) -> String {
+    hex::encode(Sha256::digest(body))
+}
+
+/// A lock file beside a file in a remote directory, held from the version
+/// check to the rename; one left behind by a crashed push goes stale
+struct DirLock(PathBuf);
+
+impl DirLock {
+    const STALE: Duration = Duration::from_secs(60);
+
+    fn take(path: &Path) -> Result<Self> {
+        for _ in 0..2 {
+            match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => return Ok(DirLock(path.to_path_buf())),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = fs::metadata(path)
+                        .and_then(|meta| meta.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok());
+                    if age.is_some_and(|age| age < Self::STALE) {
+                        bail!("{} is held by another push; try again shortly", path.display());
+                    }
+                    let _ = fs::remove_file(path);
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {}", path.display()))
+                }
+            }
+        }
+        bail!("Cannot take {}", path.display())
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// A WebDAV share only ever holds the sealed bundle
+fn no_mirror(remote: &Remote) -> Result<usize> {
+    bail!("{} is a WebDAV share; it takes `sync push` and `sync pull`, not mirrored state", remote)
+}
+
+/// Copy local state to the remote, overwriting what is there
+pub async fn push(data_dir: &Path, remote: &Remote) -> Result<usize> {
+    match remote {
+        Remote::Dir(dir) => copy_entries(data_dir, dir, true),
+        Remote::S3 { bucket, prefix } => {
+            let s3 = S3Client::from_env(bucket)?;
+            let mut pushed = 0;
+            for (relative, path) in local_files(data_dir, SYNCED)? {
+                let body = fs::read(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                s3.put(&format!("{}{}", prefix, relative), body).await?;
+                pushed += 1;
+            }
+            Ok(pushed)
+        }
+        Remote::WebDav(_) => no_mirror(remote),
+    }
+}
+
+/// Push and report the outcome; a failed push must not stop the miner
+pub async fn checkpoint(data_dir: &Path, remote: &Remote) {
+    match push(data_dir, remote).await {
+        Ok(n) => say!("📤 Pushed {} file(s) to {}", n, remote),
+        Err(e) => say!("❌ Failed to push state to {}: {}", remote, e),
+    }
+}
+
+/// Copy remote state into the data dir, never overwriting local files
+pub async fn pull(remote: &Remote, data_dir: &Path) -> Result<usize> {
+    match remote {
+        Remote::Dir(dir) if !dir.exists() => Ok(0),
+        Remote::Dir(dir) => copy_entries(dir, data_dir, false),
+        Remote::S3 { bucket, prefix } => {
+            let s3 = S3Client::from_env(bucket)?;
+            let mut pulled = 0;
+            for key in s3.list(prefix).await? {
+                let Some(relative) = key.strip_prefix(prefix.as_str()) else { continue };
+                if !is_synced(relative, SYNCED) {
+                    continue;
+                }
+                let path = data_dir.join(relative);
+                if path.exists() {
+                    continue;
+                }
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, s3.get(&key).await?)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                pulled += 1;
+            }
+            Ok(pulled)
+        }
+        Remote::WebDav(_) => no_mirror(remote),
+    }
+}
+
+/// Only the files and directories in `names`, and nothing that could escape the data dir
+pub fn is_synced(relative: &str, names: &[&str]) -> bool {
+    let mut parts = relative.split('/');
+    parts.next().is_some_and(|top| names.contains(&top))
+        && relative.split('/').all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+/// ("receipts/x.json", full path) for every file of `names` in the data dir
+pub fn local_files(data_dir: &Path, names: &[&str]) -> Result<Vec<(String, PathBuf)>> {
+    fn walk(path: &Path, relative: String, out: &mut Vec<(String, PathBuf)>) -> Result<()> {
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                walk(&entry.path(), format!("{}/{}", relative, name), out)?;
+            }
+        } else if path.is_file() {
+            out.push((relative, path.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    for name in names {
+        walk(&data_dir.join(name), name.to_string(), &mut files)?;
+    }
+    Ok(files)
+}
+
+fn copy_entries(from: &Path, to: &Path, overwrite: bool) -> Result<usize> {
+    let mut copied = 0;
+    for name in SYNCED {
+        copied += copy_tree(&from.join(name), &to.join(name), overwrite)?;
+    }
+    Ok(copied)
+}
+
+fn copy_tree(from: &Path, to: &Path, overwrite: bool) -> Result<usize> {
+    if from.is_dir() {
+        fs::create_dir_all(to).with_context(|| format!("Failed to create {}", to.display()))?;
+        let mut copied = 0;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copied += copy_tree(&entry.path(), &to.join(entry.file_name()), overwrite)?;
+        }
+        return Ok(copied);
+    }
+    if !from.is_file() || (!overwrite && to.exists()) {
+        return Ok(0);
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(from, to)
+        .with_context(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+    Ok(1)
+}
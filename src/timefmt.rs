@@ -0,0 +1,83 @@
+// ==================== TIME DISPLAY ====================
+//
+// The server speaks UTC ISO strings; users think in local time and
+// "how long until". Everything printed for humans goes through here.
+
+use chrono::{DateTime, Local, Utc};
+use std::time::Duration;
+
+use crate::parse_deadline;
+
+/// "3 d 4 h", "3 h 12 m", "12 m 5 s", "45 s"
+pub fn humanize(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().abs();
+    let (d, h, m, s) = (secs / 86_400, secs % 86_400 / 3_600, secs % 3_600 / 60, secs % 60);
+    if d > 0 {
+        format!("{} d {} h", d, h)
+    } else if h > 0 {
+        format!("{} h {} m", h, m)
+    } else if m > 0 {
+        format!("{} m {} s", m, s)
+    } else {
+        format!("{} s", s)
+    }
+}
+
+pub fn local(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z").to_string()
+}
+
+/// Deadline in local time with a countdown, e.g. "2025-11-02 06:59:59 +07:00 (ends in 3 h 12 m)"
+pub fn deadline(raw: &str) -> String {
+    match parse_deadline(raw) {
+        Some(time) => {
+            let left = time - Utc::now();
+            if left > chrono::Duration::zero() {
+                format!("{} (ends in {})", local(time), humanize(left))
+            } else {
+                format!("{} (ended {} ago)", local(time), humanize(left))
+            }
+        }
+        None => raw.to_string(),
+    }
+}
+
+/// Past event in local time, e.g. "2025-11-02 06:59:59 +07:00 (5 m 2 s ago)"
+pub fn timestamp(raw: &str) -> String {
+    match parse_deadline(raw) {
+        Some(time) => format!("{} ({} ago)", local(time), humanize(Utc::now() - time)),
+        None => raw.to_string(),
+    }
+}
+
+/// Parse "90", "90s", "45m", "2h" or combinations like "1h30m"; bare numbers are seconds
+pub fn parse_duration(raw: &str) -> Result<Duration, String> {
+    let raw = raw.trim();
+    if let Ok(secs) = raw.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in raw.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("unknown unit '{}' in '{}' (use d, h, m or s)", c, raw)),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| format!("missing number before '{}' in '{}'", c, raw))?;
+        total = total.saturating_add(value.saturating_mul(unit));
+        digits.clear();
+    }
+    if !digits.is_empty() || raw.is_empty() {
+        return Err(format!("invalid duration '{}' (e.g. 90s, 45m, 1h30m)", raw));
+    }
+    Ok(Duration::from_secs(total))
+}
@@ -0,0 +1,157 @@
+// ==================== TLS ====================
+//
+// Which TLS stack the HTTP clients use is a build choice first: the
+// `native-tls` feature (default) uses the OS library and its certificate
+// store (OpenSSL on Linux), which is what a corporate TLS-inspecting proxy
+// usually needs; `rustls` needs no OpenSSL, for distros and static builds
+// without it, and trusts the bundled Mozilla roots, plus the OS store with
+// `rustls-native-roots`. A binary built with both picks one with --tls.
+// Every client the miner makes (API, notifications, S3) starts from
+// `builder()`.
+//
+// Behind a TLS-inspecting proxy whose root is not in the store the backend
+// reads, --extra-ca-cert adds it (a PEM file, one or more certificates) on
+// top of the usual roots, so verification stays on.
+//
+// --insecure turns verification off, for debugging a proxy and nothing
+// else: whoever sits on the path then sees and can rewrite every request,
+// signatures and addresses included. It takes a second, deliberate step
+// (SCAVENGER_INSECURE_CONFIRM), has no environment variable or config file
+// key of its own so it cannot be left on by accident, is refused for
+// anything that runs unattended (daemon, headless and --loop runs, fleet
+// roles, provision and the service installers), and says so at startup, on
+// every round and before any subcommand that talks to a server.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TlsBackend {
+    /// The OS TLS library and certificate store
+    Native,
+    /// rustls, no OpenSSL needed
+    Rustls,
+}
+
+impl TlsBackend {
+    fn compiled(self) -> bool {
+        match self {
+            TlsBackend::Native => cfg!(feature = "native-tls"),
+            TlsBackend::Rustls => cfg!(feature = "rustls"),
+        }
+    }
+
+    /// Native when built in, since it also sees the OS certificate store
+    fn default_for_build() -> Self {
+        if cfg!(feature = "native-tls") {
+            TlsBackend::Native
+        } else {
+            TlsBackend::Rustls
+        }
+    }
+}
+
+static BACKEND: OnceLock<TlsBackend> = OnceLock::new();
+static EXTRA_ROOTS: OnceLock<Vec<reqwest::Certificate>> = OnceLock::new();
+static INSECURE: AtomicBool = AtomicBool::new(false);
+
+/// What SCAVENGER_INSECURE_CONFIRM has to say for --insecure to take effect
+const INSECURE_CONFIRMATION: &str = "i-accept-interception";
+
+/// Every certificate in the PEM files
+fn load_roots(paths: &[PathBuf]) -> Result<Vec<reqwest::Certificate>> {
+    let mut roots = Vec::new();
+    for path in paths {
+        let pem = std::fs::read(path)
+            .with_context(|| format!("Cannot read --extra-ca-cert {}", path.display()))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("--extra-ca-cert {} is not PEM", path.display()))?;
+        if certs.is_empty() {
+            bail!("--extra-ca-cert {} has no certificate in it", path.display());
+        }
+        roots.extend(certs);
+    }
+    Ok(roots)
+}
+
+/// --insecure, if it is allowed here and confirmed; `unattended` names the
+/// unattended role this run has (daemon, headless, coordinator, provision...),
+/// if any
+fn check_insecure(unattended: Option<&str>) -> Result<()> {
+    if let Some(role) = unattended {
+        bail!(
+            "--insecure is refused {}: an unattended miner never skips TLS verification",
+            role
+        );
+    }
+    let confirmed = std::env::var("SCAVENGER_INSECURE_CONFIRM").ok();
+    if confirmed.as_deref() != Some(INSECURE_CONFIRMATION) {
+        bail!(
+            "--insecure skips TLS verification, so anyone between you and the server can read \
+             and rewrite what the miner sends. To go ahead for this run, set \
+             SCAVENGER_INSECURE_CONFIRM={}",
+            INSECURE_CONFIRMATION
+        );
+    }
+    Ok(())
+}
+
+/// Settle the backend, extra roots and --insecure at startup; an unavailable
+/// backend, unreadable certificate or unconfirmed --insecure is an error
+pub fn init(
+    choice: Option<TlsBackend>,
+    extra_ca: &[PathBuf],
+    insecure: bool,
+    unattended: Option<&str>,
+) -> Result<TlsBackend> {
+    let backend = choice.unwrap_or_else(TlsBackend::default_for_build);
+    if !backend.compiled() {
+        let (name, feature) = match backend {
+            TlsBackend::Native => ("native", "native-tls"),
+            TlsBackend::Rustls => ("rustls", "rustls"),
+        };
+        bail!("--tls {} is not in this build (rebuild with the `{}` feature)", name, feature);
+    }
+    if insecure {
+        check_insecure(unattended)?;
+        INSECURE.store(true, Ordering::Relaxed);
+    }
+    let _ = EXTRA_ROOTS.set(load_roots(extra_ca)?);
+    let _ = BACKEND.set(backend);
+    Ok(backend)
+}
+
+pub fn backend() -> TlsBackend {
+    *BACKEND.get_or_init(TlsBackend::default_for_build)
+}
+
+pub fn insecure() -> bool {
+    INSECURE.load(Ordering::Relaxed)
+}
+
+/// The --insecure warning; at startup and on every round, so it can't
+/// scroll out of sight
+pub fn warn_if_insecure() {
+    if insecure() {
+        say!("🔓 TLS VERIFICATION IS OFF (--insecure): traffic to the server can be read and altered");
+    }
+}
+
+/// A client builder on the chosen backend, trusting the extra roots
+pub fn builder() -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(insecure());
+    for root in EXTRA_ROOTS.get().into_iter().flatten() {
+        builder = builder.add_root_certificate(root.clone());
+    }
+    match backend() {
+        #[cfg(feature = "native-tls")]
+        TlsBackend::Native => builder.use_native_tls(),
+        #[cfg(feature = "rustls")]
+        TlsBackend::Rustls => builder.use_rustls_tls(),
+        #[allow(unreachable_patterns)]
+        _ => builder,
+    }
+}
@@ -0,0 +1,107 @@
+// ==================== DIFFICULTY TREND ====================
+//
+// The challenge archive (challenges.jsonl) as a table: how difficulty,
+// and with it the expected work per solution, moved over the event.
+// The history command puts the same trend next to our own results.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+use crate::{chart, window};
+use crate::store::{SolutionStatus, Store};
+use crate::{parse_deadline, timefmt, DifficultyRule};
+
+pub fn print(store: &Store, rule: DifficultyRule) -> Result<()> {
+    let mut challenges = store.challenges()?;
+    if challenges.is_empty() {
+        say!("📭 No challenges archived yet; they are recorded as the miner fetches them");
+        return Ok(());
+    }
+    challenges.sort_by_key(|c| (c.challenge.day, c.challenge.challenge_number));
+
+    say!("📈 Difficulty over {} challenge(s)", challenges.len());
+    let expected: Vec<f64> = challenges
+        .iter()
+        .map(|c| rule.probability(&c.challenge.difficulty).map_or(f64::NAN, |p| 1.0 / p))
+        .collect();
+    say!("   Expected hashes: {}", chart::labelled(&expected, chart_width(), ""));
+    say!();
+    say!(
+        "   {:>4} {:>4}  {:<10} {:>16}  {:<12} First seen",
+        "Day", "#", "Difficulty", "Expected hashes", "Change"
+    );
+    let mut previous: Option<f64> = None;
+    for record in &challenges {
+        let challenge = &record.challenge;
+        let p = rule.probability(&challenge.difficulty).filter(|&p| p > 0.0);
+        let expected = p.map(|p| format!("{:.0}", 1.0 / p)).unwrap_or_else(|| "?".to_string());
+        let change = match (previous, p) {
+            (Some(before), Some(now)) if now < before => format!("x{:.1} harder", before / now),
+            (Some(before), Some(now)) if now > before => format!("x{:.1} easier", now / before),
+            (Some(_), Some(_)) => "=".to_string(),
+            _ => String::new(),
+        };
+        say!(
+            "   {:>4} {:>4}  {:<10} {:>16}  {:<12} {}",
+            challenge.day,
+            challenge.challenge_number,
+            challenge.difficulty,
+            expected,
+            change,
+            parse_deadline(&record.first_seen).map_or(record.first_seen.clone(), timefmt::local)
+        );
+        previous = p.or(previous);
+    }
+    Ok(())
+}
+
+fn chart_width() -> usize {
+    crate::console::width().saturating_sub(40).max(10)
+}
+
+/// The `history` command: solutions per day beside the day's difficulty
+pub fn history(store: &Store, rule: DifficultyRule) -> Result<()> {
+    let solutions = store.solutions()?;
+    let challenges = store.challenges()?;
+    if solutions.is_empty() && challenges.is_empty() {
+        say!("📭 No history yet in this data dir");
+        return Ok(());
+    }
+
+    // Day -> (accepted, other outcomes); day -> hardest expected hashes
+    let mut per_day: BTreeMap<u32, (u64, u64)> = BTreeMap::new();
+    for solution in &solutions {
+        let day = per_day.entry(solution.challenge.day).or_default();
+        if solution.status == SolutionStatus::Accepted {
+            day.0 += 1;
+        } else {
+            day.1 += 1;
+        }
+    }
+    let mut hardest: BTreeMap<u32, f64> = BTreeMap::new();
+    let archived = challenges.iter().map(|c| &c.challenge);
+    for challenge in archived.chain(solutions.iter().map(|s| &s.challenge)) {
+        if let Some(p) = rule.probability(&challenge.difficulty).filter(|&p| p > 0.0) {
+            let day = hardest.entry(challenge.day).or_insert(0.0);
+            *day = day.max(1.0 / p);
+        }
+        per_day.entry(challenge.day).or_default();
+    }
+
+    let accepted: Vec<f64> = per_day.values().map(|&(accepted, _)| accepted as f64).collect();
+    let difficulty: Vec<f64> = per_day
+        .keys()
+        .map(|day| hardest.get(day).copied().unwrap_or(f64::NAN))
+        .collect();
+    say!("📜 History over {} day(s)", per_day.len());
+    say!("   Accepted: {}", chart::labelled(&accepted, chart_width(), ""));
+    say!("   Difficulty: {}", chart::labelled(&difficulty, chart_width(), " hashes"));
+    say!();
+    say!("   {:>4} {:>9} {:>7} {:>16}", "Day", "Accepted", "Other", "Hardest (hashes)");
+    for (day, (accepted, other)) in &per_day {
+        let expected = hardest.get(day).map_or("?".to_string(), |h| format!("{:.0}", h));
+        say!("   {:>4} {:>9} {:>7} {:>16}", day, accepted, other, expected);
+    }
+    window::print(&solutions);
+    Ok(())
+}
@@ -0,0 +1,307 @@
+// ==================== DASHBOARD ====================
+//
+// --tui: a full-screen view of a mining run in place of the one-line
+// progress, which says little once several threads are busy. It shows the
+// challenge and its deadlines counting down, the rate of every worker and
+// in total, solutions found this session and the latest messages; `p`
+// pauses and resumes the workers, `q` (or Ctrl+C, which raw mode delivers
+// as a key) stops the miner like a first Ctrl+C would. It takes the place
+// of the event renderer for one challenge at a time, so prompts between
+// rounds stay ordinary terminal output, and while it is up every `say!`
+// line lands in its log pane; they are printed once it closes, so the
+// scrollback keeps them. Only interactive runs in a terminal get it.
+
+use chrono::{DateTime, Local, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, Paragraph, Row, Table};
+use ratatui::Frame;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::engine::{MiningEvent, MiningOptions, SharedProgress};
+use crate::{console, status, timefmt, Challenge};
+
+/// Redraw (and key poll) interval
+const TICK: Duration = Duration::from_millis(250);
+
+/// Per-worker rates are measured over this long
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Messages kept for the log pane
+const LOG_LINES: usize = 200;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The whole run's token, for `q`
+static QUIT: OnceLock<CancellationToken> = OnceLock::new();
+
+/// Solutions found this session, newest last
+static SOLUTIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Use the dashboard for mining from now on, if this run can show one
+pub fn enable(interactive: bool, quit: CancellationToken) {
+    use std::io::IsTerminal;
+    if !interactive || !std::io::stdout().is_terminal() {
+        say!("⚠️  --tui needs an interactive run in a terminal; showing the progress line");
+        return;
+    }
+    let _ = QUIT.set(quit);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Note a verified solution for the solutions pane
+pub fn solution_found(challenge_id: &str, nonce: &str) {
+    let found = format!("{}  {}  0x{}", Local::now().format("%H:%M:%S"), challenge_id, nonce);
+    SOLUTIONS.lock().unwrap().push(found);
+}
+
+/// "in 3 h 12 m" / "passed 5 m ago" for a deadline
+fn countdown(time: DateTime<Utc>) -> String {
+    let left = time - Utc::now();
+    if left > chrono::Duration::zero() {
+        format!("{} (in {})", timefmt::local(time), timefmt::humanize(left))
+    } else {
+        format!("{} (passed {} ago)", timefmt::local(time), timefmt::humanize(left))
+    }
+}
+
+struct Dashboard<'a> {
+    shared: &'a SharedProgress,
+    challenge: &'a Challenge,
+    options: &'a MiningOptions,
+    started: Instant,
+    /// Hashes per worker at the last rate sample
+    sampled: Vec<u64>,
+    sampled_at: Instant,
+    rates: Vec<f64>,
+    /// Why the power guard paused mining, while it does
+    power_pause: Option<String>,
+    log: VecDeque<String>,
+}
+
+impl<'a> Dashboard<'a> {
+    fn sample(&mut self) {
+        let elapsed = self.sampled_at.elapsed();
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        for (worker, hashes) in self.shared.worker_hashes.iter().enumerate() {
+            let hashes = hashes.load(Ordering::Relaxed);
+            self.rates[worker] = (hashes - self.sampled[worker]) as f64 / elapsed.as_secs_f64();
+            self.sampled[worker] = hashes;
+        }
+        self.sampled_at = Instant::now();
+    }
+
+    fn collect_log(&mut self) {
+        for text in console::take_captured() {
+            let lines = text.lines().filter(|line| !line.trim().is_empty());
+            self.log.extend(lines.map(str::to_string));
+        }
+        while self.log.len() > LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+
+    fn event(&mut self, event: MiningEvent) {
+        match &event {
+            MiningEvent::Paused(reason) => self.power_pause = Some(reason.clone()),
+            MiningEvent::Resumed => self.power_pause = None,
+            _ => {}
+        }
+        crate::engine::render_event(event, &self.challenge.difficulty, self.options);
+    }
+
+    fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            KeyCode::Char('p') | KeyCode::Char(' ') => {
+                let held = !self.shared.held.load(Ordering::Relaxed);
+                self.shared.held.store(held, Ordering::Relaxed);
+                if held {
+                    say!("⏸️  Paused (p to resume)");
+                } else {
+                    say!("▶️  Resuming mining");
+                }
+            }
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => self.quit(),
+            KeyCode::Char('q') | KeyCode::Esc => self.quit(),
+            _ => {}
+        }
+    }
+
+    fn quit(&self) {
+        if !self.options.cancel.is_cancelled() {
+            say!("🛑 Stopping...");
+        }
+        match QUIT.get() {
+            Some(quit) => quit.cancel(),
+            None => self.options.cancel.cancel(),
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, middle, log, footer] = Layout::vertical([
+            Constraint::Length(7),
+            Constraint::Min(6),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [workers, solutions] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(middle);
+
+        frame.render_widget(self.header(), header);
+        frame.render_widget(self.workers(), workers);
+        frame.render_widget(self.solutions(), solutions);
+
+        let height = log.height.saturating_sub(2) as usize;
+        let skip = self.log.len().saturating_sub(height);
+        let recent: Vec<Line> = self.log.iter().skip(skip).map(|l| Line::raw(l.as_str())).collect();
+        frame.render_widget(List::new(recent).block(Block::bordered().title(" Log ")), log);
+
+        let keys = Paragraph::new(" p pause/resume   q quit")
+            .style(Style::new().add_modifier(Modifier::DIM));
+        frame.render_widget(keys, footer);
+    }
+
+    fn header(&self) -> Paragraph<'static> {
+        let elapsed = self.started.elapsed();
+        let hashes = self.shared.hashes.load(Ordering::Relaxed);
+        let total: f64 = self.rates.iter().sum();
+        let state = if self.options.cancel.is_cancelled() {
+            ("Stopping".to_string(), Color::Red)
+        } else if self.shared.held.load(Ordering::Relaxed) {
+            ("Paused (p to resume)".to_string(), Color::Yellow)
+        } else if let Some(reason) = &self.power_pause {
+            (format!("Paused: {}", reason), Color::Yellow)
+        } else {
+            ("Mining".to_string(), Color::Green)
+        };
+        let challenge = self.challenge;
+        let lines = vec![
+            Line::raw(format!(
+                "Challenge  {}  (day {}, #{})   Difficulty {}",
+                challenge.challenge_id,
+                challenge.day,
+                challenge.challenge_number,
+                challenge.difficulty
+            )),
+            Line::raw(format!(
+                "Period ends  {}",
+                self.options.deadline.map_or_else(|| "unknown".to_string(), countdown)
+            )),
+            Line::raw(format!(
+                "Submit by    {}",
+                self.options.expires_at.map_or_else(|| "unknown".to_string(), countdown)
+            )),
+            Line::raw(format!(
+                "Elapsed {}   Total {:.0} H/s   Hashes {} of {}",
+                timefmt::humanize(chrono::Duration::seconds(elapsed.as_secs() as i64)),
+                total,
+                hashes,
+                self.options.max_iterations
+            )),
+            Line::styled(state.0, Style::new().fg(state.1).add_modifier(Modifier::BOLD)),
+        ];
+        Paragraph::new(lines).block(Block::bordered().title(" Scavenger Miner "))
+    }
+
+    fn workers(&self) -> Table<'static> {
+        let rows = self.rates.iter().enumerate().map(|(worker, rate)| {
+            let hashes = self.shared.worker_hashes[worker].load(Ordering::Relaxed);
+            let name = format!("CPU {}", worker + 1);
+            Row::new([name, format!("{:.0} H/s", rate), hashes.to_string()])
+        });
+        let widths = [Constraint::Length(8), Constraint::Length(14), Constraint::Min(10)];
+        let header = Row::new(["Worker", "Rate", "Hashes"]);
+        Table::new(rows, widths)
+            .header(header.style(Style::new().add_modifier(Modifier::BOLD)))
+            .block(Block::bordered().title(" Workers "))
+    }
+
+    fn solutions(&self) -> List<'static> {
+        let found = SOLUTIONS.lock().unwrap().clone();
+        let accepted = status::current().map_or(0, |s| s.accepted);
+        let title = format!(" Solutions: {} found, {} accepted ", found.len(), accepted);
+        let items: Vec<Line> = if found.is_empty() {
+            vec![Line::raw("none yet this session")]
+        } else {
+            found.into_iter().rev().map(Line::raw).collect()
+        };
+        List::new(items).block(Block::new().borders(Borders::ALL).title(title))
+    }
+}
+
+/// Show the dashboard until the mining loop drops `events`; the plain
+/// renderer if the terminal cannot be taken over
+pub(crate) fn run(
+    events: Receiver<MiningEvent>,
+    shared: &SharedProgress,
+    challenge: &Challenge,
+    options: &MiningOptions,
+    started: Instant,
+) {
+    let mut terminal = match ratatui::try_init() {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            say!("⚠️  Cannot start the dashboard ({}), showing the progress line", e);
+            ENABLED.store(false, Ordering::Relaxed);
+            return crate::engine::render_events(events, &challenge.difficulty, options);
+        }
+    };
+    console::begin_capture();
+    let workers = shared.worker_hashes.len();
+    let mut dashboard = Dashboard {
+        shared,
+        challenge,
+        options,
+        started,
+        sampled: vec![0; workers],
+        sampled_at: started,
+        rates: vec![0.0; workers],
+        power_pause: None,
+        log: VecDeque::new(),
+    };
+
+    // Until the workers are done, or the terminal stops taking draws
+    loop {
+        let done = loop {
+            match events.try_recv() {
+                Ok(event) => dashboard.event(event),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if done {
+            break;
+        }
+        dashboard.sample();
+        dashboard.collect_log();
+        if terminal.draw(|frame| dashboard.draw(frame)).is_err() {
+            break;
+        }
+        if event::poll(TICK).unwrap_or(false) {
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind == KeyEventKind::Press {
+                    dashboard.key(key.code, key.modifiers);
+                }
+            }
+        }
+    }
+
+    ratatui::restore();
+    console::end_capture();
+    crate::engine::render_events(events, &challenge.difficulty, options);
+}
@@ -0,0 +1,222 @@
+// ==================== API STRUCTURES ====================
+//
+// Wire types of the Scavenger Mine API. Everything here is parsed from
+// server data, so none of it may assume well-formed input.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const BASE_URL: &str = "https://scavenger.prod.gd.midnighttge.io";
+
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct TandCResponse {
+    pub version: String,
+    pub content: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct RegistrationResponse {
+    #[serde(rename = "registrationReceipt")]
+    pub registration_receipt: Option<RegistrationReceipt>,
+    #[serde(flatten)]
+    pub extra: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct RegistrationReceipt {
+    pub preimage: String,
+    pub signature: String,
+    pub timestamp: String,
+}
+
+/// Typed view of ChallengeResponse.code
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+#[non_exhaustive]
+pub enum ChallengeCode {
+    Active,
+    Closed,
+    Upcoming,
+    Maintenance,
+    Unknown(String),
+}
+
+impl From<String> for ChallengeCode {
+    fn from(code: String) -> Self {
+        match code.to_lowercase().as_str() {
+            "active" => ChallengeCode::Active,
+            "closed" | "after" | "ended" => ChallengeCode::Closed,
+            "upcoming" | "before" | "not_started" => ChallengeCode::Upcoming,
+            "maintenance" => ChallengeCode::Maintenance,
+            _ => ChallengeCode::Unknown(code),
+        }
+    }
+}
+
+impl ChallengeCode {
+    /// Plain name for logs and archives ("active", "closed", ... or the raw code)
+    pub fn as_str(&self) -> &str {
+        match self {
+            ChallengeCode::Active => "active",
+            ChallengeCode::Closed => "closed",
+            ChallengeCode::Upcoming => "upcoming",
+            ChallengeCode::Maintenance => "maintenance",
+            ChallengeCode::Unknown(code) => code,
+        }
+    }
+
+    /// Unknown codes are mined anyway rather than risk idling through a live challenge
+    pub fn is_mineable(&self) -> bool {
+        matches!(self, ChallengeCode::Active | ChallengeCode::Unknown(_))
+    }
+
+    /// How long to wait before asking the server again
+    pub fn poll_interval(&self) -> Duration {
+        match self {
+            ChallengeCode::Closed => Duration::from_secs(300),
+            ChallengeCode::Maintenance => Duration::from_secs(120),
+            _ => Duration::from_secs(60),
+        }
+    }
+}
+
+impl std::fmt::Display for ChallengeCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChallengeCode::Active => write!(f, "🟢 active"),
+            ChallengeCode::Closed => write!(f, "🔴 closed"),
+            ChallengeCode::Upcoming => write!(f, "🕒 upcoming"),
+            ChallengeCode::Maintenance => write!(f, "🛠️  maintenance"),
+            ChallengeCode::Unknown(code) => write!(f, "❔ {}", code),
+        }
+    }
+}
+
+/// /challenge answered with a status but no challenge: the event has not
+/// started, is paused or is over. An error so every caller stops short of
+/// mining, typed so the daemon can tell it from an outage.
+#[derive(Debug, Clone)]
+pub struct NoChallenge {
+    pub code: ChallengeCode,
+    /// When the server says mining starts (again), if it says
+    pub starts_at: Option<String>,
+}
+
+impl NoChallenge {
+    /// The answer, when it names no challenge
+    pub fn from_response(value: &serde_json::Value) -> Option<NoChallenge> {
+        if value.get("challenge").is_some_and(|c| !c.is_null()) {
+            return None;
+        }
+        let code = value.get("code").and_then(|c| c.as_str()).unwrap_or("closed");
+        let starts_at = ["starts_at", "next_challenge_starts_at", "mining_period_starts"]
+            .iter()
+            .find_map(|key| value.get(*key).and_then(|v| v.as_str()))
+            .map(str::to_string);
+        Some(NoChallenge { code: ChallengeCode::from(code.to_string()), starts_at })
+    }
+
+    /// The same answer again, for workers asking a coordinator
+    pub fn to_value(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({ "code": self.code.as_str() });
+        if let Some(starts_at) = &self.starts_at {
+            value["starts_at"] = starts_at.clone().into();
+        }
+        value
+    }
+
+    /// Whether `error` is this, anywhere in its chain
+    pub fn find(error: &anyhow::Error) -> Option<&NoChallenge> {
+        error.chain().find_map(|cause| cause.downcast_ref::<NoChallenge>())
+    }
+}
+
+impl std::fmt::Display for NoChallenge {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "No challenge to mine, the event is {}", self.code.as_str())?;
+        match &self.starts_at {
+            Some(starts_at) => write!(f, " (mining starts {})", starts_at),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for NoChallenge {}
+
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct ChallengeResponse {
+    pub code: ChallengeCode,
+    pub challenge: Challenge,
+    #[serde(rename = "mining_period_ends")]
+    pub mining_period_ends: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Challenge {
+    pub challenge_id: String,
+    pub day: u32,
+    pub challenge_number: u32,
+    pub difficulty: String,
+    pub no_pre_mine: String,
+    pub latest_submission: String,
+    pub no_pre_mine_hour: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SolutionResponse {
+    pub crypto_receipt: Option<CryptoReceipt>,
+    #[serde(flatten)]
+    pub extra: serde_json::Value,
+    /// A retried submit was told the address already solved it after an
+    /// earlier try that may have reached the server: most likely that try got
+    /// in, though no receipt shows it
+    #[serde(skip)]
+    pub landed_earlier: bool,
+}
+
+impl SolutionResponse {
+    /// A receipt now, or (most likely) one issued to an earlier try of the
+    /// same submit
+    pub fn accepted(&self) -> bool {
+        self.crypto_receipt.is_some() || self.landed_earlier
+    }
+
+    /// The server turned it away because the address already has a solution
+    pub fn already_submitted(&self) -> bool {
+        already_submitted(&self.extra)
+    }
+}
+
+/// Start of the server's message when the address already solved the challenge
+const ALREADY_SUBMITTED: &str = "solution already";
+
+/// Whether a solution answer says the address already has a solution
+pub fn already_submitted(response: &serde_json::Value) -> bool {
+    response
+        .get("message")
+        .and_then(|m| m.as_str())
+        .is_some_and(|m| m.to_lowercase().starts_with(ALREADY_SUBMITTED))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CryptoReceipt {
+    pub preimage: String,
+    pub timestamp: String,
+    pub signature: String,
+}
+
+/// Parse a server timestamp such as mining_period_ends
+pub fn parse_deadline(mining_period_ends: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(mining_period_ends)
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
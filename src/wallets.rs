@@ -0,0 +1,32 @@
+// ==================== WALLETS ====================
+//
+// --wallet names the extension to put first on the signing page; the page
+// itself lists whatever CIP-30 wallets the browser has injected.
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Wallet {
+    Nami,
+    Eternl,
+    Lace,
+    Yoroi,
+    Typhon,
+    /// No preference
+    Other,
+}
+
+impl Wallet {
+    /// Key under window.cardano
+    pub fn key(self) -> Option<&'static str> {
+        match self {
+            Wallet::Nami => Some("nami"),
+            Wallet::Eternl => Some("eternl"),
+            Wallet::Lace => Some("lace"),
+            Wallet::Yoroi => Some("yoroi"),
+            // window.cardano.typhon is Typhon's pre-CIP-30 API
+            Wallet::Typhon => Some("typhoncip30"),
+            Wallet::Other => None,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// ==================== WASM EXPORTS ====================
+//
+// The verification half of the miner for web pages and Node tools:
+// rebuild a preimage, check a digest against a difficulty, and check a
+// receipt file written by the miner (receipts/<id>.json). With the `hash`
+// feature a `Verifier` also recomputes digests, at the price of building
+// the 1 GB ROM inside the wasm memory.
+
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::mining;
+use crate::types::Challenge;
+
+/// The fields of a miner receipt that verification needs
+#[derive(Deserialize)]
+struct Receipt {
+    address: String,
+    challenge: Challenge,
+    nonce: String,
+    preimage: String,
+    /// Full 64-byte digest, hex encoded
+    hash: String,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct ReceiptCheck {
+    /// The stored preimage is what nonce, address and challenge produce
+    pub preimage_matches: bool,
+    /// The stored digest passes the challenge difficulty
+    pub meets_difficulty: bool,
+    /// The digest was recomputed (Verifier only)
+    pub hash_checked: bool,
+    /// The recomputed digest equals the stored one
+    pub hash_matches: bool,
+}
+
+#[wasm_bindgen]
+impl ReceiptCheck {
+    /// Everything that was checked passed
+    pub fn ok(&self) -> bool {
+        self.preimage_matches && self.meets_difficulty && (!self.hash_checked || self.hash_matches)
+    }
+}
+
+fn parse_receipt(receipt_json: &str) -> Result<Receipt, JsError> {
+    serde_json::from_str(receipt_json).map_err(|e| JsError::new(&format!("invalid receipt: {}", e)))
+}
+
+fn check(receipt: &Receipt) -> Result<ReceiptCheck, JsError> {
+    let digest = hex::decode(&receipt.hash)
+        .map_err(|e| JsError::new(&format!("invalid hash hex: {}", e)))?;
+    let expected = mining::build_preimage(&receipt.nonce, &receipt.address, &receipt.challenge);
+    Ok(ReceiptCheck {
+        preimage_matches: expected == receipt.preimage,
+        meets_difficulty: mining::meets_difficulty(&digest, &receipt.challenge.difficulty),
+        hash_checked: false,
+        hash_matches: false,
+    })
+}
+
+/// Preimage for a nonce (16 hex chars), address and challenge JSON
+#[wasm_bindgen(js_name = buildPreimage)]
+pub fn build_preimage(nonce: &str, address: &str, challenge_json: &str) -> Result<String, JsError> {
+    let challenge: Challenge = serde_json::from_str(challenge_json)
+        .map_err(|e| JsError::new(&format!("invalid challenge: {}", e)))?;
+    Ok(mining::build_preimage(nonce, address, &challenge))
+}
+
+/// Does a hex digest pass a hex difficulty?
+#[wasm_bindgen(js_name = meetsDifficulty)]
+pub fn meets_difficulty(hash_hex: &str, difficulty: &str) -> Result<bool, JsError> {
+    let digest =
+        hex::decode(hash_hex).map_err(|e| JsError::new(&format!("invalid hash hex: {}", e)))?;
+    Ok(mining::meets_difficulty(&digest, difficulty))
+}
+
+/// Check a receipt without recomputing the digest
+#[wasm_bindgen(js_name = verifyReceipt)]
+pub fn verify_receipt(receipt_json: &str) -> Result<ReceiptCheck, JsError> {
+    check(&parse_receipt(receipt_json)?)
+}
+
+/// Holds one challenge's ROM so digests can be recomputed
+#[cfg(feature = "hash")]
+#[wasm_bindgen]
+pub struct Verifier {
+    no_pre_mine: String,
+    rom: ashmaize::Rom,
+}
+
+#[cfg(feature = "hash")]
+#[wasm_bindgen]
+impl Verifier {
+    /// Builds the ROM for this no_pre_mine; slow and memory hungry
+    #[wasm_bindgen(constructor)]
+    pub fn new(no_pre_mine: &str) -> Verifier {
+        Verifier {
+            no_pre_mine: no_pre_mine.to_string(),
+            rom: mining::build_rom(no_pre_mine.as_bytes()),
+        }
+    }
+
+    /// Hex digest of a preimage
+    pub fn hash(&self, preimage: &str) -> String {
+        hex::encode(mining::hash_preimage(&self.rom, preimage))
+    }
+
+    /// Full check, including recomputing the digest
+    #[wasm_bindgen(js_name = verifyReceipt)]
+    pub fn verify_receipt(&self, receipt_json: &str) -> Result<ReceiptCheck, JsError> {
+        let receipt = parse_receipt(receipt_json)?;
+        if receipt.challenge.no_pre_mine != self.no_pre_mine {
+            return Err(JsError::new("receipt is for a different challenge ROM"));
+        }
+        let mut result = check(&receipt)?;
+        result.hash_checked = true;
+        result.hash_matches = self.hash(&receipt.preimage) == receipt.hash.to_lowercase();
+        Ok(result)
+    }
+}
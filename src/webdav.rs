@@ -0,0 +1,101 @@
+// ==================== WEBDAV CLIENT ====================
+//
+// Conditional PUT and GET of single files on a WebDAV share (Nextcloud,
+// ownCloud, a NAS, Apache mod_dav...), for the sealed bundles of `sync
+// push/pull`.
+// The URL names a collection; it is created on the first PUT if missing.
+//
+// Credentials come from the environment, for HTTP Basic auth:
+//   WEBDAV_USERNAME, WEBDAV_PASSWORD
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::{ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
+use std::time::Duration;
+
+use crate::tls;
+
+pub struct WebDavClient {
+    client: Client,
+    collection: Url,
+    auth: Option<(String, String)>,
+}
+
+impl WebDavClient {
+    pub fn from_env(collection: &Url) -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let mut collection = collection.clone();
+        if !collection.path().ends_with('/') {
+            collection.set_path(&format!("{}/", collection.path()));
+        }
+        Ok(WebDavClient {
+            client: tls::builder().timeout(Duration::from_secs(60)).build()?,
+            collection,
+            auth: var("WEBDAV_USERNAME")
+                .map(|user| (user, var("WEBDAV_PASSWORD").unwrap_or_default())),
+        })
+    }
+
+    /// PUT only if the file still has the ETag `expected`, or is absent
+    /// when None; false when it has changed. `Some(None)` puts unconditionally.
+    pub async fn put_if(
+        &self,
+        name: &str,
+        body: Vec<u8>,
+        expected: Option<Option<&str>>,
+    ) -> Result<bool> {
+        // 405 means the collection is already there
+        let mkcol = Method::from_bytes(b"MKCOL")?;
+        let made = self.send(mkcol, self.collection.clone(), Vec::new()).await?;
+        if !made.status().is_success() && made.status() != StatusCode::METHOD_NOT_ALLOWED {
+            bail!("Creating {} failed: {}", self.collection, made.status());
+        }
+        let mut request = self.request(Method::PUT, self.url(name)?, body);
+        match expected {
+            None => request = request.header(IF_NONE_MATCH, "*"),
+            Some(Some(etag)) => request = request.header(IF_MATCH, etag),
+            Some(None) => {}
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            bail!("PUT {} failed: {}", name, response.status());
+        }
+        Ok(true)
+    }
+
+    /// The file and its ETag, or None when there is none yet
+    pub async fn get(&self, name: &str) -> Result<Option<(Vec<u8>, Option<String>)>> {
+        let response = self.send(Method::GET, self.url(name)?, Vec::new()).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let etag = response
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                Ok(Some((response.bytes().await?.to_vec(), etag)))
+            }
+            status => bail!("GET {} failed: {}", name, status),
+        }
+    }
+
+    fn url(&self, name: &str) -> Result<Url> {
+        self.collection.join(name).with_context(|| format!("Bad file name {}", name))
+    }
+
+    async fn send(&self, method: Method, url: Url, body: Vec<u8>) -> Result<reqwest::Response> {
+        Ok(self.request(method, url, body).send().await?)
+    }
+
+    fn request(&self, method: Method, url: Url, body: Vec<u8>) -> RequestBuilder {
+        let request = self.client.request(method, url).body(body);
+        match &self.auth {
+            Some((user, password)) => request.basic_auth(user, Some(password)),
+            None => request,
+        }
+    }
+}
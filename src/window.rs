@@ -0,0 +1,87 @@
+// ==================== SUBMISSION WINDOW ====================
+//
+// Where in a challenge's window each solution landed: 0.0 when we first
+// saw the challenge, 1.0 at its latest_submission. Aggregated over the
+// history it shows whether solutions come late (more hardware or an
+// earlier start would help) or early (it wouldn't change much).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::chart;
+use crate::store::{SolutionRecord, SolutionStatus, Store};
+use crate::{parse_deadline, Challenge};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowPosition {
+    pub found: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submitted: Option<f64>,
+}
+
+/// Fraction of `start .. end` elapsed at `at`, clamped to 0.0..=1.0
+fn fraction(start: DateTime<Utc>, end: DateTime<Utc>, at: DateTime<Utc>) -> Option<f64> {
+    let span = (end - start).num_milliseconds();
+    (span > 0).then(|| ((at - start).num_milliseconds() as f64 / span as f64).clamp(0.0, 1.0))
+}
+
+/// The window opens when the challenge was first archived and closes at latest_submission
+pub fn position(
+    store: &Store,
+    challenge: &Challenge,
+    found_at: DateTime<Utc>,
+    submitted_at: Option<DateTime<Utc>>,
+) -> Option<WindowPosition> {
+    let id = &challenge.challenge_id;
+    let opened = store
+        .challenges()
+        .ok()?
+        .into_iter()
+        .find(|c| &c.challenge.challenge_id == id)
+        .and_then(|c| parse_deadline(&c.first_seen))?;
+    let closes = parse_deadline(&challenge.latest_submission)?;
+    Some(WindowPosition {
+        found: fraction(opened, closes, found_at)?,
+        submitted: submitted_at.and_then(|at| fraction(opened, closes, at)),
+    })
+}
+
+/// Where in the window solutions were found, split by outcome
+pub fn print(solutions: &[SolutionRecord]) {
+    let placed: Vec<(&SolutionRecord, WindowPosition)> =
+        solutions.iter().filter_map(|s| Some((s, s.window?))).collect();
+    if placed.is_empty() {
+        return;
+    }
+    say!();
+    say!("🪟 Submission window (0% = challenge first seen, 100% = latest_submission)");
+    for (label, accepted) in [("Accepted", true), ("Not accepted", false)] {
+        let mut found: Vec<f64> = placed
+            .iter()
+            .filter(|(s, _)| (s.status == SolutionStatus::Accepted) == accepted)
+            .map(|(_, w)| w.found)
+            .collect();
+        if found.is_empty() {
+            continue;
+        }
+        found.sort_by(f64::total_cmp);
+        let median = found[found.len() / 2];
+        let mut deciles = [0f64; 10];
+        for f in &found {
+            deciles[((f * 10.0) as usize).min(9)] += 1.0;
+        }
+        say!(
+            "   {:<13} {:>4} found, median at {:>3.0}%  {}",
+            label,
+            found.len(),
+            median * 100.0,
+            chart::sparkline(&deciles, 10)
+        );
+    }
+    let delays: Vec<f64> =
+        placed.iter().filter_map(|(_, w)| Some(w.submitted? - w.found)).collect();
+    if !delays.is_empty() {
+        let mean = delays.iter().sum::<f64>() / delays.len() as f64;
+        say!("   Found to submitted: {:.2}% of the window on average", mean * 100.0);
+    }
+}
@@ -0,0 +1,152 @@
+// ==================== WORK HOURS ====================
+//
+// A workstation that mines needs its cores back while someone works on it.
+// --work-hours names those hours in local time ("mon-fri 09:00-18:00",
+// "22:00-06:00" for a night shift) and --work-yield the share of the
+// threads that step aside then; at the end of the hours they are taken
+// back without a restart. This is a clock schedule, not idle detection:
+// it runs like the final sprint, the mining loop checks it and emits an
+// event when the hours start or end, and the yielded workers park as sprint
+// workers do until their turn. Sprint threads count among the yielded, so
+// a sprint inside work hours does not take the machine back.
+
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone, Weekday};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkHours {
+    /// Monday first
+    days: [bool; 7],
+    start: NaiveTime,
+    /// Before `start` when the hours run past midnight
+    end: NaiveTime,
+}
+
+/// --work-hours [DAYS ]HH:MM-HH:MM, DAYS as "mon-fri" or "mon,wed,fri"
+pub fn parse(raw: &str) -> Result<WorkHours, String> {
+    let (days, times) = match raw.trim().split_once(char::is_whitespace) {
+        Some((days, times)) => (parse_days(days)?, times.trim()),
+        None => ([true; 7], raw.trim()),
+    };
+    let time = |s: &str| {
+        NaiveTime::parse_from_str(s.trim(), "%H:%M")
+            .map_err(|_| format!("'{}' is not a time of day (HH:MM)", s.trim()))
+    };
+    let Some((start, end)) = times.split_once('-') else {
+        return Err(format!("'{}': expected HH:MM-HH:MM", raw));
+    };
+    let (start, end) = (time(start)?, time(end)?);
+    if start == end {
+        return Err(format!("'{}' starts and ends at the same time", raw));
+    }
+    Ok(WorkHours { days, start, end })
+}
+
+fn parse_days(raw: &str) -> Result<[bool; 7], String> {
+    let day = |s: &str| {
+        s.trim()
+            .parse::<Weekday>()
+            .map(|d| d.num_days_from_monday() as usize)
+            .map_err(|_| format!("'{}' is not a day of the week", s.trim()))
+    };
+    let mut days = [false; 7];
+    for part in raw.split(',') {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (day(first)?, day(last)?),
+            None => (day(part)?, day(part)?),
+        };
+        // "fri-mon" runs over the weekend
+        let mut d = first;
+        loop {
+            days[d] = true;
+            if d == last {
+                break;
+            }
+            d = (d + 1) % 7;
+        }
+    }
+    Ok(days)
+}
+
+impl WorkHours {
+    /// Whether `at` falls inside the hours; hours past midnight belong to the
+    /// day they started on
+    pub fn contains<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+        let day = at.weekday().num_days_from_monday() as usize;
+        let time = at.time();
+        if self.start < self.end {
+            self.days[day] && time >= self.start && time < self.end
+        } else {
+            (self.days[day] && time >= self.start) || (self.days[(day + 6) % 7] && time < self.end)
+        }
+    }
+}
+
+impl fmt::Display for WorkHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+        if !self.days.iter().all(|&d| d) {
+            let names: Vec<&str> = (0..7).filter(|&d| self.days[d]).map(|d| NAMES[d]).collect();
+            write!(f, "{} ", names.join(","))?;
+        }
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+/// The work hours and how much of the machine they take
+#[derive(Debug, Clone, Copy)]
+pub struct Yield {
+    pub hours: WorkHours,
+    /// Percent of the threads
+    pub percent: f64,
+}
+
+impl Yield {
+    /// How many of `threads` step aside during the hours
+    pub fn threads(&self, threads: usize) -> usize {
+        ((threads as f64 * self.percent / 100.0).round() as usize).min(threads)
+    }
+
+    pub fn in_effect(&self) -> bool {
+        self.hours.contains(&Local::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn weekday_hours() {
+        let hours = parse("mon-fri 09:00-18:00").unwrap();
+        // 2026-10-16 is a Friday
+        let at = |d: u32, h: u32, m: u32| Utc.with_ymd_and_hms(2026, 10, d, h, m, 0).unwrap();
+        assert!(hours.contains(&at(16, 9, 0)));
+        assert!(hours.contains(&at(16, 17, 59)));
+        assert!(!hours.contains(&at(16, 18, 0)));
+        assert!(!hours.contains(&at(16, 8, 59)));
+        assert!(!hours.contains(&at(17, 12, 0)));
+        assert_eq!(hours.to_string(), "mon,tue,wed,thu,fri 09:00-18:00");
+    }
+
+    #[test]
+    fn hours_past_midnight_belong_to_their_first_day() {
+        let hours = parse("fri-sat 22:00-06:00").unwrap();
+        let at = |d: u32, h: u32| Utc.with_ymd_and_hms(2026, 10, d, h, 0, 0).unwrap();
+        assert!(hours.contains(&at(16, 23)));
+        assert!(hours.contains(&at(17, 5)));
+        assert!(hours.contains(&at(18, 5)));
+        assert!(!hours.contains(&at(16, 5)));
+        assert!(!hours.contains(&at(18, 23)));
+        assert!(parse("22:00-06:00").unwrap().contains(&at(14, 2)));
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        assert!(parse("09:00").is_err());
+        assert!(parse("9-17").is_err());
+        assert!(parse("09:00-09:00").is_err());
+        assert!(parse("weekdays 09:00-17:00").is_err());
+    }
+}